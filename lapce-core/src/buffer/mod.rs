@@ -290,6 +290,16 @@ pub fn indent_unit(&self) -> &'static str {
         self.indent_style.as_str()
     }
 
+    pub fn indent_style(&self) -> IndentStyle {
+        self.indent_style
+    }
+
+    /// Overrides the detected indentation, such as with the one an
+    /// `.editorconfig` file sets
+    pub fn set_indent_style(&mut self, indent_style: IndentStyle) {
+        self.indent_style = indent_style;
+    }
+
     pub fn reset_edit_type(&mut self) {
         self.last_edit_type = EditType::Other;
     }
@@ -235,6 +235,10 @@ pub enum FocusCommand {
     JumpToNextSnippetPlaceholder,
     #[strum(serialize = "jump_to_prev_snippet_placeholder")]
     JumpToPrevSnippetPlaceholder,
+    /// Inserts the snippet given as its argument, so it's only useful in a
+    /// keymap
+    #[strum(serialize = "insert_snippet")]
+    InsertSnippet,
     #[strum(serialize = "page_up")]
     PageUp,
     #[strum(serialize = "page_down")]
@@ -253,6 +257,13 @@ pub enum FocusCommand {
     ShowCodeActions,
     #[strum(serialize = "get_completion")]
     GetCompletion,
+    #[strum(message = "Trigger Parameter Hints")]
+    #[strum(serialize = "get_signature")]
+    GetSignature,
+    #[strum(serialize = "next_signature")]
+    NextSignature,
+    #[strum(serialize = "previous_signature")]
+    PreviousSignature,
     /// This will close a modal, such as the settings window or completion
     #[strum(message = "Close Modal")]
     #[strum(serialize = "modal.close")]
@@ -263,6 +274,18 @@ pub enum FocusCommand {
     #[strum(message = "Go to Type Definition")]
     #[strum(serialize = "goto_type_definition")]
     GotoTypeDefinition,
+    #[strum(message = "Go to Implementation")]
+    #[strum(serialize = "goto_implementation")]
+    GotoImplementation,
+    #[strum(message = "Go to Declaration")]
+    #[strum(serialize = "goto_declaration")]
+    GotoDeclaration,
+    #[strum(message = "Show Call Hierarchy")]
+    #[strum(serialize = "show_call_hierarchy")]
+    ShowCallHierarchy,
+    #[strum(message = "Show Type Hierarchy")]
+    #[strum(serialize = "show_type_hierarchy")]
+    ShowTypeHierarchy,
     #[strum(serialize = "jump_location_backward")]
     JumpLocationBackward,
     #[strum(serialize = "jump_location_forward")]
@@ -278,12 +301,36 @@ pub enum FocusCommand {
     #[strum(message = "Go to Previous Difference")]
     #[strum(serialize = "previous_diff")]
     PreviousDiff,
+    #[strum(message = "Go to Next Occurrence of Symbol")]
+    #[strum(serialize = "next_occurrence")]
+    NextOccurrence,
+    #[strum(message = "Go to Previous Occurrence of Symbol")]
+    #[strum(serialize = "previous_occurrence")]
+    PreviousOccurrence,
     #[strum(message = "Toggle Code Lens")]
     #[strum(serialize = "toggle_code_lens")]
     ToggleCodeLens,
+    #[strum(message = "Fold")]
+    #[strum(serialize = "fold")]
+    Fold,
+    #[strum(message = "Unfold")]
+    #[strum(serialize = "unfold")]
+    Unfold,
+    #[strum(message = "Toggle Fold")]
+    #[strum(serialize = "toggle_fold")]
+    ToggleFold,
+    #[strum(message = "Fold All")]
+    #[strum(serialize = "fold_all")]
+    FoldAll,
+    #[strum(message = "Unfold All")]
+    #[strum(serialize = "unfold_all")]
+    UnfoldAll,
     #[strum(serialize = "format_document")]
     #[strum(message = "Format Document")]
     FormatDocument,
+    #[strum(serialize = "format_selection")]
+    #[strum(message = "Format Selection")]
+    FormatSelection,
     #[strum(serialize = "search")]
     Search,
     #[strum(serialize = "inline_find_right")]
@@ -308,6 +355,15 @@ pub enum FocusCommand {
     SelectNextSyntaxItem,
     #[strum(serialize = "select_previous_syntax_item")]
     SelectPreviousSyntaxItem,
+    #[strum(message = "Merge Conflict: Accept Current Change")]
+    #[strum(serialize = "merge_conflict.accept_current")]
+    AcceptConflictCurrent,
+    #[strum(message = "Merge Conflict: Accept Incoming Change")]
+    #[strum(serialize = "merge_conflict.accept_incoming")]
+    AcceptConflictIncoming,
+    #[strum(message = "Merge Conflict: Accept Both Changes")]
+    #[strum(serialize = "merge_conflict.accept_both")]
+    AcceptConflictBoth,
 }
 
 #[derive(
@@ -0,0 +1,186 @@
+use std::ops::Range;
+
+use xi_rope::Rope;
+
+const START_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const END_MARKER: &str = ">>>>>>>";
+
+/// A region git couldn't merge, described by the lines its markers are on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// The `<<<<<<<` line that starts the current change
+    pub start: usize,
+    /// The `|||||||` line that starts the common ancestor, which is only there
+    /// with the `diff3` conflict style
+    pub base: Option<usize>,
+    /// The `=======` line that starts the incoming change
+    pub separator: usize,
+    /// The `>>>>>>>` line that ends the conflict
+    pub end: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Keep the change of the branch that was checked out
+    Current,
+    /// Keep the change being merged in
+    Incoming,
+    /// Keep the current change followed by the incoming one
+    Both,
+}
+
+impl Conflict {
+    pub fn contains(&self, line: usize) -> bool {
+        self.start <= line && line <= self.end
+    }
+
+    /// The lines of the current change
+    pub fn current(&self) -> Range<usize> {
+        self.start + 1..self.base.unwrap_or(self.separator)
+    }
+
+    /// The lines of the incoming change
+    pub fn incoming(&self) -> Range<usize> {
+        self.separator + 1..self.end
+    }
+
+    /// The offsets of the whole conflict including its markers
+    pub fn range(&self, text: &Rope) -> Range<usize> {
+        text.offset_of_line(self.start)..text.offset_of_line(self.end + 1)
+    }
+
+    /// The text the whole conflict is replaced with when it's resolved
+    pub fn resolve(&self, text: &Rope, resolution: ConflictResolution) -> String {
+        let lines = |lines: Range<usize>| {
+            text.slice_to_cow(
+                text.offset_of_line(lines.start)..text.offset_of_line(lines.end),
+            )
+            .to_string()
+        };
+        match resolution {
+            ConflictResolution::Current => lines(self.current()),
+            ConflictResolution::Incoming => lines(self.incoming()),
+            ConflictResolution::Both => {
+                let mut current = lines(self.current());
+                // The marker line ends the current change when it's the last
+                // line of a file without a trailing newline
+                if !current.is_empty() && !current.ends_with('\n') {
+                    current.push('\n');
+                }
+                current + &lines(self.incoming())
+            }
+        }
+    }
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    match line.strip_prefix(marker) {
+        Some(rest) => rest.is_empty() || rest.starts_with(char::is_whitespace),
+        None => false,
+    }
+}
+
+/// Find the conflict markers in the text, skipping any that aren't complete
+pub fn find_conflicts(text: &Rope) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut start = None;
+    let mut base = None;
+    let mut separator = None;
+    for (line, content) in text.lines(..).enumerate() {
+        if is_marker(&content, START_MARKER) {
+            start = Some(line);
+            base = None;
+            separator = None;
+        } else if start.is_none() {
+            continue;
+        } else if separator.is_none() {
+            if base.is_none() && is_marker(&content, BASE_MARKER) {
+                base = Some(line);
+            } else if content.trim_end() == SEPARATOR_MARKER {
+                separator = Some(line);
+            }
+        } else if is_marker(&content, END_MARKER) {
+            if let (Some(start), Some(separator)) = (start.take(), separator.take())
+            {
+                conflicts.push(Conflict {
+                    start,
+                    base: base.take(),
+                    separator,
+                    end: line,
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEXT: &str = "a
+<<<<<<< HEAD
+ours
+=======
+theirs
+>>>>>>> feature
+b
+";
+
+    #[test]
+    fn test_find_conflicts() {
+        let text = Rope::from(TEXT);
+        assert_eq!(
+            find_conflicts(&text),
+            vec![Conflict {
+                start: 1,
+                base: None,
+                separator: 3,
+                end: 5,
+            }]
+        );
+
+        let text = Rope::from(
+            "<<<<<<< HEAD\nours\n||||||| base\nbase\n=======\ntheirs\n>>>>>>> x",
+        );
+        assert_eq!(
+            find_conflicts(&text),
+            vec![Conflict {
+                start: 0,
+                base: Some(2),
+                separator: 4,
+                end: 6,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_conflicts_incomplete() {
+        let text = Rope::from("<<<<<<< HEAD\nours\n=======\ntheirs\n");
+        assert!(find_conflicts(&text).is_empty());
+
+        let text = Rope::from("<<<<<<<< not a marker\n=======\n>>>>>>>\n");
+        assert!(find_conflicts(&text).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_conflict() {
+        let text = Rope::from(TEXT);
+        let conflict = find_conflicts(&text)[0];
+        assert_eq!(conflict.range(&text), 2..51);
+        assert_eq!(
+            conflict.resolve(&text, ConflictResolution::Current),
+            "ours\n"
+        );
+        assert_eq!(
+            conflict.resolve(&text, ConflictResolution::Incoming),
+            "theirs\n"
+        );
+        assert_eq!(
+            conflict.resolve(&text, ConflictResolution::Both),
+            "ours\ntheirs\n"
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use tree_sitter::TreeCursor;
+use xi_rope::{Rope, RopeDelta, Transformer};
+
+/// A region of lines that can be folded, which hides all of its lines but
+/// the first one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FoldRange {
+    pub start_line: usize,
+    /// The last line that gets hidden
+    pub end_line: usize,
+}
+
+impl FoldRange {
+    pub fn new(start_line: usize, end_line: usize) -> Self {
+        Self {
+            start_line,
+            end_line,
+        }
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        self.start_line <= line && line <= self.end_line
+    }
+
+    /// The lines that are hidden when it's folded
+    pub fn hidden(&self) -> Range<usize> {
+        self.start_line + 1..self.end_line + 1
+    }
+}
+
+/// The regions of a document that are folded. They are kept as the offsets of
+/// the start of their first and last lines so they follow the text as it's
+/// edited.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Folds {
+    folds: Vec<(usize, usize)>,
+}
+
+impl Folds {
+    pub fn is_empty(&self) -> bool {
+        self.folds.is_empty()
+    }
+
+    fn range(text: &Rope, fold: (usize, usize)) -> FoldRange {
+        FoldRange::new(text.line_of_offset(fold.0), text.line_of_offset(fold.1))
+    }
+
+    pub fn fold(&mut self, text: &Rope, range: FoldRange) {
+        if range.end_line <= range.start_line || self.ranges(text).contains(&range) {
+            return;
+        }
+        self.folds.push((
+            text.offset_of_line(range.start_line),
+            text.offset_of_line(range.end_line),
+        ));
+    }
+
+    /// Fold all the regions, which replaces the existing folds
+    pub fn fold_all(&mut self, text: &Rope, ranges: &[FoldRange]) {
+        self.folds = ranges
+            .iter()
+            .filter(|range| range.end_line > range.start_line)
+            .map(|range| {
+                (
+                    text.offset_of_line(range.start_line),
+                    text.offset_of_line(range.end_line),
+                )
+            })
+            .collect();
+    }
+
+    /// Unfold the regions that start at the line, returning whether there was
+    /// any
+    pub fn unfold(&mut self, text: &Rope, line: usize) -> bool {
+        let len = self.folds.len();
+        self.folds
+            .retain(|fold| text.line_of_offset(fold.0) != line);
+        self.folds.len() != len
+    }
+
+    /// Unfold the regions that hide the line
+    pub fn unfold_hiding(&mut self, text: &Rope, line: usize) {
+        self.folds
+            .retain(|fold| !Self::range(text, *fold).hidden().contains(&line));
+    }
+
+    pub fn is_folded(&self, text: &Rope, line: usize) -> bool {
+        self.folds
+            .iter()
+            .any(|fold| text.line_of_offset(fold.0) == line)
+    }
+
+    pub fn clear(&mut self) {
+        self.folds.clear();
+    }
+
+    pub fn ranges(&self, text: &Rope) -> Vec<FoldRange> {
+        self.folds
+            .iter()
+            .map(|fold| Self::range(text, *fold))
+            .collect()
+    }
+
+    pub fn apply_delta(&mut self, delta: &RopeDelta) {
+        let mut transformer = Transformer::new(delta);
+        for (start, end) in self.folds.iter_mut() {
+            *start = transformer.transform(*start, true);
+            *end = transformer.transform(*end, true);
+        }
+    }
+
+    /// Drop the folds whose lines were deleted, which is checked once all the
+    /// deltas of an edit are applied
+    pub fn retain_valid(&mut self, text: &Rope) {
+        self.folds.retain(|fold| {
+            let range = Self::range(text, *fold);
+            range.end_line > range.start_line
+        });
+    }
+
+    pub fn folded_lines(&self, text: &Rope) -> FoldedLines {
+        let mut hidden: Vec<Range<usize>> = self
+            .folds
+            .iter()
+            .map(|fold| Self::range(text, *fold).hidden())
+            .filter(|lines| !lines.is_empty())
+            .collect();
+        hidden.sort_by_key(|lines| lines.start);
+
+        // Nested folds are hidden by the outer one
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(hidden.len());
+        for lines in hidden {
+            match merged.last_mut() {
+                Some(last) if lines.start <= last.end => {
+                    last.end = last.end.max(lines.end);
+                }
+                _ => merged.push(lines),
+            }
+        }
+        FoldedLines { hidden: merged }
+    }
+}
+
+/// The lines hidden by the folds, which maps between the lines of the buffer
+/// and the lines that are displayed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FoldedLines {
+    hidden: Vec<Range<usize>>,
+}
+
+impl FoldedLines {
+    pub fn is_empty(&self) -> bool {
+        self.hidden.is_empty()
+    }
+
+    /// The number of lines that are hidden
+    pub fn hidden_len(&self) -> usize {
+        self.hidden.iter().map(|lines| lines.len()).sum()
+    }
+
+    pub fn is_hidden(&self, line: usize) -> bool {
+        self.hidden.iter().any(|lines| lines.contains(&line))
+    }
+
+    /// The line that is displayed for the line, which is the first line of
+    /// the fold if it's hidden
+    pub fn visible_line(&self, line: usize) -> usize {
+        match self.hidden.iter().find(|lines| lines.contains(&line)) {
+            Some(lines) => lines.start - 1,
+            None => line,
+        }
+    }
+
+    /// The index of the line among the lines that are displayed
+    pub fn visual_line(&self, line: usize) -> usize {
+        let line = self.visible_line(line);
+        let hidden: usize = self
+            .hidden
+            .iter()
+            .take_while(|lines| lines.end <= line)
+            .map(|lines| lines.len())
+            .sum();
+        line - hidden
+    }
+
+    /// The line of the buffer that is displayed at the index
+    pub fn actual_line(&self, visual_line: usize) -> usize {
+        let mut line = visual_line;
+        for lines in self.hidden.iter() {
+            if lines.start > line {
+                break;
+            }
+            line += lines.len();
+        }
+        line
+    }
+}
+
+/// The regions of the syntax tree that span several lines, to fold when the
+/// language server doesn't provide folding ranges. The closing delimiter of a
+/// region is kept visible.
+pub fn fold_ranges_from_tree(
+    cursor: &mut TreeCursor,
+    text: &Rope,
+) -> Vec<FoldRange> {
+    let mut ranges: Vec<FoldRange> = Vec::new();
+    let mut depth = 0;
+    loop {
+        let node = cursor.node();
+        let start = node.start_position();
+        let end = node.end_position();
+        // The root spans the whole text
+        if depth > 0 && end.row > start.row {
+            let mut end_line = end.row;
+            let last_line =
+                text.slice_to_cow(text.offset_of_line(end_line)..node.end_byte());
+            let last_line = last_line.trim_start();
+            if last_line.is_empty()
+                || last_line.starts_with(['}', ')', ']'])
+                || last_line.starts_with("</")
+            {
+                end_line -= 1;
+            }
+            if end_line > start.row {
+                ranges.push(FoldRange::new(start.row, end_line));
+            }
+        }
+
+        if cursor.goto_first_child() {
+            depth += 1;
+            continue;
+        }
+        loop {
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            if cursor.goto_parent() {
+                depth -= 1;
+            } else {
+                // Only keep the largest region starting on each line
+                ranges.sort_by(|a, b| {
+                    a.start_line
+                        .cmp(&b.start_line)
+                        .then(b.end_line.cmp(&a.end_line))
+                });
+                ranges.dedup_by_key(|range| range.start_line);
+                return ranges;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xi_rope::{Delta, Interval};
+
+    use super::*;
+
+    #[test]
+    fn test_folded_lines() {
+        let text = Rope::from("a\nb\nc\nd\ne\nf\ng\nh\n");
+        let mut folds = Folds::default();
+        folds.fold(&text, FoldRange::new(1, 3));
+        folds.fold(&text, FoldRange::new(2, 3));
+        folds.fold(&text, FoldRange::new(5, 6));
+
+        let lines = folds.folded_lines(&text);
+        assert_eq!(lines.hidden_len(), 3);
+        assert!(lines.is_hidden(2));
+        assert!(!lines.is_hidden(4));
+        assert_eq!(lines.visible_line(3), 1);
+        assert_eq!(lines.visual_line(3), 1);
+        assert_eq!(lines.visual_line(4), 2);
+        assert_eq!(lines.visual_line(7), 4);
+        assert_eq!(lines.actual_line(1), 1);
+        assert_eq!(lines.actual_line(2), 4);
+        assert_eq!(lines.actual_line(4), 7);
+
+        assert!(folds.unfold(&text, 1));
+        assert!(!folds.unfold(&text, 1));
+        assert_eq!(folds.folded_lines(&text).visual_line(4), 3);
+    }
+
+    #[test]
+    fn test_folds_follow_edits() {
+        let text = Rope::from("a\nb\nc\nd\n");
+        let mut folds = Folds::default();
+        folds.fold(&text, FoldRange::new(1, 2));
+
+        // A line inserted above moves the fold down
+        let delta = Delta::simple_edit(Interval::new(0, 0), Rope::from("x\n"), 8);
+        let text = delta.apply(&text);
+        folds.apply_delta(&delta);
+        folds.retain_valid(&text);
+        assert_eq!(folds.ranges(&text), vec![FoldRange::new(2, 3)]);
+
+        // Typing at the start of its lines keeps it
+        let delta =
+            Delta::simple_edit(Interval::new(4, 4), Rope::from("y"), text.len());
+        let text = delta.apply(&text);
+        folds.apply_delta(&delta);
+        folds.retain_valid(&text);
+        assert_eq!(folds.ranges(&text), vec![FoldRange::new(2, 3)]);
+
+        // Deleting its lines drops it
+        let delta =
+            Delta::simple_edit(Interval::new(4, 9), Rope::from(""), text.len());
+        let text = delta.apply(&text);
+        folds.apply_delta(&delta);
+        folds.retain_valid(&text);
+        assert!(folds.is_empty());
+    }
+}
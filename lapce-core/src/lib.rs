@@ -1,15 +1,18 @@
 pub mod buffer;
 pub mod chars;
 pub mod command;
+pub mod conflict;
 pub mod cursor;
 pub mod editor;
 pub mod encoding;
+pub mod fold;
 pub mod indent;
 pub mod language;
 pub mod lens;
 pub mod mode;
 pub mod movement;
 pub mod register;
+pub mod save_action;
 pub mod selection;
 pub mod style;
 pub mod syntax;
@@ -0,0 +1,142 @@
+//! The edits that tidy a document up before it's saved, each of which is
+//! applied as an undo step of its own.
+
+use xi_rope::Rope;
+
+use crate::selection::Selection;
+
+/// Splits a raw line into its content and its line ending
+fn split_line_ending(line: &str) -> (&str, &str) {
+    let content = line.trim_end_matches(|c| c == '\r' || c == '\n');
+    (content, &line[content.len()..])
+}
+
+/// The edits that remove the whitespace at the end of each line, except
+/// where `keep` says the whitespace starting at an offset is part of the
+/// text, such as inside a string literal
+pub fn trailing_whitespace_edits(
+    text: &Rope,
+    keep: impl Fn(usize) -> bool,
+) -> Vec<(Selection, String)> {
+    let mut edits = Vec::new();
+    let mut offset = 0;
+    for line in text.lines_raw(..) {
+        let (content, _) = split_line_ending(&line);
+        let trimmed = content.trim_end_matches(|c| c == ' ' || c == '\t');
+        let start = offset + trimmed.len();
+        let end = offset + content.len();
+        if start < end && !keep(start) {
+            edits.push((Selection::region(start, end), String::new()));
+        }
+        offset += line.len();
+    }
+    edits
+}
+
+/// The edits that make sure the text ends with a line ending, or that it
+/// doesn't end with any
+pub fn final_newline_edits(
+    text: &Rope,
+    ensure: bool,
+    line_ending: &str,
+) -> Vec<(Selection, String)> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let mut start = text.len();
+    while start > 0 && matches!(text.byte_at(start - 1), b'\n' | b'\r') {
+        start -= 1;
+    }
+    if ensure {
+        if start == text.len() {
+            vec![(Selection::caret(text.len()), line_ending.to_string())]
+        } else {
+            Vec::new()
+        }
+    } else if start < text.len() {
+        vec![(Selection::region(start, text.len()), String::new())]
+    } else {
+        Vec::new()
+    }
+}
+
+/// The edits that convert the line endings which aren't `line_ending`
+pub fn line_ending_edits(
+    text: &Rope,
+    line_ending: &str,
+) -> Vec<(Selection, String)> {
+    let mut edits = Vec::new();
+    let mut offset = 0;
+    for line in text.lines_raw(..) {
+        let (content, ending) = split_line_ending(&line);
+        if !ending.is_empty() && ending != line_ending {
+            edits.push((
+                Selection::region(offset + content.len(), offset + line.len()),
+                line_ending.to_string(),
+            ));
+        }
+        offset += line.len();
+    }
+    edits
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn apply(text: &str, edits: Vec<(Selection, String)>) -> String {
+        let mut text = text.to_string();
+        for (selection, new_text) in edits.iter().rev() {
+            let region = selection.regions()[0];
+            text.replace_range(region.min()..region.max(), new_text);
+        }
+        text
+    }
+
+    #[test]
+    fn test_trailing_whitespace_edits() {
+        let text = "fn main() {  \n    let s = \"a  \n  b\";\t\n}   ";
+        let rope = Rope::from(text);
+        let edits = trailing_whitespace_edits(&rope, |_| false);
+        assert_eq!(
+            apply(text, edits),
+            "fn main() {\n    let s = \"a\n  b\";\n}"
+        );
+
+        // The whitespace inside the string literal is kept
+        let string = text.find('"').unwrap()..text.rfind('"').unwrap();
+        let edits =
+            trailing_whitespace_edits(&rope, |offset| string.contains(&offset));
+        assert_eq!(
+            apply(text, edits),
+            "fn main() {\n    let s = \"a  \n  b\";\n}"
+        );
+    }
+
+    #[test]
+    fn test_final_newline_edits() {
+        let ensure = |text: &str| {
+            apply(text, final_newline_edits(&Rope::from(text), true, "\n"))
+        };
+        assert_eq!(ensure("a\nb"), "a\nb\n");
+        assert_eq!(ensure("a\nb\n"), "a\nb\n");
+        assert_eq!(ensure(""), "");
+
+        let trim = |text: &str| {
+            apply(text, final_newline_edits(&Rope::from(text), false, "\n"))
+        };
+        assert_eq!(trim("a\nb\r\n\n"), "a\nb");
+        assert_eq!(trim("a\nb"), "a\nb");
+    }
+
+    #[test]
+    fn test_line_ending_edits() {
+        let text = "a\r\nb\nc\r\n";
+        let edits = line_ending_edits(&Rope::from(text), "\n");
+        assert_eq!(apply(text, edits), "a\nb\nc\n");
+
+        let edits = line_ending_edits(&Rope::from(text), "\r\n");
+        assert_eq!(apply(text, edits), "a\r\nb\r\nc\r\n");
+    }
+}
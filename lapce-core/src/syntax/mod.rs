@@ -25,6 +25,7 @@
 };
 
 use crate::{
+    fold::{fold_ranges_from_tree, FoldRange},
     language::LapceLanguage,
     lens::{Lens, LensBuilder},
     style::SCOPES,
@@ -524,6 +525,8 @@ pub struct Syntax {
     pub line_height: usize,
     pub lens_height: usize,
     pub styles: Option<Arc<Spans<Style>>>,
+    /// The regions that can be folded according to the syntax tree
+    pub folding_ranges: Arc<Vec<FoldRange>>,
 }
 
 impl std::fmt::Debug for Syntax {
@@ -536,6 +539,7 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             .field("line_height", &self.line_height)
             .field("lens_height", &self.lens_height)
             .field("styles", &self.styles)
+            .field("folding_ranges", &self.folding_ranges)
             .finish()
     }
 }
@@ -556,6 +560,7 @@ pub fn from_language(language: LapceLanguage) -> Syntax {
             lens_height: 0,
             normal_lines: Vec::new(),
             styles: None,
+            folding_ranges: Arc::new(Vec::new()),
         }
     }
 
@@ -627,6 +632,12 @@ pub fn parse(
             Vec::new()
         };
 
+        let folding_ranges = if let Some(tree) = tree {
+            Arc::new(fold_ranges_from_tree(&mut tree.walk(), &new_text))
+        } else {
+            self.folding_ranges.clone()
+        };
+
         let lens = Self::lens_from_normal_lines(
             new_text.line_of_offset(new_text.len()) + 1,
             self.line_height,
@@ -638,6 +649,7 @@ pub fn parse(
         self.lens = lens;
         self.normal_lines = normal_lines;
         self.styles = styles;
+        self.folding_ranges = folding_ranges;
         self.text = new_text
 
         // Syntax {
@@ -767,6 +779,37 @@ fn find_tag_in_children(&self, node: Node, tag: &str) -> Option<usize> {
         None
     }
 
+    /// Whether the character at the offset is string content, which is
+    /// where the innermost capture of the highlight query is a string one,
+    /// so that the code in a substitution of a template string isn't
+    pub fn is_in_string(&self, offset: usize) -> bool {
+        let mut highlights: Vec<Highlight> = Vec::new();
+        for event in self
+            .layers
+            .highlight_iter(&self.text, Some(offset..offset + 1), None)
+            .flatten()
+        {
+            match event {
+                HighlightEvent::HighlightStart(hl) => highlights.push(hl),
+                HighlightEvent::HighlightEnd => {
+                    highlights.pop();
+                }
+                HighlightEvent::Source { start, end } => {
+                    if start <= offset && offset < end {
+                        return highlights
+                            .last()
+                            .and_then(|hl| SCOPES.get(hl.0))
+                            .map(|scope| {
+                                scope.starts_with("string") || *scope == "escape"
+                            })
+                            .unwrap_or(false);
+                    }
+                }
+            }
+        }
+        false
+    }
+
     pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         let tree = self.layers.try_tree()?;
         let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
@@ -784,6 +827,75 @@ pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         }
         Some(offsets)
     }
+
+    /// The ranges of the identifiers with the same name as the one at the
+    /// offset, which highlight its occurrences when there isn't a language
+    /// server to provide them. The names are only matched as text, within
+    /// the function, closure or class the identifier is in, so it's a
+    /// guess rather than a resolution of the references.
+    pub fn identifier_occurrences(
+        &self,
+        offset: usize,
+    ) -> Option<Vec<(usize, usize)>> {
+        let tree = self.layers.try_tree()?;
+        let root = tree.root_node();
+        let is_identifier = |node: &Node| {
+            node.child_count() == 0 && node.kind().contains("identifier")
+        };
+        // The cursor can be right after the identifier as well
+        let node = [offset, offset.saturating_sub(1)]
+            .into_iter()
+            .filter_map(|offset| root.descendant_for_byte_range(offset, offset + 1))
+            .find(is_identifier)?;
+        let name = self.text.slice_to_cow(node.byte_range());
+        let scope = identifier_scope(node).unwrap_or(root);
+
+        let mut occurrences = Vec::new();
+        let mut cursor = scope.walk();
+        loop {
+            let node = cursor.node();
+            if is_identifier(&node)
+                && node.byte_range().len() == name.len()
+                && self.text.slice_to_cow(node.byte_range()) == name
+            {
+                occurrences.push((node.start_byte(), node.end_byte()));
+            }
+
+            if cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                // The cursor doesn't go above the node it started from
+                if !cursor.goto_parent() {
+                    return Some(occurrences);
+                }
+            }
+        }
+    }
+}
+
+/// The function, closure or class an identifier is used in, which is the
+/// one around it when the identifier is its name
+fn identifier_scope(identifier: Node) -> Option<Node> {
+    let is_scope = |node: &Node| {
+        let kind = node.kind();
+        // The parameters and the types of functions aren't scopes
+        node.child_by_field_name("body").is_some()
+            && ["function", "method", "closure", "lambda", "class"]
+                .iter()
+                .any(|scope| kind.contains(scope))
+    };
+    let mut node = identifier.parent()?;
+    loop {
+        let is_name = node
+            .child_by_field_name("name")
+            .map(|name| name.id() == identifier.id())
+            .unwrap_or(false);
+        if is_scope(&node) && !is_name {
+            return Some(node);
+        }
+        node = node.parent()?;
+    }
 }
 
 #[cfg(test)]
@@ -804,6 +916,56 @@ fn test_lens() {
         assert_eq!(33, lens.height_of_line(5));
     }
 
+    #[cfg(feature = "lang-rust")]
+    #[test]
+    fn test_identifier_occurrences() {
+        let text = "fn one(value: usize) -> usize {\n    value + 1\n}\n\n\
+                    fn two() -> usize {\n    let value = one(2);\n    value\n}\n";
+        let mut syntax = Syntax::from_language(LapceLanguage::Rust);
+        syntax.parse(0, Rope::from(text), None);
+        let occurrences = |offset| syntax.identifier_occurrences(offset).unwrap();
+
+        // Only the ones in the same function
+        let first = text.find("value").unwrap();
+        let second = text[first + 1..].find("value").unwrap() + first + 1;
+        assert_eq!(
+            occurrences(second),
+            vec![(first, first + 5), (second, second + 5)]
+        );
+        let last = text.rfind("value").unwrap();
+        assert_eq!(occurrences(last + 5).len(), 2);
+
+        // The name of a function is looked for around the function
+        let one = text.find("one").unwrap();
+        let call = text.rfind("one").unwrap();
+        assert_eq!(occurrences(one), vec![(one, one + 3), (call, call + 3)]);
+    }
+
+    #[cfg(all(feature = "lang-rust", feature = "lang-javascript"))]
+    #[test]
+    fn test_is_in_string() {
+        let syntax = |language, text: &str| {
+            let mut syntax = Syntax::from_language(language);
+            syntax.parse(0, Rope::from(text), None);
+            syntax
+        };
+
+        let text = "let s = \"a  \n b\";  \nlet n = 1;\n";
+        let rust = syntax(LapceLanguage::Rust, text);
+        assert!(rust.is_in_string(text.find("a ").unwrap() + 1));
+        assert!(rust.is_in_string(text.find('b').unwrap()));
+        assert!(!rust.is_in_string(text.find(";  ").unwrap() + 1));
+        assert!(!rust.is_in_string(text.find('n').unwrap()));
+
+        let text = "const s = `a ${ x } b  \nc`;\n";
+        let js = syntax(LapceLanguage::Javascript, text);
+        assert!(js.is_in_string(text.find("a ").unwrap() + 1));
+        assert!(!js.is_in_string(text.find("{ ").unwrap() + 1));
+        assert!(!js.is_in_string(text.find('x').unwrap()));
+        assert!(js.is_in_string(text.find("b ").unwrap() + 1));
+        assert!(!js.is_in_string(text.find("const").unwrap()));
+    }
+
     #[test]
     fn test_lens_iter() {
         let lens = Syntax::lens_from_normal_lines(5, 25, 2, &[0, 2, 4]);
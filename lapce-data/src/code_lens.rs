@@ -0,0 +1,288 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::Arc,
+};
+
+use lapce_core::fold::FoldedLines;
+use lapce_rpc::plugin::PluginId;
+use lsp_types::CodeLens;
+use xi_rope::{Rope, RopeDelta, Transformer};
+
+/// The code lenses of a document from the language server, which are
+/// displayed on a virtual line above the line they are for
+#[derive(Clone, Default)]
+pub struct CodeLenses {
+    /// The plugin that provided the code lenses, which resolves them and runs
+    /// their commands
+    pub plugin_id: Option<PluginId>,
+    /// The code lenses with the offset of the start of their line, so they
+    /// follow the text as it's edited
+    lenses: Arc<Vec<(usize, CodeLens)>>,
+    /// The lines with a virtual line above them, which leaves out the lines
+    /// hidden by folds
+    lines: Vec<usize>,
+    /// The code lenses that were sent to be resolved
+    resolving: Rc<RefCell<HashSet<usize>>>,
+}
+
+impl CodeLenses {
+    pub fn new(plugin_id: PluginId, text: &Rope, lenses: Vec<CodeLens>) -> Self {
+        let last_line = text.line_of_offset(text.len());
+        let mut lenses: Vec<(usize, CodeLens)> = lenses
+            .into_iter()
+            .map(|lens| {
+                let line = (lens.range.start.line as usize).min(last_line);
+                (text.offset_of_line(line), lens)
+            })
+            .collect();
+        lenses.sort_by_key(|(offset, lens)| (*offset, lens.range.start.character));
+        Self {
+            plugin_id: Some(plugin_id),
+            lenses: Arc::new(lenses),
+            lines: Vec::new(),
+            resolving: Rc::new(RefCell::new(HashSet::new())),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The lines with a virtual line above them
+    pub fn lines(&self) -> &[usize] {
+        &self.lines
+    }
+
+    pub fn get(&self, index: usize) -> Option<&CodeLens> {
+        self.lenses.get(index).map(|(_, lens)| lens)
+    }
+
+    /// The code lenses of the line with their index
+    pub fn line_lenses(
+        &self,
+        text: &Rope,
+        line: usize,
+    ) -> impl Iterator<Item = (usize, &CodeLens)> {
+        let start = text.offset_of_line(line);
+        let end = text.offset_of_line(line + 1);
+        self.lenses
+            .iter()
+            .enumerate()
+            .filter(move |(_, (offset, _))| {
+                *offset >= start && (*offset < end || start == end)
+            })
+            .map(|(i, (_, lens))| (i, lens))
+    }
+
+    /// The code lenses of the lines that need to be resolved and weren't sent
+    /// to be resolved yet, which are marked as sent
+    pub fn take_unresolved(
+        &self,
+        text: &Rope,
+        lines: &[usize],
+    ) -> Vec<(usize, CodeLens)> {
+        let mut resolving = self.resolving.borrow_mut();
+        let mut unresolved = Vec::new();
+        for line in lines {
+            for (i, lens) in self.line_lenses(text, *line) {
+                if lens.command.is_none() && resolving.insert(i) {
+                    unresolved.push((i, lens.clone()));
+                }
+            }
+        }
+        unresolved
+    }
+
+    /// Gives the code lenses the commands the previous ones at the same range
+    /// were resolved to, so that a new request after an edit doesn't send
+    /// them all to be resolved again. The lenses at a range are matched in
+    /// order, when there are as many of them as before.
+    pub fn keep_resolved(&mut self, previous: &CodeLenses) {
+        if self.plugin_id != previous.plugin_id {
+            return;
+        }
+        let key = |lens: &CodeLens| {
+            let range = lens.range;
+            (
+                range.start.line,
+                range.start.character,
+                range.end.line,
+                range.end.character,
+            )
+        };
+
+        let mut previous_lenses: HashMap<_, Vec<&CodeLens>> = HashMap::new();
+        for (_, lens) in previous.lenses.iter() {
+            previous_lenses.entry(key(lens)).or_default().push(lens);
+        }
+        let mut counts: HashMap<_, usize> = HashMap::new();
+        for (_, lens) in self.lenses.iter() {
+            *counts.entry(key(lens)).or_default() += 1;
+        }
+
+        let mut seen: HashMap<_, usize> = HashMap::new();
+        for (_, lens) in Arc::make_mut(&mut self.lenses).iter_mut() {
+            let key = key(lens);
+            let seen = seen.entry(key).or_default();
+            let i = *seen;
+            *seen += 1;
+            let previous_lenses = match previous_lenses.get(&key) {
+                Some(lenses) if Some(&lenses.len()) == counts.get(&key) => lenses,
+                _ => continue,
+            };
+            if lens.command.is_none() {
+                lens.command = previous_lenses[i].command.clone();
+            }
+        }
+    }
+
+    pub fn resolve(&mut self, index: usize, lens: CodeLens) {
+        if let Some((_, old)) = Arc::make_mut(&mut self.lenses).get_mut(index) {
+            *old = lens;
+        }
+    }
+
+    pub fn apply_delta(&mut self, delta: &RopeDelta) {
+        if self.lenses.is_empty() {
+            return;
+        }
+        let mut transformer = Transformer::new(delta);
+        for (offset, _) in Arc::make_mut(&mut self.lenses).iter_mut() {
+            *offset = transformer.transform(*offset, true);
+        }
+    }
+
+    /// Update the lines with a virtual line, once the text or the folds have
+    /// changed
+    pub fn update_lines(&mut self, text: &Rope, folded_lines: &FoldedLines) {
+        let mut lines: Vec<usize> = self
+            .lenses
+            .iter()
+            .map(|(offset, _)| text.line_of_offset(*offset))
+            .filter(|line| !folded_lines.is_hidden(*line))
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+        self.lines = lines;
+    }
+
+    /// The row the line is displayed at, which is after the virtual lines
+    /// above it
+    pub fn visual_line(&self, folded_lines: &FoldedLines, line: usize) -> usize {
+        let line = folded_lines.visible_line(line);
+        folded_lines.visual_line(line)
+            + self.lines.partition_point(|lens_line| *lens_line <= line)
+    }
+
+    /// The line displayed at the row, and whether the row is the virtual line
+    /// above it
+    pub fn actual_line(
+        &self,
+        folded_lines: &FoldedLines,
+        row: usize,
+    ) -> (usize, bool) {
+        // The virtual line of the i-th line is at its visual line plus i
+        let before = self
+            .lines
+            .iter()
+            .enumerate()
+            .position(|(i, line)| folded_lines.visual_line(*line) + i >= row);
+        let before = before.unwrap_or(self.lines.len());
+        if let Some(line) = self.lines.get(before) {
+            if folded_lines.visual_line(*line) + before == row {
+                return (*line, true);
+            }
+        }
+        (folded_lines.actual_line(row - before), false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lapce_core::fold::{FoldRange, Folds};
+    use lsp_types::{Command, Position, Range};
+
+    use super::*;
+
+    fn lens(line: u32) -> CodeLens {
+        CodeLens {
+            range: Range {
+                start: Position { line, character: 0 },
+                end: Position { line, character: 0 },
+            },
+            command: None,
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_visual_lines() {
+        let text = Rope::from("a\nb\nc\nd\ne\nf\n");
+        let mut folds = Folds::default();
+        folds.fold(&text, FoldRange::new(3, 4));
+        let folded_lines = folds.folded_lines(&text);
+
+        let mut lenses = CodeLenses::new(
+            PluginId(0),
+            &text,
+            vec![lens(1), lens(1), lens(3), lens(4)],
+        );
+        lenses.update_lines(&text, &folded_lines);
+        assert_eq!(lenses.lines(), &[1, 3]);
+
+        // a, [lens], b, c, [lens], d, f
+        assert_eq!(lenses.visual_line(&folded_lines, 0), 0);
+        assert_eq!(lenses.visual_line(&folded_lines, 1), 2);
+        assert_eq!(lenses.visual_line(&folded_lines, 3), 5);
+        assert_eq!(lenses.visual_line(&folded_lines, 5), 6);
+        assert_eq!(lenses.actual_line(&folded_lines, 0), (0, false));
+        assert_eq!(lenses.actual_line(&folded_lines, 1), (1, true));
+        assert_eq!(lenses.actual_line(&folded_lines, 2), (1, false));
+        assert_eq!(lenses.actual_line(&folded_lines, 3), (2, false));
+        assert_eq!(lenses.actual_line(&folded_lines, 4), (3, true));
+        assert_eq!(lenses.actual_line(&folded_lines, 5), (3, false));
+        assert_eq!(lenses.actual_line(&folded_lines, 6), (5, false));
+    }
+
+    #[test]
+    fn test_take_unresolved() {
+        let text = Rope::from("a\nb\nc\n");
+        let mut lenses = CodeLenses::new(PluginId(0), &text, vec![lens(0), lens(2)]);
+        lenses.update_lines(&text, &FoldedLines::default());
+        let unresolved = lenses.take_unresolved(&text, &[0, 1]);
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].0, 0);
+        // It's only sent once
+        assert!(lenses.take_unresolved(&text, &[0, 1]).is_empty());
+    }
+
+    #[test]
+    fn test_keep_resolved() {
+        let text = Rope::from("a\nb\nc\n");
+        let mut previous =
+            CodeLenses::new(PluginId(0), &text, vec![lens(0), lens(0), lens(2)]);
+        for (index, title) in [(0, "run"), (1, "debug"), (2, "references")] {
+            let mut resolved = previous.get(index).unwrap().clone();
+            resolved.command =
+                Some(Command::new(title.to_string(), title.to_string(), None));
+            previous.resolve(index, resolved);
+        }
+
+        // A line was added above the last lens, which moved its range
+        let text = Rope::from("a\nb\nb\nc\n");
+        let mut lenses =
+            CodeLenses::new(PluginId(0), &text, vec![lens(0), lens(0), lens(3)]);
+        lenses.keep_resolved(&previous);
+        let titles: Vec<Option<&str>> = (0..3)
+            .map(|i| {
+                let command = lenses.get(i).unwrap().command.as_ref();
+                command.map(|command| command.title.as_str())
+            })
+            .collect();
+        assert_eq!(titles, vec![Some("run"), Some("debug"), None]);
+        lenses.update_lines(&text, &FoldedLines::default());
+        assert_eq!(lenses.take_unresolved(&text, &[0, 3]).len(), 1);
+    }
+}
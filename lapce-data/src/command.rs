@@ -1,6 +1,6 @@
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use druid::{
     EventCtx, FileInfo, Point, Rect, Selector, SingleUse, Size, WidgetId, WindowId,
 };
@@ -9,17 +9,25 @@
 use lapce_core::command::{
     EditCommand, FocusCommand, MotionModeCommand, MoveCommand, MultiSelectionCommand,
 };
+use lapce_core::fold::FoldRange;
 use lapce_core::syntax::Syntax;
-use lapce_rpc::plugin::{PluginId, VoltInfo, VoltMetadata};
+use lapce_rpc::plugin::{PaletteProvider, PluginId, VoltInfo, VoltMetadata};
 use lapce_rpc::{
-    buffer::BufferId, file::FileNodeItem, source_control::DiffInfo, style::Style,
+    buffer::{BufferId, EditorConfigProperties},
+    file::FileNodeItem,
+    source_control::DiffInfo,
+    style::Style,
     terminal::TermId,
+    RequestId,
 };
 use lsp_types::{
-    CodeActionOrCommand, CodeActionResponse, CompletionItem, CompletionResponse,
-    InlayHint, Location, Position, ProgressParams, PublishDiagnosticsParams,
-    SelectionRange, TextEdit, WorkspaceEdit,
+    CallHierarchyItem, CodeActionOrCommand, CodeActionResponse, CodeLens,
+    CompletionItem, CompletionResponse, DocumentHighlight, InlayHint, Location,
+    MessageActionItem, MessageType, Position, ProgressParams,
+    PublishDiagnosticsParams, SelectionRange, SignatureHelp, TextEdit,
+    WorkspaceEdit,
 };
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::Value;
 use strum::{self, EnumMessage, IntoEnumIterator};
 use strum_macros::{Display, EnumIter, EnumMessage, EnumString, IntoStaticStr};
@@ -29,15 +37,18 @@
 use crate::data::{LapceMainSplitData, LapceTabData, LapceWorkspace};
 use crate::document::BufferContent;
 use crate::editor::{EditorPosition, Line, LineCol};
+use crate::hierarchy::HierarchyKind;
 use crate::menu::MenuKind;
 use crate::rich_text::RichText;
 use crate::selection_range::SelectionRangeDirection;
 use crate::update::ReleaseInfo;
 use crate::{
+    config::{CompositeCommand, LapceConfig},
     data::{EditorTabChild, SplitContent},
     editor::EditorLocation,
     keypress::{KeyMap, KeyPress},
     palette::{PaletteItem, PaletteType},
+    panel::PanelKind,
     proxy::ProxyStatus,
     search::Match,
     split::{SplitDirection, SplitMoveDirection},
@@ -47,6 +58,12 @@
 pub const LAPCE_OPEN_FILE: Selector<FileInfo> = Selector::new("lapce.open-file");
 pub const LAPCE_SAVE_FILE_AS: Selector<FileInfo> =
     Selector::new("lapce.save-file-as");
+pub const LAPCE_IMPORT_KEYBINDINGS: Selector<FileInfo> =
+    Selector::new("lapce.import-keybindings");
+pub const LAPCE_EXPORT_SETTINGS_BUNDLE: Selector<FileInfo> =
+    Selector::new("lapce.export-settings-bundle");
+pub const LAPCE_IMPORT_SETTINGS_BUNDLE: Selector<FileInfo> =
+    Selector::new("lapce.import-settings-bundle");
 pub const LAPCE_COMMAND: Selector<LapceCommand> = Selector::new("lapce.new-command");
 pub const LAPCE_UI_COMMAND: Selector<LapceUICommand> =
     Selector::new("lapce.ui_command");
@@ -65,10 +82,15 @@ pub enum CommandKind {
     Focus(FocusCommand),
     MotionMode(MotionModeCommand),
     MultiSelection(MultiSelectionCommand),
+    /// A command of the `[commands]` settings, which runs a list of commands
+    Composite {
+        name: String,
+        desc: Option<String>,
+    },
 }
 
 impl CommandKind {
-    pub fn desc(&self) -> Option<&'static str> {
+    pub fn desc(&self) -> Option<&str> {
         match &self {
             CommandKind::Workbench(cmd) => cmd.get_message(),
             CommandKind::Edit(cmd) => cmd.get_message(),
@@ -76,10 +98,13 @@ pub fn desc(&self) -> Option<&'static str> {
             CommandKind::Focus(cmd) => cmd.get_message(),
             CommandKind::MotionMode(cmd) => cmd.get_message(),
             CommandKind::MultiSelection(cmd) => cmd.get_message(),
+            CommandKind::Composite { name, desc } => {
+                Some(desc.as_deref().unwrap_or(name))
+            }
         }
     }
 
-    pub fn str(&self) -> &'static str {
+    pub fn str(&self) -> &str {
         match &self {
             CommandKind::Workbench(cmd) => cmd.into(),
             CommandKind::Edit(cmd) => cmd.into(),
@@ -87,6 +112,7 @@ pub fn str(&self) -> &'static str {
             CommandKind::Focus(cmd) => cmd.into(),
             CommandKind::MotionMode(cmd) => cmd.into(),
             CommandKind::MultiSelection(cmd) => cmd.into(),
+            CommandKind::Composite { name, .. } => name,
         }
     }
 }
@@ -99,6 +125,8 @@ pub fn is_palette_command(&self) -> bool {
             match cmd {
                 LapceWorkbenchCommand::Palette
                 | LapceWorkbenchCommand::PaletteLine
+                | LapceWorkbenchCommand::PaletteGoToLine
+                | LapceWorkbenchCommand::PaletteSwitch
                 | LapceWorkbenchCommand::PaletteSymbol
                 | LapceWorkbenchCommand::PaletteCommand
                 | LapceWorkbenchCommand::ChangeFileLanguage
@@ -106,12 +134,138 @@ pub fn is_palette_command(&self) -> bool {
                 | LapceWorkbenchCommand::ConnectSshHost
                 | LapceWorkbenchCommand::ConnectWsl
                 | LapceWorkbenchCommand::PaletteWorkspace => return true,
+                // These only ask for their input in the palette when they
+                // weren't given it up front
+                LapceWorkbenchCommand::CreateBranch
+                | LapceWorkbenchCommand::DeleteBranch
+                | LapceWorkbenchCommand::RenameBranch
+                | LapceWorkbenchCommand::SourceControlStash
+                | LapceWorkbenchCommand::SourceControlStashIncludeUntracked
+                | LapceWorkbenchCommand::SourceControlStashApply
+                | LapceWorkbenchCommand::SourceControlStashPop
+                | LapceWorkbenchCommand::SourceControlStashDrop => {
+                    return self.data.is_none()
+                }
                 _ => {}
             }
         }
 
         false
     }
+
+    /// Parses the `data` of the command into the arguments it takes, which
+    /// fails when the command doesn't take any or they're malformed
+    pub fn args(&self) -> Result<Option<CommandArgs>> {
+        let data = match &self.data {
+            Some(data) => data.clone(),
+            None => return Ok(None),
+        };
+        let args = match &self.kind {
+            CommandKind::Workbench(cmd) => match cmd {
+                LapceWorkbenchCommand::ToggleMaximizedPanel
+                | LapceWorkbenchCommand::TogglePanelVisual
+                | LapceWorkbenchCommand::TogglePanelFocus
+                | LapceWorkbenchCommand::ShowPanel
+                | LapceWorkbenchCommand::HidePanel => {
+                    CommandArgs::Panel(single_arg(data, "panel")?)
+                }
+                LapceWorkbenchCommand::OpenFile
+                | LapceWorkbenchCommand::OpenFolder => {
+                    CommandArgs::Path(single_arg(data, "path")?)
+                }
+                LapceWorkbenchCommand::CheckoutBranch
+                | LapceWorkbenchCommand::RenameBranch => {
+                    CommandArgs::Text(single_arg(data, "branch")?)
+                }
+                LapceWorkbenchCommand::CreateBranch => {
+                    let args = branch_args(data)?;
+                    if args.force {
+                        return Err(anyhow!("unknown field `force`"));
+                    }
+                    CommandArgs::Text(match args.from {
+                        Some(from) => format!("{} {from}", args.branch),
+                        None => args.branch,
+                    })
+                }
+                LapceWorkbenchCommand::DeleteBranch => {
+                    let args = branch_args(data)?;
+                    if args.from.is_some() {
+                        return Err(anyhow!("unknown field `from`"));
+                    }
+                    CommandArgs::Branch {
+                        branch: args.branch,
+                        force: args.force,
+                    }
+                }
+                LapceWorkbenchCommand::SourceControlStash
+                | LapceWorkbenchCommand::SourceControlStashIncludeUntracked => {
+                    CommandArgs::Text(single_arg(data, "message")?)
+                }
+                LapceWorkbenchCommand::SourceControlStashApply
+                | LapceWorkbenchCommand::SourceControlStashPop
+                | LapceWorkbenchCommand::SourceControlStashDrop => {
+                    CommandArgs::Index(single_arg(data, "index")?)
+                }
+                _ => return Err(anyhow!("{} takes no arguments", cmd)),
+            },
+            CommandKind::Focus(FocusCommand::InsertSnippet) => {
+                CommandArgs::Text(single_arg(data, "snippet")?)
+            }
+            kind => return Err(anyhow!("{} takes no arguments", kind.str())),
+        };
+        Ok(Some(args))
+    }
+}
+
+/// The arguments of a command, given by the `args` of a keymap, the steps of
+/// a composite command, or the UI that runs it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommandArgs {
+    Panel(PanelKind),
+    Path(PathBuf),
+    Text(String),
+    Index(usize),
+    Branch { branch: String, force: bool },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BranchArgs {
+    branch: String,
+    #[serde(default)]
+    force: bool,
+    from: Option<String>,
+}
+
+/// Reads the arguments of a branch command, where a string is the name of
+/// the branch
+fn branch_args(data: Value) -> Result<BranchArgs> {
+    match data {
+        Value::String(branch) => Ok(BranchArgs {
+            branch,
+            force: false,
+            from: None,
+        }),
+        data => Ok(serde_json::from_value(data)?),
+    }
+}
+
+/// Reads an argument that is given either on its own or as the only field
+/// of a table, such as `"src/main.rs"` or `{ path = "src/main.rs" }`
+fn single_arg<T: DeserializeOwned>(data: Value, field: &str) -> Result<T> {
+    let value = match data {
+        Value::Object(mut table) => {
+            let value = table
+                .remove(field)
+                .ok_or_else(|| anyhow!("missing field `{field}`"))?;
+            if let Some(key) = table.keys().next() {
+                return Err(anyhow!("unknown field `{key}`"));
+            }
+            value
+        }
+        value => value,
+    };
+    Ok(serde_json::from_value(value)?)
 }
 
 #[derive(PartialEq, Eq)]
@@ -174,6 +328,65 @@ pub fn lapce_internal_commands() -> IndexMap<String, LapceCommand> {
     commands
 }
 
+/// The built-in commands, and the composite commands of the settings that
+/// don't have the name of a built-in one
+pub fn lapce_commands(config: &LapceConfig) -> IndexMap<String, LapceCommand> {
+    let mut commands = lapce_internal_commands();
+    for (name, composite) in config.commands.iter() {
+        if commands.contains_key(name) {
+            log::warn!("Composite command {name} has the name of a built-in one");
+            continue;
+        }
+        commands.insert(
+            name.clone(),
+            LapceCommand {
+                kind: CommandKind::Composite {
+                    name: name.clone(),
+                    desc: composite.description.clone(),
+                },
+                data: None,
+            },
+        );
+    }
+    commands
+}
+
+/// The commands that the steps of a composite command run, leaving out the
+/// steps that are unknown or that are composite commands themselves
+pub fn composite_steps(
+    commands: &IndexMap<String, LapceCommand>,
+    name: &str,
+    composite: &CompositeCommand,
+) -> Vec<LapceCommand> {
+    composite
+        .commands
+        .iter()
+        .filter_map(|step| match commands.get(step.command()) {
+            Some(LapceCommand {
+                kind: CommandKind::Composite { .. },
+                ..
+            }) => {
+                log::warn!(
+                    "Composite command {name} can't run another one, {}",
+                    step.command()
+                );
+                None
+            }
+            Some(command) => Some(LapceCommand {
+                kind: command.kind.clone(),
+                data: step.args().cloned(),
+            }),
+            None => {
+                log::warn!(
+                    "Composite command {name} has an unknown command {}",
+                    step.command()
+                );
+                None
+            }
+        })
+        .collect()
+}
+
 #[derive(
     Display,
     EnumString,
@@ -234,10 +447,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open Keyboard Shortcuts File")]
     OpenKeyboardShortcutsFile,
 
+    #[strum(serialize = "import_keybindings")]
+    #[strum(message = "Import VS Code or Vim Keybindings")]
+    ImportKeybindings,
+
     #[strum(serialize = "open_log_file")]
     #[strum(message = "Open Log File")]
     OpenLogFile,
 
+    #[strum(serialize = "open_lsp_logs")]
+    #[strum(message = "Open Language Server Logs")]
+    OpenLspLogs,
+
     #[strum(serialize = "open_logs_directory")]
     #[strum(message = "Open Logs Directory")]
     OpenLogsDirectory,
@@ -301,6 +522,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.line")]
     PaletteLine,
 
+    #[strum(message = "Go to Line/Column")]
+    #[strum(serialize = "palette.go_to_line")]
+    PaletteGoToLine,
+
     #[strum(serialize = "palette")]
     #[strum(message = "Go to File")]
     Palette,
@@ -319,9 +544,69 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.workspace")]
     PaletteWorkspace,
 
+    #[strum(message = "Switch to Editor, File or Workspace")]
+    #[strum(serialize = "palette.switch")]
+    PaletteSwitch,
+
     #[strum(serialize = "source_control.checkout_branch")]
     CheckoutBranch,
 
+    #[strum(message = "Source Control: Create Branch")]
+    #[strum(serialize = "source_control.create_branch")]
+    CreateBranch,
+
+    #[strum(message = "Source Control: Delete Branch")]
+    #[strum(serialize = "source_control.delete_branch")]
+    DeleteBranch,
+
+    #[strum(message = "Source Control: Rename Branch")]
+    #[strum(serialize = "source_control.rename_branch")]
+    RenameBranch,
+
+    #[strum(message = "Source Control: Fetch")]
+    #[strum(serialize = "source_control.fetch")]
+    SourceControlFetch,
+
+    #[strum(message = "Source Control: Pull")]
+    #[strum(serialize = "source_control.pull")]
+    SourceControlPull,
+
+    #[strum(message = "Source Control: Pull (Rebase)")]
+    #[strum(serialize = "source_control.pull_rebase")]
+    SourceControlPullRebase,
+
+    #[strum(message = "Source Control: Push")]
+    #[strum(serialize = "source_control.push")]
+    SourceControlPush,
+
+    #[strum(message = "Source Control: Stash Changes")]
+    #[strum(serialize = "source_control.stash")]
+    SourceControlStash,
+
+    #[strum(message = "Source Control: Stash Changes (Include Untracked)")]
+    #[strum(serialize = "source_control.stash_include_untracked")]
+    SourceControlStashIncludeUntracked,
+
+    #[strum(message = "Source Control: Apply Stash")]
+    #[strum(serialize = "source_control.stash_apply")]
+    SourceControlStashApply,
+
+    #[strum(message = "Source Control: Pop Stash")]
+    #[strum(serialize = "source_control.stash_pop")]
+    SourceControlStashPop,
+
+    #[strum(message = "Source Control: Drop Stash")]
+    #[strum(serialize = "source_control.stash_drop")]
+    SourceControlStashDrop,
+
+    #[strum(message = "Source Control: Toggle Amend Last Commit")]
+    #[strum(serialize = "source_control.toggle_amend")]
+    SourceControlToggleAmend,
+
+    #[strum(message = "Source Control: Toggle Sign-off")]
+    #[strum(serialize = "source_control.toggle_sign_off")]
+    SourceControlToggleSignOff,
+
     #[strum(serialize = "toggle_maximized_panel")]
     ToggleMaximizedPanel,
 
@@ -372,6 +657,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_focus")]
     ToggleSearchFocus,
 
+    #[strum(message = "Toggle Call Hierarchy Focus")]
+    #[strum(serialize = "toggle_call_hierarchy_focus")]
+    ToggleCallHierarchyFocus,
+
+    #[strum(message = "Toggle Type Hierarchy Focus")]
+    #[strum(serialize = "toggle_type_hierarchy_focus")]
+    ToggleTypeHierarchyFocus,
+
     // Visual toggle commands
     #[strum(serialize = "toggle_terminal_visual")]
     ToggleTerminalVisual,
@@ -391,6 +684,12 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_visual")]
     ToggleSearchVisual,
 
+    #[strum(serialize = "toggle_call_hierarchy_visual")]
+    ToggleCallHierarchyVisual,
+
+    #[strum(serialize = "toggle_type_hierarchy_visual")]
+    ToggleTypeHierarchyVisual,
+
     #[strum(serialize = "focus_editor")]
     FocusEditor,
 
@@ -416,6 +715,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Export current settings to a theme file")]
     ExportCurrentThemeSettings,
 
+    #[strum(serialize = "export_settings_bundle")]
+    #[strum(message = "Export Settings Bundle")]
+    ExportSettingsBundle,
+
+    #[strum(serialize = "import_settings_bundle")]
+    #[strum(message = "Import Settings Bundle")]
+    ImportSettingsBundle,
+
+    #[strum(serialize = "apply_settings_bundle")]
+    #[strum(message = "Apply Imported Settings Bundle")]
+    ApplySettingsBundle,
+
     #[strum(serialize = "install_theme")]
     #[strum(message = "Install current theme file")]
     InstallTheme,
@@ -477,6 +788,8 @@ pub enum LapceUICommand {
     InitBufferContentLineCol(InitBufferContent<LineCol>),
     /// UTF16 LSP positions
     InitBufferContentLsp(InitBufferContent<Position>),
+    /// The properties the `.editorconfig` files set for an opened file
+    UpdateEditorConfig(PathBuf, EditorConfigProperties),
     OpenFileChanged {
         path: PathBuf,
         content: Rope,
@@ -499,6 +812,20 @@ pub enum LapceUICommand {
     },
     ShowAbout,
     ShowAlert(AlertContentData),
+    /// Shows a message in the corner of the window, whose picked action is
+    /// sent back for `request_id`
+    ShowNotification {
+        title: String,
+        message: String,
+        typ: MessageType,
+        actions: Vec<MessageActionItem>,
+        request_id: Option<RequestId>,
+    },
+    /// Closes the notification, after picking the action at the index
+    CloseNotification {
+        id: u64,
+        action: Option<usize>,
+    },
     ShowMenu(Point, Arc<Vec<MenuKind>>),
     ShowWindow,
     ShowGitBranches {
@@ -507,6 +834,12 @@ pub enum LapceUICommand {
     },
     UpdateSearchInput(String),
     UpdateSearch(String),
+    /// Replace the content of the commit message input, which is for amending
+    /// the last commit if `amend` is set
+    UpdateCommitMessage {
+        message: String,
+        amend: bool,
+    },
     GlobalSearchResult(String, Arc<HashMap<PathBuf, Vec<Match>>>),
     CancelFilePicker,
     SetWorkspace(LapceWorkspace),
@@ -525,16 +858,53 @@ pub enum LapceUICommand {
     ResolveCompletion(BufferId, u64, usize, Box<CompletionItem>),
     UpdateCompletion(usize, String, CompletionResponse, PluginId),
     UpdateHover(usize, Arc<Vec<RichText>>),
+    UpdateSignature {
+        request_id: usize,
+        offset: usize,
+        signature: Option<SignatureHelp>,
+    },
     UpdateVoltReadme(RichText),
     UpdateInlayHints {
         path: PathBuf,
         rev: u64,
         hints: Spans<InlayHint>,
     },
-    UpdateCodeActions(PathBuf, u64, usize, CodeActionResponse),
+    UpdateFoldingRanges {
+        path: PathBuf,
+        rev: u64,
+        ranges: Vec<FoldRange>,
+    },
+    /// Fold or unfold the region starting at the line, from the gutter
+    ToggleFold(usize),
+    UpdateCodeLens {
+        path: PathBuf,
+        rev: u64,
+        plugin_id: PluginId,
+        lenses: Vec<CodeLens>,
+    },
+    CodeLensResolved {
+        path: PathBuf,
+        rev: u64,
+        index: usize,
+        lens: CodeLens,
+    },
+    /// Request the code lenses of the open files again
+    RefreshCodeLens,
+    UpdateDocumentHighlights {
+        path: PathBuf,
+        rev: u64,
+        offset: usize,
+        highlights: Vec<DocumentHighlight>,
+    },
+    UpdateCodeActions(PathBuf, u64, usize, PluginId, CodeActionResponse),
     CancelPalette,
-    RunCodeAction(CodeActionOrCommand),
+    RunCodeAction(CodeActionOrCommand, PluginId),
     ApplyWorkspaceEdit(WorkspaceEdit),
+    /// An edit a server asked to apply, which it waits to hear back about
+    ApplyWorkspaceEditRequest {
+        edit: WorkspaceEdit,
+        request_id: RequestId,
+    },
     ShowCodeActions(Option<Point>),
     Hide,
     ResignFocus,
@@ -552,6 +922,7 @@ pub enum LapceUICommand {
     UpdatePaletteInput(String),
     UpdatePaletteItems(String, im::Vector<PaletteItem>),
     FilterPaletteItems(String, String, im::Vector<PaletteItem>),
+    RegisterPaletteProvider(String, PluginId, PaletteProvider),
     UpdateKeymapsFilter(String),
     ResetSettingsFile(String, String),
     UpdateSettingsFile(String, String, Value),
@@ -596,6 +967,17 @@ pub enum LapceUICommand {
     DocumentFormat(PathBuf, u64, Result<Vec<TextEdit>>),
     DocumentFormatAndSave(PathBuf, u64, Result<Vec<TextEdit>>, Option<WidgetId>),
     DocumentSave(PathBuf, Option<WidgetId>),
+    /// The code actions to run before the document is formatted and saved
+    CodeActionsOnSave {
+        rev: u64,
+        result: Result<Vec<(PluginId, CodeActionOrCommand)>>,
+        exit: bool,
+    },
+    /// Format and save the document, once the commands of its code actions
+    /// on save have run
+    FormatAndSave {
+        exit: bool,
+    },
     BufferSave(PathBuf, u64, Option<WidgetId>),
     UpdateSemanticStyles(BufferId, PathBuf, u64, Arc<Spans<Style>>),
     UpdateTerminalTitle(TermId, String),
@@ -655,11 +1037,29 @@ pub enum LapceUICommand {
     EditorTabSwap(usize, usize),
     JumpToPosition(Option<WidgetId>, Position, bool),
     JumpToLine(Option<WidgetId>, usize),
+    JumpToLineCol(Option<WidgetId>, LineCol),
     JumpToLocation(Option<WidgetId>, EditorLocation, bool),
     JumpToLspLocation(Option<WidgetId>, EditorLocation<Position>, bool),
     JumpToLineLocation(Option<WidgetId>, EditorLocation<Line>),
     JumpToLineColLocation(Option<WidgetId>, EditorLocation<LineCol>, bool),
     ToggleProblem(PathBuf),
+    /// Show the prepared items of a call or type hierarchy in its panel
+    UpdateHierarchy {
+        kind: HierarchyKind,
+        plugin_id: PluginId,
+        items: Vec<CallHierarchyItem>,
+    },
+    UpdateHierarchyChildren {
+        kind: HierarchyKind,
+        rev: u64,
+        path: Vec<usize>,
+        items: Vec<CallHierarchyItem>,
+    },
+    ToggleHierarchyItem {
+        kind: HierarchyKind,
+        path: Vec<usize>,
+    },
+    ToggleHierarchyDirection(HierarchyKind),
     TerminalJumpToLine(i32),
     GoToLocation(Option<WidgetId>, EditorLocation, bool),
     GotoDefinition {
@@ -793,3 +1193,132 @@ pub fn execute(&self, ctx: &mut EventCtx, data: &mut LapceTabData) {
         ctx.set_handled();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn args(command: &str, data: Value) -> Result<Option<CommandArgs>> {
+        LapceCommand {
+            kind: lapce_internal_commands().remove(command).unwrap().kind,
+            data: Some(data),
+        }
+        .args()
+    }
+
+    #[test]
+    fn test_command_args() {
+        assert_eq!(
+            args("open_file", json!("src/main.rs")).unwrap(),
+            Some(CommandArgs::Path(PathBuf::from("src/main.rs")))
+        );
+        assert_eq!(
+            args("open_file", json!({ "path": "src/main.rs" })).unwrap(),
+            Some(CommandArgs::Path(PathBuf::from("src/main.rs")))
+        );
+        assert_eq!(
+            args("show_panel", json!("Terminal")).unwrap(),
+            Some(CommandArgs::Panel(PanelKind::Terminal))
+        );
+        assert_eq!(
+            args("source_control.stash_pop", json!(1)).unwrap(),
+            Some(CommandArgs::Index(1))
+        );
+        assert_eq!(
+            args(
+                "source_control.delete_branch",
+                json!({ "branch": "old", "force": true })
+            )
+            .unwrap(),
+            Some(CommandArgs::Branch {
+                branch: "old".to_string(),
+                force: true,
+            })
+        );
+        assert_eq!(
+            args(
+                "source_control.create_branch",
+                json!({ "branch": "new", "from": "main" })
+            )
+            .unwrap(),
+            Some(CommandArgs::Text("new main".to_string()))
+        );
+
+        assert!(args("open_file", json!({ "file": "src/main.rs" })).is_err());
+        assert!(args("open_file", json!({ "path": "a", "line": 1 })).is_err());
+        assert!(
+            args("source_control.delete_branch", json!({ "name": "old" })).is_err()
+        );
+        assert!(args("source_control.stash_pop", json!("last")).is_err());
+        assert!(args("save", json!("now")).is_err());
+        assert!(args("insert_snippet", json!({ "snippet": "$1" })).is_ok());
+    }
+
+    fn composite(commands: &str) -> CompositeCommand {
+        toml_edit::easy::from_str(&format!("commands = {commands}")).unwrap()
+    }
+
+    #[test]
+    fn test_composite_command_steps() {
+        let steps = composite(
+            r#"["save", { command = "open_file", args = { path = "a.rs" } },
+                { command = "save_all" }]"#,
+        )
+        .commands;
+        assert_eq!((steps[0].command(), steps[0].args()), ("save", None));
+        assert_eq!(
+            (steps[1].command(), steps[1].args()),
+            ("open_file", Some(&json!({ "path": "a.rs" })))
+        );
+        assert_eq!((steps[2].command(), steps[2].args()), ("save_all", None));
+    }
+
+    #[test]
+    fn test_composite_commands() {
+        let mut config = LapceConfig::default();
+        config
+            .commands
+            .insert("save_all".to_string(), composite(r#"["save"]"#));
+        config.commands.insert(
+            "save_and_focus_terminal".to_string(),
+            composite(r#"["save_all", "toggle_terminal_focus"]"#),
+        );
+        let commands = lapce_commands(&config);
+        // A built-in command keeps its name
+        assert_eq!(
+            commands.get("save_all").unwrap().kind,
+            lapce_internal_commands().get("save_all").unwrap().kind
+        );
+        assert_eq!(
+            commands.get("save_and_focus_terminal").unwrap().kind,
+            CommandKind::Composite {
+                name: "save_and_focus_terminal".to_string(),
+                desc: None,
+            }
+        );
+
+        let steps = composite_steps(
+            &commands,
+            "steps",
+            &composite(
+                r#"["save_all", "no_such_command", "save_and_focus_terminal",
+                    { command = "open_file", args = "src/main.rs" }]"#,
+            ),
+        );
+        assert_eq!(
+            steps,
+            vec![
+                LapceCommand {
+                    kind: commands.get("save_all").unwrap().kind.clone(),
+                    data: None,
+                },
+                LapceCommand {
+                    kind: commands.get("open_file").unwrap().kind.clone(),
+                    data: Some(json!("src/main.rs")),
+                },
+            ]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     io::Write,
     path::{Path, PathBuf},
     sync::Arc,
@@ -9,6 +10,7 @@
     Color, ExtEventSink, FontFamily, Size, Target,
 };
 use indexmap::IndexMap;
+use lapce_core::language::LapceLanguage;
 use lapce_proxy::{directory::Directory, plugin::wasi::find_all_volts};
 use once_cell::sync::Lazy;
 use parking_lot::{Mutex, RwLock};
@@ -53,6 +55,8 @@ impl LapceTheme {
     pub const EDITOR_CURRENT_LINE: &str = "editor.current_line";
     pub const EDITOR_LINK: &str = "editor.link";
     pub const EDITOR_VISIBLE_WHITESPACE: &str = "editor.visible_whitespace";
+    pub const EDITOR_HIGHLIGHT_READ: &str = "editor.highlight.read";
+    pub const EDITOR_HIGHLIGHT_WRITE: &str = "editor.highlight.write";
 
     pub const INLAY_HINT_FOREGROUND: &str = "inlay_hint.foreground";
     pub const INLAY_HINT_BACKGROUND: &str = "inlay_hint.background";
@@ -154,6 +158,19 @@ pub struct CoreConfig {
     pub custom_titlebar: bool,
 }
 
+/// What to do with the final newline of a document on save
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FinalNewline {
+    /// Leave it as it is
+    #[default]
+    Keep,
+    /// Add one if there isn't one
+    Ensure,
+    /// Remove it if there is one
+    Trim,
+}
+
 #[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct EditorConfig {
@@ -191,6 +208,10 @@ pub struct EditorConfig {
         desc = "How long (in ms) it should take before the hover information appears"
     )]
     pub hover_delay: u64,
+    #[field_names(
+        desc = "How long (in ms) the cursor should stay before the other occurrences of the symbol under it are highlighted"
+    )]
+    pub document_highlight_delay: u64,
     #[field_names(
         desc = "If modal mode should have relative line numbers (though, not in insert mode)"
     )]
@@ -199,6 +220,26 @@ pub struct EditorConfig {
         desc = "Whether it should format the document on save (if there is an available formatter)"
     )]
     pub format_on_save: bool,
+    #[field_names(
+        desc = "Whether formatting on save only formats the lines changed since the last commit (if the formatter supports ranges)"
+    )]
+    pub format_on_save_modified_lines_only: bool,
+    #[field_names(
+        desc = "Whether it should format the code as you type, on the characters the formatter asks for"
+    )]
+    pub format_on_type: bool,
+    #[field_names(
+        desc = "Whether it should trim the trailing whitespace on save, except inside string literals"
+    )]
+    pub trim_trailing_whitespace_on_save: bool,
+    #[field_names(
+        desc = "What to do with the final newline on save: \"keep\" it as it is, \"ensure\" there is one, or \"trim\" it"
+    )]
+    pub final_newline_on_save: FinalNewline,
+    #[field_names(
+        desc = "The kind of code action to run on save, such as \"source.organizeImports\" or \"source.fixAll\". If empty, none is run."
+    )]
+    pub code_action_kind_on_save: String,
     #[field_names(desc = "If inlay hints should be displayed")]
     pub enable_inlay_hints: bool,
     #[field_names(
@@ -293,6 +334,40 @@ pub fn error_lens_font_family(&self) -> FontFamily {
         }
     }
 
+    /// These settings with the ones of an `[editor.lang.<language>]` table on
+    /// top, along with the errors of the settings that were left out
+    pub fn with_overrides(
+        &self,
+        overrides: &serde_json::Value,
+    ) -> (Self, Vec<String>) {
+        let mut editor = self.clone();
+        let overrides = match overrides.as_object() {
+            Some(overrides) => overrides,
+            None => return (editor, vec!["not a table".to_string()]),
+        };
+        let mut errors = Vec::new();
+        for (key, value) in overrides {
+            match editor.with_setting(key, value) {
+                Ok(new) => editor = new,
+                Err(err) => errors.push(format!("{key}: {err}")),
+            }
+        }
+        (editor, errors)
+    }
+
+    fn with_setting(
+        &self,
+        key: &str,
+        value: &serde_json::Value,
+    ) -> Result<Self, String> {
+        let mut editor = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        match editor.get_mut(key) {
+            Some(setting) => *setting = value.clone(),
+            None => return Err("unknown setting".to_string()),
+        }
+        serde_json::from_value(editor).map_err(|e| e.to_string())
+    }
+
     pub fn error_lens_font_size(&self) -> usize {
         if self.error_lens_font_size == 0 {
             self.inlay_hint_font_size()
@@ -586,6 +661,44 @@ fn get(&self, name: &str) -> Option<&Color> {
     }
 }
 
+/// A command of a `[commands.<name>]` table, which runs its commands one
+/// after the other
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompositeCommand {
+    /// The name in the command palette, instead of the command name
+    #[serde(default)]
+    pub description: Option<String>,
+    pub commands: Vec<CompositeCommandStep>,
+}
+
+/// A command name, or a table with the name and the arguments of the command
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum CompositeCommandStep {
+    Command(String),
+    WithArgs {
+        command: String,
+        #[serde(default)]
+        args: Option<serde_json::Value>,
+    },
+}
+
+impl CompositeCommandStep {
+    pub fn command(&self) -> &str {
+        match self {
+            CompositeCommandStep::Command(command) => command,
+            CompositeCommandStep::WithArgs { command, .. } => command,
+        }
+    }
+
+    pub fn args(&self) -> Option<&serde_json::Value> {
+        match self {
+            CompositeCommandStep::Command(_) => None,
+            CompositeCommandStep::WithArgs { args, .. } => args.as_ref(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct LapceConfig {
     #[serde(skip)]
@@ -595,8 +708,15 @@ pub struct LapceConfig {
     pub editor: EditorConfig,
     pub terminal: TerminalConfig,
     pub theme: ThemeConfig,
+    /// The composite commands, by their command name
+    #[serde(default)]
+    pub commands: BTreeMap<String, CompositeCommand>,
     #[serde(flatten)]
     pub plugins: HashMap<String, serde_json::Value>,
+    /// The editor settings of the languages with an `[editor.lang.<language>]`
+    /// table
+    #[serde(skip)]
+    pub editor_languages: HashMap<LapceLanguage, EditorConfig>,
     #[serde(skip)]
     pub default_theme: ThemeConfig,
     #[serde(skip)]
@@ -651,15 +771,45 @@ fn handle_event(&mut self, event: notify::Result<notify::Event>) {
 impl LapceConfig {
     pub fn load(workspace: &LapceWorkspace, disabled_volts: &[String]) -> Self {
         let config = Self::merge_config(workspace, None);
-        let mut lapce_config: LapceConfig = config
-            .try_deserialize()
-            .unwrap_or_else(|_| DEFAULT_LAPCE_CONFIG.clone());
+        let mut lapce_config: LapceConfig =
+            config.try_deserialize().unwrap_or_else(|err| {
+                log::error!("Failed to load the settings: {err}");
+                DEFAULT_LAPCE_CONFIG.clone()
+            });
         let available_themes = Self::load_themes(disabled_volts);
         lapce_config.available_themes = available_themes;
         lapce_config.resolve_theme(workspace);
         lapce_config
     }
 
+    /// Puts the `[editor.lang.<language>]` tables of the settings on top of
+    /// the editor settings. Tables of unknown languages and the settings
+    /// that don't fit are left out.
+    fn resolve_editor_languages(&mut self, config: &config::Config) {
+        let languages: HashMap<String, serde_json::Value> =
+            config.get("editor.lang").unwrap_or_default();
+        self.editor_languages = languages
+            .iter()
+            .filter_map(|(name, overrides)| {
+                let language = LapceLanguage::from_name(name)?;
+                let (editor, errors) = self.editor.with_overrides(overrides);
+                for error in errors {
+                    log::error!(
+                        "Ignored a setting of [editor.lang.{name}]: {error}"
+                    );
+                }
+                Some((language, editor))
+            })
+            .collect();
+    }
+
+    /// The editor settings of a document in the language
+    pub fn editor_config(&self, language: Option<LapceLanguage>) -> &EditorConfig {
+        language
+            .and_then(|language| self.editor_languages.get(&language))
+            .unwrap_or(&self.editor)
+    }
+
     fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
         let mut default_lapce_config = DEFAULT_LAPCE_CONFIG.clone();
         if let Some((_, theme_config)) = self
@@ -689,6 +839,7 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
         }
         self.resolve_colors(Some(&default_lapce_config));
         self.default_theme = default_lapce_config.theme.clone();
+        self.resolve_editor_languages(&Self::merge_config(workspace, None));
         self.update_id();
     }
 
@@ -1192,16 +1343,18 @@ pub fn recent_workspaces_file() -> Option<PathBuf> {
         Some(path)
     }
 
+    /// The width of a tab that is `tab_width` spaces wide
     pub fn tab_width(
         &self,
         text: &mut PietText,
         font_family: FontFamily,
         font_size: usize,
+        tab_width: usize,
     ) -> f64 {
         {
             let info = self.tab_layout_info.read();
             if let Some(width) = info.get(&(font_family.clone(), font_size)) {
-                return self.editor.tab_width as f64 * *width;
+                return tab_width as f64 * *width;
             };
         }
 
@@ -1217,6 +1370,36 @@ pub fn tab_width(
         self.tab_layout_info
             .write()
             .insert((font_family, font_size), width);
-        self.editor.tab_width as f64 * width
+        tab_width as f64 * width
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_overrides() {
+        let editor = EditorConfig {
+            tab_width: 4,
+            font_size: 13,
+            ..Default::default()
+        };
+        let (rust, errors) = editor.with_overrides(&serde_json::json!({
+            "tab-width": 2,
+            "font-size": "big",
+            "no-such-setting": true,
+        }));
+        assert_eq!(rust.tab_width, 2);
+        assert_eq!(rust.font_size, 13);
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|error| error.starts_with("font-size:")));
+        assert!(errors
+            .iter()
+            .any(|error| error.starts_with("no-such-setting:")));
+
+        let (same, errors) = editor.with_overrides(&serde_json::json!(2));
+        assert_eq!(same.tab_width, 4);
+        assert_eq!(errors.len(), 1);
     }
 }
@@ -5,7 +5,10 @@
     io::{BufReader, Read, Write},
     path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
+    sync::{
+        atomic::{self, AtomicUsize},
+        Arc,
+    },
     thread,
     time::Instant,
 };
@@ -33,27 +36,32 @@
 use lapce_proxy::{directory::Directory, VERSION};
 use lapce_rpc::{
     buffer::BufferId,
-    core::{CoreMessage, CoreNotification},
-    plugin::VoltInfo,
-    proxy::ProxyResponse,
+    core::{CoreMessage, CoreNotification, CoreResponse},
+    plugin::{PluginId, VoltInfo},
+    proxy::{ProxyResponse, ProxyRpcHandler},
     source_control::FileDiff,
     terminal::TermId,
-    RpcMessage,
+    RequestId, RpcMessage,
 };
 
-use lsp_types::{Diagnostic, DiagnosticSeverity, Position, ProgressToken, TextEdit};
+use lsp_types::{
+    CallHierarchyItem, Diagnostic, DiagnosticSeverity, DocumentChangeOperation,
+    DocumentChanges, MessageActionItem, MessageType, Position, ProgressToken,
+    TextEdit, WorkspaceEdit,
+};
 use notify::Watcher;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 use xi_rope::{Rope, RopeDelta};
 
 use crate::{
     about::AboutData,
     alert::{AlertContentData, AlertData},
     command::{
-        CommandKind, EnsureVisiblePosition, InitBufferContentCb, LapceCommand,
-        LapceUICommand, LapceWorkbenchCommand, LAPCE_COMMAND, LAPCE_OPEN_FILE,
-        LAPCE_OPEN_FOLDER, LAPCE_UI_COMMAND,
+        composite_steps, CommandArgs, CommandKind, EnsureVisiblePosition,
+        InitBufferContentCb, LapceCommand, LapceUICommand, LapceWorkbenchCommand,
+        LAPCE_COMMAND, LAPCE_EXPORT_SETTINGS_BUNDLE, LAPCE_IMPORT_KEYBINDINGS,
+        LAPCE_IMPORT_SETTINGS_BUNDLE, LAPCE_OPEN_FILE, LAPCE_OPEN_FOLDER,
+        LAPCE_UI_COMMAND,
     },
     completion::CompletionData,
     config::{ConfigWatcher, GetConfig, LapceConfig, LapceTheme},
@@ -62,23 +70,30 @@
         SplitInfo, TabsInfo, WindowInfo, WorkspaceInfo,
     },
     document::{BufferContent, Document, LocalBufferKind},
-    editor::{EditorLocation, EditorPosition, LapceEditorBufferData, Line, TabRect},
+    editor::{
+        apply_edit, workspace_edits, workspace_operation, EditorLocation,
+        EditorPosition, LapceEditorBufferData, Line, LineCol, TabRect,
+    },
     explorer::FileExplorerData,
     find::Find,
+    hierarchy::{HierarchyData, HierarchyKind},
     hover::HoverData,
     keypress::KeyPressData,
+    notification::NotificationData,
     palette::{PaletteData, PaletteType, PaletteViewData},
     panel::{
         PanelContainerPosition, PanelData, PanelKind, PanelOrder, PanelPosition,
     },
     picker::FilePickerData,
-    plugin::PluginData,
+    plugin::{PluginData, PluginLoadStatus},
     problem::ProblemData,
     proxy::{LapceProxy, ProxyStatus, TermEvent},
     rename::RenameData,
     search::SearchData,
     settings::LapceSettingsPanelData,
-    source_control::SourceControlData,
+    settings_bundle::SettingsBundle,
+    signature::SignatureState,
+    source_control::{GitBranchAction, GitStashAction, SourceControlData},
     split::{SplitDirection, SplitMoveDirection},
     terminal::TerminalSplitData,
     title::TitleData,
@@ -117,6 +132,7 @@ pub fn load(event_sink: ExtEventSink, paths: Vec<PathBuf>) -> Self {
         let keypress = Arc::new(KeyPressData::new(&config, event_sink.clone()));
         let panel_orders = db
             .get_panel_orders()
+            .map(Self::add_missing_panels)
             .unwrap_or_else(|_| Self::default_panel_orders());
         let latest_release = Arc::new(None);
 
@@ -264,12 +280,34 @@ pub fn default_panel_orders() -> PanelOrder {
         );
         order.insert(
             PanelPosition::BottomLeft,
-            im::vector![PanelKind::Terminal, PanelKind::Search, PanelKind::Problem,],
+            im::vector![
+                PanelKind::Terminal,
+                PanelKind::Search,
+                PanelKind::Problem,
+                PanelKind::CallHierarchy,
+                PanelKind::TypeHierarchy,
+            ],
         );
 
         order
     }
 
+    /// Panels which are newer than the saved orders go to where they are by
+    /// default
+    fn add_missing_panels(mut orders: PanelOrder) -> PanelOrder {
+        for (position, kinds) in Self::default_panel_orders() {
+            for kind in kinds {
+                if orders.values().any(|order| order.contains(&kind)) {
+                    continue;
+                }
+                let mut order = orders.get(&position).cloned().unwrap_or_default();
+                order.push_back(kind);
+                orders.insert(position, order);
+            }
+        }
+        orders
+    }
+
     pub fn reload_env(&self, env: &mut Env) {
         env.set(theme::SCROLLBAR_WIDTH, 10.0);
         env.set(theme::SCROLLBAR_EDGE_WIDTH, 0.0);
@@ -589,12 +627,15 @@ pub struct LapceTabData {
     pub main_split: LapceMainSplitData,
     pub completion: Arc<CompletionData>,
     pub hover: Arc<HoverData>,
+    pub signature: Arc<SignatureState>,
     pub rename: Arc<RenameData>,
     pub terminal: Arc<TerminalSplitData>,
     pub palette: Arc<PaletteData>,
     pub find: Arc<Find>,
     pub source_control: Arc<SourceControlData>,
     pub problem: Arc<ProblemData>,
+    pub call_hierarchy: Arc<HierarchyData>,
+    pub type_hierarchy: Arc<HierarchyData>,
     pub search: Arc<SearchData>,
     pub plugin: Arc<PluginData>,
     pub picker: Arc<FilePickerData>,
@@ -606,6 +647,7 @@ pub struct LapceTabData {
     pub settings: Arc<LapceSettingsPanelData>,
     pub about: Arc<AboutData>,
     pub alert: Arc<AlertData>,
+    pub notifications: Arc<NotificationData>,
     #[data(ignore)]
     pub term_tx: Arc<Sender<(TermId, TermEvent)>>,
     #[data(ignore)]
@@ -621,6 +663,10 @@ pub struct LapceTabData {
     pub progresses: Arc<Vec<WorkProgress>>,
     pub drag: Arc<Option<(Vec2, Vec2, DragContent)>>,
     pub latest_release: Arc<Option<ReleaseInfo>>,
+    /// The settings bundle that was imported, which waits to be applied
+    /// until its review has been looked over
+    #[data(ignore)]
+    pub pending_settings_bundle: Option<Arc<SettingsBundle>>,
 }
 
 impl GetConfig for LapceTabData {
@@ -669,6 +715,7 @@ pub fn new(
         let palette = Arc::new(PaletteData::new(config.clone(), proxy.clone()));
         let completion = Arc::new(CompletionData::new(config.clone()));
         let hover = Arc::new(HoverData::new());
+        let signature = Arc::new(SignatureState::new());
         let rename = Arc::new(RenameData::new());
         let source_control = Arc::new(SourceControlData::new());
         let settings = Arc::new(LapceSettingsPanelData::new());
@@ -781,10 +828,13 @@ pub fn new(
             main_split,
             completion,
             hover,
+            signature,
             rename,
             terminal,
             plugin,
             problem,
+            call_hierarchy: Arc::new(HierarchyData::new(HierarchyKind::Call)),
+            type_hierarchy: Arc::new(HierarchyData::new(HierarchyKind::Type)),
             search,
             find: Arc::new(Find::new(0)),
             picker: file_picker,
@@ -797,6 +847,7 @@ pub fn new(
             settings,
             about,
             alert,
+            notifications: Arc::new(NotificationData::new()),
             proxy_status: Arc::new(ProxyStatus::Connecting),
             keypress,
             window_origin: Rc::new(RefCell::new(Point::ZERO)),
@@ -807,6 +858,7 @@ pub fn new(
             progresses: Arc::new(Vec::new()),
             drag: Arc::new(None),
             latest_release,
+            pending_settings_bundle: None,
         };
         tab.start_update_process(event_sink);
         tab
@@ -875,6 +927,7 @@ pub fn editor_view_content(
             main_split: self.main_split.clone(),
             completion: self.completion.clone(),
             hover: self.hover.clone(),
+            signature: self.signature.clone(),
             rename: self.rename.clone(),
             focus_area: self.focus_area.clone(),
             source_control: self.source_control.clone(),
@@ -923,6 +976,7 @@ pub fn update_from_editor_buffer_data(
     ) {
         self.completion = editor_buffer_data.completion.clone();
         self.hover = editor_buffer_data.hover.clone();
+        self.signature = editor_buffer_data.signature.clone();
         self.rename = editor_buffer_data.rename.clone();
         self.main_split = editor_buffer_data.main_split.clone();
         self.find = editor_buffer_data.find.clone();
@@ -1060,6 +1114,48 @@ pub fn rename_origin(
         }
     }
 
+    pub fn signature_origin(
+        &self,
+        text: &mut PietText,
+        tab_size: Size,
+        signature_size: Size,
+        config: &LapceConfig,
+    ) -> Point {
+        let editor =
+            match self.main_split.editors.get(&self.signature.editor_view_id) {
+                Some(editor) => editor,
+                None => return Point::ZERO,
+            };
+        let offset = match self.signature.offset {
+            Some(offset) => offset,
+            None => return Point::ZERO,
+        };
+
+        let doc = self.main_split.editor_doc(editor.view_id);
+        // The text could have been deleted since the signature help was shown
+        let offset = offset.min(doc.buffer().len());
+        let (point_above, point_below) =
+            doc.points_of_offset(text, offset, &editor.view, config);
+
+        // Shown above the line so it's out of the way of the completion
+        let mut origin = *editor.window_origin.borrow()
+            - self.window_origin.borrow().to_vec2()
+            + Vec2::new(point_above.x, point_above.y - signature_size.height);
+        if origin.y < 0.0 {
+            origin.y = editor.window_origin.borrow().y
+                - self.window_origin.borrow().y
+                + point_below.y;
+        }
+        if origin.x + signature_size.width + 1.0 > tab_size.width {
+            origin.x = tab_size.width - signature_size.width - 1.0;
+        }
+        if origin.x <= 0.0 {
+            origin.x = 0.0;
+        }
+
+        origin
+    }
+
     pub fn hover_origin(
         &self,
         text: &mut PietText,
@@ -1125,6 +1221,7 @@ pub fn palette_view_data(&self) -> PaletteViewData {
             find: self.find.clone(),
             focus_area: self.focus_area.clone(),
             terminal: self.terminal.clone(),
+            source_control: self.source_control.clone(),
         }
     }
 
@@ -1132,7 +1229,7 @@ pub fn run_workbench_command(
         &mut self,
         ctx: &mut EventCtx,
         command: &LapceWorkbenchCommand,
-        data: Option<Value>,
+        args: Option<CommandArgs>,
         _count: Option<usize>,
         _env: &Env,
     ) {
@@ -1165,7 +1262,21 @@ pub fn run_workbench_command(
                 }
             }
             LapceWorkbenchCommand::OpenFolder => {
-                if !self.workspace.kind.is_remote() {
+                if let Some(CommandArgs::Path(path)) = args {
+                    let workspace = LapceWorkspace {
+                        kind: self.workspace.kind.clone(),
+                        path: Some(path),
+                        last_open: std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .unwrap()
+                            .as_secs(),
+                    };
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::SetWorkspace(workspace),
+                        Target::Window(*self.window_id),
+                    ));
+                } else if !self.workspace.kind.is_remote() {
                     let options = FileDialogOptions::new()
                         .select_directories()
                         .accept_command(LAPCE_OPEN_FOLDER);
@@ -1190,7 +1301,27 @@ pub fn run_workbench_command(
                 }
             }
             LapceWorkbenchCommand::OpenFile => {
-                if !self.workspace.kind.is_remote() {
+                if let Some(CommandArgs::Path(path)) = args {
+                    // A relative path is relative to the workspace
+                    let path = match self.workspace.path.as_ref() {
+                        Some(workspace) if path.is_relative() => {
+                            workspace.join(path)
+                        }
+                        _ => path,
+                    };
+                    self.main_split.jump_to_location(
+                        ctx,
+                        None,
+                        false,
+                        EditorLocation {
+                            path,
+                            position: None::<usize>,
+                            scroll_offset: None,
+                            history: None,
+                        },
+                        &self.config,
+                    );
+                } else if !self.workspace.kind.is_remote() {
                     let options =
                         FileDialogOptions::new().accept_command(LAPCE_OPEN_FILE);
                     ctx.submit_command(
@@ -1273,6 +1404,22 @@ pub fn run_workbench_command(
                     );
                 }
             }
+            LapceWorkbenchCommand::OpenLspLogs => {
+                for path in self.proxy.lsp_log_files() {
+                    self.main_split.jump_to_location(
+                        ctx,
+                        None,
+                        false,
+                        EditorLocation {
+                            path,
+                            position: None::<usize>,
+                            scroll_offset: None,
+                            history: None,
+                        },
+                        &self.config,
+                    );
+                }
+            }
             LapceWorkbenchCommand::OpenSettings => {
                 self.main_split.open_settings(ctx, false, &self.config);
             }
@@ -1333,6 +1480,13 @@ pub fn run_workbench_command(
                     );
                 }
             }
+            LapceWorkbenchCommand::ImportKeybindings => {
+                // The keymaps are local, so is the file they're imported from
+                let options = FileDialogOptions::new()
+                    .show_hidden()
+                    .accept_command(LAPCE_IMPORT_KEYBINDINGS);
+                ctx.submit_command(druid::commands::SHOW_OPEN_PANEL.with(options));
+            }
             LapceWorkbenchCommand::Palette => {
                 ctx.submit_command(Command::new(
                     LAPCE_UI_COMMAND,
@@ -1347,6 +1501,13 @@ pub fn run_workbench_command(
                     Target::Widget(self.palette.widget_id),
                 ));
             }
+            LapceWorkbenchCommand::PaletteGoToLine => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::GoToLine)),
+                    Target::Widget(self.palette.widget_id),
+                ));
+            }
             LapceWorkbenchCommand::PaletteSymbol => {
                 ctx.submit_command(Command::new(
                     LAPCE_UI_COMMAND,
@@ -1375,6 +1536,13 @@ pub fn run_workbench_command(
                     Target::Widget(self.palette.widget_id),
                 ));
             }
+            LapceWorkbenchCommand::PaletteSwitch => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::Switch)),
+                    Target::Widget(self.palette.widget_id),
+                ));
+            }
             LapceWorkbenchCommand::NewWindowTab => {
                 ctx.submit_command(Command::new(
                     LAPCE_UI_COMMAND,
@@ -1425,10 +1593,8 @@ pub fn run_workbench_command(
                 ));
             }
             LapceWorkbenchCommand::ToggleMaximizedPanel => {
-                if let Some(data) = data {
-                    if let Ok(kind) = serde_json::from_value::<PanelKind>(data) {
-                        Arc::make_mut(&mut self.panel).toggle_maximize(&kind);
-                    }
+                if let Some(CommandArgs::Panel(kind)) = args {
+                    Arc::make_mut(&mut self.panel).toggle_maximize(&kind);
                 } else {
                     Arc::make_mut(&mut self.panel).toggle_active_maximize();
                 }
@@ -1465,14 +1631,18 @@ pub fn run_workbench_command(
             LapceWorkbenchCommand::ToggleProblemVisual => {
                 self.toggle_panel_visual(ctx, PanelKind::Problem);
             }
+            LapceWorkbenchCommand::ToggleCallHierarchyVisual => {
+                self.toggle_panel_visual(ctx, PanelKind::CallHierarchy);
+            }
+            LapceWorkbenchCommand::ToggleTypeHierarchyVisual => {
+                self.toggle_panel_visual(ctx, PanelKind::TypeHierarchy);
+            }
             LapceWorkbenchCommand::ToggleTerminalVisual => {
                 self.toggle_panel_visual(ctx, PanelKind::Terminal);
             }
             LapceWorkbenchCommand::TogglePanelVisual => {
-                if let Some(data) = data {
-                    if let Ok(kind) = serde_json::from_value::<PanelKind>(data) {
-                        self.toggle_panel_visual(ctx, kind);
-                    }
+                if let Some(CommandArgs::Panel(kind)) = args {
+                    self.toggle_panel_visual(ctx, kind);
                 }
             }
             LapceWorkbenchCommand::TogglePanelLeftVisual => {
@@ -1502,29 +1672,29 @@ pub fn run_workbench_command(
             LapceWorkbenchCommand::ToggleProblemFocus => {
                 self.toggle_panel_focus(ctx, PanelKind::Problem);
             }
+            LapceWorkbenchCommand::ToggleCallHierarchyFocus => {
+                self.toggle_panel_focus(ctx, PanelKind::CallHierarchy);
+            }
+            LapceWorkbenchCommand::ToggleTypeHierarchyFocus => {
+                self.toggle_panel_focus(ctx, PanelKind::TypeHierarchy);
+            }
             LapceWorkbenchCommand::ToggleTerminalFocus => {
                 self.toggle_panel_focus(ctx, PanelKind::Terminal);
             }
             LapceWorkbenchCommand::TogglePanelFocus => {
-                if let Some(data) = data {
-                    if let Ok(kind) = serde_json::from_value::<PanelKind>(data) {
-                        self.toggle_panel_focus(ctx, kind);
-                    }
+                if let Some(CommandArgs::Panel(kind)) = args {
+                    self.toggle_panel_focus(ctx, kind);
                 }
             }
 
             LapceWorkbenchCommand::ShowPanel => {
-                if let Some(data) = data {
-                    if let Ok(kind) = serde_json::from_value::<PanelKind>(data) {
-                        self.show_panel(ctx, kind);
-                    }
+                if let Some(CommandArgs::Panel(kind)) = args {
+                    self.show_panel(ctx, kind);
                 }
             }
             LapceWorkbenchCommand::HidePanel => {
-                if let Some(data) = data {
-                    if let Ok(kind) = serde_json::from_value::<PanelKind>(data) {
-                        self.hide_panel(ctx, kind);
-                    }
+                if let Some(CommandArgs::Panel(kind)) = args {
+                    self.hide_panel(ctx, kind);
                 }
             }
             LapceWorkbenchCommand::SourceControlInit => {
@@ -1545,31 +1715,61 @@ pub fn run_workbench_command(
                         },
                     )
                     .collect();
-                if diffs.is_empty() {
+                // Amending can just reword the last commit
+                if diffs.is_empty() && !self.source_control.amend {
                     return;
                 }
                 let doc = self
                     .main_split
                     .local_docs
-                    .get_mut(&LocalBufferKind::SourceControl)
+                    .get(&LocalBufferKind::SourceControl)
                     .unwrap();
                 let message = doc.buffer().to_string();
                 let message = message.trim();
                 if message.is_empty() {
                     return;
                 }
-                self.proxy.proxy_rpc.git_commit(message.to_string(), diffs);
-                Arc::make_mut(doc).reload(Rope::from(""), true);
-                let editor = self
-                    .main_split
-                    .editors
-                    .get_mut(&self.source_control.editor_view_id)
-                    .unwrap();
-                Arc::make_mut(editor).cursor = if self.config.core.modal {
-                    Cursor::new(CursorMode::Normal(0), None, None)
-                } else {
-                    Cursor::new(CursorMode::Insert(Selection::caret(0)), None, None)
-                };
+                let event_sink = ctx.get_external_handle();
+                let proxy_rpc = self.proxy.proxy_rpc.clone();
+                let tab_id = self.id;
+                self.proxy.proxy_rpc.git_commit(
+                    message.to_string(),
+                    diffs,
+                    self.source_control.amend,
+                    self.source_control.sign_off,
+                    move |result| match result {
+                        // The message is kept when the commit fails so it can
+                        // be fixed up and tried again
+                        Ok(_) => {
+                            Self::load_commit_message(
+                                &proxy_rpc, false, tab_id, event_sink,
+                            );
+                        }
+                        Err(e) => {
+                            let _ = event_sink.submit_command(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::ShowAlert(AlertContentData {
+                                    title: "Git".to_string(),
+                                    msg: e.message,
+                                    buttons: Vec::new(),
+                                }),
+                                Target::Widget(tab_id),
+                            );
+                        }
+                    },
+                );
+            }
+            LapceWorkbenchCommand::SourceControlToggleAmend => {
+                Self::load_commit_message(
+                    &self.proxy.proxy_rpc,
+                    !self.source_control.amend,
+                    self.id,
+                    ctx.get_external_handle(),
+                );
+            }
+            LapceWorkbenchCommand::SourceControlToggleSignOff => {
+                let source_control = Arc::make_mut(&mut self.source_control);
+                source_control.sign_off = !source_control.sign_off;
             }
             LapceWorkbenchCommand::SourceControlDiscardActiveFileChanges => {
                 if let Some(editor) = self.main_split.active_editor() {
@@ -1583,12 +1783,129 @@ pub fn run_workbench_command(
             LapceWorkbenchCommand::SourceControlDiscardWorkspaceChanges => {
                 self.proxy.proxy_rpc.git_discard_workspace_changes();
             }
-            LapceWorkbenchCommand::CheckoutBranch => match data {
-                Some(Value::String(branch)) => {
+            LapceWorkbenchCommand::CheckoutBranch => match args {
+                Some(CommandArgs::Text(branch)) => {
                     self.proxy.proxy_rpc.git_checkout(branch)
                 }
                 _ => log::error!("checkout called without a branch"), // TODO: How do I show a result to the user here?
             },
+            LapceWorkbenchCommand::SourceControlFetch => {
+                self.proxy.proxy_rpc.git_fetch();
+            }
+            LapceWorkbenchCommand::SourceControlPull => {
+                self.proxy.proxy_rpc.git_pull(false);
+            }
+            LapceWorkbenchCommand::SourceControlPullRebase => {
+                self.proxy.proxy_rpc.git_pull(true);
+            }
+            LapceWorkbenchCommand::SourceControlPush => {
+                self.proxy.proxy_rpc.git_push();
+            }
+            LapceWorkbenchCommand::SourceControlStash
+            | LapceWorkbenchCommand::SourceControlStashIncludeUntracked => {
+                let include_untracked = *command
+                    == LapceWorkbenchCommand::SourceControlStashIncludeUntracked;
+                match args {
+                    Some(CommandArgs::Text(message)) => {
+                        self.proxy.proxy_rpc.git_stash_save(
+                            Some(message).filter(|m| !m.is_empty()),
+                            include_untracked,
+                        );
+                    }
+                    _ => ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::RunPalette(Some(PaletteType::GitStash(
+                            if include_untracked {
+                                GitStashAction::SaveIncludeUntracked
+                            } else {
+                                GitStashAction::Save
+                            },
+                        ))),
+                        Target::Widget(self.palette.widget_id),
+                    )),
+                }
+            }
+            LapceWorkbenchCommand::SourceControlStashApply
+            | LapceWorkbenchCommand::SourceControlStashPop
+            | LapceWorkbenchCommand::SourceControlStashDrop => match args {
+                Some(CommandArgs::Index(index)) => match command {
+                    LapceWorkbenchCommand::SourceControlStashApply => {
+                        self.proxy.proxy_rpc.git_stash_apply(index)
+                    }
+                    LapceWorkbenchCommand::SourceControlStashPop => {
+                        self.proxy.proxy_rpc.git_stash_pop(index)
+                    }
+                    _ => self.proxy.proxy_rpc.git_stash_drop(index),
+                },
+                _ => {
+                    let action = match command {
+                        LapceWorkbenchCommand::SourceControlStashApply => {
+                            GitStashAction::Apply
+                        }
+                        LapceWorkbenchCommand::SourceControlStashPop => {
+                            GitStashAction::Pop
+                        }
+                        _ => GitStashAction::Drop,
+                    };
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::RunPalette(Some(PaletteType::GitStash(
+                            action,
+                        ))),
+                        Target::Widget(self.palette.widget_id),
+                    ));
+                }
+            },
+            LapceWorkbenchCommand::CreateBranch => match args {
+                // The input is the branch name, optionally followed by the
+                // ref it should start from
+                Some(CommandArgs::Text(input)) => {
+                    let mut parts = input.split_whitespace();
+                    if let Some(name) = parts.next() {
+                        self.proxy.proxy_rpc.git_create_branch(
+                            name.to_string(),
+                            parts.next().map(|s| s.to_string()),
+                            true,
+                        );
+                    }
+                }
+                _ => ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::GitBranch(
+                        GitBranchAction::Create,
+                    ))),
+                    Target::Widget(self.palette.widget_id),
+                )),
+            },
+            LapceWorkbenchCommand::DeleteBranch => match args {
+                Some(CommandArgs::Branch { branch, force }) => {
+                    self.proxy.proxy_rpc.git_delete_branch(branch, force);
+                }
+                _ => ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::GitBranch(
+                        GitBranchAction::Delete,
+                    ))),
+                    Target::Widget(self.palette.widget_id),
+                )),
+            },
+            LapceWorkbenchCommand::RenameBranch => match args {
+                Some(CommandArgs::Text(new_name)) => {
+                    if !self.source_control.branch.is_empty() {
+                        self.proxy.proxy_rpc.git_rename_branch(
+                            self.source_control.branch.clone(),
+                            new_name,
+                        );
+                    }
+                }
+                _ => ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::GitBranch(
+                        GitBranchAction::Rename,
+                    ))),
+                    Target::Widget(self.palette.widget_id),
+                )),
+            },
 
             LapceWorkbenchCommand::ConnectSshHost => {
                 ctx.submit_command(Command::new(
@@ -1620,6 +1937,29 @@ pub fn run_workbench_command(
             LapceWorkbenchCommand::ExportCurrentThemeSettings => {
                 self.main_split.export_theme(ctx, &self.config);
             }
+            LapceWorkbenchCommand::ExportSettingsBundle => {
+                let options = FileDialogOptions::new()
+                    .default_name("lapce-settings.json")
+                    .accept_command(LAPCE_EXPORT_SETTINGS_BUNDLE);
+                ctx.submit_command(druid::commands::SHOW_SAVE_PANEL.with(options));
+            }
+            LapceWorkbenchCommand::ImportSettingsBundle => {
+                let options = FileDialogOptions::new()
+                    .accept_command(LAPCE_IMPORT_SETTINGS_BUNDLE);
+                ctx.submit_command(druid::commands::SHOW_OPEN_PANEL.with(options));
+            }
+            LapceWorkbenchCommand::ApplySettingsBundle => {
+                match self.pending_settings_bundle.take() {
+                    Some(bundle) => self.apply_settings_bundle(bundle),
+                    None => self.show_notification(
+                        "Settings Bundle".to_string(),
+                        "Import a settings bundle before applying it".to_string(),
+                        MessageType::WARNING,
+                        Vec::new(),
+                        None,
+                    ),
+                }
+            }
             LapceWorkbenchCommand::InstallTheme => {
                 self.main_split.install_theme(ctx, &self.config);
             }
@@ -1702,15 +2042,22 @@ pub fn run_command(
     ) {
         match &command.kind {
             CommandKind::Workbench(cmd) => {
-                self.run_workbench_command(
-                    ctx,
-                    cmd,
-                    command.data.clone(),
-                    count,
-                    env,
-                );
-            }
-            CommandKind::Focus(_) | CommandKind::Edit(_) | CommandKind::Move(_) => {
+                let args = match command.args() {
+                    Ok(args) => args,
+                    Err(err) => {
+                        log::warn!("invalid arguments for {cmd}: {err}");
+                        return;
+                    }
+                };
+                self.run_workbench_command(ctx, cmd, args, count, env);
+            }
+            // Motion mode and multi-selection commands go to the editor as
+            // well, as the palette and composite command steps run them too
+            CommandKind::Focus(_)
+            | CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MotionMode(_)
+            | CommandKind::MultiSelection(_) => {
                 let widget_id = if *self.focus != self.palette.input_editor {
                     *self.focus
                 } else if let Some(active_tab) = self.main_split.active_tab.as_ref()
@@ -1732,7 +2079,26 @@ pub fn run_command(
                     Target::Widget(widget_id),
                 ));
             }
-            _ => {}
+            CommandKind::Composite { name, .. } => {
+                self.run_composite_command(ctx, name);
+            }
+        }
+    }
+
+    /// Runs the commands of a composite command in order, each of them the
+    /// way it would run on its own, so that a command runs in the widget
+    /// that the commands before it focused
+    fn run_composite_command(&self, ctx: &mut EventCtx, name: &str) {
+        let composite = match self.config.commands.get(name) {
+            Some(composite) => composite,
+            None => return,
+        };
+        for command in composite_steps(&self.keypress.commands, name, composite) {
+            ctx.submit_command(Command::new(
+                LAPCE_COMMAND,
+                command,
+                Target::Widget(self.id),
+            ));
         }
     }
 
@@ -1818,6 +2184,8 @@ pub fn show_panel(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
             PanelKind::Terminal => self.terminal.widget_id,
             PanelKind::Search => self.search.active,
             PanelKind::Problem => self.problem.widget_id,
+            PanelKind::CallHierarchy => self.call_hierarchy.widget_id,
+            PanelKind::TypeHierarchy => self.type_hierarchy.widget_id,
         };
         if let PanelKind::Search = kind {
             ctx.submit_command(Command::new(
@@ -1838,6 +2206,223 @@ pub fn show_panel(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
         ));
     }
 
+    pub fn hierarchy_mut(&mut self, kind: HierarchyKind) -> &mut HierarchyData {
+        match kind {
+            HierarchyKind::Call => Arc::make_mut(&mut self.call_hierarchy),
+            HierarchyKind::Type => Arc::make_mut(&mut self.type_hierarchy),
+        }
+    }
+
+    /// Shows the prepared items in the hierarchy's panel, and fetches their
+    /// children right away
+    pub fn update_hierarchy(
+        &mut self,
+        ctx: &mut EventCtx,
+        kind: HierarchyKind,
+        plugin_id: PluginId,
+        items: Vec<CallHierarchyItem>,
+    ) {
+        let proxy = self.proxy.clone();
+        let tab_id = self.id;
+        let hierarchy = self.hierarchy_mut(kind);
+        hierarchy.set_roots(plugin_id, items);
+        hierarchy.fetch_root_children(&proxy, tab_id, ctx.get_external_handle());
+        self.show_panel(
+            ctx,
+            match kind {
+                HierarchyKind::Call => PanelKind::CallHierarchy,
+                HierarchyKind::Type => PanelKind::TypeHierarchy,
+            },
+        );
+    }
+
+    pub fn toggle_hierarchy_item(
+        &mut self,
+        ctx: &mut EventCtx,
+        kind: HierarchyKind,
+        path: &[usize],
+    ) {
+        let proxy = self.proxy.clone();
+        let tab_id = self.id;
+        let hierarchy = self.hierarchy_mut(kind);
+        if let Some(item) = hierarchy.toggle(path) {
+            hierarchy.fetch_children(
+                path.to_vec(),
+                item,
+                &proxy,
+                tab_id,
+                ctx.get_external_handle(),
+            );
+        }
+    }
+
+    pub fn toggle_hierarchy_direction(
+        &mut self,
+        ctx: &mut EventCtx,
+        kind: HierarchyKind,
+    ) {
+        let proxy = self.proxy.clone();
+        let tab_id = self.id;
+        let hierarchy = self.hierarchy_mut(kind);
+        hierarchy.toggle_direction();
+        hierarchy.fetch_root_children(&proxy, tab_id, ctx.get_external_handle());
+    }
+
+    /// Shows what importing the settings bundle would change, and keeps it
+    /// to be applied with `ApplySettingsBundle`
+    pub fn import_settings_bundle(
+        &mut self,
+        ctx: &mut EventCtx,
+        bundle: SettingsBundle,
+    ) {
+        let review = bundle.review(&self.plugin);
+        self.main_split
+            .show_settings_bundle_review(ctx, review, &self.config);
+        self.pending_settings_bundle = Some(Arc::new(bundle));
+        self.show_notification(
+            "Settings Bundle".to_string(),
+            "Review the changes, then run \"Apply Imported Settings Bundle\" \
+             to apply them"
+                .to_string(),
+            MessageType::INFO,
+            Vec::new(),
+            None,
+        );
+    }
+
+    /// Writes the files of the settings bundle, takes over its disabled
+    /// volts, and installs the volts of it that aren't installed yet
+    fn apply_settings_bundle(&mut self, bundle: Arc<SettingsBundle>) {
+        // The volts to install are looked up in the registry, so the bundle
+        // is kept for later while the registry isn't there
+        let registry_error = match self.plugin.volts.status {
+            PluginLoadStatus::Success => None,
+            PluginLoadStatus::Loading => Some("the volt registry is still loading"),
+            PluginLoadStatus::Failed => Some("the volt registry couldn't be loaded"),
+        };
+        if let Some(err) = registry_error {
+            self.pending_settings_bundle = Some(bundle);
+            self.show_notification(
+                "Settings Bundle".to_string(),
+                format!("Couldn't apply the settings bundle yet: {err}"),
+                MessageType::WARNING,
+                Vec::new(),
+                None,
+            );
+            return;
+        }
+
+        if let Err(err) = bundle.write_files() {
+            self.show_notification(
+                "Settings Bundle".to_string(),
+                format!("Couldn't apply the settings bundle: {err}"),
+                MessageType::ERROR,
+                Vec::new(),
+                None,
+            );
+            return;
+        }
+
+        let plugin = Arc::make_mut(&mut self.plugin);
+        let disabled: HashSet<String> =
+            bundle.disabled_volts.iter().cloned().collect();
+        for (id, meta) in plugin.installed.iter() {
+            let was_disabled = plugin.disabled.contains(id);
+            if disabled.contains(id) && !was_disabled {
+                self.proxy.proxy_rpc.disable_volt(meta.info());
+            } else if was_disabled
+                && !disabled.contains(id)
+                && !plugin.workspace_disabled.contains(id)
+            {
+                self.proxy.proxy_rpc.enable_volt(meta.info());
+            }
+        }
+        plugin.disabled = disabled;
+        let _ = self
+            .db
+            .save_disabled_volts(plugin.disabled.iter().collect());
+
+        // The registry only has the latest version of a volt
+        let mut missing = Vec::new();
+        let mut installs = Vec::new();
+        for volt in bundle.volts.iter() {
+            if plugin.installed.contains_key(&volt.id) {
+                continue;
+            }
+            match plugin.volts.volts.get(&volt.id) {
+                Some(info) => installs.push(info.clone()),
+                None => missing.push(volt.id.clone()),
+            }
+        }
+        let proxy = self.proxy.clone();
+        std::thread::spawn(move || {
+            for volt in installs {
+                if let Err(err) = PluginData::install_volt(proxy.clone(), volt) {
+                    log::warn!("install volt err: {err:?}");
+                }
+            }
+        });
+
+        let (message, typ) = if missing.is_empty() {
+            ("Applied the settings bundle".to_string(), MessageType::INFO)
+        } else {
+            (
+                format!(
+                    "Applied the settings bundle, but these volts aren't in the \
+                     registry:\n{}",
+                    missing.join("\n")
+                ),
+                MessageType::WARNING,
+            )
+        };
+        self.show_notification(
+            "Settings Bundle".to_string(),
+            message,
+            typ,
+            Vec::new(),
+            None,
+        );
+    }
+
+    pub fn show_notification(
+        &mut self,
+        title: String,
+        message: String,
+        typ: MessageType,
+        actions: Vec<MessageActionItem>,
+        request_id: Option<RequestId>,
+    ) {
+        let dropped = Arc::make_mut(&mut self.notifications)
+            .push(title, message, typ, actions, request_id);
+        for notification in dropped {
+            self.answer_notification(notification.request_id, None);
+        }
+    }
+
+    /// Closes the notification, and lets the server which is waiting on it
+    /// know which action was picked
+    pub fn close_notification(&mut self, id: u64, action: Option<usize>) {
+        if let Some(notification) = Arc::make_mut(&mut self.notifications).remove(id)
+        {
+            let action =
+                action.and_then(|action| notification.actions.get(action).cloned());
+            self.answer_notification(notification.request_id, action);
+        }
+    }
+
+    fn answer_notification(
+        &self,
+        request_id: Option<RequestId>,
+        action: Option<MessageActionItem>,
+    ) {
+        if let Some(request_id) = request_id {
+            self.proxy.core_rpc.handle_response(
+                request_id,
+                Ok(CoreResponse::ShowMessageRequest { action }),
+            );
+        }
+    }
+
     fn toggle_panel_visual(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
         if self.panel.is_panel_visible(&kind) {
             self.hide_panel(ctx, kind);
@@ -1848,7 +2433,11 @@ fn toggle_panel_visual(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
 
     fn toggle_panel_focus(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
         let should_hide = match kind {
-            PanelKind::FileExplorer | PanelKind::Plugin | PanelKind::Problem => {
+            PanelKind::FileExplorer
+            | PanelKind::Plugin
+            | PanelKind::Problem
+            | PanelKind::CallHierarchy
+            | PanelKind::TypeHierarchy => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
@@ -1864,6 +2453,45 @@ fn toggle_panel_focus(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
         }
     }
 
+    /// Fill the commit message input with the last commit's message when
+    /// amending it, or with the repository's commit template otherwise
+    pub fn load_commit_message(
+        proxy_rpc: &ProxyRpcHandler,
+        amend: bool,
+        tab_id: WidgetId,
+        event_sink: ExtEventSink,
+    ) {
+        proxy_rpc.git_get_commit_message(amend, move |result| {
+            if let Ok(ProxyResponse::GitGetCommitMessage { message }) = result {
+                let _ = event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::UpdateCommitMessage { message, amend },
+                    Target::Widget(tab_id),
+                );
+            }
+        });
+    }
+
+    pub fn set_commit_message(&mut self, message: &str, amend: bool) {
+        Arc::make_mut(&mut self.source_control).amend = amend;
+        let doc = self
+            .main_split
+            .local_docs
+            .get_mut(&LocalBufferKind::SourceControl)
+            .unwrap();
+        Arc::make_mut(doc).reload(Rope::from(message), true);
+        let editor = self
+            .main_split
+            .editors
+            .get_mut(&self.source_control.editor_view_id)
+            .unwrap();
+        Arc::make_mut(editor).cursor = if self.config.core.modal {
+            Cursor::new(CursorMode::Normal(0), None, None)
+        } else {
+            Cursor::new(CursorMode::Insert(Selection::caret(0)), None, None)
+        };
+    }
+
     pub fn read_picker_pwd(&mut self, ctx: &mut EventCtx) {
         let path = self.picker.pwd.clone();
         let event_sink = ctx.get_external_handle();
@@ -2161,17 +2789,41 @@ pub fn document_format_and_save(
         rev: u64,
         result: &Result<Vec<TextEdit>>,
         exit_widget_id: Option<WidgetId>,
+        config: &LapceConfig,
     ) {
         self.document_format(path, rev, result);
-        self.document_save(ctx, path, exit_widget_id);
+        self.document_save(ctx, path, exit_widget_id, config);
     }
 
+    /// Runs the save actions that tidy the text up, each as an undo step of
+    /// its own, and then writes the file
     pub fn document_save(
         &mut self,
         ctx: &mut EventCtx,
         path: &Path,
         exit_widget_id: Option<WidgetId>,
+        config: &LapceConfig,
     ) {
+        let save_actions = self.open_docs.get(path).unwrap().save_actions(config);
+        if save_actions.trim_trailing_whitespace {
+            let edits = self
+                .open_docs
+                .get(path)
+                .unwrap()
+                .trailing_whitespace_edits();
+            self.apply_save_action(path, &edits);
+        }
+        if let Some(ensure) = save_actions.final_newline {
+            let edits = self
+                .open_docs
+                .get(path)
+                .unwrap()
+                .final_newline_edits(ensure);
+            self.apply_save_action(path, &edits);
+        }
+        let edits = self.open_docs.get(path).unwrap().line_ending_edits();
+        self.apply_save_action(path, &edits);
+
         let doc = self.open_docs.get(path).unwrap();
         let rev = doc.rev();
         let event_sink = ctx.get_external_handle();
@@ -2180,18 +2832,43 @@ pub fn document_save(
         self.proxy.proxy_rpc.save(
             rev,
             path.clone(),
-            Box::new(move |result| {
-                if let Ok(ProxyResponse::SaveResponse {}) = result {
+            Box::new(move |result| match result {
+                Ok(ProxyResponse::SaveResponse {}) => {
                     let _ = event_sink.submit_command(
                         LAPCE_UI_COMMAND,
                         LapceUICommand::BufferSave(path, rev, exit_widget_id),
                         Target::Widget(tab_id),
                     );
                 }
+                Ok(_) => {}
+                Err(err) => {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::ShowNotification {
+                            title: format!("Couldn't save {}", path.display()),
+                            message: err.message,
+                            typ: MessageType::ERROR,
+                            actions: Vec::new(),
+                            request_id: None,
+                        },
+                        Target::Widget(tab_id),
+                    );
+                }
             }),
         );
     }
 
+    fn apply_save_action(&mut self, path: &Path, edits: &[(Selection, String)]) {
+        if edits.is_empty() {
+            return;
+        }
+        let edits = edits
+            .iter()
+            .map(|(selection, text)| (selection, text.as_str()))
+            .collect::<Vec<(&Selection, &str)>>();
+        self.edit(path, &edits, EditType::Other);
+    }
+
     pub fn diagnostics_items(
         &self,
         severity: DiagnosticSeverity,
@@ -2768,6 +3445,107 @@ pub fn jump_to_location_cb<
         editor_view_id
     }
 
+    /// Applies a workspace edit, opening the files it changes that aren't
+    /// open yet, and answers the server that asked for it once all of its
+    /// text edits have been applied
+    pub fn apply_workspace_edit(
+        &mut self,
+        ctx: &mut EventCtx,
+        edit: &WorkspaceEdit,
+        request_id: Option<RequestId>,
+        config: &LapceConfig,
+    ) {
+        // Nothing is applied unless all of the edit can be
+        let mut open = Vec::new();
+        let mut unopened = Vec::new();
+        for (url, edits) in workspace_edits(edit).unwrap_or_default() {
+            let path = match url.to_file_path() {
+                Ok(path) => path,
+                Err(_) => {
+                    self.answer_workspace_edit(
+                        request_id,
+                        Some(format!("{url} isn't a file")),
+                    );
+                    return;
+                }
+            };
+            match self.open_docs.get(&path) {
+                Some(doc) if doc.loaded() => open.push((path, edits)),
+                Some(_) => {
+                    self.answer_workspace_edit(
+                        request_id,
+                        Some(format!("{path:?} is still being opened")),
+                    );
+                    return;
+                }
+                None => unopened.push((path, edits)),
+            }
+        }
+
+        if let Some(DocumentChanges::Operations(op)) = edit.document_changes.as_ref()
+        {
+            op.iter()
+                .flat_map(|op| match op {
+                    DocumentChangeOperation::Op(op) => Some(op),
+                    _ => None,
+                })
+                .flat_map(workspace_operation)
+                .map(|cmd| Command::new(LAPCE_UI_COMMAND, cmd, Target::Auto))
+                .for_each(|cmd| ctx.submit_command(cmd));
+        }
+
+        for (path, edits) in open {
+            apply_edit(self, &path, &edits);
+        }
+        if unopened.is_empty() {
+            self.answer_workspace_edit(request_id, None);
+            return;
+        }
+
+        // The answer waits for the last of the files to be loaded
+        let remaining = Arc::new(AtomicUsize::new(unopened.len()));
+        for (path, edits) in unopened {
+            let location = EditorLocation {
+                path: path.clone(),
+                position: edits.get(0).map(|edit| edit.range.start),
+                scroll_offset: None,
+                history: None,
+            };
+            let remaining = remaining.clone();
+            let callback =
+                move |_: &mut EventCtx, main_split: &mut LapceMainSplitData| {
+                    apply_edit(main_split, &path, &edits);
+                    if remaining.fetch_sub(1, atomic::Ordering::AcqRel) == 1 {
+                        main_split.answer_workspace_edit(request_id, None);
+                    }
+                };
+            self.jump_to_location_cb(
+                ctx,
+                None,
+                false,
+                location,
+                config,
+                Some(callback),
+            );
+        }
+    }
+
+    fn answer_workspace_edit(
+        &self,
+        request_id: Option<RequestId>,
+        failure_reason: Option<String>,
+    ) {
+        if let Some(failure_reason) = failure_reason.as_ref() {
+            log::warn!("workspace edit wasn't applied: {failure_reason}");
+        }
+        if let Some(request_id) = request_id {
+            self.proxy.core_rpc.handle_response(
+                request_id,
+                Ok(CoreResponse::ApplyWorkspaceEdit { failure_reason }),
+            );
+        }
+    }
+
     pub fn can_jump_location_backward(&self) -> bool {
         self.current_location >= 1
     }
@@ -2859,6 +3637,18 @@ pub fn export_theme(&mut self, ctx: &mut EventCtx, config: &LapceConfig) {
         doc.reload(Rope::from(config.export_theme()), true);
     }
 
+    pub fn show_settings_bundle_review(
+        &mut self,
+        ctx: &mut EventCtx,
+        review: String,
+        config: &LapceConfig,
+    ) {
+        let id = self.new_file(ctx, config);
+        let doc = self.scratch_docs.get_mut(&id).unwrap();
+        let doc = Arc::make_mut(doc);
+        doc.reload(Rope::from(review), true);
+    }
+
     pub fn new_file(
         &mut self,
         ctx: &mut EventCtx,
@@ -2954,6 +3744,7 @@ pub fn go_to_location_cb<
                 doc.scroll_offset =
                     Vec2::new(info.scroll_offset.0, info.scroll_offset.1);
                 doc.cursor_offset = info.cursor_offset;
+                doc.saved_folds = info.folds;
             }
 
             let cb: Option<InitBufferContentCb> = cb.map(|cb| Box::new(cb) as _);
@@ -3068,6 +3859,32 @@ pub fn jump_to_line(
             self.jump_to_location(ctx, editor_view_id, true, location, config);
         }
     }
+
+    pub fn jump_to_line_col(
+        &mut self,
+        ctx: &mut EventCtx,
+        editor_view_id: Option<WidgetId>,
+        position: LineCol,
+        config: &LapceConfig,
+    ) {
+        let editor =
+            self.get_editor_or_new(ctx, editor_view_id, true, None, false, config);
+        let path = if let BufferContent::File(path) = &editor.content {
+            Some(path.clone())
+        } else {
+            None
+        };
+
+        if let Some(path) = path {
+            let location = EditorLocation {
+                path,
+                position: Some(position),
+                scroll_offset: None,
+                history: None,
+            };
+            self.jump_to_location(ctx, editor_view_id, true, location, config);
+        }
+    }
 }
 
 impl LapceMainSplitData {
@@ -9,6 +9,7 @@
 use anyhow::{anyhow, Result};
 use crossbeam_channel::{unbounded, Sender};
 use druid::{ExtEventSink, Point, Rect, Size, Vec2, WidgetId};
+use lapce_core::fold::FoldRange;
 
 use lapce_proxy::directory::Directory;
 use serde::{Deserialize, Serialize};
@@ -27,10 +28,15 @@
     split::SplitDirection,
 };
 
+/// The most commands that are remembered as recently run
+const MAX_RECENT_COMMANDS: usize = 10;
+
 pub enum SaveEvent {
     Workspace(LapceWorkspace, WorkspaceInfo),
     Tabs(TabsInfo),
     Buffer(BufferInfo),
+    FileOpen(LapceWorkspace, PathBuf),
+    CommandRun(String),
 }
 
 #[derive(Clone)]
@@ -263,6 +269,36 @@ pub struct BufferInfo {
     pub path: PathBuf,
     pub scroll_offset: (f64, f64),
     pub cursor_offset: usize,
+    #[serde(default)]
+    pub folds: Vec<FoldRange>,
+}
+
+/// How often and how recently a file of a workspace was opened, which ranks
+/// the files in the palette
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFrecency {
+    pub count: usize,
+    /// The seconds since the epoch at the last open
+    pub last_open: u64,
+}
+
+impl FileFrecency {
+    /// The most files of a workspace that are remembered
+    const MAX_FILES: usize = 500;
+
+    /// The open count weighted by how long ago the last open was
+    pub fn score(&self, now: u64) -> f64 {
+        const HOUR: u64 = 60 * 60;
+        const DAY: u64 = 24 * HOUR;
+        let weight = match now.saturating_sub(self.last_open) {
+            age if age < 4 * HOUR => 1.0,
+            age if age < DAY => 0.7,
+            age if age < 7 * DAY => 0.5,
+            age if age < 30 * DAY => 0.3,
+            _ => 0.1,
+        };
+        self.count as f64 * weight
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -371,6 +407,12 @@ pub fn new() -> Result<Self> {
                     SaveEvent::Buffer(info) => {
                         let _ = local_db.insert_buffer(&info);
                     }
+                    SaveEvent::FileOpen(workspace, path) => {
+                        let _ = local_db.insert_file_open(&workspace, path);
+                    }
+                    SaveEvent::CommandRun(command) => {
+                        let _ = local_db.insert_command_run(command);
+                    }
                 }
             }
         });
@@ -661,11 +703,92 @@ pub fn save_doc_position(&self, workspace: &LapceWorkspace, doc: &Document) {
                 path: path.clone(),
                 scroll_offset: (doc.scroll_offset.x, doc.scroll_offset.y),
                 cursor_offset: doc.cursor_offset,
+                folds: doc.folds(),
             };
             let _ = self.save_tx.send(SaveEvent::Buffer(info));
         }
     }
 
+    pub fn save_file_open(&self, workspace: &LapceWorkspace, path: &Path) {
+        let _ = self
+            .save_tx
+            .send(SaveEvent::FileOpen(workspace.clone(), path.to_path_buf()));
+    }
+
+    fn insert_file_open(
+        &self,
+        workspace: &LapceWorkspace,
+        path: PathBuf,
+    ) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let mut files = self.get_file_frecency(workspace).unwrap_or_default();
+        let frecency = files.entry(path).or_default();
+        frecency.count += 1;
+        frecency.last_open = now;
+        if files.len() > FileFrecency::MAX_FILES {
+            if let Some(path) = files
+                .iter()
+                .min_by_key(|(_, frecency)| frecency.last_open)
+                .map(|(path, _)| path.clone())
+            {
+                files.remove(&path);
+            }
+        }
+
+        let files = serde_json::to_string(&files)?;
+        let sled_db = self.get_db()?;
+        sled_db.insert(format!("file_frecency:{}", workspace), files.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    pub fn get_file_frecency(
+        &self,
+        workspace: &LapceWorkspace,
+    ) -> Result<HashMap<PathBuf, FileFrecency>> {
+        let sled_db = self.get_db()?;
+        let files = sled_db
+            .get(format!("file_frecency:{}", workspace))?
+            .ok_or_else(|| anyhow!("can't find file frecency"))?;
+        let files = std::str::from_utf8(&files)?;
+        let files: HashMap<PathBuf, FileFrecency> = serde_json::from_str(files)?;
+        Ok(files)
+    }
+
+    /// Remembers a command that was run from the command palette
+    pub fn save_command_run(&self, command: &str) {
+        let _ = self
+            .save_tx
+            .send(SaveEvent::CommandRun(command.to_string()));
+    }
+
+    fn insert_command_run(&self, command: String) -> Result<()> {
+        let mut commands = self.get_recent_commands().unwrap_or_default();
+        commands.retain(|c| c != &command);
+        commands.insert(0, command);
+        commands.truncate(MAX_RECENT_COMMANDS);
+
+        let commands = serde_json::to_string(&commands)?;
+        let sled_db = self.get_db()?;
+        sled_db.insert("recent_commands", commands.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    /// The names of the commands last run from the command palette, the most
+    /// recent first
+    pub fn get_recent_commands(&self) -> Result<Vec<String>> {
+        let sled_db = self.get_db()?;
+        let commands = sled_db
+            .get("recent_commands")?
+            .ok_or_else(|| anyhow!("can't find recent commands"))?;
+        let commands = std::str::from_utf8(&commands)?;
+        let commands: Vec<String> = serde_json::from_str(commands)?;
+        Ok(commands)
+    }
+
     pub fn get_tabs_info(&self) -> Result<TabsInfo> {
         let sled_db = self.get_db()?;
         let tabs = sled_db
@@ -17,25 +17,32 @@
 use lapce_core::{
     buffer::{Buffer, DiffLines, InvalLines},
     command::{EditCommand, MultiSelectionCommand},
+    conflict::{find_conflicts, Conflict},
     cursor::{ColPosition, Cursor, CursorMode},
     editor::{EditType, Editor},
+    fold::{FoldRange, FoldedLines, Folds},
+    indent::IndentStyle,
     language::LapceLanguage,
     mode::{Mode, MotionMode},
     movement::{LinePosition, Movement},
     register::{Clipboard, Register, RegisterData},
+    save_action,
     selection::{SelRegion, Selection},
     style::line_styles,
     syntax::Syntax,
     word::WordCursor,
 };
 use lapce_rpc::{
-    buffer::BufferId,
+    buffer::{
+        BufferId, EditorConfigIndentStyle, EditorConfigProperties, LineEnding,
+    },
+    plugin::PluginId,
     proxy::ProxyResponse,
     style::{LineStyle, LineStyles, Style},
 };
 use lsp_types::{
-    CodeActionOrCommand, CodeActionResponse, DiagnosticSeverity, InlayHint,
-    InlayHintLabel,
+    CodeActionOrCommand, CodeActionResponse, CodeLens, DiagnosticSeverity,
+    DiagnosticTag, DocumentHighlight, InlayHint, InlayHintLabel,
 };
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
@@ -46,9 +53,11 @@
 
 use crate::selection_range::SelectionRangeDirection;
 use crate::{
+    code_lens::CodeLenses,
     command::{InitBufferContentCb, LapceUICommand, LAPCE_UI_COMMAND},
-    config::{LapceConfig, LapceTheme},
+    config::{EditorConfig, FinalNewline, LapceConfig, LapceTheme},
     data::{EditorDiagnostic, EditorView},
+    document_highlight::DocumentHighlights,
     editor::{EditorLocation, EditorPosition},
     find::{Find, FindProgress},
     history::DocumentHistory,
@@ -78,6 +87,7 @@ fn put_string(&mut self, s: impl AsRef<str>) {
 pub struct LineExtraStyle {
     pub bg_color: Option<Color>,
     pub under_line: Option<Color>,
+    pub strike_through: Option<Color>,
 }
 
 pub struct TextLayoutLine {
@@ -399,6 +409,13 @@ pub fn end_offset_size_iter(
     }
 }
 
+/// What is done to the text before the document is saved
+pub struct SaveActions {
+    pub trim_trailing_whitespace: bool,
+    /// Whether the final newline is ensured or trimmed, if anything
+    pub final_newline: Option<bool>,
+}
+
 #[derive(Clone)]
 pub struct Document {
     id: BufferId,
@@ -415,12 +432,29 @@ pub struct Document {
     histories: im::HashMap<String, DocumentHistory>,
     pub cursor_offset: usize,
     pub scroll_offset: Vec2,
-    pub code_actions: im::HashMap<usize, CodeActionResponse>,
+    /// The code actions at an offset, with the plugin that provided them to
+    /// run their commands on
+    pub code_actions: im::HashMap<usize, (PluginId, CodeActionResponse)>,
     pub inlay_hints: Option<Spans<InlayHint>>,
     pub diagnostics: Option<Arc<Vec<EditorDiagnostic>>>,
+    /// The merge conflicts left in the file by git
+    pub conflicts: Arc<Vec<Conflict>>,
+    folds: Folds,
+    folded_lines: FoldedLines,
+    /// The folding ranges of the language server, which are used instead of
+    /// the ones of the syntax tree
+    lsp_folding_ranges: Option<Arc<Vec<FoldRange>>>,
+    /// The folds saved for the file, which are restored once it's loaded
+    pub saved_folds: Vec<FoldRange>,
+    code_lens: CodeLenses,
+    /// The occurrences of the symbol at the cursor, which are cleared once the
+    /// text is edited
+    document_highlights: Arc<DocumentHighlights>,
     pub syntax_selection_range: Option<SyntaxSelectionRanges>,
     pub find: Rc<RefCell<Find>>,
     find_progress: Rc<RefCell<FindProgress>>,
+    /// The properties the `.editorconfig` files set for the file
+    editorconfig: EditorConfigProperties,
     pub event_sink: ExtEventSink,
     pub proxy: Arc<LapceProxy>,
 }
@@ -461,8 +495,16 @@ pub fn new(
             code_actions: im::HashMap::new(),
             inlay_hints: None,
             diagnostics: None,
+            conflicts: Arc::new(Vec::new()),
+            folds: Folds::default(),
+            folded_lines: FoldedLines::default(),
+            lsp_folding_ranges: None,
+            saved_folds: Vec::new(),
+            code_lens: CodeLenses::default(),
+            document_highlights: Arc::new(DocumentHighlights::default()),
             find: Rc::new(RefCell::new(Find::new(0))),
             find_progress: Rc::new(RefCell::new(FindProgress::Ready)),
+            editorconfig: EditorConfigProperties::default(),
             event_sink,
             proxy,
             syntax_selection_range: None,
@@ -499,10 +541,123 @@ pub fn rev(&self) -> u64 {
     pub fn init_content(&mut self, content: Rope) {
         self.buffer.init_content(content);
         self.buffer.detect_indent(self.syntax.as_ref());
+        self.apply_editorconfig_indent();
         self.loaded = true;
+        let last_line = self.buffer.last_line();
+        for range in std::mem::take(&mut self.saved_folds) {
+            if range.end_line <= last_line {
+                self.folds.fold(self.buffer.text(), range);
+            }
+        }
         self.on_update(None);
     }
 
+    pub fn editorconfig(&self) -> &EditorConfigProperties {
+        &self.editorconfig
+    }
+
+    pub fn set_editorconfig(&mut self, editorconfig: EditorConfigProperties) {
+        self.editorconfig = editorconfig;
+        if self.loaded {
+            self.apply_editorconfig_indent();
+            self.clear_text_layout_cache();
+        }
+    }
+
+    /// Uses the indentation of the `.editorconfig` files over the detected
+    /// one, keeping what they don't set
+    fn apply_editorconfig_indent(&mut self) {
+        let detected = self.buffer.indent_style();
+        let spaces = |size: Option<usize>| {
+            let detected = match detected {
+                IndentStyle::Spaces(spaces) => spaces,
+                IndentStyle::Tabs => 4,
+            };
+            IndentStyle::Spaces(
+                size.map(|size| size.clamp(1, 8) as u8).unwrap_or(detected),
+            )
+        };
+        let indent_style = match (self.editorconfig.indent_style, detected) {
+            (Some(EditorConfigIndentStyle::Tab), _) => IndentStyle::Tabs,
+            (Some(EditorConfigIndentStyle::Space), _) => {
+                spaces(self.editorconfig.indent_size)
+            }
+            (None, IndentStyle::Tabs) => IndentStyle::Tabs,
+            (None, IndentStyle::Spaces(_)) => spaces(self.editorconfig.indent_size),
+        };
+        self.buffer.set_indent_style(indent_style);
+    }
+
+    /// The line ending the `.editorconfig` files set, or else the one of the
+    /// first line
+    pub fn line_ending(&self) -> LineEnding {
+        if let Some(line_ending) = self.editorconfig.end_of_line {
+            return line_ending;
+        }
+        match self.buffer.text().lines_raw(..).next() {
+            Some(line) if line.ends_with("\r\n") => LineEnding::CrLf,
+            _ => LineEnding::Lf,
+        }
+    }
+
+    /// The number of spaces a tab is shown as
+    pub fn tab_width(&self, config: &LapceConfig) -> usize {
+        self.editorconfig
+            .tab_width
+            .unwrap_or_else(|| self.editor_config(config).tab_width)
+    }
+
+    /// The edits that trim the trailing whitespace, which is kept inside
+    /// string literals
+    pub fn trailing_whitespace_edits(&self) -> Vec<(Selection, String)> {
+        save_action::trailing_whitespace_edits(self.buffer.text(), |offset| {
+            self.syntax
+                .as_ref()
+                .map(|syntax| syntax.is_in_string(offset))
+                .unwrap_or(false)
+        })
+    }
+
+    /// The edits that add the final newline, or trim it when `ensure` is false
+    pub fn final_newline_edits(&self, ensure: bool) -> Vec<(Selection, String)> {
+        save_action::final_newline_edits(
+            self.buffer.text(),
+            ensure,
+            self.line_ending().as_str(),
+        )
+    }
+
+    /// The edits that convert the line endings to the ones the
+    /// `.editorconfig` files set
+    pub fn line_ending_edits(&self) -> Vec<(Selection, String)> {
+        match self.editorconfig.end_of_line {
+            Some(line_ending) => save_action::line_ending_edits(
+                self.buffer.text(),
+                line_ending.as_str(),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    /// The save actions that tidy the text up, where the `.editorconfig`
+    /// files win over the editor settings
+    pub fn save_actions(&self, config: &LapceConfig) -> SaveActions {
+        let editor_config = self.editor_config(config);
+        SaveActions {
+            trim_trailing_whitespace: self
+                .editorconfig
+                .trim_trailing_whitespace
+                .unwrap_or(editor_config.trim_trailing_whitespace_on_save),
+            final_newline: self.editorconfig.insert_final_newline.or(
+                match editor_config.final_newline_on_save {
+                    FinalNewline::Ensure => Some(true),
+                    FinalNewline::Trim => Some(false),
+                    FinalNewline::Keep => None,
+                },
+            ),
+        }
+    }
+
     pub fn set_language(&mut self, language: LapceLanguage) {
         self.syntax = Some(Syntax::from_language(language));
     }
@@ -580,8 +735,19 @@ pub fn retrieve_file<P: EditorPosition + Send + 'static>(
             let proxy = self.proxy.clone();
             std::thread::spawn(move || {
                 proxy.proxy_rpc.new_buffer(id, path.clone(), move |result| {
-                    if let Ok(ProxyResponse::NewBufferResponse { content }) = result
+                    if let Ok(ProxyResponse::NewBufferResponse {
+                        content,
+                        editorconfig,
+                    }) = result
                     {
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateEditorConfig(
+                                path.clone(),
+                                editorconfig,
+                            ),
+                            Target::Widget(tab_id),
+                        );
                         let _ = event_sink.submit_command(
                             LAPCE_UI_COMMAND,
                             P::init_buffer_content_cmd(
@@ -858,18 +1024,317 @@ pub fn get_inlay_hints(&self) {
         }
     }
 
+    pub fn get_folding_ranges(&self) {
+        if !self.loaded() {
+            return;
+        }
+
+        if let BufferContent::File(path) = self.content() {
+            let tab_id = self.tab_id;
+            let path = path.clone();
+            let rev = self.rev();
+            let event_sink = self.event_sink.clone();
+            self.proxy
+                .proxy_rpc
+                .get_folding_ranges(path.clone(), move |result| {
+                    if let Ok(ProxyResponse::GetFoldingRanges { ranges }) = result {
+                        let ranges = ranges
+                            .into_iter()
+                            .map(|range| {
+                                FoldRange::new(
+                                    range.start_line as usize,
+                                    range.end_line as usize,
+                                )
+                            })
+                            .filter(|range| range.end_line > range.start_line)
+                            .collect();
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateFoldingRanges {
+                                path,
+                                rev,
+                                ranges,
+                            },
+                            Target::Widget(tab_id),
+                        );
+                    }
+                });
+        }
+    }
+
+    pub fn get_code_lens(&self) {
+        if !self.loaded() {
+            return;
+        }
+
+        if let BufferContent::File(path) = self.content() {
+            let tab_id = self.tab_id;
+            let path = path.clone();
+            let rev = self.rev();
+            let event_sink = self.event_sink.clone();
+            self.proxy
+                .proxy_rpc
+                .get_code_lens(path.clone(), move |result| {
+                    if let Ok(ProxyResponse::GetCodeLens { plugin_id, lenses }) =
+                        result
+                    {
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateCodeLens {
+                                path,
+                                rev,
+                                plugin_id,
+                                lenses,
+                            },
+                            Target::Widget(tab_id),
+                        );
+                    }
+                });
+        }
+    }
+
+    /// Send the code lenses of the lines that don't have a command yet to be
+    /// resolved, which is done once they are displayed
+    pub fn resolve_code_lens(&self, lines: &[usize]) {
+        let plugin_id = match self.code_lens.plugin_id {
+            Some(plugin_id) => plugin_id,
+            None => return,
+        };
+        let path = match self.content() {
+            BufferContent::File(path) => path,
+            _ => return,
+        };
+        let rev = self.rev();
+        for (index, lens) in
+            self.code_lens.take_unresolved(self.buffer.text(), lines)
+        {
+            let tab_id = self.tab_id;
+            let path = path.clone();
+            let event_sink = self.event_sink.clone();
+            self.proxy
+                .proxy_rpc
+                .code_lens_resolve(plugin_id, lens, move |result| {
+                    if let Ok(ProxyResponse::CodeLensResolve { lens }) = result {
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::CodeLensResolved {
+                                path,
+                                rev,
+                                index,
+                                lens: *lens,
+                            },
+                            Target::Widget(tab_id),
+                        );
+                    }
+                });
+        }
+    }
+
+    /// Request the occurrences of the symbol at the offset, which are found
+    /// in the syntax tree if the language server doesn't provide any
+    pub fn get_document_highlights(&self, offset: usize) {
+        if !self.loaded() {
+            return;
+        }
+
+        if let BufferContent::File(path) = self.content() {
+            let tab_id = self.tab_id;
+            let path = path.clone();
+            let rev = self.rev();
+            let position = self.buffer.offset_to_position(offset);
+            let event_sink = self.event_sink.clone();
+            self.proxy.proxy_rpc.get_document_highlights(
+                path.clone(),
+                position,
+                move |result| {
+                    let highlights = match result {
+                        Ok(ProxyResponse::GetDocumentHighlights { highlights }) => {
+                            highlights
+                        }
+                        _ => Vec::new(),
+                    };
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::UpdateDocumentHighlights {
+                            path,
+                            rev,
+                            offset,
+                            highlights,
+                        },
+                        Target::Widget(tab_id),
+                    );
+                },
+            );
+        }
+    }
+
     fn on_update(&mut self, deltas: Option<SmallVec<[RopeDelta; 3]>>) {
         self.find.borrow_mut().unset();
+        if !self.document_highlights.is_empty() {
+            self.document_highlights = Arc::new(DocumentHighlights::default());
+        }
         *self.find_progress.borrow_mut() = FindProgress::Started;
+        self.update_folds();
         self.get_inlay_hints();
+        self.get_folding_ranges();
+        // The code lenses of an edit are asked for once the typing stops
+        if deltas.is_none() {
+            self.get_code_lens();
+        }
         self.clear_style_cache();
         self.trigger_syntax_change(deltas);
         self.get_semantic_styles();
         self.clear_sticky_headers_cache();
         self.trigger_head_change();
+        self.update_conflicts();
         self.notify_special();
     }
 
+    fn update_conflicts(&mut self) {
+        if let BufferContent::File(_) = &self.content {
+            let conflicts = find_conflicts(self.buffer.text());
+            if conflicts != *self.conflicts {
+                self.conflicts = Arc::new(conflicts);
+            }
+        }
+    }
+
+    fn update_folds(&mut self) {
+        self.folds.retain_valid(self.buffer.text());
+        self.folded_lines = self.folds.folded_lines(self.buffer.text());
+        self.code_lens
+            .update_lines(self.buffer.text(), &self.folded_lines);
+    }
+
+    /// The regions that can be folded
+    pub fn folding_ranges(&self) -> &[FoldRange] {
+        if let Some(ranges) = self
+            .lsp_folding_ranges
+            .as_ref()
+            .filter(|ranges| !ranges.is_empty())
+        {
+            return ranges;
+        }
+        self.syntax()
+            .map(|syntax| syntax.folding_ranges.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn set_folding_ranges(&mut self, ranges: Vec<FoldRange>) {
+        self.lsp_folding_ranges = Some(Arc::new(ranges));
+    }
+
+    /// The regions that are folded
+    pub fn folds(&self) -> Vec<FoldRange> {
+        self.folds.ranges(self.buffer.text())
+    }
+
+    pub fn folded_lines(&self) -> &FoldedLines {
+        &self.folded_lines
+    }
+
+    pub fn code_lens(&self) -> &CodeLenses {
+        &self.code_lens
+    }
+
+    pub fn set_code_lens(&mut self, plugin_id: PluginId, lenses: Vec<CodeLens>) {
+        let previous = std::mem::take(&mut self.code_lens);
+        self.code_lens = CodeLenses::new(plugin_id, self.buffer.text(), lenses);
+        self.code_lens.keep_resolved(&previous);
+        self.code_lens
+            .update_lines(self.buffer.text(), &self.folded_lines);
+    }
+
+    pub fn update_code_lens(&mut self, index: usize, lens: CodeLens) {
+        self.code_lens.resolve(index, lens);
+    }
+
+    pub fn document_highlights(&self) -> &DocumentHighlights {
+        &self.document_highlights
+    }
+
+    pub fn set_document_highlights(
+        &mut self,
+        offset: usize,
+        highlights: Vec<DocumentHighlight>,
+    ) {
+        let highlights = if highlights.is_empty() {
+            self.syntax()
+                .filter(|syntax| syntax.rev == self.rev())
+                .and_then(|syntax| syntax.identifier_occurrences(offset))
+                .map(DocumentHighlights::from_syntax)
+                .unwrap_or_default()
+        } else {
+            DocumentHighlights::new(&self.buffer, highlights)
+        };
+        if highlights != *self.document_highlights {
+            self.document_highlights = Arc::new(highlights);
+        }
+    }
+
+    /// The row the line is displayed at in the normal view, which skips the
+    /// lines hidden by folds and counts the virtual lines of the code lenses
+    pub fn visual_line(&self, line: usize) -> usize {
+        self.code_lens.visual_line(&self.folded_lines, line)
+    }
+
+    /// The line displayed at the row in the normal view, which is the line
+    /// below for the virtual line of its code lenses
+    pub fn actual_line(&self, row: usize) -> usize {
+        self.code_lens.actual_line(&self.folded_lines, row).0
+    }
+
+    /// The line whose code lenses are displayed at the row
+    pub fn code_lens_line(&self, row: usize) -> Option<usize> {
+        if self.code_lens.is_empty() {
+            return None;
+        }
+        let (line, is_lens) = self.code_lens.actual_line(&self.folded_lines, row);
+        is_lens.then_some(line)
+    }
+
+    /// The number of rows in the normal view
+    pub fn num_visual_lines(&self) -> usize {
+        self.buffer.num_lines() - self.folded_lines.hidden_len()
+            + self.code_lens.lines().len()
+    }
+
+    pub fn is_folded(&self, line: usize) -> bool {
+        self.folds.is_folded(self.buffer.text(), line)
+    }
+
+    pub fn fold(&mut self, range: FoldRange) {
+        self.folds.fold(self.buffer.text(), range);
+        self.update_folds();
+    }
+
+    /// Unfold the regions starting at the line, returning whether there was
+    /// any
+    pub fn unfold(&mut self, line: usize) -> bool {
+        let unfolded = self.folds.unfold(self.buffer.text(), line);
+        self.update_folds();
+        unfolded
+    }
+
+    /// Unfold the regions hiding the line so it's displayed
+    pub fn reveal_line(&mut self, line: usize) {
+        if self.folded_lines.is_hidden(line) {
+            self.folds.unfold_hiding(self.buffer.text(), line);
+            self.update_folds();
+        }
+    }
+
+    pub fn fold_all(&mut self) {
+        let ranges = self.folding_ranges().to_vec();
+        self.folds.fold_all(self.buffer.text(), &ranges);
+        self.update_folds();
+    }
+
+    pub fn unfold_all(&mut self) {
+        self.folds.clear();
+        self.update_folds();
+    }
+
     fn notify_special(&self) {
         match &self.content {
             BufferContent::File(_) => {}
@@ -978,6 +1443,15 @@ pub fn buffer_mut(&mut self) -> &mut Buffer {
         &mut self.buffer
     }
 
+    pub fn language(&self) -> Option<LapceLanguage> {
+        self.syntax.as_ref().map(|syntax| syntax.language)
+    }
+
+    /// The editor settings for the language of the document
+    pub fn editor_config<'a>(&self, config: &'a LapceConfig) -> &'a EditorConfig {
+        config.editor_config(self.language())
+    }
+
     pub fn syntax(&self) -> Option<&Syntax> {
         self.syntax.as_ref()
     }
@@ -1010,7 +1484,7 @@ pub fn line_phantom_text(
     ) -> PhantomTextLine {
         let start_offset = self.buffer.offset_of_line(line);
         let end_offset = self.buffer.offset_of_line(line + 1);
-        let hints = if config.editor.enable_inlay_hints {
+        let hints = if self.editor_config(config).enable_inlay_hints {
             self.inlay_hints.as_ref().map(|hints| {
                 hints.iter_chunks(start_offset..end_offset).filter_map(
                     |(interval, inlay_hint)| {
@@ -1030,7 +1504,7 @@ pub fn line_phantom_text(
             None
         };
 
-        let diagnostics = if config.editor.enable_error_lens {
+        let diagnostics = if self.editor_config(config).enable_error_lens {
             // Is end line a good place to use?
             self.diagnostics.as_ref().map(|diags| {
                 diags.iter().filter(|diag| {
@@ -1056,6 +1530,8 @@ fn apply_deltas(&mut self, deltas: &[(RopeDelta, InvalLines)]) {
             self.update_styles(delta);
             self.update_inlay_hints(delta);
             self.update_diagnostics(delta);
+            self.folds.apply_delta(delta);
+            self.code_lens.apply_delta(delta);
             if let BufferContent::File(path) = &self.content {
                 self.proxy.proxy_rpc.update(
                     path.clone(),
@@ -1084,7 +1560,7 @@ pub fn do_insert(
             &mut self.buffer,
             s,
             self.syntax.as_ref(),
-            config.editor.auto_closing_matching_pairs,
+            self.editor_config(config).auto_closing_matching_pairs,
         );
         self.buffer_mut().set_cursor_before(old_cursor);
         self.buffer_mut().set_cursor_after(cursor.mode.clone());
@@ -1233,9 +1709,9 @@ pub fn do_multi_selection(
                         };
                         let search_str = self.buffer.slice_to_cow(start..end);
                         let search_case_sensitive =
-                            config.editor.multicursor_case_sensitive;
+                            self.editor_config(config).multicursor_case_sensitive;
                         let search_whole_word =
-                            config.editor.multicursor_whole_words;
+                            self.editor_config(config).multicursor_whole_words;
                         let mut find = Find::new(0);
                         find.set_find(
                             &search_str,
@@ -1271,10 +1747,11 @@ pub fn do_multi_selection(
                             let r = selection.last_inserted().unwrap();
                             let search_str =
                                 self.buffer.slice_to_cow(r.min()..r.max());
-                            let search_case_sensitive =
-                                config.editor.multicursor_case_sensitive;
+                            let search_case_sensitive = self
+                                .editor_config(config)
+                                .multicursor_case_sensitive;
                             let search_whole_word =
-                                config.editor.multicursor_whole_words;
+                                self.editor_config(config).multicursor_whole_words;
                             let mut find = Find::new(0);
                             find.set_find(
                                 &search_str,
@@ -1477,7 +1954,9 @@ pub fn line_col_of_point(
                 }
             }
             EditorView::Normal => (
-                (point.y / config.editor.line_height() as f64).floor() as usize,
+                self.actual_line(
+                    (point.y / config.editor.line_height() as f64).floor() as usize,
+                ),
                 config.editor.font_size,
             ),
         };
@@ -1608,7 +2087,7 @@ pub fn points_of_line_col(
                 }
             }
             EditorView::Normal => (
-                config.editor.line_height() * line,
+                config.editor.line_height() * self.visual_line(line),
                 config.editor.line_height(),
                 config.editor.font_size,
             ),
@@ -1785,8 +2264,12 @@ fn new_text_layout(
         let line_content =
             phantom_text.combine_with_text(line_content_original.clone());
 
-        let tab_width =
-            config.tab_width(text, config.editor.font_family(), font_size);
+        let tab_width = config.tab_width(
+            text,
+            config.editor.font_family(),
+            font_size,
+            self.tab_width(config),
+        );
 
         let font_family = if self.content.is_input() {
             config.ui.font_family()
@@ -1823,6 +2306,43 @@ fn new_text_layout(
             }
         }
 
+        // Fade the code that diagnostics tag as unnecessary, and remember the
+        // deprecated code to strike it through
+        let mut deprecated = Vec::new();
+        for diagnostic in self.diagnostics.iter().flat_map(|d| d.iter()) {
+            let tags = match diagnostic.diagnostic.tags.as_ref() {
+                Some(tags) => tags,
+                None => continue,
+            };
+            let (start_line, start_col) =
+                self.buffer.offset_to_line_col(diagnostic.range.0);
+            let (end_line, end_col) =
+                self.buffer.offset_to_line_col(diagnostic.range.1);
+            if start_line > line || end_line < line {
+                continue;
+            }
+            let start = if start_line == line { start_col } else { 0 };
+            let end = if end_line == line {
+                end_col
+            } else {
+                self.buffer.line_end_col(line, true)
+            };
+            let start = phantom_text.col_at(start);
+            let end = phantom_text.col_at(end);
+
+            if tags.contains(&DiagnosticTag::UNNECESSARY) {
+                layout_builder = layout_builder.range_attribute(
+                    start..end,
+                    TextAttribute::TextColor(
+                        config.get_color_unchecked(LapceTheme::EDITOR_DIM).clone(),
+                    ),
+                );
+            }
+            if tags.contains(&DiagnosticTag::DEPRECATED) {
+                deprecated.push((start, end));
+            }
+        }
+
         // Give the inlay hints their styling
         for (offset, size, _, col) in phantom_text.offset_size_iter() {
             let start = col + offset;
@@ -1905,11 +2425,30 @@ fn new_text_layout(
                             .clone(),
                     ),
                     under_line: None,
+                    strike_through: None,
+                },
+            ));
+        }
+
+        for (start, end) in deprecated {
+            let x0 = layout_text.hit_test_text_position(start).point.x;
+            let x1 = layout_text.hit_test_text_position(end).point.x;
+            extra_style.push((
+                x0,
+                Some(x1),
+                LineExtraStyle {
+                    bg_color: None,
+                    under_line: None,
+                    strike_through: Some(
+                        config
+                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                            .clone(),
+                    ),
                 },
             ));
         }
 
-        let is_error_lens_to_eol = config.editor.error_lens_end_of_line;
+        let is_error_lens_to_eol = self.editor_config(config).error_lens_end_of_line;
 
         let mut max_severity = None;
         let mut end_column = None;
@@ -1954,6 +2493,7 @@ fn new_text_layout(
                 LineExtraStyle {
                     bg_color: Some(config.get_color_unchecked(theme_prop).clone()),
                     under_line: None,
+                    strike_through: None,
                 },
             ));
         }
@@ -1962,7 +2502,8 @@ fn new_text_layout(
             &layout_text,
             &line_content,
             config,
-            tab_width,
+            self.editor_config(config),
+            tab_width / self.tab_width(config) as f64,
             text,
             font_size,
         );
@@ -1981,11 +2522,12 @@ fn new_layout_whitespace(
         layout_text: &PietTextLayout,
         line_content: &str,
         config: &LapceConfig,
-        tab_width: f64,
+        editor_config: &EditorConfig,
+        space_width: f64,
         text: &mut PietText,
         font_size: usize,
     ) -> Option<PietTextLayout> {
-        if config.editor.render_whitespace == "none" {
+        if editor_config.render_whitespace == "none" {
             return None;
         }
 
@@ -1994,7 +2536,7 @@ fn new_layout_whitespace(
         let mut render_between = false;
 
         // TODO: render whitespaces only on highlighted text
-        match config.editor.render_whitespace.as_str() {
+        match editor_config.render_whitespace.as_str() {
             "all" => {
                 render_leading = true;
                 render_boundary = true;
@@ -2011,7 +2553,6 @@ fn new_layout_whitespace(
         // Create new line, replacing whitespaces with visible characters
         // and replacing visible characters with spaces.
         let line_count = line_content.chars().count();
-        let space = tab_width / config.editor.tab_width as f64;
         let mut whitespace_buffer: Vec<char> = Vec::new();
         let mut rendered_whitespaces = String::new();
         let mut char_found = false;
@@ -2023,7 +2564,7 @@ fn new_layout_whitespace(
                     // as the source code layout
                     let start = layout_text.hit_test_text_position(ii).point.x;
                     let end = layout_text.hit_test_text_position(ii + 1).point.x;
-                    let spaces = ((end - start) / space) as usize;
+                    let spaces = ((end - start) / space_width) as usize;
                     let buffer_len = whitespace_buffer.len() + (spaces - 1);
                     whitespace_buffer.resize(buffer_len, ' ');
                 }
@@ -2317,7 +2858,12 @@ pub fn move_offset(
                         )
                     }
                     EditorView::Normal => {
-                        (line.saturating_sub(count), config.editor.font_size)
+                        let visual_line = self.folded_lines.visual_line(line);
+                        (
+                            self.folded_lines
+                                .actual_line(visual_line.saturating_sub(count)),
+                            config.editor.font_size,
+                        )
                     }
                 };
 
@@ -2385,7 +2931,13 @@ pub fn move_offset(
                             config.editor.font_size,
                         )
                     }
-                    EditorView::Normal => (line + count, config.editor.font_size),
+                    EditorView::Normal => {
+                        let visual_line = self.folded_lines.visual_line(line);
+                        (
+                            self.folded_lines.actual_line(visual_line + count),
+                            config.editor.font_size,
+                        )
+                    }
                 };
 
                 let line = line.min(last_line);
@@ -2556,7 +3108,11 @@ pub fn code_action_size(
     ) -> Size {
         let prev_offset = self.buffer.prev_code_boundary(offset);
         let empty_vec = Vec::new();
-        let code_actions = self.code_actions.get(&prev_offset).unwrap_or(&empty_vec);
+        let code_actions = self
+            .code_actions
+            .get(&prev_offset)
+            .map(|(_, actions)| actions)
+            .unwrap_or(&empty_vec);
 
         let action_text_layouts: Vec<PietTextLayout> = code_actions
             .iter()
@@ -0,0 +1,158 @@
+use lapce_core::buffer::Buffer;
+use lsp_types::{DocumentHighlight, DocumentHighlightKind};
+
+/// The occurrences of the symbol at the cursor, which are highlighted in the
+/// editor and on the scroll bar
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentHighlights {
+    /// The ranges of the occurrences sorted by their start, with whether the
+    /// symbol is read or written there
+    ranges: Vec<(usize, usize, DocumentHighlightKind)>,
+}
+
+impl DocumentHighlights {
+    pub fn new(buffer: &Buffer, highlights: Vec<DocumentHighlight>) -> Self {
+        let ranges = highlights
+            .into_iter()
+            .map(|highlight| {
+                (
+                    buffer.offset_of_position(&highlight.range.start),
+                    buffer.offset_of_position(&highlight.range.end),
+                    highlight.kind.unwrap_or(DocumentHighlightKind::TEXT),
+                )
+            })
+            .collect();
+        Self::from_ranges(ranges)
+    }
+
+    /// The occurrences found in the syntax tree, which don't know how the
+    /// symbol is used
+    pub fn from_syntax(ranges: Vec<(usize, usize)>) -> Self {
+        Self::from_ranges(
+            ranges
+                .into_iter()
+                .map(|(start, end)| (start, end, DocumentHighlightKind::TEXT))
+                .collect(),
+        )
+    }
+
+    fn from_ranges(mut ranges: Vec<(usize, usize, DocumentHighlightKind)>) -> Self {
+        ranges.retain(|(start, end, _)| start < end);
+        ranges.sort_by_key(|(start, end, _)| (*start, *end));
+        ranges.dedup_by_key(|(start, _, _)| *start);
+        Self { ranges }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn ranges(&self) -> &[(usize, usize, DocumentHighlightKind)] {
+        &self.ranges
+    }
+
+    /// The index of the occurrence the offset is in, which includes the
+    /// offset right after it
+    fn index_of(&self, offset: usize) -> Option<usize> {
+        self.ranges
+            .iter()
+            .position(|(start, end, _)| *start <= offset && offset <= *end)
+    }
+
+    pub fn contains(&self, offset: usize) -> bool {
+        self.index_of(offset).is_some()
+    }
+
+    /// The start of the occurrence after the offset, wrapping around to the
+    /// first one
+    pub fn next(&self, offset: usize) -> Option<usize> {
+        let index = match self.index_of(offset) {
+            Some(index) => index + 1,
+            None => self
+                .ranges
+                .partition_point(|(start, _, _)| *start <= offset),
+        };
+        let index = if index < self.ranges.len() { index } else { 0 };
+        self.ranges.get(index).map(|(start, _, _)| *start)
+    }
+
+    /// The start of the occurrence before the offset, wrapping around to the
+    /// last one
+    pub fn previous(&self, offset: usize) -> Option<usize> {
+        let index = self
+            .index_of(offset)
+            .unwrap_or_else(|| {
+                self.ranges.partition_point(|(_, end, _)| *end < offset)
+            })
+            .checked_sub(1)
+            .unwrap_or_else(|| self.ranges.len().saturating_sub(1));
+        self.ranges.get(index).map(|(start, _, _)| *start)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::{Position, Range};
+
+    use super::*;
+
+    fn highlight(
+        line: u32,
+        start: u32,
+        end: u32,
+        kind: Option<DocumentHighlightKind>,
+    ) -> DocumentHighlight {
+        DocumentHighlight {
+            range: Range {
+                start: Position {
+                    line,
+                    character: start,
+                },
+                end: Position {
+                    line,
+                    character: end,
+                },
+            },
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let buffer = Buffer::new("let a = 1;\na += a;\n");
+        let highlights = DocumentHighlights::new(
+            &buffer,
+            vec![
+                highlight(1, 5, 6, Some(DocumentHighlightKind::READ)),
+                highlight(0, 4, 5, Some(DocumentHighlightKind::WRITE)),
+                highlight(1, 0, 1, None),
+            ],
+        );
+        assert_eq!(
+            highlights.ranges(),
+            &[
+                (4, 5, DocumentHighlightKind::WRITE),
+                (11, 12, DocumentHighlightKind::TEXT),
+                (16, 17, DocumentHighlightKind::READ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_previous() {
+        let highlights =
+            DocumentHighlights::from_syntax(vec![(10, 13), (0, 3), (20, 23)]);
+        assert!(highlights.contains(13));
+        assert!(!highlights.contains(14));
+
+        assert_eq!(highlights.next(1), Some(10));
+        assert_eq!(highlights.next(15), Some(20));
+        assert_eq!(highlights.next(21), Some(0));
+        assert_eq!(highlights.previous(11), Some(0));
+        assert_eq!(highlights.previous(15), Some(10));
+        assert_eq!(highlights.previous(2), Some(20));
+
+        assert_eq!(DocumentHighlights::default().next(0), None);
+        assert_eq!(DocumentHighlights::default().previous(0), None);
+    }
+}
@@ -2,7 +2,7 @@
 use crate::command::LapceCommand;
 use crate::command::LAPCE_COMMAND;
 use crate::command::LAPCE_SAVE_FILE_AS;
-use crate::command::{CommandExecuted, CommandKind};
+use crate::command::{CommandArgs, CommandExecuted, CommandKind};
 use crate::completion::{CompletionData, CompletionStatus, Snippet};
 use crate::config::LapceConfig;
 use crate::data::EditorView;
@@ -14,14 +14,16 @@
 use crate::document::BufferContent;
 use crate::document::Document;
 use crate::document::LocalBufferKind;
+use crate::hierarchy::HierarchyKind;
 use crate::hover::HoverData;
 use crate::hover::HoverStatus;
 use crate::keypress::KeyMap;
-use crate::keypress::KeyPressFocus;
+use crate::keypress::{ContextValue, KeyPressFocus};
 use crate::palette::PaletteData;
 use crate::proxy::path_from_url;
 use crate::rename::RenameData;
 use crate::selection_range::SelectionRangeDirection;
+use crate::signature::SignatureState;
 use crate::{
     command::{
         EnsureVisiblePosition, InitBufferContent, LapceUICommand, LAPCE_UI_COMMAND,
@@ -46,19 +48,25 @@
 use lapce_core::command::{
     EditCommand, FocusCommand, MotionModeCommand, MultiSelectionCommand,
 };
+use lapce_core::conflict::ConflictResolution;
 use lapce_core::editor::EditType;
 use lapce_core::mode::{Mode, MotionMode};
 use lapce_core::selection::InsertDrift;
 use lapce_core::selection::Selection;
 pub use lapce_core::syntax::Syntax;
+use lapce_rpc::plugin::PluginId;
 use lapce_rpc::proxy::ProxyResponse;
+use lapce_rpc::RpcError;
 use lsp_types::request::GotoTypeDefinitionResponse;
+use lsp_types::CodeActionKind;
 use lsp_types::CodeActionOrCommand;
 use lsp_types::CompletionTextEdit;
 use lsp_types::DocumentChangeOperation;
 use lsp_types::DocumentChanges;
 use lsp_types::OneOf;
 use lsp_types::ResourceOp;
+use lsp_types::SignatureHelpContext;
+use lsp_types::SignatureHelpTriggerKind;
 use lsp_types::TextEdit;
 use lsp_types::Url;
 use lsp_types::WorkspaceEdit;
@@ -71,7 +79,10 @@
 use std::thread;
 use std::{collections::HashMap, sync::Arc};
 use std::{iter::Iterator, path::PathBuf};
-use std::{str::FromStr, time::Duration};
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
 use xi_rope::Rope;
 use xi_rope::{RopeDelta, Transformer};
 
@@ -225,6 +236,7 @@ pub struct LapceEditorBufferData {
     pub doc: Arc<Document>,
     pub completion: Arc<CompletionData>,
     pub hover: Arc<HoverData>,
+    pub signature: Arc<SignatureState>,
     pub rename: Arc<RenameData>,
     pub main_split: LapceMainSplitData,
     pub focus_area: FocusArea,
@@ -241,6 +253,25 @@ fn doc_mut(&mut self) -> &mut Document {
         Arc::make_mut(&mut self.doc)
     }
 
+    /// Sends the code lenses in view to be resolved, which is done once for
+    /// each of them, as they are scrolled to or come in
+    pub fn resolve_visible_code_lens(&self) {
+        if !self.editor.view.is_normal() || self.doc.code_lens().is_empty() {
+            return;
+        }
+
+        let line_height = self.config.editor.line_height() as f64;
+        let scroll_offset = self.editor.scroll_offset;
+        let start_row = (scroll_offset.y / line_height).floor() as usize;
+        let end_row = ((scroll_offset.y + self.editor.size.borrow().height)
+            / line_height)
+            .ceil() as usize;
+        let lines: Vec<usize> = (start_row..end_row + 1)
+            .filter_map(|row| self.doc.code_lens_line(row))
+            .collect();
+        self.doc.resolve_code_lens(&lines);
+    }
+
     pub fn sync_buffer_position(&mut self, scroll_offset: Vec2) {
         let cursor_offset = self.editor.cursor.offset();
         if self.doc.cursor_offset != cursor_offset
@@ -310,8 +341,10 @@ pub fn get_code_actions(&self, ctx: &mut EventCtx) {
                     path.clone(),
                     position,
                     move |result| {
-                        if let Ok(ProxyResponse::GetCodeActionsResponse { resp }) =
-                            result
+                        if let Ok(ProxyResponse::GetCodeActionsResponse {
+                            plugin_id,
+                            resp,
+                        }) = result
                         {
                             let _ = event_sink.submit_command(
                                 LAPCE_UI_COMMAND,
@@ -319,6 +352,7 @@ pub fn get_code_actions(&self, ctx: &mut EventCtx) {
                                     path,
                                     rev,
                                     prev_offset,
+                                    plugin_id,
                                     resp,
                                 ),
                                 Target::Auto,
@@ -358,6 +392,11 @@ fn has_hover(&self) -> bool {
         self.hover.status != HoverStatus::Inactive && !self.hover.is_empty()
     }
 
+    fn has_signature(&self) -> bool {
+        self.signature.is_active()
+            && self.signature.editor_view_id == self.editor.view_id
+    }
+
     fn has_rename(&self) -> bool {
         self.rename.active
     }
@@ -367,76 +406,57 @@ pub fn apply_workspace_edit(
         ctx: &mut EventCtx,
         edit: &WorkspaceEdit,
     ) {
-        if let Some(DocumentChanges::Operations(op)) = edit.document_changes.as_ref()
-        {
-            op.iter()
-                .flat_map(|op| match op {
-                    DocumentChangeOperation::Op(op) => Some(op),
-                    _ => None,
-                })
-                .flat_map(workspace_operation)
-                .map(|cmd| Command::new(LAPCE_UI_COMMAND, cmd, Target::Auto))
-                .for_each(|cmd| ctx.submit_command(cmd));
-        }
-        if let BufferContent::File(path) = &self.editor.content {
-            if let Some(edits) = workspace_edits(edit) {
-                for (url, edits) in edits {
-                    if url_matches_path(path, &url) {
-                        apply_edit(&mut self.main_split, path, &edits);
-                    } else if let Ok(url_path) = url.to_file_path() {
-                        // If it is not for the file we have open then we assume that
-                        // we may have to load it
-                        // So we jump to the location that the edits were at.
-                        // TODO: url_matches_path checks if the url path 'goes back' to the original url
-                        // Should we do that here?
-
-                        // We choose to just jump to the start of the first edit. The edit function will jump
-                        // appropriately when we actually apply the edits.
-                        let position = edits.get(0).map(|edit| edit.range.start);
-                        let location = EditorLocation {
-                            path: url_path.clone(),
-                            position,
-                            scroll_offset: None,
-                            history: None,
-                        };
-
-                        // Note: For some reason Rust is unsure about what type the arguments are if we don't specify them
-                        // Perhaps this could be fixed by being very explicit about the lifetimes in the jump_to_location_cb fn?
-                        let callback = move |_: &mut EventCtx, main_split: &mut LapceMainSplitData| {
-                            // The file has been loaded, so we want to apply the edits now.
-                            apply_edit(main_split, &url_path, &edits);
-                        };
-                        self.main_split.jump_to_location_cb(
-                            ctx,
-                            None,
-                            false,
-                            location,
-                            &self.config,
-                            Some(callback),
-                        );
-                    } else {
-                        log::warn!("Text edits failed to apply to URL {url:?} because it was not found");
-                    }
-                }
-            }
-        }
+        self.main_split
+            .apply_workspace_edit(ctx, edit, None, &self.config);
     }
 
     pub fn run_code_action(
         &mut self,
         ctx: &mut EventCtx,
         action: &CodeActionOrCommand,
+        plugin_id: PluginId,
     ) {
         match action {
-            CodeActionOrCommand::Command(_cmd) => {}
+            CodeActionOrCommand::Command(cmd) => {
+                self.execute_command(plugin_id, cmd);
+            }
             CodeActionOrCommand::CodeAction(action) => {
                 if let Some(edit) = action.edit.as_ref() {
                     self.apply_workspace_edit(ctx, edit);
                 }
+                // The command runs after the edit is applied
+                if let Some(cmd) = action.command.as_ref() {
+                    self.execute_command(plugin_id, cmd);
+                }
             }
         }
     }
 
+    pub fn run_code_lens(&self, index: usize) {
+        let code_lens = self.doc.code_lens();
+        if let (Some(plugin_id), Some(command)) = (
+            code_lens.plugin_id,
+            code_lens.get(index).and_then(|lens| lens.command.as_ref()),
+        ) {
+            self.execute_command(plugin_id, command);
+        }
+    }
+
+    /// Run the command on the plugin that provided it. The server applies its
+    /// changes with `workspace/applyEdit` requests
+    pub fn execute_command(&self, plugin_id: PluginId, cmd: &lsp_types::Command) {
+        let title = cmd.title.clone();
+        self.proxy.proxy_rpc.execute_command(
+            plugin_id,
+            cmd.clone(),
+            move |result| {
+                if let Err(err) = result {
+                    log::warn!("command {title} failed: {}", err.message);
+                }
+            },
+        );
+    }
+
     pub fn apply_completion_item(&mut self, item: &CompletionItem) -> Result<()> {
         let additional_edit: Option<Vec<_>> =
             item.additional_text_edits.as_ref().map(|edits| {
@@ -585,6 +605,39 @@ pub fn apply_completion_item(&mut self, item: &CompletionItem) -> Result<()> {
         Ok(())
     }
 
+    /// Replaces the selection with the snippet, and selects its first
+    /// placeholder
+    fn insert_snippet(&mut self, snippet: &str) -> Result<()> {
+        let snippet = Snippet::from_str(snippet)?;
+        let text = snippet.text();
+        let selection = self.editor.cursor.edit_selection(self.doc.buffer());
+        let start_offset = selection.min_offset();
+        let (delta, inval_lines) = Arc::make_mut(&mut self.doc)
+            .do_raw_edit(&[(&selection, text.as_str())], EditType::Other);
+        let selection = selection.apply_delta(&delta, true, InsertDrift::Default);
+
+        let mut transformer = Transformer::new(&delta);
+        let offset = transformer.transform(start_offset, false);
+        let snippet_tabs = snippet.tabs(offset);
+
+        if snippet_tabs.is_empty() {
+            Arc::make_mut(&mut self.editor)
+                .cursor
+                .update_selection(self.doc.buffer(), selection);
+            self.apply_deltas(&[(delta, inval_lines)]);
+            return Ok(());
+        }
+
+        let (_tab, (start, end)) = &snippet_tabs[0];
+        let mut selection = Selection::new();
+        selection
+            .add_region(lapce_core::selection::SelRegion::new(*start, *end, None));
+        Arc::make_mut(&mut self.editor).cursor.set_insert(selection);
+        self.apply_deltas(&[(delta, inval_lines)]);
+        Arc::make_mut(&mut self.editor).add_snippet_placeholders(snippet_tabs);
+        Ok(())
+    }
+
     pub fn cancel_completion(&mut self) {
         if self.completion.status == CompletionStatus::Inactive {
             return;
@@ -598,6 +651,79 @@ pub fn cancel_hover(&mut self) {
         hover.cancel();
     }
 
+    pub fn cancel_signature(&mut self) {
+        if !self.signature.is_active()
+            && self.signature.received_id == self.signature.request_id
+        {
+            return;
+        }
+        Arc::make_mut(&mut self.signature).clear();
+    }
+
+    /// Fold the innermost region around the line that isn't folded yet
+    pub fn fold(&mut self, line: usize) {
+        let range = self
+            .doc
+            .folding_ranges()
+            .iter()
+            .filter(|range| {
+                range.contains(line) && !self.doc.is_folded(range.start_line)
+            })
+            .max_by_key(|range| range.start_line)
+            .copied();
+        if let Some(range) = range {
+            Arc::make_mut(&mut self.doc).fold(range);
+            self.move_cursor_out_of_folds();
+            self.save_folds();
+        }
+    }
+
+    pub fn toggle_fold(&mut self, line: usize) {
+        if Arc::make_mut(&mut self.doc).unfold(line) {
+            self.save_folds();
+        } else {
+            self.fold(line);
+        }
+    }
+
+    /// The folds are saved with the position in the file
+    fn save_folds(&self) {
+        self.main_split
+            .db
+            .save_doc_position(&self.main_split.workspace, &self.doc);
+    }
+
+    /// Move the cursor to the line displayed in place of the hidden line it's
+    /// on
+    fn move_cursor_out_of_folds(&mut self) {
+        let line = self
+            .doc
+            .buffer()
+            .line_of_offset(self.editor.cursor.offset());
+        let visible_line = self.doc.folded_lines().visible_line(line);
+        if visible_line != line {
+            let offset = self
+                .doc
+                .buffer()
+                .first_non_blank_character_on_line(visible_line);
+            Arc::make_mut(&mut self.editor)
+                .cursor
+                .set_offset(offset, false, false);
+        }
+    }
+
+    /// Unfold the regions the cursor was moved into, so it's never hidden
+    pub fn reveal_cursor(&mut self) {
+        let line = self
+            .doc
+            .buffer()
+            .line_of_offset(self.editor.cursor.offset());
+        if self.doc.folded_lines().is_hidden(line) {
+            Arc::make_mut(&mut self.doc).reveal_line(line);
+            self.save_folds();
+        }
+    }
+
     pub fn cancel_rename(&mut self, ctx: &mut EventCtx) {
         let rename = Arc::make_mut(&mut self.rename);
         rename.cancel();
@@ -762,6 +888,58 @@ fn update_completion(
         }
     }
 
+    /// Ask the language server for the signature of the call at the cursor,
+    /// which updates the signature help instead if it's already showing
+    fn update_signature(
+        &mut self,
+        ctx: &mut EventCtx,
+        trigger_kind: SignatureHelpTriggerKind,
+        trigger_character: Option<String>,
+    ) {
+        if self.get_mode() != Mode::Insert {
+            self.cancel_signature();
+            return;
+        }
+        if !self.doc.loaded() {
+            return;
+        }
+        let path = match self.doc.content() {
+            BufferContent::File(path) => path.clone(),
+            _ => return,
+        };
+
+        let is_retrigger = self.has_signature();
+        let offset = self.editor.cursor.offset();
+        let position = self.doc.buffer().offset_to_position(offset);
+        let context = SignatureHelpContext {
+            trigger_kind,
+            trigger_character,
+            is_retrigger,
+            active_signature_help: if is_retrigger {
+                self.signature.active_signature_help()
+            } else {
+                None
+            },
+        };
+        let signature = Arc::make_mut(&mut self.signature);
+        if !is_retrigger {
+            // It's showing for another editor
+            if signature.is_active() {
+                signature.clear();
+            }
+            signature.editor_view_id = self.editor.view_id;
+            signature.buffer_id = self.doc.id();
+        }
+        signature.request(
+            self.proxy.clone(),
+            path,
+            offset,
+            position,
+            context,
+            ctx.get_external_handle(),
+        );
+    }
+
     /// return true if there's existing hover and it's not changed
     pub fn check_hover(
         &mut self,
@@ -968,6 +1146,46 @@ fn next_error(&mut self, ctx: &mut EventCtx) {
         }
     }
 
+    /// Asks for the items of the call or type hierarchy at the cursor, which
+    /// then show up in the hierarchy's panel
+    fn prepare_hierarchy(&mut self, ctx: &mut EventCtx, kind: HierarchyKind) {
+        if let BufferContent::File(path) = self.doc.content() {
+            let offset = self.editor.cursor.offset();
+            let position = self.doc.buffer().offset_to_position(offset);
+            let tab_id = *self.main_split.tab_id;
+            let event_sink = ctx.get_external_handle();
+            let f = move |result: Result<ProxyResponse, RpcError>| {
+                if let Ok(ProxyResponse::PrepareHierarchy { plugin_id, items }) =
+                    result
+                {
+                    if !items.is_empty() {
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateHierarchy {
+                                kind,
+                                plugin_id,
+                                items,
+                            },
+                            Target::Widget(tab_id),
+                        );
+                    }
+                }
+            };
+            match kind {
+                HierarchyKind::Call => self.proxy.proxy_rpc.prepare_call_hierarchy(
+                    path.clone(),
+                    position,
+                    f,
+                ),
+                HierarchyKind::Type => self.proxy.proxy_rpc.prepare_type_hierarchy(
+                    path.clone(),
+                    position,
+                    f,
+                ),
+            }
+        }
+    }
+
     fn jump_location_forward(&mut self, ctx: &mut EventCtx) -> Option<()> {
         if self.main_split.locations.is_empty() {
             return None;
@@ -1104,7 +1322,7 @@ fn scroll(
         ));
     }
 
-    pub fn current_code_actions(&self) -> Option<&CodeActionResponse> {
+    pub fn current_code_actions(&self) -> Option<&(PluginId, CodeActionResponse)> {
         let offset = self.editor.cursor.offset();
         let prev_offset = self.doc.buffer().prev_code_boundary(offset);
         self.doc.code_actions.get(&prev_offset)
@@ -1288,7 +1506,158 @@ fn save(&mut self, ctx: &mut EventCtx, exit: bool) {
         }
 
         if let BufferContent::File(path) = self.doc.content() {
-            let format_on_save = self.config.editor.format_on_save;
+            let kind = &self
+                .doc
+                .editor_config(&self.config)
+                .code_action_kind_on_save;
+            if kind.is_empty() {
+                self.format_and_save(ctx, exit);
+                return;
+            }
+
+            let kind = CodeActionKind::from(kind.clone());
+            let path = path.clone();
+            let proxy = self.proxy.clone();
+            let rev = self.doc.rev();
+            let event_sink = ctx.get_external_handle();
+            let view_id = self.editor.view_id;
+            thread::spawn(move || {
+                let result = code_actions_on_save(&proxy, path, kind);
+                let _ = event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::CodeActionsOnSave { rev, result, exit },
+                    Target::Widget(view_id),
+                );
+            });
+        } else if let BufferContent::Scratch(..) = self.doc.content() {
+            let content = self.doc.content().clone();
+            let view_id = self.editor.view_id;
+            self.main_split.current_save_as =
+                Some(Arc::new((content, view_id, exit)));
+            let options =
+                FileDialogOptions::new().accept_command(LAPCE_SAVE_FILE_AS);
+            ctx.submit_command(druid::commands::SHOW_SAVE_PANEL.with(options));
+        }
+    }
+
+    /// Applies the edits of the code actions to run on save, unless the
+    /// document has changed since, and then runs their commands before the
+    /// document is formatted and saved
+    pub fn apply_code_actions_on_save(
+        &mut self,
+        ctx: &mut EventCtx,
+        rev: u64,
+        result: &Result<Vec<(PluginId, CodeActionOrCommand)>>,
+        exit: bool,
+    ) {
+        let path = match self.doc.content() {
+            BufferContent::File(path) => path.clone(),
+            _ => return,
+        };
+
+        match result {
+            Ok(actions) if self.doc.rev() == rev => {
+                let mut edits = Vec::new();
+                let mut commands = Vec::new();
+                for (plugin_id, action) in actions {
+                    match action {
+                        CodeActionOrCommand::Command(command) => {
+                            commands.push((*plugin_id, command.clone()));
+                        }
+                        CodeActionOrCommand::CodeAction(action) => {
+                            let action_edits = action
+                                .edit
+                                .as_ref()
+                                .and_then(workspace_edits)
+                                .and_then(|edits| {
+                                    edits.into_iter().find(|(url, _)| {
+                                        url_matches_path(&path, url)
+                                    })
+                                });
+                            if let Some((_, action_edits)) = action_edits {
+                                edits.extend(action_edits);
+                            }
+                            if let Some(command) = action.command.as_ref() {
+                                commands.push((*plugin_id, command.clone()));
+                            }
+                        }
+                    }
+                }
+
+                // The actions are computed on the same text, so the edits of
+                // one that overlap the edits of another are left out
+                if !edits.is_empty() {
+                    apply_edit(
+                        &mut self.main_split,
+                        &path,
+                        &merge_range_edits(edits),
+                    );
+                    self.doc = self.main_split.open_docs.get(&path).unwrap().clone();
+                }
+
+                if !commands.is_empty() {
+                    self.execute_commands_and_save(ctx, commands, exit);
+                    return;
+                }
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("code actions on save failed: {err}"),
+        }
+
+        self.format_and_save(ctx, exit);
+    }
+
+    /// Runs the commands of the code actions on save one after the other,
+    /// waiting for each so that the edits the servers apply with
+    /// `workspace/applyEdit` come before the document is formatted and saved
+    fn execute_commands_and_save(
+        &self,
+        ctx: &mut EventCtx,
+        commands: Vec<(PluginId, lsp_types::Command)>,
+        exit: bool,
+    ) {
+        let proxy = self.proxy.clone();
+        let event_sink = ctx.get_external_handle();
+        let view_id = self.editor.view_id;
+        thread::spawn(move || {
+            for (plugin_id, command) in commands {
+                let title = command.title.clone();
+                let (sender, receiver) = bounded(1);
+                proxy.proxy_rpc.execute_command(
+                    plugin_id,
+                    command,
+                    Box::new(move |result| {
+                        let _ = sender.send(result);
+                    }),
+                );
+                match receiver.recv_timeout(Duration::from_secs(1)) {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(err)) => {
+                        log::warn!("command {title} failed: {}", err.message)
+                    }
+                    Err(err) => log::warn!("command {title} failed: {err}"),
+                }
+            }
+
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::FormatAndSave { exit },
+                Target::Widget(view_id),
+            );
+        });
+    }
+
+    pub fn format_and_save(&mut self, ctx: &mut EventCtx, exit: bool) {
+        if let BufferContent::File(path) = self.doc.content() {
+            let editor_config = self.doc.editor_config(&self.config);
+            let format_on_save = editor_config.format_on_save;
+            let modified_lines = if format_on_save
+                && editor_config.format_on_save_modified_lines_only
+            {
+                self.modified_line_ranges()
+            } else {
+                None
+            };
             let path = path.clone();
             let proxy = self.proxy.clone();
             let rev = self.doc.rev();
@@ -1297,6 +1666,19 @@ fn save(&mut self, ctx: &mut EventCtx, exit: bool) {
             let tab_id = self.main_split.tab_id.clone();
             let (sender, receiver) = bounded(1);
             thread::spawn(move || {
+                if let Some(ranges) = modified_lines {
+                    let result = range_formatting(&proxy, &path, ranges);
+                    let exit = if exit { Some(view_id) } else { None };
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DocumentFormatAndSave(
+                            path, rev, result, exit,
+                        ),
+                        Target::Widget(*tab_id),
+                    );
+                    return;
+                }
+
                 proxy.proxy_rpc.get_document_formatting(
                     path.clone(),
                     Box::new(move |result| {
@@ -1334,17 +1716,64 @@ fn save(&mut self, ctx: &mut EventCtx, exit: bool) {
                     Target::Widget(*tab_id),
                 );
             });
-        } else if let BufferContent::Scratch(..) = self.doc.content() {
-            let content = self.doc.content().clone();
-            let view_id = self.editor.view_id;
-            self.main_split.current_save_as =
-                Some(Arc::new((content, view_id, exit)));
-            let options =
-                FileDialogOptions::new().accept_command(LAPCE_SAVE_FILE_AS);
-            ctx.submit_command(druid::commands::SHOW_SAVE_PANEL.with(options));
         }
     }
 
+    /// The ranges of the lines changed since the last commit, or `None` if
+    /// the file isn't in it and the whole document is new
+    fn modified_line_ranges(&self) -> Option<Vec<lsp_types::Range>> {
+        let history = self.doc.get_history("head")?;
+        if !history.is_loaded() {
+            return None;
+        }
+        let buffer = self.doc.buffer();
+        let ranges = merge_line_ranges(history.changed_lines())
+            .into_iter()
+            .map(|lines| lsp_types::Range {
+                start: buffer.offset_to_position(buffer.offset_of_line(lines.start)),
+                end: buffer.offset_to_position(buffer.offset_of_line(lines.end)),
+            })
+            .collect();
+        Some(ranges)
+    }
+
+    /// Lets the language server format the code around a typed character,
+    /// which the proxy only passes on for the server's trigger characters
+    fn format_on_type(&self, ctx: &mut EventCtx, ch: &str) {
+        if !self.doc.editor_config(&self.config).format_on_type
+            || self.get_mode() != Mode::Insert
+            || !self.doc.loaded()
+        {
+            return;
+        }
+        let path = match self.doc.content() {
+            BufferContent::File(path) => path.clone(),
+            _ => return,
+        };
+
+        let rev = self.doc.rev();
+        let offset = self.editor.cursor.offset();
+        let position = self.doc.buffer().offset_to_position(offset);
+        let tab_id = *self.main_split.tab_id;
+        let event_sink = ctx.get_external_handle();
+        self.proxy.proxy_rpc.get_on_type_formatting(
+            path.clone(),
+            position,
+            ch.to_string(),
+            move |result| {
+                if let Ok(ProxyResponse::GetDocumentFormatting { edits }) = result {
+                    if !edits.is_empty() {
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::DocumentFormat(path, rev, Ok(edits)),
+                            Target::Widget(tab_id),
+                        );
+                    }
+                }
+            },
+        );
+    }
+
     fn run_move_command(
         &mut self,
         ctx: &mut EventCtx,
@@ -1391,6 +1820,13 @@ fn run_move_command(
         }
         self.cancel_completion();
         self.cancel_hover();
+        if self.has_signature() {
+            self.update_signature(
+                ctx,
+                SignatureHelpTriggerKind::CONTENT_CHANGE,
+                None,
+            );
+        }
         CommandExecuted::Yes
     }
 
@@ -1425,6 +1861,16 @@ fn run_edit_command(
             self.cancel_completion();
         }
         self.apply_deltas(&deltas);
+        if self.has_signature() {
+            self.update_signature(
+                ctx,
+                SignatureHelpTriggerKind::CONTENT_CHANGE,
+                None,
+            );
+        }
+        if cmd == &EditCommand::InsertNewLine && !deltas.is_empty() {
+            self.format_on_type(ctx, "\n");
+        }
 
         CommandExecuted::Yes
     }
@@ -1455,6 +1901,9 @@ fn run_focus_command(
                 if self.has_hover() {
                     self.cancel_hover();
                 }
+                if self.has_signature() {
+                    self.cancel_signature();
+                }
                 if self.is_rename() {
                     self.cancel_rename(ctx);
                 }
@@ -1859,6 +2308,15 @@ fn run_focus_command(
                 // we allow empty inputs to allow for cases where the user wants to get the autocompletion beforehand
                 self.update_completion(ctx, true);
             }
+            GetSignature => {
+                self.update_signature(ctx, SignatureHelpTriggerKind::INVOKED, None);
+            }
+            NextSignature => {
+                Arc::make_mut(&mut self.signature).next_signature();
+            }
+            PreviousSignature => {
+                Arc::make_mut(&mut self.signature).previous_signature();
+            }
             GotoDefinition => {
                 if let BufferContent::File(path) = self.doc.content() {
                     let offset = self.editor.cursor.offset();
@@ -2016,6 +2474,53 @@ fn run_focus_command(
                     );
                 }
             }
+            GotoImplementation => {
+                if let BufferContent::File(path) = self.doc.content() {
+                    let offset = self.editor.cursor.offset();
+                    let event_sink = ctx.get_external_handle();
+                    let position = self.doc.buffer().offset_to_position(offset);
+                    self.proxy.proxy_rpc.get_implementation(
+                        path.clone(),
+                        position,
+                        move |result| {
+                            if let Ok(ProxyResponse::GetImplementation {
+                                locations,
+                            }) = result
+                            {
+                                process_get_references(
+                                    offset, locations, event_sink,
+                                );
+                            }
+                        },
+                    );
+                }
+            }
+            GotoDeclaration => {
+                if let BufferContent::File(path) = self.doc.content() {
+                    let offset = self.editor.cursor.offset();
+                    let event_sink = ctx.get_external_handle();
+                    let position = self.doc.buffer().offset_to_position(offset);
+                    self.proxy.proxy_rpc.get_declaration(
+                        path.clone(),
+                        position,
+                        move |result| {
+                            if let Ok(ProxyResponse::GetDeclaration { locations }) =
+                                result
+                            {
+                                process_get_references(
+                                    offset, locations, event_sink,
+                                );
+                            }
+                        },
+                    );
+                }
+            }
+            ShowCallHierarchy => {
+                self.prepare_hierarchy(ctx, HierarchyKind::Call);
+            }
+            ShowTypeHierarchy => {
+                self.prepare_hierarchy(ctx, HierarchyKind::Type);
+            }
             JumpLocationBackward => {
                 self.jump_location_backward(ctx);
             }
@@ -2028,6 +2533,55 @@ fn run_focus_command(
             NextDiff => {
                 self.next_diff(ctx);
             }
+            NextOccurrence => {
+                let offset = self.editor.cursor.offset();
+                if let Some(offset) = self.doc.document_highlights().next(offset) {
+                    Arc::make_mut(&mut self.editor)
+                        .cursor
+                        .set_offset(offset, false, false);
+                }
+            }
+            PreviousOccurrence => {
+                let offset = self.editor.cursor.offset();
+                if let Some(offset) = self.doc.document_highlights().previous(offset)
+                {
+                    Arc::make_mut(&mut self.editor)
+                        .cursor
+                        .set_offset(offset, false, false);
+                }
+            }
+            Fold => {
+                let line = self
+                    .doc
+                    .buffer()
+                    .line_of_offset(self.editor.cursor.offset());
+                self.fold(line);
+            }
+            Unfold => {
+                let line = self
+                    .doc
+                    .buffer()
+                    .line_of_offset(self.editor.cursor.offset());
+                if Arc::make_mut(&mut self.doc).unfold(line) {
+                    self.save_folds();
+                }
+            }
+            ToggleFold => {
+                let line = self
+                    .doc
+                    .buffer()
+                    .line_of_offset(self.editor.cursor.offset());
+                self.toggle_fold(line);
+            }
+            FoldAll => {
+                Arc::make_mut(&mut self.doc).fold_all();
+                self.move_cursor_out_of_folds();
+                self.save_folds();
+            }
+            UnfoldAll => {
+                Arc::make_mut(&mut self.doc).unfold_all();
+                self.save_folds();
+            }
             ToggleCodeLens => {
                 let editor = Arc::make_mut(&mut self.editor);
                 editor.view = match editor.view {
@@ -2077,6 +2631,43 @@ fn run_focus_command(
                     });
                 }
             }
+            FormatSelection => {
+                if let BufferContent::File(path) = self.doc.content() {
+                    let buffer = self.doc.buffer();
+                    let selection = self.editor.cursor.edit_selection(buffer);
+                    if selection.is_empty() {
+                        return CommandExecuted::Yes;
+                    }
+                    // Without a selection it formats the line of the cursor
+                    let (start, end) = if selection.is_caret() {
+                        let line =
+                            buffer.line_of_offset(self.editor.cursor.offset());
+                        (
+                            buffer.offset_of_line(line),
+                            buffer.offset_of_line(line + 1),
+                        )
+                    } else {
+                        (selection.min_offset(), selection.max_offset())
+                    };
+                    let range = lsp_types::Range {
+                        start: buffer.offset_to_position(start),
+                        end: buffer.offset_to_position(end),
+                    };
+                    let path = path.clone();
+                    let proxy = self.proxy.clone();
+                    let rev = self.doc.rev();
+                    let event_sink = ctx.get_external_handle();
+                    let tab_id = self.main_split.tab_id.clone();
+                    thread::spawn(move || {
+                        let result = range_formatting(&proxy, &path, vec![range]);
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::DocumentFormat(path, rev, result),
+                            Target::Widget(*tab_id),
+                        );
+                    });
+                }
+            }
             Search => {
                 Arc::make_mut(&mut self.find).visual = true;
                 let region = match &self.editor.cursor.mode {
@@ -2230,11 +2821,43 @@ fn run_focus_command(
             }
             SelectPreviousSyntaxItem => self
                 .run_selection_range_command(ctx, SelectionRangeDirection::Previous),
+            AcceptConflictCurrent => {
+                self.resolve_conflict(ConflictResolution::Current);
+            }
+            AcceptConflictIncoming => {
+                self.resolve_conflict(ConflictResolution::Incoming);
+            }
+            AcceptConflictBoth => {
+                self.resolve_conflict(ConflictResolution::Both);
+            }
             _ => return CommandExecuted::No,
         }
         CommandExecuted::Yes
     }
 
+    /// Replace the merge conflict the cursor is in with the change to keep
+    fn resolve_conflict(&mut self, resolution: ConflictResolution) {
+        let line = self
+            .doc
+            .buffer()
+            .line_of_offset(self.editor.cursor.offset());
+        let conflict = match self.doc.conflicts.iter().find(|c| c.contains(line)) {
+            Some(conflict) => *conflict,
+            None => return,
+        };
+
+        let text = self.doc.buffer().text();
+        let range = conflict.range(text);
+        let content = conflict.resolve(text, resolution);
+        let selection = Selection::region(range.start, range.end);
+        let (delta, inval_lines) = Arc::make_mut(&mut self.doc)
+            .do_raw_edit(&[(&selection, content.as_str())], EditType::Other);
+        Arc::make_mut(&mut self.editor)
+            .cursor
+            .update_selection(self.doc.buffer(), Selection::caret(range.start));
+        self.apply_deltas(&[(delta, inval_lines)]);
+    }
+
     fn run_selection_range_command(
         &mut self,
         ctx: &mut EventCtx,
@@ -2366,10 +2989,12 @@ fn check_condition(&self, condition: &str) -> bool {
             "in_snippet" => self.editor.snippet.is_some(),
             "completion_focus" => self.has_completions(),
             "hover_focus" => self.has_hover(),
+            "signature_focus" => self.has_signature() && self.signature.len() > 1,
             "list_focus" => self.has_completions() || self.is_palette(),
             "rename_focus" => self.has_rename(),
             "modal_focus" => {
                 (self.has_completions() && !self.config.core.modal)
+                    || (self.has_signature() && !self.config.core.modal)
                     || self.has_hover()
                     || self.is_palette()
                     || self.has_rename()
@@ -2378,6 +3003,27 @@ fn check_condition(&self, condition: &str) -> bool {
         }
     }
 
+    fn context_value(&self, key: &str) -> Option<ContextValue> {
+        let path = match &self.editor.content {
+            BufferContent::File(path) => Some(path),
+            _ => None,
+        };
+        let value = match key {
+            "editor_lang" => self
+                .doc
+                .language()
+                .map(|language| language.to_string().to_lowercase())?,
+            "resource_path" => path?.to_string_lossy().to_string(),
+            "resource_filename" => path?.file_name()?.to_string_lossy().to_string(),
+            "resource_extname" => path?
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default(),
+            _ => return None,
+        };
+        Some(ContextValue::String(value))
+    }
+
     fn receive_char(&mut self, ctx: &mut EventCtx, c: &str) {
         if self.get_mode() == Mode::Insert {
             let doc = Arc::make_mut(&mut self.doc);
@@ -2394,6 +3040,14 @@ fn receive_char(&mut self, ctx: &mut EventCtx, c: &str) {
             }
             self.cancel_hover();
             self.apply_deltas(&deltas);
+            // The server decides whether the character should show the
+            // signature help
+            self.update_signature(
+                ctx,
+                SignatureHelpTriggerKind::TRIGGER_CHARACTER,
+                Some(c.to_string()),
+            );
+            self.format_on_type(ctx, c);
         } else if let Some(direction) = self.editor.inline_find.clone() {
             self.inline_find(ctx, direction.clone(), c);
             let editor = Arc::make_mut(&mut self.editor);
@@ -2417,12 +3071,29 @@ fn run_command(
                 let movement = cmd.to_movement(count);
                 self.run_move_command(ctx, &movement, count, mods)
             }
+            CommandKind::Focus(FocusCommand::InsertSnippet) => {
+                match command.args() {
+                    Ok(Some(CommandArgs::Text(snippet))) => {
+                        if let Err(err) = self.insert_snippet(&snippet) {
+                            log::warn!("invalid snippet {snippet}: {err}");
+                        }
+                        CommandExecuted::Yes
+                    }
+                    Ok(_) => CommandExecuted::No,
+                    Err(err) => {
+                        log::warn!("invalid arguments for insert_snippet: {err}");
+                        CommandExecuted::No
+                    }
+                }
+            }
             CommandKind::Focus(cmd) => self.run_focus_command(ctx, cmd, count, mods),
             CommandKind::MotionMode(cmd) => self.run_motion_mode_command(ctx, cmd),
             CommandKind::MultiSelection(cmd) => {
                 self.run_multi_selection_command(ctx, cmd)
             }
-            CommandKind::Workbench(_) => CommandExecuted::No,
+            CommandKind::Workbench(_) | CommandKind::Composite { .. } => {
+                CommandExecuted::No
+            }
         };
         let doc = self.doc.clone();
         if doc.content() != old_doc.content() || doc.rev() != old_doc.rev() {
@@ -2494,6 +3165,150 @@ fn next_in_file_errors_offset(
     ((*file_diagnostics[0].0).clone(), file_diagnostics[0].1[0])
 }
 
+/// The code actions of the kind to run on save, from every plugin, with
+/// the edits of the ones that need `codeAction/resolve` resolved
+fn code_actions_on_save(
+    proxy: &LapceProxy,
+    path: PathBuf,
+    kind: CodeActionKind,
+) -> Result<Vec<(PluginId, CodeActionOrCommand)>> {
+    let (sender, receiver) = bounded(1);
+    proxy.proxy_rpc.get_code_actions_of_kind(
+        path,
+        kind,
+        Box::new(move |result| {
+            let _ = sender.send(result);
+        }),
+    );
+    let actions = match receiver
+        .recv_timeout(Duration::from_secs(1))?
+        .map_err(|e| anyhow!("{:?}", e))?
+    {
+        ProxyResponse::GetCodeActionsOfKindResponse { actions } => actions,
+        _ => return Err(anyhow!("wrong response")),
+    };
+
+    let actions: Vec<_> = actions
+        .into_iter()
+        .map(|(plugin_id, action)| match action {
+            CodeActionOrCommand::CodeAction(action) if action.edit.is_none() => {
+                let (sender, receiver) = bounded(1);
+                proxy.proxy_rpc.code_action_resolve(
+                    plugin_id,
+                    action.clone(),
+                    Box::new(move |result| {
+                        let _ = sender.send(result);
+                    }),
+                );
+                (
+                    plugin_id,
+                    CodeActionOrCommand::CodeAction(action),
+                    Some(receiver),
+                )
+            }
+            action => (plugin_id, action, None),
+        })
+        .collect();
+
+    // An action that can't be resolved is still run for its command
+    let deadline = Instant::now() + Duration::from_secs(1);
+    Ok(actions
+        .into_iter()
+        .map(|(plugin_id, action, receiver)| {
+            let resolved = receiver
+                .and_then(|receiver| receiver.recv_deadline(deadline).ok())
+                .and_then(|result| match result {
+                    Ok(ProxyResponse::CodeActionResolve { action }) => {
+                        Some(CodeActionOrCommand::CodeAction(*action))
+                    }
+                    _ => None,
+                });
+            (plugin_id, resolved.unwrap_or(action))
+        })
+        .collect())
+}
+
+/// Formats the ranges, which are all asked for at once so that the wait is
+/// the same as when formatting the whole document
+fn range_formatting(
+    proxy: &LapceProxy,
+    path: &Path,
+    ranges: Vec<lsp_types::Range>,
+) -> Result<Vec<TextEdit>> {
+    let receivers: Vec<_> = ranges
+        .into_iter()
+        .map(|range| {
+            let (sender, receiver) = bounded(1);
+            proxy.proxy_rpc.get_document_range_formatting(
+                path.to_path_buf(),
+                range,
+                Box::new(move |result| {
+                    let _ = sender.send(result);
+                }),
+            );
+            receiver
+        })
+        .collect();
+
+    let deadline = Instant::now() + Duration::from_secs(1);
+    let mut edits = Vec::new();
+    for receiver in receivers {
+        let response = receiver
+            .recv_deadline(deadline)?
+            .map_err(|e| anyhow!("{:?}", e))?;
+        if let ProxyResponse::GetDocumentFormatting { edits: range_edits } = response
+        {
+            edits.extend(range_edits);
+        } else {
+            return Err(anyhow!("wrong response"));
+        }
+    }
+    Ok(merge_range_edits(edits))
+}
+
+/// Joins the line ranges that overlap or touch, so that a server which
+/// edits whole lines formats them in one range rather than in two ranges
+/// whose edits overlap
+fn merge_line_ranges(
+    mut ranges: Vec<std::ops::Range<usize>>,
+) -> Vec<std::ops::Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<std::ops::Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Sorts the edits, and drops the ones that overlap an edit before them,
+/// which happens when code actions edit the same text, or a server edits
+/// more than the range it was asked for, since the buffer can't apply those
+fn merge_range_edits(mut edits: Vec<TextEdit>) -> Vec<TextEdit> {
+    let key = |position: &lsp_types::Position| (position.line, position.character);
+    edits.sort_by(|a, b| {
+        (key(&a.range.start), key(&a.range.end))
+            .cmp(&(key(&b.range.start), key(&b.range.end)))
+    });
+
+    let mut merged: Vec<TextEdit> = Vec::with_capacity(edits.len());
+    for edit in edits {
+        if let Some(last) = merged.last() {
+            if edit.range == last.range
+                || key(&edit.range.start) < key(&last.range.end)
+            {
+                continue;
+            }
+        }
+        merged.push(edit);
+    }
+    merged
+}
+
 fn process_get_references(
     offset: usize,
     locations: Vec<Location>,
@@ -2528,7 +3343,9 @@ fn process_get_references(
     }
 }
 
-fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
+pub(crate) fn workspace_edits(
+    edit: &WorkspaceEdit,
+) -> Option<HashMap<Url, Vec<TextEdit>>> {
     if let Some(changes) = edit.changes.as_ref() {
         return Some(changes.clone());
     }
@@ -2570,7 +3387,7 @@ fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>>
     Some(edits)
 }
 
-fn workspace_operation(op: &ResourceOp) -> Option<LapceUICommand> {
+pub(crate) fn workspace_operation(op: &ResourceOp) -> Option<LapceUICommand> {
     Some(match op {
         ResourceOp::Create(p) => LapceUICommand::CreateFileOpen {
             path: p.uri.to_file_path().ok()?,
@@ -2603,7 +3420,11 @@ fn url_matches_path(path: &Path, url: &Url) -> bool {
     matches
 }
 
-fn apply_edit(main_split: &mut LapceMainSplitData, path: &Path, edits: &[TextEdit]) {
+pub(crate) fn apply_edit(
+    main_split: &mut LapceMainSplitData,
+    path: &Path,
+    edits: &[TextEdit],
+) {
     let doc = match main_split.open_docs.get(path) {
         Some(doc) => doc,
         None => return,
@@ -2659,3 +3480,65 @@ fn show_completion(
 
     show_completion
 }
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range};
+
+    use super::*;
+
+    fn edit(start: (u32, u32), end: (u32, u32), text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(
+                Position::new(start.0, start.1),
+                Position::new(end.0, end.1),
+            ),
+            new_text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_merge_line_ranges() {
+        assert_eq!(
+            merge_line_ranges(vec![6..7, 2..4, 0..2, 3..5, 9..10]),
+            vec![0..5, 6..7, 9..10]
+        );
+    }
+
+    #[test]
+    fn test_merge_range_edits() {
+        // Two hunks next to each other, lines 0-1 and 2-3, are formatted as
+        // one range, so the whole line edits of the server cover both
+        assert_eq!(merge_line_ranges(vec![0..2, 2..4]), vec![0..4]);
+        let formatting = vec![
+            edit((2, 0), (3, 0), "c\n"),
+            edit((0, 0), (2, 0), "a\nb\n"),
+            edit((3, 0), (4, 0), "d\n"),
+        ];
+        // A code action that edits the same text as the formatting
+        let action = vec![edit((1, 0), (2, 0), "B\n")];
+        let edits = merge_range_edits(
+            formatting.into_iter().chain(action.into_iter()).collect(),
+        );
+        assert_eq!(
+            edits,
+            vec![
+                edit((0, 0), (2, 0), "a\nb\n"),
+                edit((2, 0), (3, 0), "c\n"),
+                edit((3, 0), (4, 0), "d\n")
+            ]
+        );
+
+        let mut buffer = Buffer::new(" a\n b\n c\n d\n");
+        let edits: Vec<(Selection, &str)> = edits
+            .iter()
+            .map(|edit| {
+                let start = buffer.offset_of_position(&edit.range.start);
+                let end = buffer.offset_of_position(&edit.range.end);
+                (Selection::region(start, end), edit.new_text.as_str())
+            })
+            .collect();
+        buffer.edit(&edits, EditType::Other);
+        assert_eq!(buffer.text().to_string(), "a\nb\nc\nd\n");
+    }
+}
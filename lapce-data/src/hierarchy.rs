@@ -0,0 +1,326 @@
+use druid::{ExtEventSink, Target, WidgetId};
+use lapce_rpc::{plugin::PluginId, proxy::ProxyResponse, RpcError};
+use lsp_types::CallHierarchyItem;
+
+use crate::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    proxy::LapceProxy,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HierarchyKind {
+    Call,
+    Type,
+}
+
+/// What the children of an item in the hierarchy tree are
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HierarchyDirection {
+    IncomingCalls,
+    OutgoingCalls,
+    Supertypes,
+    Subtypes,
+}
+
+impl HierarchyDirection {
+    pub fn toggled(&self) -> Self {
+        match self {
+            HierarchyDirection::IncomingCalls => HierarchyDirection::OutgoingCalls,
+            HierarchyDirection::OutgoingCalls => HierarchyDirection::IncomingCalls,
+            HierarchyDirection::Supertypes => HierarchyDirection::Subtypes,
+            HierarchyDirection::Subtypes => HierarchyDirection::Supertypes,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HierarchyDirection::IncomingCalls => "Incoming Calls",
+            HierarchyDirection::OutgoingCalls => "Outgoing Calls",
+            HierarchyDirection::Supertypes => "Supertypes",
+            HierarchyDirection::Subtypes => "Subtypes",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HierarchyNode {
+    pub item: CallHierarchyItem,
+    /// `None` until they are fetched from the language server
+    pub children: Option<Vec<HierarchyNode>>,
+    pub expanded: bool,
+}
+
+impl HierarchyNode {
+    fn new(item: CallHierarchyItem) -> Self {
+        Self {
+            item,
+            children: None,
+            expanded: false,
+        }
+    }
+}
+
+/// A row of the hierarchy tree as it's shown in the panel
+pub struct HierarchyRow<'a> {
+    pub depth: usize,
+    /// The indices of the node and its ancestors, starting from the root
+    pub path: Vec<usize>,
+    pub node: &'a HierarchyNode,
+}
+
+#[derive(Clone)]
+pub struct HierarchyData {
+    pub widget_id: WidgetId,
+    pub split_id: WidgetId,
+    pub tree_id: WidgetId,
+    pub kind: HierarchyKind,
+    pub direction: HierarchyDirection,
+    /// The plugin which prepared the roots, and is asked for their children
+    pub plugin_id: Option<PluginId>,
+    /// Bumped whenever the tree is rebuilt, so that children which arrive
+    /// for an older tree are dropped
+    pub rev: u64,
+    pub roots: Vec<HierarchyNode>,
+}
+
+impl HierarchyData {
+    pub fn new(kind: HierarchyKind) -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            split_id: WidgetId::next(),
+            tree_id: WidgetId::next(),
+            kind,
+            direction: match kind {
+                HierarchyKind::Call => HierarchyDirection::IncomingCalls,
+                HierarchyKind::Type => HierarchyDirection::Supertypes,
+            },
+            plugin_id: None,
+            rev: 0,
+            roots: Vec::new(),
+        }
+    }
+
+    /// Replaces the tree with the prepared items, which start out expanded
+    pub fn set_roots(&mut self, plugin_id: PluginId, items: Vec<CallHierarchyItem>) {
+        self.rev += 1;
+        self.plugin_id = Some(plugin_id);
+        self.roots = items
+            .into_iter()
+            .map(|item| HierarchyNode {
+                expanded: true,
+                ..HierarchyNode::new(item)
+            })
+            .collect();
+    }
+
+    /// Switches between callers and callees, or supertypes and subtypes,
+    /// which throws away all the children fetched so far
+    pub fn toggle_direction(&mut self) {
+        self.rev += 1;
+        self.direction = self.direction.toggled();
+        for root in self.roots.iter_mut() {
+            root.children = None;
+            root.expanded = true;
+        }
+    }
+
+    pub fn node(&self, path: &[usize]) -> Option<&HierarchyNode> {
+        let (first, rest) = path.split_first()?;
+        let mut node = self.roots.get(*first)?;
+        for index in rest {
+            node = node.children.as_ref()?.get(*index)?;
+        }
+        Some(node)
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> Option<&mut HierarchyNode> {
+        let (first, rest) = path.split_first()?;
+        let mut node = self.roots.get_mut(*first)?;
+        for index in rest {
+            node = node.children.as_mut()?.get_mut(*index)?;
+        }
+        Some(node)
+    }
+
+    /// Expands or collapses the node, and returns its item if it was expanded
+    /// and its children still need to be fetched
+    pub fn toggle(&mut self, path: &[usize]) -> Option<CallHierarchyItem> {
+        let node = self.node_mut(path)?;
+        node.expanded = !node.expanded;
+        if node.expanded && node.children.is_none() {
+            Some(node.item.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn set_children(&mut self, path: &[usize], items: Vec<CallHierarchyItem>) {
+        if let Some(node) = self.node_mut(path) {
+            node.children =
+                Some(items.into_iter().map(HierarchyNode::new).collect());
+        }
+    }
+
+    /// The nodes which are visible, in the order they are shown
+    pub fn rows(&self) -> Vec<HierarchyRow> {
+        let mut rows = Vec::new();
+        let mut stack: Vec<(usize, Vec<usize>, &HierarchyNode)> = self
+            .roots
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, node)| (0, vec![i], node))
+            .collect();
+        while let Some((depth, path, node)) = stack.pop() {
+            if node.expanded {
+                if let Some(children) = node.children.as_ref() {
+                    for (i, child) in children.iter().enumerate().rev() {
+                        let mut child_path = path.clone();
+                        child_path.push(i);
+                        stack.push((depth + 1, child_path, child));
+                    }
+                }
+            }
+            rows.push(HierarchyRow { depth, path, node });
+        }
+        rows
+    }
+
+    pub fn fetch_root_children(
+        &self,
+        proxy: &LapceProxy,
+        tab_id: WidgetId,
+        event_sink: ExtEventSink,
+    ) {
+        for (i, root) in self.roots.iter().enumerate() {
+            self.fetch_children(
+                vec![i],
+                root.item.clone(),
+                proxy,
+                tab_id,
+                event_sink.clone(),
+            );
+        }
+    }
+
+    /// Asks the language server for the children of the node at the path,
+    /// which come back to the tab as `UpdateHierarchyChildren`
+    pub fn fetch_children(
+        &self,
+        path: Vec<usize>,
+        item: CallHierarchyItem,
+        proxy: &LapceProxy,
+        tab_id: WidgetId,
+        event_sink: ExtEventSink,
+    ) {
+        let plugin_id = match self.plugin_id {
+            Some(plugin_id) => plugin_id,
+            None => return,
+        };
+        let kind = self.kind;
+        let rev = self.rev;
+        let f = move |result: Result<ProxyResponse, RpcError>| {
+            let items = match result {
+                Ok(ProxyResponse::HierarchyItems { items }) => items,
+                _ => Vec::new(),
+            };
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::UpdateHierarchyChildren {
+                    kind,
+                    rev,
+                    path,
+                    items,
+                },
+                Target::Widget(tab_id),
+            );
+        };
+        let proxy_rpc = &proxy.proxy_rpc;
+        match self.direction {
+            HierarchyDirection::IncomingCalls => {
+                proxy_rpc.call_hierarchy_incoming_calls(plugin_id, item, f)
+            }
+            HierarchyDirection::OutgoingCalls => {
+                proxy_rpc.call_hierarchy_outgoing_calls(plugin_id, item, f)
+            }
+            HierarchyDirection::Supertypes => {
+                proxy_rpc.type_hierarchy_supertypes(plugin_id, item, f)
+            }
+            HierarchyDirection::Subtypes => {
+                proxy_rpc.type_hierarchy_subtypes(plugin_id, item, f)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::{Position, Range, SymbolKind, Url};
+
+    use super::*;
+
+    fn item(name: &str) -> CallHierarchyItem {
+        let range = Range::new(Position::new(0, 0), Position::new(0, 1));
+        CallHierarchyItem {
+            name: name.to_string(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: Url::parse("file:///main.rs").unwrap(),
+            range,
+            selection_range: range,
+            data: None,
+        }
+    }
+
+    fn names(data: &HierarchyData) -> Vec<(usize, String)> {
+        data.rows()
+            .iter()
+            .map(|row| (row.depth, row.node.item.name.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut data = HierarchyData::new(HierarchyKind::Call);
+        data.set_roots(PluginId(0), vec![item("a"), item("b")]);
+        assert_eq!(data.rev, 1);
+        assert_eq!(data.toggle(&[0]), None);
+        assert_eq!(data.toggle(&[0]), Some(item("a")));
+        data.set_children(&[0], vec![item("c"), item("d")]);
+        assert_eq!(data.toggle(&[0, 1]), Some(item("d")));
+        data.set_children(&[0, 1], vec![item("e")]);
+        assert_eq!(
+            names(&data),
+            vec![
+                (0, "a".to_string()),
+                (1, "c".to_string()),
+                (1, "d".to_string()),
+                (2, "e".to_string()),
+                (0, "b".to_string()),
+            ]
+        );
+        assert_eq!(data.rows()[3].path, vec![0, 1, 0]);
+
+        data.toggle(&[0, 1]);
+        assert_eq!(data.toggle(&[0, 1]), None);
+        data.toggle(&[0]);
+        assert_eq!(
+            names(&data),
+            vec![(0, "a".to_string()), (0, "b".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_toggle_direction() {
+        let mut data = HierarchyData::new(HierarchyKind::Type);
+        assert_eq!(data.direction, HierarchyDirection::Supertypes);
+        data.set_roots(PluginId(0), vec![item("a")]);
+        data.set_children(&[0], vec![item("b")]);
+        data.toggle_direction();
+        assert_eq!(data.direction, HierarchyDirection::Subtypes);
+        assert_eq!(data.rev, 2);
+        assert_eq!(data.node(&[0]).unwrap().children, None);
+        assert!(data.node(&[0]).unwrap().expanded);
+    }
+}
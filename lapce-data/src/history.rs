@@ -1,6 +1,7 @@
 use std::{
     cell::RefCell,
     collections::HashMap,
+    ops::Range,
     rc::Rc,
     sync::{atomic, Arc},
 };
@@ -123,8 +124,12 @@ fn new_text_layout(
         let line_content = self.buffer.as_ref().unwrap().line_content(line);
         let font_family = config.editor.font_family();
         let font_size = config.editor.font_size;
-        let tab_width =
-            config.tab_width(text, config.editor.font_family(), font_size);
+        let tab_width = config.tab_width(
+            text,
+            config.editor.font_family(),
+            font_size,
+            config.editor.tab_width,
+        );
         let mut layout_builder = text
             .new_text_layout(line_content.to_string())
             .font(font_family, font_size as f64)
@@ -173,12 +178,15 @@ pub fn retrieve(&self, doc: &Document) {
             let id = doc.id();
             let tab_id = doc.tab_id;
             let path = path.clone();
+            let version = self.version.clone();
             let proxy = doc.proxy.clone();
             let event_sink = doc.event_sink.clone();
             std::thread::spawn(move || {
-                proxy
-                    .proxy_rpc
-                    .get_buffer_head(id, path.clone(), move |result| {
+                proxy.proxy_rpc.get_buffer_head(
+                    id,
+                    path.clone(),
+                    version,
+                    move |result| {
                         if let Ok(ProxyResponse::BufferHeadResponse {
                             version,
                             content,
@@ -194,7 +202,8 @@ pub fn retrieve(&self, doc: &Document) {
                                 Target::Widget(tab_id),
                             );
                         }
-                    })
+                    },
+                )
             });
         }
     }
@@ -245,6 +254,18 @@ pub fn changes(&self) -> &[DiffLines] {
         &self.changes
     }
 
+    /// Whether the content of the version has been retrieved, which it never
+    /// is for files the version doesn't have
+    pub fn is_loaded(&self) -> bool {
+        self.buffer.is_some()
+    }
+
+    /// The lines of the buffer which were added or changed since the version,
+    /// leaving out the lines which were only deleted
+    pub fn changed_lines(&self) -> Vec<Range<usize>> {
+        changed_lines(&self.changes)
+    }
+
     pub fn update_changes(&mut self, changes: Arc<Vec<DiffLines>>) {
         self.changes = changes;
     }
@@ -286,3 +307,33 @@ fn retrieve_history_styles(&self, doc: &Document) {
         }
     }
 }
+
+fn changed_lines(changes: &[DiffLines]) -> Vec<Range<usize>> {
+    changes
+        .iter()
+        .filter_map(|change| match change {
+            DiffLines::Right(range) => Some(range.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_changed_lines() {
+        let changes = vec![
+            DiffLines::Both(0..3, 0..3),
+            DiffLines::Left(3..4),
+            DiffLines::Right(3..5),
+            DiffLines::Skip(4..20, 5..21),
+            DiffLines::Left(20..22),
+            DiffLines::Both(22..23, 21..22),
+            DiffLines::Right(22..23),
+        ];
+        assert_eq!(changed_lines(&changes), vec![3..5, 22..23]);
+        assert!(changed_lines(&[DiffLines::Left(0..1)]).is_empty());
+    }
+}
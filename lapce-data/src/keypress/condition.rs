@@ -0,0 +1,327 @@
+use anyhow::{anyhow, Result};
+use regex::{Regex, RegexBuilder};
+
+use super::{ContextValue, KeyPressFocus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A parsed `when` condition of a keymap, where `&&` binds tighter than
+/// `||` and `!` tighter than both
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// A context key that is true, such as `editor_focus`
+    Key(String),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    /// A context key compared with a value, such as `editor_lang == rust`
+    Compare(String, CompareOp, String),
+    /// A context key matched with a regex, such as
+    /// `resource_extname =~ /\.md$/i`
+    Matches {
+        key: String,
+        pattern: Pattern,
+    },
+}
+
+/// The regex of a condition, compiled when the condition is parsed
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+    flags: String,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str, flags: &str) -> Result<Self> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(flags.contains('i'))
+            .multi_line(flags.contains('m'))
+            .dot_matches_new_line(flags.contains('s'))
+            .build()?;
+        Ok(Self {
+            regex,
+            flags: flags.to_string(),
+        })
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str() && self.flags == other.flags
+    }
+}
+
+impl Eq for Pattern {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    LeftParen,
+    RightParen,
+    Not,
+    And,
+    Or,
+    Compare(CompareOp),
+    Match,
+    Word(&'a str),
+    Regex(&'a str, &'a str),
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | ':')
+}
+
+fn tokenize(condition: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = condition.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let (token, len) = if rest.starts_with("&&") {
+            (Token::And, 2)
+        } else if rest.starts_with("||") {
+            (Token::Or, 2)
+        } else if rest.starts_with("==") {
+            (Token::Compare(CompareOp::Eq), 2)
+        } else if rest.starts_with("!=") {
+            (Token::Compare(CompareOp::NotEq), 2)
+        } else if rest.starts_with("<=") {
+            (Token::Compare(CompareOp::Le), 2)
+        } else if rest.starts_with(">=") {
+            (Token::Compare(CompareOp::Ge), 2)
+        } else if rest.starts_with("=~") {
+            (Token::Match, 2)
+        } else if c == '/' && tokens.last() == Some(&Token::Match) {
+            // The pattern runs to the next unescaped slash, and the flags
+            // follow it
+            let mut end = None;
+            let mut escaped = false;
+            for (i, c) in rest.char_indices().skip(1) {
+                match c {
+                    '\\' if !escaped => escaped = true,
+                    '/' if !escaped => {
+                        end = Some(i);
+                        break;
+                    }
+                    _ => escaped = false,
+                }
+            }
+            let end = end.ok_or_else(|| anyhow!("unterminated regex"))?;
+            let flags_len = rest[end + 1..]
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len() - end - 1);
+            let flags = &rest[end + 1..end + 1 + flags_len];
+            (Token::Regex(&rest[1..end], flags), end + 1 + flags_len)
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| anyhow!("unterminated string"))?;
+            (Token::Word(&rest[1..end + 1]), end + 2)
+        } else {
+            match c {
+                '(' => (Token::LeftParen, 1),
+                ')' => (Token::RightParen, 1),
+                '!' => (Token::Not, 1),
+                '<' => (Token::Compare(CompareOp::Lt), 1),
+                '>' => (Token::Compare(CompareOp::Gt), 1),
+                c if is_word_char(c) => {
+                    let len = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+                    (Token::Word(&rest[..len]), len)
+                }
+                c => return Err(anyhow!("unexpected character {c}")),
+            }
+        };
+        tokens.push(token);
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Condition> {
+        let mut condition = self.parse_and()?;
+        while self.peek() == Some(Token::Or) {
+            self.next();
+            let right = self.parse_and()?;
+            condition = Condition::Or(Box::new(condition), Box::new(right));
+        }
+        Ok(condition)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition> {
+        let mut condition = self.parse_unary()?;
+        while self.peek() == Some(Token::And) {
+            self.next();
+            let right = self.parse_unary()?;
+            condition = Condition::And(Box::new(condition), Box::new(right));
+        }
+        Ok(condition)
+    }
+
+    fn parse_unary(&mut self) -> Result<Condition> {
+        match self.next() {
+            Some(Token::Not) => Ok(Condition::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LeftParen) => {
+                let condition = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RightParen) => Ok(condition),
+                    _ => Err(anyhow!("missing closing parenthesis")),
+                }
+            }
+            Some(Token::Word(key)) => match self.peek() {
+                Some(Token::Compare(op)) => {
+                    self.next();
+                    match self.next() {
+                        Some(Token::Word(value)) => Ok(Condition::Compare(
+                            key.to_string(),
+                            op,
+                            value.to_string(),
+                        )),
+                        _ => Err(anyhow!("missing value to compare {key} with")),
+                    }
+                }
+                Some(Token::Match) => {
+                    self.next();
+                    match self.next() {
+                        Some(Token::Regex(pattern, flags)) => {
+                            Ok(Condition::Matches {
+                                key: key.to_string(),
+                                pattern: Pattern::new(pattern, flags)?,
+                            })
+                        }
+                        _ => Err(anyhow!("missing regex to match {key} with")),
+                    }
+                }
+                _ => Ok(Condition::Key(key.to_string())),
+            },
+            token => Err(anyhow!("unexpected {token:?}")),
+        }
+    }
+}
+
+impl Condition {
+    pub fn parse(condition: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(condition)?,
+            pos: 0,
+        };
+        let parsed = parser.parse_or()?;
+        match parser.peek() {
+            Some(token) => Err(anyhow!("unexpected {token:?}")),
+            None => Ok(parsed),
+        }
+    }
+
+    pub fn check<T: KeyPressFocus>(&self, check: &T) -> bool {
+        match self {
+            Condition::Key(key) => value_of(key, check).is_truthy(),
+            Condition::Not(condition) => !condition.check(check),
+            Condition::And(left, right) => left.check(check) && right.check(check),
+            Condition::Or(left, right) => left.check(check) || right.check(check),
+            Condition::Compare(key, op, value) => {
+                let ordering = match value_of(key, check) {
+                    ContextValue::Bool(b) => {
+                        value.parse::<bool>().ok().map(|value| b.cmp(&value))
+                    }
+                    ContextValue::Number(n) => value
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(|value| n.partial_cmp(&value)),
+                    ContextValue::String(s) => Some(s.cmp(value)),
+                };
+                match ordering {
+                    Some(ordering) => match op {
+                        CompareOp::Eq => ordering.is_eq(),
+                        CompareOp::NotEq => ordering.is_ne(),
+                        CompareOp::Lt => ordering.is_lt(),
+                        CompareOp::Le => ordering.is_le(),
+                        CompareOp::Gt => ordering.is_gt(),
+                        CompareOp::Ge => ordering.is_ge(),
+                    },
+                    None => *op == CompareOp::NotEq,
+                }
+            }
+            Condition::Matches { key, pattern } => {
+                pattern.regex.is_match(&value_of(key, check).to_string())
+            }
+        }
+    }
+}
+
+impl CompareOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::NotEq => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // `||` is the only operator that binds looser than `&&`
+        let operand = |condition: &Condition| match condition {
+            Condition::Or(..) => format!("({condition})"),
+            _ => condition.to_string(),
+        };
+        match self {
+            Condition::Key(key) => write!(f, "{key}"),
+            Condition::Not(condition) => match condition.as_ref() {
+                Condition::And(..) | Condition::Or(..) => {
+                    write!(f, "!({condition})")
+                }
+                _ => write!(f, "!{condition}"),
+            },
+            Condition::And(left, right) => {
+                write!(f, "{} && {}", operand(left), operand(right))
+            }
+            Condition::Or(left, right) => {
+                write!(f, "{left} || {right}")
+            }
+            Condition::Compare(key, op, value) => {
+                if !value.is_empty() && value.chars().all(is_word_char) {
+                    write!(f, "{key} {} {value}", op.as_str())
+                } else if value.contains('\'') {
+                    write!(f, "{key} {} \"{value}\"", op.as_str())
+                } else {
+                    write!(f, "{key} {} '{value}'", op.as_str())
+                }
+            }
+            Condition::Matches { key, pattern } => {
+                write!(f, "{key} =~ /{}/{}", pattern.regex.as_str(), pattern.flags)
+            }
+        }
+    }
+}
+
+/// The value of a context key, where the keys without a typed value are
+/// the boolean ones of `check_condition`
+fn value_of<T: KeyPressFocus>(key: &str, check: &T) -> ContextValue {
+    check
+        .context_value(key)
+        .unwrap_or_else(|| ContextValue::Bool(check.check_condition(key)))
+}
@@ -0,0 +1,761 @@
+//! Converts the keybindings of VS Code and Vim into Lapce keymaps, for
+//! the commands and context keys that have a Lapce counterpart.
+
+use anyhow::{anyhow, Result};
+use lapce_core::mode::Modes;
+use serde::Deserialize;
+
+use super::{condition::Condition, KeyMap, KeyPress};
+use crate::command::{lapce_internal_commands, LapceCommand};
+
+/// The Lapce commands of the VS Code command IDs
+const VSCODE_COMMANDS: &[(&str, &str)] = &[
+    ("workbench.action.quickOpen", "palette"),
+    ("workbench.action.showCommands", "palette.command"),
+    ("workbench.action.gotoLine", "palette.go_to_line"),
+    ("workbench.action.gotoSymbol", "palette.symbol"),
+    (
+        "workbench.action.showAllSymbols",
+        "palette.workspace_symbol",
+    ),
+    ("workbench.action.openRecent", "palette.workspace"),
+    ("workbench.action.openSettings", "open_settings"),
+    (
+        "workbench.action.openGlobalKeybindings",
+        "open_keyboard_shortcuts",
+    ),
+    ("workbench.action.selectTheme", "change_theme"),
+    (
+        "workbench.action.editor.changeLanguageMode",
+        "change_file_language",
+    ),
+    ("workbench.action.files.openFolder", "open_folder"),
+    ("workbench.action.files.openFile", "open_file"),
+    ("workbench.action.files.newUntitledFile", "new_file"),
+    ("workbench.action.files.save", "save"),
+    ("workbench.action.files.saveAll", "save_all"),
+    ("workbench.action.closeActiveEditor", "split_close"),
+    ("workbench.action.newWindow", "new_window"),
+    ("workbench.action.closeWindow", "close_window"),
+    ("workbench.action.reloadWindow", "reload_window"),
+    ("workbench.action.quit", "quit"),
+    ("workbench.action.nextEditor", "next_editor_tab"),
+    ("workbench.action.previousEditor", "previous_editor_tab"),
+    ("workbench.action.splitEditor", "split_vertical"),
+    ("workbench.action.splitEditorRight", "split_vertical"),
+    ("workbench.action.splitEditorDown", "split_horizontal"),
+    ("workbench.action.focusLeftGroup", "split_left"),
+    ("workbench.action.focusRightGroup", "split_right"),
+    ("workbench.action.focusAboveGroup", "split_up"),
+    ("workbench.action.focusBelowGroup", "split_down"),
+    ("workbench.action.focusActiveEditorGroup", "focus_editor"),
+    ("workbench.action.togglePanel", "toggle_panel_bottom_visual"),
+    (
+        "workbench.action.toggleSidebarVisibility",
+        "toggle_panel_left_visual",
+    ),
+    (
+        "workbench.action.toggleMaximizedPanel",
+        "toggle_maximized_panel",
+    ),
+    (
+        "workbench.action.terminal.toggleTerminal",
+        "toggle_terminal_focus",
+    ),
+    ("workbench.action.terminal.focus", "focus_terminal"),
+    ("workbench.view.explorer", "toggle_file_explorer_focus"),
+    ("workbench.view.scm", "toggle_source_control_focus"),
+    ("workbench.view.search", "toggle_search_focus"),
+    ("workbench.action.findInFiles", "toggle_search_focus"),
+    ("workbench.view.extensions", "toggle_plugin_focus"),
+    ("workbench.actions.view.problems", "toggle_problem_focus"),
+    ("workbench.action.navigateBack", "jump_location_backward"),
+    ("workbench.action.navigateForward", "jump_location_forward"),
+    ("workbench.action.editor.nextChange", "next_diff"),
+    ("workbench.action.editor.previousChange", "previous_diff"),
+    ("workbench.action.closeQuickOpen", "modal.close"),
+    ("workbench.action.quickOpenSelectNext", "list.next"),
+    ("workbench.action.quickOpenSelectPrevious", "list.previous"),
+    ("editor.action.revealDefinition", "goto_definition"),
+    ("editor.action.revealDeclaration", "goto_declaration"),
+    ("editor.action.goToTypeDefinition", "goto_type_definition"),
+    ("editor.action.goToImplementation", "goto_implementation"),
+    ("editor.showCallHierarchy", "show_call_hierarchy"),
+    ("editor.showTypeHierarchy", "show_type_hierarchy"),
+    ("editor.action.rename", "rename_symbol"),
+    ("editor.action.quickFix", "show_code_actions"),
+    ("editor.action.triggerSuggest", "get_completion"),
+    ("editor.action.triggerParameterHints", "get_signature"),
+    ("editor.action.formatDocument", "format_document"),
+    ("editor.action.formatSelection", "format_selection"),
+    ("editor.action.commentLine", "toggle_line_comment"),
+    ("editor.action.moveLinesUpAction", "move_line_up"),
+    ("editor.action.moveLinesDownAction", "move_line_down"),
+    ("editor.action.copyLinesUpAction", "duplicate_line_up"),
+    ("editor.action.copyLinesDownAction", "duplicate_line_down"),
+    ("editor.action.insertLineAfter", "new_line_below"),
+    ("editor.action.insertLineBefore", "new_line_above"),
+    ("editor.action.indentLines", "indent_line"),
+    ("editor.action.outdentLines", "outdent_line"),
+    ("editor.action.joinLines", "join_lines"),
+    ("editor.action.insertCursorAbove", "insert_cursor_above"),
+    ("editor.action.insertCursorBelow", "insert_cursor_below"),
+    (
+        "editor.action.insertCursorAtEndOfEachLineSelected",
+        "insert_cursor_end_of_line",
+    ),
+    (
+        "editor.action.addSelectionToNextFindMatch",
+        "select_next_current",
+    ),
+    (
+        "editor.action.moveSelectionToNextFindMatch",
+        "select_skip_current",
+    ),
+    ("editor.action.selectHighlights", "select_all_current"),
+    ("expandLineSelection", "select_current_line"),
+    ("editor.action.selectAll", "select_all"),
+    ("editor.action.jumpToBracket", "match_pairs"),
+    ("editor.action.marker.next", "next_error"),
+    ("editor.action.marker.nextInFiles", "next_error"),
+    ("editor.action.marker.prev", "previous_error"),
+    ("editor.action.marker.prevInFiles", "previous_error"),
+    ("editor.fold", "fold"),
+    ("editor.unfold", "unfold"),
+    ("editor.toggleFold", "toggle_fold"),
+    ("editor.foldAll", "fold_all"),
+    ("editor.unfoldAll", "unfold_all"),
+    ("actions.find", "search"),
+    ("editor.action.nextMatchFindAction", "search_forward"),
+    ("editor.action.previousMatchFindAction", "search_backward"),
+    ("undo", "undo"),
+    ("redo", "redo"),
+    ("editor.action.clipboardCopyAction", "clipboard_copy"),
+    ("editor.action.clipboardCutAction", "clipboard_cut"),
+    ("editor.action.clipboardPasteAction", "clipboard_paste"),
+    ("deleteLeft", "delete_backward"),
+    ("deleteRight", "delete_forward"),
+    ("deleteWordLeft", "delete_word_backward"),
+    ("deleteWordRight", "delete_word_forward"),
+    ("deleteAllLeft", "delete_to_beginning_of_line"),
+    ("cursorUp", "up"),
+    ("cursorDown", "down"),
+    ("cursorLeft", "left"),
+    ("cursorRight", "right"),
+    ("cursorHome", "line_start_non_blank"),
+    ("cursorEnd", "line_end"),
+    ("cursorTop", "document_start"),
+    ("cursorBottom", "document_end"),
+    ("cursorWordLeft", "word_backward"),
+    ("cursorWordEndRight", "word_end_forward"),
+    ("cursorPageUp", "page_up"),
+    ("cursorPageDown", "page_down"),
+    ("scrollLineUp", "scroll_up"),
+    ("scrollLineDown", "scroll_down"),
+    (
+        "jumpToNextSnippetPlaceholder",
+        "jump_to_next_snippet_placeholder",
+    ),
+    (
+        "jumpToPrevSnippetPlaceholder",
+        "jump_to_prev_snippet_placeholder",
+    ),
+    ("editor.action.insertSnippet", "insert_snippet"),
+    ("selectNextSuggestion", "list.next"),
+    ("selectPrevSuggestion", "list.previous"),
+    ("selectNextPageSuggestion", "list.next_page"),
+    ("selectPrevPageSuggestion", "list.previous_page"),
+    ("acceptSelectedSuggestion", "list.select"),
+    ("hideSuggestWidget", "modal.close"),
+];
+
+/// The Lapce context keys of the VS Code ones
+const VSCODE_CONTEXT_KEYS: &[(&str, &str)] = &[
+    ("editorFocus", "editor_focus"),
+    ("editorTextFocus", "editor_focus"),
+    ("textInputFocus", "editor_focus"),
+    ("inputFocus", "input_focus"),
+    ("findInputFocussed", "search_focus"),
+    ("suggestWidgetVisible", "completion_focus"),
+    ("parameterHintsVisible", "signature_focus"),
+    ("editorHoverVisible", "hover_focus"),
+    ("renameInputVisible", "rename_focus"),
+    ("inSnippetMode", "in_snippet"),
+    ("inQuickOpen", "palette_focus"),
+    ("listFocus", "list_focus"),
+    ("isInDiffEditor", "diff_focus"),
+    ("terminalFocus", "terminal_focus"),
+    ("panelFocus", "panel_focus"),
+    ("editorLangId", "editor_lang"),
+    ("resourceExtname", "resource_extname"),
+    ("resourceFilename", "resource_filename"),
+    ("resourcePath", "resource_path"),
+];
+
+/// The Lapce commands of the Ex commands on the right side of a Vim mapping
+const VIM_EX_COMMANDS: &[(&str, &str)] = &[
+    ("w", "save"),
+    ("write", "save"),
+    ("wa", "save_all"),
+    ("wall", "save_all"),
+    ("wq", "save_and_exit"),
+    ("x", "save_and_exit"),
+    ("q", "split_close"),
+    ("quit", "split_close"),
+    ("q!", "force_exit"),
+    ("qa", "quit"),
+    ("qall", "quit"),
+    ("bn", "next_editor_tab"),
+    ("bnext", "next_editor_tab"),
+    ("bp", "previous_editor_tab"),
+    ("bprevious", "previous_editor_tab"),
+    ("vs", "split_vertical"),
+    ("vsplit", "split_vertical"),
+    ("sp", "split_horizontal"),
+    ("split", "split_horizontal"),
+    ("noh", "clear_search"),
+    ("nohlsearch", "clear_search"),
+    ("enew", "new_file"),
+    ("Files", "palette"),
+    ("Commands", "palette.command"),
+    ("Rg", "toggle_search_focus"),
+    ("NERDTreeToggle", "toggle_file_explorer_visual"),
+];
+
+/// The Lapce commands of the keys on the right side of a Vim mapping, with
+/// the special keys in lowercase
+const VIM_KEYS: &[(&str, &str)] = &[
+    ("<esc>", "normal_mode"),
+    ("<c-w>h", "split_left"),
+    ("<c-w>j", "split_down"),
+    ("<c-w>k", "split_up"),
+    ("<c-w>l", "split_right"),
+    ("<c-w>v", "split_vertical"),
+    ("<c-w>s", "split_horizontal"),
+    ("<c-w>c", "split_close"),
+    ("<c-w>q", "split_close"),
+    ("<c-w>x", "split_exchange"),
+    ("h", "left"),
+    ("j", "down"),
+    ("k", "up"),
+    ("l", "right"),
+    ("w", "word_forward"),
+    ("b", "word_backward"),
+    ("e", "word_end_forward"),
+    ("0", "line_start"),
+    ("^", "line_start_non_blank"),
+    ("$", "line_end"),
+    ("gg", "go_to_line_default_first"),
+    ("G", "go_to_line_default_last"),
+    ("%", "match_pairs"),
+    ("H", "top_of_window"),
+    ("L", "bottom_of_window"),
+    ("zz", "center_of_window"),
+    ("n", "search_forward"),
+    ("N", "search_backward"),
+    ("*", "search_whole_word_forward"),
+    ("u", "undo"),
+    ("<c-r>", "redo"),
+    ("p", "paste"),
+    ("P", "paste_before"),
+    ("J", "join_lines"),
+    (">>", "indent_line"),
+    ("<<", "outdent_line"),
+    ("o", "new_line_below"),
+    ("O", "new_line_above"),
+    ("i", "insert_mode"),
+    ("I", "insert_first_non_blank"),
+    ("a", "append"),
+    ("A", "append_end_of_line"),
+    ("v", "toggle_visual_mode"),
+    ("V", "toggle_linewise_visual_mode"),
+    ("<c-v>", "toggle_blockwise_visual_mode"),
+    ("za", "toggle_fold"),
+    ("zc", "fold"),
+    ("zo", "unfold"),
+    ("zM", "fold_all"),
+    ("zR", "unfold_all"),
+    ("gd", "goto_definition"),
+];
+
+fn lookup(table: &[(&str, &'static str)], key: &str) -> Option<&'static str> {
+    table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+/// The keymaps that could be imported, and the bindings that couldn't be,
+/// each with the reason
+#[derive(Debug, Default)]
+pub struct KeymapImport {
+    pub keymaps: Vec<KeyMap>,
+    pub unmapped: Vec<String>,
+    /// The keymaps left out because the keymaps file already has them
+    pub existing: usize,
+}
+
+#[derive(Deserialize)]
+struct VscodeKeybinding {
+    key: String,
+    command: String,
+    #[serde(default)]
+    when: Option<String>,
+    #[serde(default)]
+    args: Option<serde_json::Value>,
+}
+
+/// Imports the bindings of a VS Code `keybindings.json`, where the removals
+/// of a binding, with a `-` before the command, stay removals
+pub fn import_vscode(content: &str) -> Result<KeymapImport> {
+    let bindings: Vec<VscodeKeybinding> =
+        serde_json::from_str(&strip_json_comments(content))?;
+
+    let mut import = KeymapImport::default();
+    for binding in bindings {
+        match vscode_keymap(&binding) {
+            Ok(keymap) => import.keymaps.push(keymap),
+            Err(err) => import
+                .unmapped
+                .push(format!("{} ({}): {err}", binding.key, binding.command)),
+        }
+    }
+    Ok(import)
+}
+
+fn vscode_keymap(binding: &VscodeKeybinding) -> Result<KeyMap> {
+    let (removal, command) = match binding.command.strip_prefix('-') {
+        Some(command) => ("-", command),
+        None => ("", binding.command.as_str()),
+    };
+    let command = lookup(VSCODE_COMMANDS, command)
+        .ok_or_else(|| anyhow!("unknown command"))?;
+
+    let mut keys = Vec::new();
+    for chord in binding.key.split_whitespace() {
+        let mut parts = chord.split('+').collect::<Vec<_>>();
+        let key = parts.pop().unwrap_or_default();
+        let mut mods = Vec::new();
+        for part in parts {
+            mods.push(match part.to_lowercase().as_str() {
+                "ctrl" => "ctrl",
+                "shift" => "shift",
+                "alt" => "alt",
+                "cmd" | "meta" | "win" => "meta",
+                other => return Err(anyhow!("unknown modifier {other}")),
+            });
+        }
+        let key = match key.to_lowercase().as_str() {
+            "" => return Err(anyhow!("missing key")),
+            key if key.starts_with("numpad") || key.starts_with("oem_") => {
+                return Err(anyhow!("unknown key {key}"));
+            }
+            key => key.to_string(),
+        };
+        mods.push(&key);
+        keys.push(mods.join("+"));
+    }
+
+    let when = binding.when.as_deref().map(vscode_when).transpose()?;
+
+    // The arguments are kept when Lapce's command takes the same ones
+    if binding.args.is_some() && removal.is_empty() {
+        let kind = lapce_internal_commands()
+            .remove(command)
+            .map(|cmd| cmd.kind)
+            .ok_or_else(|| anyhow!("unknown command"))?;
+        LapceCommand {
+            kind,
+            data: binding.args.clone(),
+        }
+        .args()?;
+    }
+
+    Ok(KeyMap {
+        key: parse_keys(&keys)?,
+        modes: Modes::empty(),
+        when,
+        command: format!("{removal}{command}"),
+        args: binding.args.clone().filter(|_| removal.is_empty()),
+    })
+}
+
+/// Translates the context keys of a `when` clause, which has the same
+/// syntax as the keymap conditions
+fn vscode_when(when: &str) -> Result<Condition> {
+    fn translate(condition: Condition) -> Result<Condition> {
+        let key = |key: String| {
+            lookup(VSCODE_CONTEXT_KEYS, &key)
+                .map(|key| key.to_string())
+                .ok_or_else(|| anyhow!("unknown context key {key}"))
+        };
+        Ok(match condition {
+            Condition::Key(k) => Condition::Key(key(k)?),
+            Condition::Not(c) => Condition::Not(Box::new(translate(*c)?)),
+            Condition::And(l, r) => {
+                Condition::And(Box::new(translate(*l)?), Box::new(translate(*r)?))
+            }
+            Condition::Or(l, r) => {
+                Condition::Or(Box::new(translate(*l)?), Box::new(translate(*r)?))
+            }
+            Condition::Compare(k, op, value) => {
+                Condition::Compare(key(k)?, op, value)
+            }
+            Condition::Matches { key: k, pattern } => Condition::Matches {
+                key: key(k)?,
+                pattern,
+            },
+        })
+    }
+
+    translate(Condition::parse(when)?)
+}
+
+/// Removes the comments and the trailing commas that VS Code allows in its
+/// JSON files
+fn strip_json_comments(content: &str) -> String {
+    let mut json = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            json.push(c);
+            match c {
+                '\\' => json.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                json.push(c);
+            }
+            ('/', Some(&'/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        json.push(c);
+                        break;
+                    }
+                }
+            }
+            ('/', Some(&'*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            (']' | '}', _) => {
+                let trimmed = json.trim_end().len();
+                if json[..trimmed].ends_with(',') {
+                    json.truncate(trimmed - 1);
+                }
+                json.push(c);
+            }
+            _ => json.push(c),
+        }
+    }
+    json
+}
+
+/// Imports the `map` commands of a `.vimrc`, where the right side is either
+/// an Ex command like `:w<CR>` or keys that have a Lapce command
+pub fn import_vim(content: &str) -> KeymapImport {
+    let mut import = KeymapImport::default();
+    let mut leader = "\\".to_string();
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(value) = line
+            .strip_prefix("let mapleader")
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+        {
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            leader = match value.to_lowercase().as_str() {
+                "\\<space>" | "<space>" | " " => " ".to_string(),
+                _ => value.to_string(),
+            };
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let modes = match words.next() {
+            Some("map" | "noremap") => Modes::NORMAL | Modes::VISUAL,
+            Some("nmap" | "nnoremap") => Modes::NORMAL,
+            Some("vmap" | "vnoremap" | "xmap" | "xnoremap") => Modes::VISUAL,
+            Some("imap" | "inoremap" | "map!" | "noremap!") => Modes::INSERT,
+            _ => continue,
+        };
+
+        let mut words = words.skip_while(|word| {
+            matches!(
+                word.to_lowercase().as_str(),
+                "<silent>" | "<nowait>" | "<unique>"
+            )
+        });
+        let (lhs, rhs) = match (words.next(), words.collect::<Vec<_>>()) {
+            (Some(lhs), rhs) if !rhs.is_empty() => (lhs, rhs.join(" ")),
+            _ => {
+                import.unmapped.push(format!("{line}: missing mapping"));
+                continue;
+            }
+        };
+
+        match vim_keymap(lhs, &rhs, modes, &leader) {
+            Ok(keymap) => import.keymaps.push(keymap),
+            Err(err) => import.unmapped.push(format!("{line}: {err}")),
+        }
+    }
+    import
+}
+
+fn vim_keymap(lhs: &str, rhs: &str, modes: Modes, leader: &str) -> Result<KeyMap> {
+    let lower_special_keys = |keys: &str| {
+        let mut lowered = String::new();
+        let mut in_special = false;
+        for c in keys.chars() {
+            match c {
+                '<' => in_special = true,
+                '>' => in_special = false,
+                _ => {}
+            }
+            if in_special {
+                lowered.extend(c.to_lowercase());
+            } else {
+                lowered.push(c);
+            }
+        }
+        lowered
+    };
+
+    let rhs = lower_special_keys(rhs);
+    let command = match rhs.strip_prefix(':').and_then(|ex| ex.strip_suffix("<cr>"))
+    {
+        Some(ex) => lookup(VIM_EX_COMMANDS, ex.trim()),
+        None => lookup(VIM_KEYS, &rhs),
+    }
+    .ok_or_else(|| anyhow!("unknown command {rhs}"))?;
+
+    Ok(KeyMap {
+        key: parse_keys(&vim_keys(lhs, leader)?)?,
+        modes,
+        when: None,
+        command: command.to_string(),
+        args: None,
+    })
+}
+
+/// Splits the left side of a Vim mapping into Lapce keys, such as
+/// `<C-w>J` into `ctrl+w` and `shift+j`
+fn vim_keys(lhs: &str, leader: &str) -> Result<Vec<String>> {
+    let char_key = |c: char| match c {
+        ' ' => Ok("space".to_string()),
+        '+' => Err(anyhow!("the + key isn't supported")),
+        c if c.is_uppercase() => Ok(format!("shift+{}", c.to_lowercase())),
+        c => Ok(c.to_string()),
+    };
+
+    let mut keys = Vec::new();
+    let mut rest = lhs;
+    while let Some(c) = rest.chars().next() {
+        let special = rest
+            .strip_prefix('<')
+            .and_then(|special| Some(&special[..special.find('>')?]))
+            .filter(|special| !special.is_empty());
+        let special = match special {
+            Some(special) => special,
+            None => {
+                keys.push(char_key(c)?);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+        };
+        rest = &rest[special.len() + 2..];
+
+        let mut mods = Vec::new();
+        let mut name = special;
+        while name.len() > 2 && name.as_bytes()[1] == b'-' {
+            mods.push(match name.as_bytes()[0].to_ascii_lowercase() {
+                b'c' => "ctrl",
+                b's' => "shift",
+                b'a' | b'm' => "alt",
+                b'd' => "meta",
+                _ => return Err(anyhow!("unknown key <{special}>")),
+            });
+            name = &name[2..];
+        }
+
+        let key = match name.to_lowercase().as_str() {
+            "leader" | "localleader" if mods.is_empty() => {
+                for c in leader.chars() {
+                    keys.push(char_key(c)?);
+                }
+                continue;
+            }
+            "cr" | "enter" | "return" => "enter".to_string(),
+            "esc" => "esc".to_string(),
+            "space" => "space".to_string(),
+            "tab" => "tab".to_string(),
+            "bs" => "backspace".to_string(),
+            "del" => "delete".to_string(),
+            "lt" => "<".to_string(),
+            "bar" => "|".to_string(),
+            "bslash" => "\\".to_string(),
+            name @ ("up" | "down" | "left" | "right" | "home" | "end" | "pageup"
+            | "pagedown" | "insert") => name.to_string(),
+            name if name.len() > 1
+                && name.starts_with('f')
+                && name[1..].parse::<u8>().map(|n| n <= 12).unwrap_or(false) =>
+            {
+                name.to_string()
+            }
+            _ if name.chars().count() == 1 && !mods.is_empty() => {
+                name.to_lowercase()
+            }
+            _ if name.chars().count() == 1 => {
+                char_key(name.chars().next().unwrap())?
+            }
+            _ => return Err(anyhow!("unknown key <{special}>")),
+        };
+        mods.push(&key);
+        keys.push(mods.join("+"));
+    }
+    Ok(keys)
+}
+
+fn parse_keys(keys: &[String]) -> Result<Vec<KeyPress>> {
+    let parsed = KeyPress::parse(&keys.join(" "));
+    if keys.is_empty() || parsed.len() != keys.len() {
+        return Err(anyhow!("unknown key in {}", keys.join(" ")));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keymap: &KeyMap) -> String {
+        keymap
+            .key
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_import_vscode() {
+        let content = r#"
+// Place your key bindings in this file
+[
+    {
+        "key": "ctrl+shift+p",
+        "command": "workbench.action.quickOpen"
+    },
+    /* chords */
+    {
+        "key": "ctrl+k ctrl+c",
+        "command": "editor.action.commentLine",
+        "when": "editorTextFocus && !(suggestWidgetVisible || inSnippetMode)",
+    },
+    {
+        "key": "ctrl+d",
+        "command": "-editor.action.addSelectionToNextFindMatch",
+        "when": "editorLangId == 'rust'"
+    },
+    {
+        "key": "ctrl+j",
+        "command": "workbench.action.tasks.runTask",
+        "args": "build"
+    },
+    {
+        "key": "ctrl+l",
+        "command": "editor.action.insertSnippet",
+        "args": { "snippet": "log::info!(\"$1\");" }
+    },
+    {
+        "key": "ctrl+m",
+        "command": "editor.action.insertSnippet",
+        "args": { "name": "log" }
+    },
+    { "key": "ctrl+e", "command": "cursorEnd", "when": "isMac" },
+]
+"#;
+        let import = import_vscode(content).unwrap();
+        assert_eq!(import.keymaps.len(), 4);
+
+        assert_eq!(keys(&import.keymaps[0]), "Ctrl+Shift+p");
+        assert_eq!(import.keymaps[0].command, "palette");
+        assert_eq!(import.keymaps[0].when, None);
+
+        assert_eq!(keys(&import.keymaps[1]), "Ctrl+k Ctrl+c");
+        assert_eq!(import.keymaps[1].command, "toggle_line_comment");
+        assert_eq!(
+            import.keymaps[1].when.as_ref().unwrap().to_string(),
+            ("editor_focus && !(completion_focus || in_snippet)")
+        );
+
+        assert_eq!(import.keymaps[2].command, "-select_next_current");
+        assert_eq!(
+            import.keymaps[2].when.as_ref().unwrap().to_string(),
+            ("editor_lang == rust")
+        );
+
+        assert_eq!(import.keymaps[3].command, "insert_snippet");
+        assert_eq!(
+            import.keymaps[3].args,
+            Some(serde_json::json!({ "snippet": "log::info!(\"$1\");" }))
+        );
+
+        assert_eq!(import.unmapped.len(), 3);
+        assert!(import.unmapped[0].starts_with("ctrl+j"));
+        assert!(import.unmapped[1].starts_with("ctrl+m"));
+        assert!(import.unmapped[2].contains("isMac"));
+    }
+
+    #[test]
+    fn test_import_vim() {
+        let content = r#"
+set number
+let mapleader = ","
+nnoremap <silent> <leader>w :w<CR>
+inoremap jk <Esc>
+nnoremap <C-h> <C-w>h
+map <S-Tab> :bprevious<cr>
+nnoremap K :call ShowDocumentation()<CR>
+nnoremap <buffer> x dd
+"#;
+        let import = import_vim(content);
+        assert_eq!(import.keymaps.len(), 4);
+
+        assert_eq!(keys(&import.keymaps[0]), ", w");
+        assert_eq!(import.keymaps[0].command, "save");
+        assert_eq!(import.keymaps[0].modes, Modes::NORMAL);
+
+        assert_eq!(keys(&import.keymaps[1]), "j k");
+        assert_eq!(import.keymaps[1].command, "normal_mode");
+        assert_eq!(import.keymaps[1].modes, Modes::INSERT);
+
+        assert_eq!(keys(&import.keymaps[2]), "Ctrl+h");
+        assert_eq!(import.keymaps[2].command, "split_left");
+
+        assert_eq!(keys(&import.keymaps[3]), "Shift+Tab");
+        assert_eq!(import.keymaps[3].command, "previous_editor_tab");
+        assert_eq!(import.keymaps[3].modes, Modes::NORMAL | Modes::VISUAL);
+
+        assert_eq!(import.unmapped.len(), 2);
+    }
+
+    #[test]
+    fn test_vim_keys() {
+        assert_eq!(
+            vim_keys("<Space>gD<C-S-x>", "\\").unwrap(),
+            vec!["space", "g", "shift+d", "ctrl+shift+x"]
+        );
+        assert_eq!(vim_keys("<leader><F5>", " ").unwrap(), vec!["space", "f5"]);
+        assert!(vim_keys("<Plug>(foo)", "\\").is_err());
+    }
+}
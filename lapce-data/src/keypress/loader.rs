@@ -1,8 +1,9 @@
 use anyhow::{anyhow, Result};
 use indexmap::IndexMap;
 use lapce_core::mode::Modes;
+use serde_json::Value;
 
-use crate::keypress::{get_modes, keypress::KeyPress, KeyMap};
+use crate::keypress::{condition::Condition, get_modes, keypress::KeyPress, KeyMap};
 
 pub struct KeyMapLoader {
     keymaps: IndexMap<Vec<KeyPress>, Vec<KeyMap>>,
@@ -116,18 +117,98 @@ fn get_keymap(
             when: toml_keymap
                 .get("when")
                 .and_then(|w| w.as_str())
-                .map(|w| w.to_string()),
+                .map(|w| {
+                    Condition::parse(w)
+                        .map_err(|err| anyhow!("invalid condition {w}: {err}"))
+                })
+                .transpose()?,
             command: toml_keymap
                 .get("command")
                 .and_then(|c| c.as_str())
                 .map(|w| w.trim().to_string())
                 .unwrap_or_else(|| "".to_string()),
+            args: toml_keymap.get("args").and_then(item_to_json),
         }))
     }
 }
 
+/// Converts the `args` of a keymap, which can be any TOML value or table
+pub(super) fn item_to_json(item: &toml_edit::Item) -> Option<Value> {
+    match item {
+        toml_edit::Item::None => None,
+        toml_edit::Item::Value(value) => Some(value_to_json(value)),
+        toml_edit::Item::Table(table) => Some(Value::Object(
+            table
+                .iter()
+                .filter_map(|(key, item)| {
+                    Some((key.to_string(), item_to_json(item)?))
+                })
+                .collect(),
+        )),
+        toml_edit::Item::ArrayOfTables(array) => Some(Value::Array(
+            array
+                .iter()
+                .filter_map(|table| {
+                    item_to_json(&toml_edit::Item::Table(table.clone()))
+                })
+                .collect(),
+        )),
+    }
+}
+
+fn value_to_json(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(s) => Value::from(s.value().as_str()),
+        toml_edit::Value::Integer(i) => Value::from(*i.value()),
+        toml_edit::Value::Float(f) => Value::from(*f.value()),
+        toml_edit::Value::Boolean(b) => Value::from(*b.value()),
+        toml_edit::Value::Datetime(d) => Value::from(d.value().to_string()),
+        toml_edit::Value::Array(array) => {
+            Value::Array(array.iter().map(value_to_json).collect())
+        }
+        toml_edit::Value::InlineTable(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, value)| (key.to_string(), value_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Converts the `args` of a keymap back to TOML, where there's nothing to
+/// turn a null into
+pub(super) fn json_to_toml(value: &Value) -> Option<toml_edit::Value> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => toml_edit::Value::from(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml_edit::Value::from(i),
+            None => toml_edit::Value::from(n.as_f64()?),
+        },
+        Value::String(s) => toml_edit::Value::from(s.as_str()),
+        Value::Array(values) => {
+            let mut array = toml_edit::Array::new();
+            for value in values.iter().filter_map(json_to_toml) {
+                array.push(value);
+            }
+            toml_edit::Value::Array(array)
+        }
+        Value::Object(values) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, value) in values {
+                if let Some(value) = json_to_toml(value) {
+                    table.insert(key, value);
+                }
+            }
+            toml_edit::Value::InlineTable(table)
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
+    use serde_json::json;
+
     use super::*;
 
     #[test]
@@ -204,4 +285,73 @@ fn test_keymap() {
         let keypress = KeyPress::parse("shift+i");
         assert_eq!(keymaps.get(&keypress).unwrap().len(), 1);
     }
+
+    #[test]
+    fn test_keymap_args() {
+        let keymaps = r###"
+[[keymaps]]
+key = "ctrl+1"
+command = "open_file"
+args = "src/main.rs"
+
+[[keymaps]]
+key = "ctrl+2"
+command = "source_control.delete_branch"
+args = { branch = "old", force = true }
+
+[[keymaps]]
+key = "ctrl+3"
+command = "insert_snippet"
+
+[keymaps.args]
+snippet = "println!(\"$1\");"
+        "###;
+        let mut loader = KeyMapLoader::new();
+        loader.load_from_str(keymaps, false).unwrap();
+        let (keymaps, _) = loader.finalize();
+        let args =
+            |key: &str| keymaps.get(&KeyPress::parse(key)).unwrap()[0].args.clone();
+
+        assert_eq!(args("ctrl+1"), Some(json!("src/main.rs")));
+        assert_eq!(
+            args("ctrl+2"),
+            Some(json!({ "branch": "old", "force": true }))
+        );
+        assert_eq!(
+            args("ctrl+3"),
+            Some(json!({ "snippet": "println!(\"$1\");" }))
+        );
+
+        let value = json!({ "branch": "old", "force": true, "count": [1, 2.5] });
+        let toml = json_to_toml(&value).unwrap();
+        assert_eq!(value_to_json(&toml), value);
+    }
+
+    #[test]
+    fn test_invalid_condition() {
+        let keymaps = r###"
+[[keymaps]]
+key = "ctrl+1"
+command = "left"
+when = "editor_focus &&"
+
+[[keymaps]]
+key = "ctrl+2"
+command = "right"
+when = "resource_extname =~ /\\.md$/i"
+        "###;
+        let mut loader = KeyMapLoader::new();
+        loader.load_from_str(keymaps, false).unwrap();
+        let (keymaps, _) = loader.finalize();
+
+        assert!(keymaps.get(&KeyPress::parse("ctrl+1")).is_none());
+        assert_eq!(
+            keymaps.get(&KeyPress::parse("ctrl+2")).unwrap()[0]
+                .when
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            r"resource_extname =~ /\.md$/i"
+        );
+    }
 }
@@ -1,9 +1,9 @@
 #![allow(clippy::module_inception)]
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use druid::piet::{PietTextLayout, Text, TextLayout, TextLayoutBuilder};
 use druid::{Command, KbKey};
 use druid::{
@@ -15,17 +15,22 @@
 use indexmap::IndexMap;
 use itertools::Itertools;
 use lapce_core::mode::{Mode, Modes};
+use serde_json::Value;
 
+mod condition;
+mod import;
 mod keypress;
 mod loader;
 
 use crate::command::{
-    lapce_internal_commands, CommandExecuted, CommandKind, LapceCommand,
-    LapceUICommand, LAPCE_COMMAND, LAPCE_UI_COMMAND,
+    lapce_commands, CommandExecuted, CommandKind, LapceCommand, LapceUICommand,
+    LAPCE_COMMAND, LAPCE_UI_COMMAND,
 };
 use crate::config::{LapceConfig, LapceTheme};
+use crate::keypress::condition::Condition;
 use crate::keypress::loader::KeyMapLoader;
 
+pub use import::KeymapImport;
 pub use keypress::KeyPress;
 
 const DEFAULT_KEYMAPS_COMMON: &str =
@@ -37,8 +42,8 @@
 
 #[derive(PartialEq, Debug)]
 enum KeymapMatch {
-    Full(String),
-    Multiple(Vec<String>),
+    Full(KeyMap),
+    Multiple(Vec<KeyMap>),
     Prefix,
     None,
 }
@@ -69,12 +74,15 @@ pub fn paint_key(
     (rect, text_layout, text_layout_point)
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub struct KeyMap {
     pub key: Vec<KeyPress>,
     pub modes: Modes,
-    pub when: Option<String>,
+    /// The `when` of the keymap, parsed when the keymaps are loaded
+    pub when: Option<Condition>,
     pub command: String,
+    /// The arguments the command is run with, from the `args` of the keymap
+    pub args: Option<Value>,
 }
 
 pub enum Alignment {
@@ -122,9 +130,43 @@ pub fn paint(
     }
 }
 
+/// The value of a context key that a keymap condition can compare, such as
+/// the language of the editor in `editor_lang == rust`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl ContextValue {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            ContextValue::Bool(b) => *b,
+            ContextValue::Number(n) => *n != 0.0,
+            ContextValue::String(s) => !s.is_empty(),
+        }
+    }
+}
+
+impl std::fmt::Display for ContextValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContextValue::Bool(b) => write!(f, "{b}"),
+            ContextValue::Number(n) => write!(f, "{n}"),
+            ContextValue::String(s) => write!(f, "{s}"),
+        }
+    }
+}
+
 pub trait KeyPressFocus {
     fn get_mode(&self) -> Mode;
     fn check_condition(&self, condition: &str) -> bool;
+    /// The typed value of a context key, for the keys that aren't only
+    /// true or false
+    fn context_value(&self, _key: &str) -> Option<ContextValue> {
+        None
+    }
     fn run_command(
         &mut self,
         ctx: &mut EventCtx,
@@ -166,7 +208,7 @@ pub fn new(config: &LapceConfig, event_sink: ExtEventSink) -> Self {
             Self::get_keymaps(config).unwrap_or((IndexMap::new(), IndexMap::new()));
         let mut keypress = Self {
             pending_keypress: Vec::new(),
-            commands: Arc::new(lapce_internal_commands()),
+            commands: Arc::new(lapce_commands(config)),
             keymaps: Arc::new(keymaps),
             command_keymaps: Arc::new(command_keymaps),
             commands_with_keymap: Arc::new(Vec::new()),
@@ -183,12 +225,24 @@ pub fn new(config: &LapceConfig, event_sink: ExtEventSink) -> Self {
 
     pub fn update_keymaps(&mut self, config: &LapceConfig) {
         if let Ok((new_keymaps, new_command_keymaps)) = Self::get_keymaps(config) {
+            self.commands = Arc::new(lapce_commands(config));
             self.keymaps = Arc::new(new_keymaps);
             self.command_keymaps = Arc::new(new_command_keymaps);
             self.load_commands();
         }
     }
 
+    /// Registers the composite commands of the settings of another tab,
+    /// whose workspace can have ones of its own, if they're different
+    pub fn update_commands(keypress: &mut Arc<Self>, config: &LapceConfig) {
+        let commands = lapce_commands(config);
+        if *keypress.commands != commands {
+            let keypress = Arc::make_mut(keypress);
+            keypress.commands = Arc::new(commands);
+            keypress.load_commands();
+        }
+    }
+
     fn load_commands(&mut self) {
         let mut commands_with_keymap = Vec::new();
         let mut commands_without_keymap = Vec::new();
@@ -216,15 +270,19 @@ fn load_commands(&mut self) {
     fn run_command<T: KeyPressFocus>(
         &self,
         ctx: &mut EventCtx,
-        command: &str,
+        keymap: &KeyMap,
         count: Option<usize>,
         mods: Modifiers,
         focus: &mut T,
         env: &Env,
     ) -> CommandExecuted {
-        if let Some(cmd) = self.commands.get(command) {
+        if let Some(cmd) = self.commands.get(&keymap.command) {
+            let cmd = &LapceCommand {
+                kind: cmd.kind.clone(),
+                data: keymap.args.clone(),
+            };
             match cmd.kind {
-                CommandKind::Workbench(_) => {
+                CommandKind::Workbench(_) | CommandKind::Composite { .. } => {
                     if !focus.focus_only() {
                         ctx.submit_command(Command::new(
                             LAPCE_COMMAND,
@@ -341,17 +399,17 @@ pub fn key_down<T: KeyPressFocus>(
 
         let keymatch = self.match_keymap(&self.pending_keypress, focus);
         match keymatch {
-            KeymapMatch::Full(command) => {
+            KeymapMatch::Full(keymap) => {
                 self.pending_keypress.clear();
                 let count = self.count.take();
-                self.run_command(ctx, &command, count, mods, focus, env);
+                self.run_command(ctx, &keymap, count, mods, focus, env);
                 return true;
             }
-            KeymapMatch::Multiple(commands) => {
+            KeymapMatch::Multiple(keymaps) => {
                 self.pending_keypress.clear();
                 let count = self.count.take();
-                for command in commands {
-                    if self.run_command(ctx, &command, count, mods, focus, env)
+                for keymap in keymaps {
+                    if self.run_command(ctx, &keymap, count, mods, focus, env)
                         == CommandExecuted::Yes
                     {
                         return true;
@@ -370,10 +428,10 @@ pub fn key_down<T: KeyPressFocus>(
                 if focus.get_mode() == Mode::Insert {
                     let mut keypress = keypress.clone();
                     keypress.mods.set(Modifiers::SHIFT, false);
-                    if let KeymapMatch::Full(command) =
+                    if let KeymapMatch::Full(keymap) =
                         self.match_keymap(&[keypress], focus)
                     {
-                        if let Some(cmd) = self.commands.get(&command) {
+                        if let Some(cmd) = self.commands.get(&keymap.command) {
                             if let CommandKind::Move(_) = cmd.kind {
                                 focus.run_command(ctx, cmd, None, mods, env);
                                 return true;
@@ -438,7 +496,7 @@ fn match_keymap<T: KeyPressFocus>(
                             return false;
                         }
                         if let Some(condition) = &keymap.when {
-                            if !Self::check_condition(condition, check) {
+                            if !condition.check(check) {
                                 return false;
                             }
                         }
@@ -451,46 +509,32 @@ fn match_keymap<T: KeyPressFocus>(
         if matches.is_empty() {
             KeymapMatch::None
         } else if matches.len() == 1 && matches[0].key == keypresses {
-            KeymapMatch::Full(matches[0].command.clone())
+            KeymapMatch::Full((*matches[0]).clone())
         } else if matches.len() > 1
             && matches.iter().filter(|m| m.key != keypresses).count() == 0
         {
             KeymapMatch::Multiple(
-                matches.iter().rev().map(|m| m.command.clone()).collect(),
+                matches.iter().rev().map(|m| (*m).clone()).collect(),
             )
         } else {
             KeymapMatch::Prefix
         }
     }
 
-    fn check_condition<T: KeyPressFocus>(condition: &str, check: &T) -> bool {
-        fn check_one_condition<T: KeyPressFocus>(
-            condition: &str,
-            check: &T,
-        ) -> bool {
-            let trimmed = condition.trim();
-            if let Some(stripped) = trimmed.strip_prefix('!') {
-                !check.check_condition(stripped)
-            } else {
-                check.check_condition(trimmed)
-            }
-        }
-
-        match Condition::parse_first(condition) {
-            Condition::Single(condition) => check_one_condition(condition, check),
-            Condition::Or(left, right) => {
-                let left = check_one_condition(left, check);
-                let right = Self::check_condition(right, check);
-
-                left || right
-            }
-            Condition::And(left, right) => {
-                let left = check_one_condition(left, check);
-                let right = Self::check_condition(right, check);
-
-                left && right
-            }
+    /// Why the command of the keymap can't take its `args`, if it can't
+    pub fn args_error(&self, keymap: &KeyMap) -> Option<String> {
+        keymap.args.as_ref()?;
+        let kind = match self.commands.get(&keymap.command) {
+            Some(cmd) => cmd.kind.clone(),
+            None => return Some(format!("unknown command {}", keymap.command)),
+        };
+        LapceCommand {
+            kind,
+            data: keymap.args.clone(),
         }
+        .args()
+        .err()
+        .map(|err| err.to_string())
     }
 
     fn get_file_array() -> Option<toml_edit::ArrayOfTables> {
@@ -504,6 +548,103 @@ fn get_file_array() -> Option<toml_edit::ArrayOfTables> {
             .cloned()
     }
 
+    /// The entries of the keymaps file, which fails on a file that can't be
+    /// parsed instead of taking it as empty
+    fn read_file_array() -> Result<toml_edit::ArrayOfTables> {
+        let path = Self::file().ok_or_else(|| anyhow!("no keymaps file"))?;
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(toml_edit::ArrayOfTables::new());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let document: toml_edit::Document = content.parse()?;
+        match document.as_table().get("keymaps") {
+            Some(keymaps) => keymaps
+                .as_array_of_tables()
+                .cloned()
+                .ok_or_else(|| anyhow!("keymaps isn't an array of tables")),
+            None => Ok(toml_edit::ArrayOfTables::new()),
+        }
+    }
+
+    fn write_file_array(array: toml_edit::ArrayOfTables) -> Result<()> {
+        let mut table = toml_edit::Document::new();
+        table.insert("keymaps", toml_edit::Item::ArrayOfTables(array));
+        let path = Self::file().ok_or_else(|| anyhow!("no keymaps file"))?;
+        std::fs::write(&path, table.to_string().as_bytes())?;
+        Ok(())
+    }
+
+    /// Whether the entry of the keymaps file is the keymap
+    fn is_file_keymap(keymap: &KeyMap, value: &toml_edit::Table) -> bool {
+        Some(keymap.command.as_str())
+            == value.get("command").and_then(|c| c.as_str())
+            && keymap.when
+                == value
+                    .get("when")
+                    .and_then(|w| w.as_str())
+                    .and_then(|w| Condition::parse(w).ok())
+            && keymap.modes == get_modes(value)
+            && keymap.args == value.get("args").and_then(loader::item_to_json)
+            && Some(keymap.key.clone())
+                == value
+                    .get("key")
+                    .and_then(|v| v.as_str())
+                    .map(KeyPress::parse)
+    }
+
+    /// The entry of the keymaps file for the keymap, bound to the keys
+    fn file_keymap(keymap: &KeyMap, keys: &[KeyPress]) -> toml_edit::Table {
+        let mut table = toml_edit::Table::new();
+        table.insert(
+            "command",
+            toml_edit::value(toml_edit::Value::from(keymap.command.clone())),
+        );
+        if !keymap.modes.is_empty() {
+            table.insert(
+                "mode",
+                toml_edit::value(toml_edit::Value::from(keymap.modes.to_string())),
+            );
+        }
+        if let Some(when) = keymap.when.as_ref() {
+            table.insert(
+                "when",
+                toml_edit::value(toml_edit::Value::from(when.to_string())),
+            );
+        }
+        if let Some(args) = keymap.args.as_ref().and_then(loader::json_to_toml) {
+            table.insert("args", toml_edit::value(args));
+        }
+        table.insert(
+            "key",
+            toml_edit::value(toml_edit::Value::from(
+                keys.iter().map(|k| k.to_string()).join(" "),
+            )),
+        );
+        table
+    }
+
+    /// Adds the keymaps the entries don't have yet, and returns them
+    fn add_file_keymaps(
+        array: &mut toml_edit::ArrayOfTables,
+        keymaps: Vec<KeyMap>,
+    ) -> Vec<KeyMap> {
+        let mut added = Vec::new();
+        for keymap in keymaps {
+            if array
+                .iter()
+                .any(|value| Self::is_file_keymap(&keymap, value))
+            {
+                continue;
+            }
+            array.push(Self::file_keymap(&keymap, &keymap.key));
+            added.push(keymap);
+        }
+        added
+    }
+
     pub fn filter_commands(&mut self, pattern: &str) {
         self.filter_pattern = pattern.to_string();
         let pattern = pattern.to_string();
@@ -553,18 +694,9 @@ pub fn filter_commands(&mut self, pattern: &str) {
 
     pub fn update_file(keymap: &KeyMap, keys: &[KeyPress]) -> Option<()> {
         let mut array = Self::get_file_array().unwrap_or_default();
-        let index = array.iter().position(|value| {
-            Some(keymap.command.as_str())
-                == value.get("command").and_then(|c| c.as_str())
-                && keymap.when.as_deref()
-                    == value.get("when").and_then(|w| w.as_str())
-                && keymap.modes == get_modes(value)
-                && Some(keymap.key.clone())
-                    == value
-                        .get("key")
-                        .and_then(|v| v.as_str())
-                        .map(KeyPress::parse)
-        });
+        let index = array
+            .iter()
+            .position(|value| Self::is_file_keymap(keymap, value));
 
         if let Some(index) = index {
             if !keys.is_empty() {
@@ -578,43 +710,14 @@ pub fn update_file(keymap: &KeyMap, keys: &[KeyPress]) -> Option<()> {
                 array.remove(index);
             };
         } else {
-            let mut table = toml_edit::Table::new();
-            table.insert(
-                "command",
-                toml_edit::value(toml_edit::Value::from(keymap.command.clone())),
-            );
-            if !keymap.modes.is_empty() {
-                table.insert(
-                    "mode",
-                    toml_edit::value(toml_edit::Value::from(
-                        keymap.modes.to_string(),
-                    )),
-                );
-            }
-            if let Some(when) = keymap.when.as_ref() {
-                table.insert(
-                    "when",
-                    toml_edit::value(toml_edit::Value::from(when.to_string())),
-                );
-            }
-
             if !keys.is_empty() {
-                table.insert(
-                    "key",
-                    toml_edit::value(toml_edit::Value::from(
-                        keys.iter().map(|k| k.to_string()).join(" "),
-                    )),
-                );
-                array.push(table.clone());
+                array.push(Self::file_keymap(keymap, keys));
             }
 
             if !keymap.key.is_empty() {
-                table.insert(
-                    "key",
-                    toml_edit::value(toml_edit::Value::from(
-                        keymap.key.iter().map(|k| k.to_string()).join(" "),
-                    )),
-                );
+                // The removal matches the keymap whatever its arguments
+                let mut table = Self::file_keymap(keymap, &keymap.key);
+                table.remove("args");
                 table.insert(
                     "command",
                     toml_edit::value(toml_edit::Value::from(format!(
@@ -622,15 +725,30 @@ pub fn update_file(keymap: &KeyMap, keys: &[KeyPress]) -> Option<()> {
                         keymap.command
                     ))),
                 );
-                array.push(table.clone());
+                array.push(table);
             }
         }
 
-        let mut table = toml_edit::Document::new();
-        table.insert("keymaps", toml_edit::Item::ArrayOfTables(array));
-        let path = Self::file()?;
-        std::fs::write(&path, table.to_string().as_bytes()).ok()?;
-        None
+        Self::write_file_array(array).ok()
+    }
+
+    /// Adds the bindings of a VS Code `keybindings.json`, or of a `.vimrc`
+    /// for any other file, to the keymaps file
+    pub fn import_file(path: &Path) -> Result<KeymapImport> {
+        let content = std::fs::read_to_string(path)?;
+        let mut import = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => import::import_vscode(&content)?,
+            _ => import::import_vim(&content),
+        };
+        let mut array = Self::read_file_array()?;
+        let keymaps = std::mem::take(&mut import.keymaps);
+        let count = keymaps.len();
+        import.keymaps = Self::add_file_keymaps(&mut array, keymaps);
+        import.existing = count - import.keymaps.len();
+        if !import.keymaps.is_empty() {
+            Self::write_file_array(array)?;
+        }
+        Ok(import)
     }
 
     pub fn file() -> Option<PathBuf> {
@@ -707,41 +825,13 @@ fn get_modes(toml_keymap: &toml_edit::Table) -> Modes {
         .unwrap_or_else(Modes::empty)
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum Condition<'a> {
-    Single(&'a str),
-    Or(&'a str, &'a str),
-    And(&'a str, &'a str),
-}
-
-impl<'a> Condition<'a> {
-    fn parse_first(condition: &'a str) -> Self {
-        let or = condition.match_indices("||").next();
-        let and = condition.match_indices("&&").next();
-
-        match (or, and) {
-            (None, None) => Condition::Single(condition),
-            (Some((pos, _)), None) => {
-                Condition::Or(&condition[..pos], &condition[pos + 2..])
-            }
-            (None, Some((pos, _))) => {
-                Condition::And(&condition[..pos], &condition[pos + 2..])
-            }
-            (Some((or_pos, _)), Some((and_pos, _))) => {
-                if or_pos < and_pos {
-                    Condition::Or(&condition[..or_pos], &condition[or_pos + 2..])
-                } else {
-                    Condition::And(&condition[..and_pos], &condition[and_pos + 2..])
-                }
-            }
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
-    use crate::keypress::{Condition, KeyPressData, KeyPressFocus};
-    use lapce_core::mode::Mode;
+    use crate::keypress::{
+        condition::{CompareOp, Pattern},
+        Condition, ContextValue, KeyMap, KeyPress, KeyPressData, KeyPressFocus,
+    };
+    use lapce_core::mode::{Mode, Modes};
 
     struct MockFocus {
         accepted_conditions: &'static [&'static str],
@@ -752,6 +842,15 @@ fn check_condition(&self, condition: &str) -> bool {
             self.accepted_conditions.contains(&condition)
         }
 
+        fn context_value(&self, key: &str) -> Option<ContextValue> {
+            match key {
+                "editor_lang" => Some(ContextValue::String("rust".to_string())),
+                "resource_extname" => Some(ContextValue::String(".rs".to_string())),
+                "line_count" => Some(ContextValue::Number(42.0)),
+                _ => None,
+            }
+        }
+
         fn get_mode(&self) -> Mode {
             unimplemented!()
         }
@@ -774,22 +873,52 @@ fn receive_char(&mut self, _ctx: &mut druid::EventCtx, _c: &str) {
 
     #[test]
     fn test_parse() {
+        let key = |key: &str| Box::new(Condition::Key(key.to_string()));
+        assert_eq!(
+            Condition::Or(key("foo"), key("bar")),
+            Condition::parse("foo||bar").unwrap()
+        );
         assert_eq!(
-            Condition::Or("foo", "bar"),
-            Condition::parse_first("foo||bar")
+            Condition::And(key("foo"), key("bar")),
+            Condition::parse("foo&&bar").unwrap()
         );
         assert_eq!(
-            Condition::And("foo", "bar"),
-            Condition::parse_first("foo&&bar")
+            Condition::Or(
+                Box::new(Condition::And(key("foo"), key("bar"))),
+                key("baz")
+            ),
+            Condition::parse("foo && bar || baz").unwrap()
         );
         assert_eq!(
-            Condition::And("foo", "bar||baz"),
-            Condition::parse_first("foo&&bar||baz")
+            Condition::And(
+                key("foo"),
+                Box::new(Condition::Or(key("bar"), key("baz")))
+            ),
+            Condition::parse("foo && (bar || baz)").unwrap()
         );
         assert_eq!(
-            Condition::And("foo ", " bar || baz"),
-            Condition::parse_first("foo && bar || baz")
+            Condition::And(
+                Box::new(Condition::Not(key("foo"))),
+                Box::new(Condition::Compare(
+                    "editor_lang".to_string(),
+                    CompareOp::Eq,
+                    "rust".to_string()
+                ))
+            ),
+            Condition::parse("!foo && editor_lang == 'rust'").unwrap()
         );
+        assert_eq!(
+            Condition::Matches {
+                key: "resource_extname".to_string(),
+                pattern: Pattern::new(r"\.md$", "i").unwrap(),
+            },
+            Condition::parse(r"resource_extname =~ /\.md$/i").unwrap()
+        );
+        assert!(Condition::parse("foo && (bar || baz").is_err());
+        assert!(Condition::parse("foo bar").is_err());
+        assert!(Condition::parse("editor_lang ==").is_err());
+        assert!(Condition::parse("foo &&").is_err());
+        assert!(Condition::parse("resource_extname =~ /(/").is_err());
     }
 
     #[test]
@@ -812,14 +941,72 @@ fn test_check_condition() {
             ("foo && bar || baz", true),
             ("foo && bar && baz", false),
             ("foo && bar && !baz", true),
+            ("baz || foo && bar", true),
+            ("!(foo && bar)", false),
+            ("foo && (baz || !bar)", false),
+            ("editor_lang == rust", true),
+            ("editor_lang != rust", false),
+            ("editor_lang == \"go\"", false),
+            (
+                "foo && (editor_lang == go || resource_extname =~ /\\.RS$/i)",
+                true,
+            ),
+            ("resource_extname =~ /\\.md$/", false),
+            ("line_count > 10 && line_count <= 42", true),
+            ("line_count >= 43", false),
+            ("foo == true && baz == false", true),
         ];
 
         for (condition, should_accept) in test_cases.into_iter() {
             assert_eq!(
                 should_accept,
-                KeyPressData::check_condition(condition, &focus),
+                Condition::parse(condition).unwrap().check(&focus),
                 "Condition check failed. Condition: {condition}. Expected result: {should_accept}",
             );
         }
     }
+
+    #[test]
+    fn test_add_file_keymaps() {
+        let keymap = |key: &str, command: &str, when: Option<&str>| KeyMap {
+            key: KeyPress::parse(key),
+            modes: Modes::empty(),
+            when: when.map(|when| Condition::parse(when).unwrap()),
+            command: command.to_string(),
+            args: None,
+        };
+        let mut array: toml_edit::ArrayOfTables = r#"
+[[keymaps]]
+key = "ctrl+s"
+command = "save"
+"#
+        .parse::<toml_edit::Document>()
+        .unwrap()["keymaps"]
+            .as_array_of_tables()
+            .cloned()
+            .unwrap();
+
+        let added = KeyPressData::add_file_keymaps(
+            &mut array,
+            vec![
+                keymap("ctrl+s", "save", None),
+                keymap("ctrl+p", "palette", Some("!modal")),
+                keymap("ctrl+p", "palette", Some("!modal")),
+            ],
+        );
+        assert_eq!(added, vec![keymap("ctrl+p", "palette", Some("!modal"))]);
+        assert_eq!(array.len(), 2);
+        let entry = array.get(1).unwrap();
+        assert_eq!(entry["key"].as_str(), Some("ctrl+p"));
+        assert_eq!(entry["command"].as_str(), Some("palette"));
+        assert_eq!(entry["when"].as_str(), Some("!modal"));
+
+        // Importing the same keymaps again adds nothing
+        let added = KeyPressData::add_file_keymaps(
+            &mut array,
+            vec![keymap("ctrl+p", "palette", Some("!modal"))],
+        );
+        assert!(added.is_empty());
+        assert_eq!(array.len(), 2);
+    }
 }
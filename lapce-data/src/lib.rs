@@ -1,5 +1,6 @@
 pub mod about;
 pub mod alert;
+pub mod code_lens;
 pub mod command;
 pub mod completion;
 pub mod config;
@@ -7,15 +8,18 @@
 pub mod data;
 pub mod db;
 pub mod document;
+pub mod document_highlight;
 pub mod editor;
 pub mod explorer;
 pub mod find;
+pub mod hierarchy;
 pub mod history;
 pub mod hover;
 pub mod keypress;
 pub mod list;
 pub mod markdown;
 pub mod menu;
+pub mod notification;
 pub mod palette;
 pub mod panel;
 pub mod picker;
@@ -27,6 +31,7 @@
 pub mod search;
 pub mod selection_range;
 pub mod settings;
+pub mod settings_bundle;
 pub mod signature;
 pub mod source_control;
 pub mod split;
@@ -0,0 +1,106 @@
+use druid::WidgetId;
+use lapce_rpc::RequestId;
+use lsp_types::{MessageActionItem, MessageType};
+
+/// Older notifications are dropped once there are more than this
+const MAX_NOTIFICATIONS: usize = 5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub id: u64,
+    pub title: String,
+    pub message: String,
+    pub typ: MessageType,
+    pub actions: Vec<MessageActionItem>,
+    /// Set when a language server waits for one of the actions to be picked
+    pub request_id: Option<RequestId>,
+}
+
+/// The messages stacked in the bottom right corner of the window until
+/// they are closed
+#[derive(Clone)]
+pub struct NotificationData {
+    pub widget_id: WidgetId,
+    next_id: u64,
+    pub items: Vec<Notification>,
+}
+
+impl Default for NotificationData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotificationData {
+    pub fn new() -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            next_id: 0,
+            items: Vec::new(),
+        }
+    }
+
+    /// Adds a notification, and returns the ones it pushed out
+    pub fn push(
+        &mut self,
+        title: String,
+        message: String,
+        typ: MessageType,
+        actions: Vec<MessageActionItem>,
+        request_id: Option<RequestId>,
+    ) -> Vec<Notification> {
+        self.items.push(Notification {
+            id: self.next_id,
+            title,
+            message,
+            typ,
+            actions,
+            request_id,
+        });
+        self.next_id += 1;
+        let excess = self.items.len().saturating_sub(MAX_NOTIFICATIONS);
+        self.items.drain(..excess).collect()
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<Notification> {
+        let index = self.items.iter().position(|item| item.id == id)?;
+        Some(self.items.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_push_and_remove() {
+        let mut data = NotificationData::new();
+        for i in 0..MAX_NOTIFICATIONS {
+            let dropped = data.push(
+                "server".to_string(),
+                format!("message {i}"),
+                MessageType::INFO,
+                Vec::new(),
+                Some(i as RequestId),
+            );
+            assert!(dropped.is_empty());
+        }
+
+        let dropped = data.push(
+            "server".to_string(),
+            "last".to_string(),
+            MessageType::ERROR,
+            Vec::new(),
+            None,
+        );
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].request_id, Some(0));
+        assert_eq!(data.items.len(), MAX_NOTIFICATIONS);
+
+        let removed = data.remove(3).unwrap();
+        assert_eq!(removed.message, "message 3");
+        assert_eq!(data.remove(3), None);
+        assert_eq!(data.items.len(), MAX_NOTIFICATIONS - 1);
+        assert_eq!(data.items.last().unwrap().id, MAX_NOTIFICATIONS as u64);
+    }
+}
@@ -5,22 +5,24 @@
 use druid::{Data, Env, EventCtx};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
+use indexmap::IndexMap;
 use itertools::Itertools;
 use lapce_core::command::{EditCommand, FocusCommand};
 use lapce_core::language::LapceLanguage;
 use lapce_core::mode::Mode;
+use lapce_rpc::plugin::{PaletteProvider, PluginId, PluginPaletteItem};
 use lapce_rpc::proxy::ProxyResponse;
 use lsp_types::{DocumentSymbolResponse, Position, Range, SymbolKind};
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::command::CommandKind;
-use crate::data::{LapceWorkspace, LapceWorkspaceType};
+use crate::command::{CommandKind, LapceWorkbenchCommand};
+use crate::data::{EditorTabChild, LapceWorkspace, LapceWorkspaceType};
 use crate::document::BufferContent;
-use crate::editor::EditorLocation;
+use crate::editor::{EditorLocation, LineCol};
 use crate::list::ListData;
 use crate::panel::PanelKind;
 use crate::proxy::path_from_url;
@@ -30,9 +32,11 @@
     command::{LapceCommand, LapceUICommand},
     config::LapceConfig,
     data::{FocusArea, LapceMainSplitData, LapceTabData},
+    db::FileFrecency,
     find::Find,
     keypress::{KeyPressData, KeyPressFocus},
     proxy::LapceProxy,
+    source_control::{GitBranchAction, GitStashAction, SourceControlData},
     terminal::TerminalSplitData,
 };
 
@@ -49,6 +53,17 @@ pub enum PaletteType {
     Theme,
     SshHost,
     Language,
+    GitBranch(GitBranchAction),
+    GitStash(GitStashAction),
+    /// `:<line>[:<column>]` in the active editor
+    GoToLine,
+    /// The open editors, recent files and recent workspaces together
+    Switch,
+    /// A palette that a volt registered
+    Plugin {
+        plugin_id: PluginId,
+        provider: String,
+    },
 }
 
 impl PaletteType {
@@ -65,6 +80,11 @@ fn string(&self) -> String {
             PaletteType::Theme => "".to_string(),
             PaletteType::SshHost => "".to_string(),
             PaletteType::Language => "".to_string(),
+            PaletteType::GitBranch(_) => "".to_string(),
+            PaletteType::GitStash(_) => "".to_string(),
+            PaletteType::GoToLine => ":".to_string(),
+            PaletteType::Switch => "".to_string(),
+            PaletteType::Plugin { .. } => "".to_string(),
         }
     }
 
@@ -76,6 +96,7 @@ pub fn has_preview(&self) -> bool {
                 | PaletteType::WorkspaceSymbol
                 | PaletteType::GlobalSearch
                 | PaletteType::Reference
+                | PaletteType::GoToLine
         )
     }
 
@@ -86,7 +107,11 @@ fn get_palette_type(current_type: &PaletteType, input: &str) -> PaletteType {
             PaletteType::Reference
             | PaletteType::SshHost
             | PaletteType::Theme
-            | PaletteType::Language => {
+            | PaletteType::Language
+            | PaletteType::GitBranch(_)
+            | PaletteType::GitStash(_)
+            | PaletteType::Switch
+            | PaletteType::Plugin { .. } => {
                 return current_type.clone();
             }
             _ => (),
@@ -99,6 +124,11 @@ fn get_palette_type(current_type: &PaletteType, input: &str) -> PaletteType {
             _ if input.starts_with('@') => PaletteType::DocumentSymbol,
             _ if input.starts_with('#') => PaletteType::WorkspaceSymbol,
             _ if input.starts_with('>') => PaletteType::Workspace,
+            _ if input.starts_with(':')
+                && input[1..].starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                PaletteType::GoToLine
+            }
             _ if input.starts_with(':') => PaletteType::Command,
             _ => PaletteType::File,
         }
@@ -123,6 +153,13 @@ pub enum PaletteStatus {
 pub enum PaletteItemContent {
     File(PathBuf, PathBuf),
     Line(usize, String),
+    GoToLine {
+        line: usize,
+        /// The byte offset in the line, or the first non-blank character
+        /// when it's not given
+        column: Option<usize>,
+        text: String,
+    },
     TerminalLine(i32, String),
     DocumentSymbol {
         kind: SymbolKind,
@@ -143,6 +180,13 @@ pub enum PaletteItemContent {
     Command(LapceCommand),
     Theme(String),
     Language(String),
+    /// A palette that a volt registered, which is opened when it is picked
+    PluginProvider(PluginId, PaletteProvider),
+    PluginItem {
+        plugin_id: PluginId,
+        provider: String,
+        item: PluginPaletteItem,
+    },
 }
 
 impl PaletteItemContent {
@@ -202,6 +246,28 @@ fn select(
                     Target::Auto,
                 ));
             }
+            PaletteItemContent::GoToLine { line, column, .. } => {
+                let editor_id = if preview {
+                    Some(preview_editor_id)
+                } else {
+                    None
+                };
+                let command = match column {
+                    Some(column) => LapceUICommand::JumpToLineCol(
+                        editor_id,
+                        LineCol {
+                            line: line.saturating_sub(1),
+                            column: *column,
+                        },
+                    ),
+                    None => LapceUICommand::JumpToLine(editor_id, *line),
+                };
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    command,
+                    Target::Auto,
+                ));
+            }
             PaletteItemContent::ReferenceLocation(_rel_path, location) => {
                 let editor_id = if preview {
                     Some(preview_editor_id)
@@ -279,6 +345,9 @@ fn select(
                     ));
                 }
             }
+            // The palette hands these to the volt itself
+            PaletteItemContent::PluginProvider(..) => return false,
+            PaletteItemContent::PluginItem { .. } => {}
         }
         true
     }
@@ -288,10 +357,128 @@ fn select(
 pub struct PaletteItem {
     pub content: PaletteItemContent,
     pub filter_text: String,
+    /// The rank of the item, which the fuzzy score of the input is added to
     pub score: i64,
     pub indices: Vec<usize>,
 }
 
+/// The boost of a file's fuzzy score, which grows slower as the frecency
+/// grows so that it can't outweigh a much better match
+fn file_frecency_bonus(frecency: f64) -> i64 {
+    (frecency.ln_1p() * 16.0) as i64
+}
+
+/// The boost of a recently run command's fuzzy score, which keeps it near
+/// the top without outweighing a much better match
+const RECENT_COMMAND_BONUS: i64 = 20;
+
+/// Parses the `<line>[:<column>]` of the go to line palette, where the column
+/// can still be empty while it's being typed
+fn parse_line_col(input: &str) -> Option<(usize, Option<usize>)> {
+    let mut parts = input.splitn(2, ':');
+    let line = parts.next()?.trim().parse().ok()?;
+    let column = match parts.next().map(str::trim) {
+        None | Some("") => None,
+        Some(column) => Some(column.parse().ok()?),
+    };
+    Some((line, column))
+}
+
+fn workspace_palette_item(workspace: LapceWorkspace) -> PaletteItem {
+    let text = workspace
+        .path
+        .as_ref()
+        .unwrap()
+        .to_str()
+        .map(|p| p.to_string())
+        .unwrap();
+    let filter_text = match &workspace.kind {
+        LapceWorkspaceType::Local => text,
+        LapceWorkspaceType::RemoteSSH(user, host) => {
+            format!("[{}@{}] {}", user, host, text)
+        }
+        LapceWorkspaceType::RemoteWSL => {
+            format!("[wsl] {text}")
+        }
+    };
+    PaletteItem {
+        content: PaletteItemContent::Workspace(workspace),
+        filter_text,
+        score: 0,
+        indices: vec![],
+    }
+}
+
+/// The items of the command palette, with the recently run commands first
+/// and boosted
+fn command_items(
+    commands: &IndexMap<String, LapceCommand>,
+    recent_commands: &im::Vector<String>,
+) -> im::Vector<PaletteItem> {
+    const EXCLUDED_ITEMS: &[&str] = &["palette.command"];
+
+    let recent = recent_commands
+        .iter()
+        .filter_map(|name| commands.get(name).map(|c| (c, RECENT_COMMAND_BONUS)));
+    let others = commands
+        .iter()
+        .filter(|(name, _)| !recent_commands.contains(*name))
+        .map(|(_, c)| (c, 0));
+    recent
+        .chain(others)
+        .filter_map(|(c, score)| {
+            if EXCLUDED_ITEMS.contains(&c.kind.str()) {
+                return None;
+            }
+
+            c.kind.desc().as_ref().map(|m| PaletteItem {
+                content: PaletteItemContent::Command(c.clone()),
+                filter_text: m.to_string(),
+                score,
+                indices: vec![],
+            })
+        })
+        .collect()
+}
+
+/// The items of the switch palette: the open files, then the other recently
+/// opened files by the last open, then the other workspaces
+fn switch_items(
+    workspace: &LapceWorkspace,
+    mut paths: Vec<PathBuf>,
+    recent_files: HashMap<PathBuf, FileFrecency>,
+    recent_workspaces: Vec<LapceWorkspace>,
+) -> im::Vector<PaletteItem> {
+    let recent_files = recent_files
+        .into_iter()
+        .sorted_by(|(_, a), (_, b)| b.last_open.cmp(&a.last_open));
+    for (path, _) in recent_files {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    let workspace_path = workspace.path.as_ref();
+    let files = paths.into_iter().map(|full_path| {
+        let path = workspace_path
+            .and_then(|workspace_path| full_path.strip_prefix(workspace_path).ok())
+            .unwrap_or(&full_path)
+            .to_path_buf();
+        PaletteItem {
+            filter_text: path.to_str().unwrap_or("").to_string(),
+            content: PaletteItemContent::File(path, full_path),
+            score: 0,
+            indices: vec![],
+        }
+    });
+    let workspaces = recent_workspaces
+        .into_iter()
+        .filter(|w| w.kind != workspace.kind || w.path != workspace.path)
+        .map(workspace_palette_item);
+
+    files.chain(workspaces).collect()
+}
+
 pub struct PaletteViewLens;
 
 #[derive(Clone, Data)]
@@ -304,6 +491,7 @@ pub struct PaletteViewData {
     pub config: Arc<LapceConfig>,
     pub focus_area: FocusArea,
     pub terminal: Arc<TerminalSplitData>,
+    pub source_control: Arc<SourceControlData>,
 }
 
 impl Lens<LapceTabData, PaletteViewData> for PaletteViewLens {
@@ -338,6 +526,9 @@ pub struct PaletteListData {
     /// Should only be `None` when it hasn't been updated initially  
     /// We need this just for some rendering, and not editing it.
     pub workspace: Option<Arc<LapceWorkspace>>,
+    /// The commands last run from the command palette, which are marked as
+    /// recently used
+    pub recent_commands: im::Vector<String>,
 }
 
 #[derive(Clone)]
@@ -359,6 +550,16 @@ pub struct PaletteData {
     pub total_items: im::Vector<PaletteItem>,
     pub preview_editor: WidgetId,
     pub input_editor: WidgetId,
+    /// The palettes that volts registered
+    pub plugin_providers: im::Vector<PluginPaletteProvider>,
+}
+
+/// A palette a volt registered
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginPaletteProvider {
+    pub volt_id: String,
+    pub plugin_id: PluginId,
+    pub provider: PaletteProvider,
 }
 
 impl KeyPressFocus for PaletteViewData {
@@ -422,8 +623,14 @@ pub fn new(config: Arc<LapceConfig>, proxy: Arc<LapceProxy>) -> Self {
         let widget_id = WidgetId::next();
         let scroll_id = WidgetId::next();
         let preview_editor = WidgetId::next();
-        let mut list_data =
-            ListData::new(config, widget_id, PaletteListData { workspace: None });
+        let mut list_data = ListData::new(
+            config,
+            widget_id,
+            PaletteListData {
+                workspace: None,
+                recent_commands: im::Vector::new(),
+            },
+        );
         // TODO: Make these configurable
         list_data.line_height = Some(25);
         list_data.max_displayed_items = 15;
@@ -443,9 +650,36 @@ pub fn new(config: Arc<LapceConfig>, proxy: Arc<LapceProxy>) -> Self {
             total_items: im::Vector::new(),
             preview_editor,
             input_editor: WidgetId::next(),
+            plugin_providers: im::Vector::new(),
         }
     }
 
+    /// Adds a palette a volt registered, which replaces the one of the same
+    /// id it registered before, even before it was restarted
+    pub fn register_provider(
+        &mut self,
+        volt_id: String,
+        plugin_id: PluginId,
+        provider: PaletteProvider,
+    ) {
+        self.plugin_providers.retain(|registered| {
+            registered.provider.id != provider.id
+                || (registered.plugin_id != plugin_id
+                    && registered.volt_id != volt_id)
+        });
+        self.plugin_providers.push_back(PluginPaletteProvider {
+            volt_id,
+            plugin_id,
+            provider,
+        });
+    }
+
+    /// Drops the palettes of a volt that was disabled or removed
+    pub fn remove_providers(&mut self, volt_id: &str) {
+        self.plugin_providers
+            .retain(|registered| registered.volt_id != volt_id);
+    }
+
     pub fn len(&self) -> usize {
         self.current_items().len()
     }
@@ -475,12 +709,17 @@ pub fn get_input(&self) -> &str {
             PaletteType::Theme => &self.input,
             PaletteType::Language => &self.input,
             PaletteType::SshHost => &self.input,
+            PaletteType::GitBranch(_) => &self.input,
+            PaletteType::GitStash(_) => &self.input,
+            PaletteType::Switch => &self.input,
+            PaletteType::Plugin { .. } => &self.input,
             PaletteType::Line => &self.input[1..],
             PaletteType::DocumentSymbol => &self.input[1..],
             PaletteType::WorkspaceSymbol => &self.input[1..],
             PaletteType::Workspace => &self.input[1..],
             PaletteType::Command => &self.input[1..],
             PaletteType::GlobalSearch => &self.input[1..],
+            PaletteType::GoToLine => &self.input[1..],
         }
     }
 }
@@ -612,6 +851,12 @@ pub fn run(
                 self.get_themes(ctx, &config);
                 self.preselect_matching(ctx, &config.theme.name);
             }
+            PaletteType::GitBranch(action) => {
+                self.get_git_branches(ctx, action);
+            }
+            PaletteType::GitStash(action) => {
+                self.get_git_stashes(ctx, action);
+            }
             PaletteType::Language => {
                 self.get_languages(ctx);
                 if let Some(editor) = self.main_split.active_editor() {
@@ -622,6 +867,19 @@ pub fn run(
                     }
                 }
             }
+            PaletteType::GoToLine => {
+                self.update_go_to_line(ctx);
+            }
+            PaletteType::Switch => {
+                self.get_switch_items(ctx);
+            }
+            PaletteType::Plugin {
+                plugin_id,
+                ref provider,
+            } => {
+                let provider = provider.clone();
+                self.get_plugin_items(ctx, plugin_id, provider);
+            }
         }
 
         self.fill_list();
@@ -658,12 +916,17 @@ pub fn delete_to_beginning_of_line(&mut self, ctx: &mut EventCtx) {
             PaletteType::Theme => 0,
             PaletteType::Language => 0,
             PaletteType::SshHost => 0,
+            PaletteType::GitBranch(_) => 0,
+            PaletteType::GitStash(_) => 0,
+            PaletteType::Switch => 0,
+            PaletteType::Plugin { .. } => 0,
             PaletteType::Line => 1,
             PaletteType::DocumentSymbol => 1,
             PaletteType::WorkspaceSymbol => 1,
             PaletteType::Workspace => 1,
             PaletteType::Command => 1,
             PaletteType::GlobalSearch => 1,
+            PaletteType::GoToLine => 1,
         };
 
         if palette.cursor == start {
@@ -703,6 +966,42 @@ pub fn select(&mut self, ctx: &mut EventCtx) {
                 Target::Widget(*self.main_split.tab_id),
             ));
         }
+        let content = self
+            .palette
+            .list_data
+            .current_selected_item()
+            .map(|item| item.content.clone());
+        match &content {
+            Some(PaletteItemContent::Command(command))
+                if self.palette.palette_type == PaletteType::Command =>
+            {
+                self.main_split.db.save_command_run(command.kind.str());
+            }
+            Some(PaletteItemContent::PluginProvider(plugin_id, provider)) => {
+                self.run(
+                    ctx,
+                    Some(PaletteType::Plugin {
+                        plugin_id: *plugin_id,
+                        provider: provider.id.clone(),
+                    }),
+                    None,
+                );
+                return;
+            }
+            Some(PaletteItemContent::PluginItem {
+                plugin_id,
+                provider,
+                item,
+            }) => {
+                self.palette.proxy.proxy_rpc.select_palette_item(
+                    *plugin_id,
+                    provider.clone(),
+                    item.data.clone(),
+                );
+            }
+            _ => {}
+        }
+
         let palette = Arc::make_mut(&mut self.palette);
         if let Some(item) = palette.list_data.current_selected_item() {
             if item.content.select(ctx, false, palette.preview_editor) {
@@ -729,6 +1028,55 @@ pub fn select(&mut self, ctx: &mut EventCtx) {
                 ));
                 return;
             }
+            if let PaletteType::GitBranch(action) = self.palette.palette_type {
+                let input = self.palette.get_input().trim().to_string();
+                let command = match action {
+                    GitBranchAction::Create => {
+                        Some(LapceWorkbenchCommand::CreateBranch)
+                    }
+                    GitBranchAction::Rename => {
+                        Some(LapceWorkbenchCommand::RenameBranch)
+                    }
+                    // Deleting only works on one of the listed branches
+                    GitBranchAction::Delete => None,
+                };
+                if let Some(command) = command.filter(|_| !input.is_empty()) {
+                    ctx.submit_command(Command::new(
+                        LAPCE_COMMAND,
+                        LapceCommand {
+                            kind: CommandKind::Workbench(command),
+                            data: Some(serde_json::json!(input)),
+                        },
+                        Target::Auto,
+                    ));
+                }
+            }
+            if let PaletteType::GitStash(action) = self.palette.palette_type {
+                // The input is the stash message, which can be left empty
+                let command = match action {
+                    GitStashAction::Save => {
+                        Some(LapceWorkbenchCommand::SourceControlStash)
+                    }
+                    GitStashAction::SaveIncludeUntracked => Some(
+                        LapceWorkbenchCommand::SourceControlStashIncludeUntracked,
+                    ),
+                    // These only work on one of the listed stashes
+                    GitStashAction::Apply
+                    | GitStashAction::Pop
+                    | GitStashAction::Drop => None,
+                };
+                if let Some(command) = command {
+                    let input = self.palette.get_input().trim().to_string();
+                    ctx.submit_command(Command::new(
+                        LAPCE_COMMAND,
+                        LapceCommand {
+                            kind: CommandKind::Workbench(command),
+                            data: Some(serde_json::json!(input)),
+                        },
+                        Target::Auto,
+                    ));
+                }
+            }
             self.cancel(ctx);
         }
     }
@@ -763,7 +1111,14 @@ pub fn update_palette(&mut self, ctx: &mut EventCtx) {
             &self.palette.input,
         );
         if self.palette.palette_type != palette_type {
-            self.run(ctx, Some(palette_type), None);
+            // Keep what was typed, like the line number that switched to
+            // the go to line palette, and filter the new items by it
+            let input = self.palette.input.clone();
+            self.run(ctx, Some(palette_type), Some(input));
+        }
+
+        if self.palette.palette_type == PaletteType::GoToLine {
+            self.update_go_to_line(ctx);
             return;
         }
 
@@ -785,13 +1140,19 @@ fn get_files(&self, ctx: &mut EventCtx) {
         let run_id = self.palette.run_id.clone();
         let widget_id = self.palette.widget_id;
         let workspace = self.workspace.clone();
+        let db = self.main_split.db.clone();
         let event_sink = ctx.get_external_handle();
         self.palette.proxy.proxy_rpc.get_files(move |result| {
             if let Ok(ProxyResponse::GetFilesResponse { items }) = result {
-                let items: im::Vector<PaletteItem> = items
+                // Read along with the files, away from the UI thread
+                let frecency = db.get_file_frecency(&workspace).unwrap_or_default();
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let mut items: Vec<(u64, PaletteItem)> = items
                     .iter()
-                    .enumerate()
-                    .map(|(_index, path)| {
+                    .map(|path| {
                         let full_path = path.clone();
                         let mut path = path.clone();
                         if let Some(workspace_path) = workspace.path.as_ref() {
@@ -801,14 +1162,24 @@ fn get_files(&self, ctx: &mut EventCtx) {
                                 .to_path_buf();
                         }
                         let filter_text = path.to_str().unwrap_or("").to_string();
-                        PaletteItem {
+                        let frecency = frecency.get(&full_path);
+                        let last_open =
+                            frecency.map(|f| f.last_open).unwrap_or_default();
+                        let item = PaletteItem {
                             content: PaletteItemContent::File(path, full_path),
                             filter_text,
-                            score: 0,
+                            score: frecency
+                                .map(|f| file_frecency_bonus(f.score(now)))
+                                .unwrap_or(0),
                             indices: Vec::new(),
-                        }
+                        };
+                        (last_open, item)
                     })
                     .collect();
+                // Without a query, the recently opened files come first
+                items.sort_by(|(a, _), (b, _)| b.cmp(a));
+                let items: im::Vector<PaletteItem> =
+                    items.into_iter().map(|(_, item)| item).collect();
 
                 let _ = event_sink.submit_command(
                     LAPCE_UI_COMMAND,
@@ -846,31 +1217,134 @@ fn get_ssh_hosts(&mut self, _ctx: &mut EventCtx) {
     fn get_workspaces(&mut self, _ctx: &mut EventCtx) {
         let workspaces = LapceConfig::recent_workspaces().unwrap_or_default();
         let palette = Arc::make_mut(&mut self.palette);
-        palette.total_items = workspaces
-            .into_iter()
-            .map(|w| {
-                let text = w
-                    .path
-                    .as_ref()
-                    .unwrap()
-                    .to_str()
-                    .map(|p| p.to_string())
-                    .unwrap();
-                let filter_text = match &w.kind {
-                    LapceWorkspaceType::Local => text,
-                    LapceWorkspaceType::RemoteSSH(user, host) => {
-                        format!("[{}@{}] {}", user, host, text)
-                    }
-                    LapceWorkspaceType::RemoteWSL => {
-                        format!("[wsl] {text}")
+        palette.total_items =
+            workspaces.into_iter().map(workspace_palette_item).collect();
+    }
+
+    /// The open editors first, then the recently opened files, and then the
+    /// recent workspaces
+    fn get_switch_items(&mut self, _ctx: &mut EventCtx) {
+        let mut paths = Vec::new();
+        let active_tab = *self.main_split.active_tab;
+        let editor_tabs = self
+            .main_split
+            .editor_tabs
+            .values()
+            .sorted_by_key(|tab| Some(tab.widget_id) != active_tab);
+        for editor_tab in editor_tabs {
+            for child in editor_tab.children.iter() {
+                if let EditorTabChild::Editor(view_id, _, _) = child {
+                    if let Some(BufferContent::File(path)) = self
+                        .main_split
+                        .editors
+                        .get(view_id)
+                        .map(|editor| &editor.content)
+                    {
+                        if !paths.contains(path) {
+                            paths.push(path.clone());
+                        }
                     }
-                };
-                PaletteItem {
-                    content: PaletteItemContent::Workspace(w),
-                    filter_text,
+                }
+            }
+        }
+
+        let recent_files = self
+            .main_split
+            .db
+            .get_file_frecency(&self.workspace)
+            .unwrap_or_default();
+        let recent_workspaces = LapceConfig::recent_workspaces().unwrap_or_default();
+        let items =
+            switch_items(&self.workspace, paths, recent_files, recent_workspaces);
+        Arc::make_mut(&mut self.palette).total_items = items;
+    }
+
+    fn get_plugin_items(
+        &mut self,
+        ctx: &mut EventCtx,
+        plugin_id: PluginId,
+        provider: String,
+    ) {
+        let run_id = self.palette.run_id.clone();
+        let widget_id = self.palette.widget_id;
+        let event_sink = ctx.get_external_handle();
+        self.palette.proxy.proxy_rpc.get_palette_items(
+            plugin_id,
+            provider.clone(),
+            move |result| {
+                if let Ok(ProxyResponse::GetPaletteItems { items }) = result {
+                    let items: im::Vector<PaletteItem> = items
+                        .into_iter()
+                        .map(|item| PaletteItem {
+                            filter_text: item.label.clone(),
+                            content: PaletteItemContent::PluginItem {
+                                plugin_id,
+                                provider: provider.clone(),
+                                item,
+                            },
+                            score: 0,
+                            indices: vec![],
+                        })
+                        .collect();
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::UpdatePaletteItems(run_id, items),
+                        Target::Widget(widget_id),
+                    );
+                }
+            },
+        );
+    }
+
+    fn get_git_branches(&mut self, _ctx: &mut EventCtx, action: GitBranchAction) {
+        let palette = Arc::make_mut(&mut self.palette);
+        palette.total_items = match action {
+            // Creating and renaming take a new name, so there is nothing to pick
+            GitBranchAction::Create | GitBranchAction::Rename => im::Vector::new(),
+            GitBranchAction::Delete => self
+                .source_control
+                .branches
+                .iter()
+                .filter(|branch| **branch != self.source_control.branch)
+                .map(|branch| PaletteItem {
+                    content: PaletteItemContent::Command(LapceCommand {
+                        kind: CommandKind::Workbench(
+                            LapceWorkbenchCommand::DeleteBranch,
+                        ),
+                        data: Some(serde_json::json!(branch)),
+                    }),
+                    filter_text: branch.to_string(),
                     score: 0,
                     indices: vec![],
-                }
+                })
+                .collect(),
+        };
+    }
+
+    fn get_git_stashes(&mut self, _ctx: &mut EventCtx, action: GitStashAction) {
+        let command = match action {
+            // Saving takes a message, so there is nothing to pick
+            GitStashAction::Save | GitStashAction::SaveIncludeUntracked => {
+                Arc::make_mut(&mut self.palette).total_items = im::Vector::new();
+                return;
+            }
+            GitStashAction::Apply => LapceWorkbenchCommand::SourceControlStashApply,
+            GitStashAction::Pop => LapceWorkbenchCommand::SourceControlStashPop,
+            GitStashAction::Drop => LapceWorkbenchCommand::SourceControlStashDrop,
+        };
+        let palette = Arc::make_mut(&mut self.palette);
+        palette.total_items = self
+            .source_control
+            .stashes
+            .iter()
+            .map(|stash| PaletteItem {
+                content: PaletteItemContent::Command(LapceCommand {
+                    kind: CommandKind::Workbench(command.clone()),
+                    data: Some(serde_json::json!(stash.index)),
+                }),
+                filter_text: format!("stash@{{{}}}: {}", stash.index, stash.message),
+                score: 0,
+                indices: vec![],
             })
             .collect();
     }
@@ -907,26 +1381,91 @@ fn get_languages(&mut self, _ctx: &mut EventCtx) {
     }
 
     fn get_commands(&mut self, _ctx: &mut EventCtx) {
-        const EXCLUDED_ITEMS: &[&str] = &["palette.command"];
+        let recent_commands: im::Vector<String> = self
+            .main_split
+            .db
+            .get_recent_commands()
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        let commands = command_items(&self.keypress.commands, &recent_commands);
+        let providers =
+            self.palette
+                .plugin_providers
+                .iter()
+                .map(|registered| PaletteItem {
+                    content: PaletteItemContent::PluginProvider(
+                        registered.plugin_id,
+                        registered.provider.clone(),
+                    ),
+                    filter_text: registered.provider.name.clone(),
+                    score: 0,
+                    indices: vec![],
+                });
+        let items = commands.into_iter().chain(providers).collect();
 
         let palette = Arc::make_mut(&mut self.palette);
-        palette.total_items = self
-            .keypress
-            .commands
-            .iter()
-            .filter_map(|(_, c)| {
-                if EXCLUDED_ITEMS.contains(&c.kind.str()) {
-                    return None;
+        palette.total_items = items;
+        palette.list_data.data.recent_commands = recent_commands;
+    }
+
+    /// The one item of the go to line palette, which follows the input rather
+    /// than being filtered by it
+    fn update_go_to_line(&mut self, ctx: &mut EventCtx) {
+        let item = self.go_to_line_item();
+        let palette = Arc::make_mut(&mut self.palette);
+        palette.total_items = item.into_iter().collect();
+        palette.list_data.items = palette.total_items.clone();
+        palette.list_data.selected_index = 0;
+        palette.preview(ctx);
+    }
+
+    fn go_to_line_item(&self) -> Option<PaletteItem> {
+        let editor = self.main_split.active_editor()?;
+        let doc = self.main_split.editor_doc(editor.view_id);
+        let buffer = doc.buffer();
+        let input = self.palette.get_input();
+
+        let (line, column, text) = if input.trim().is_empty() {
+            let (line, offset) = buffer.offset_to_line_col(editor.cursor.offset());
+            let column = buffer.line_content(line)[..offset].chars().count() + 1;
+            let text = format!(
+                "Current line {}, column {column}. \
+                 Type a line number, optionally followed by :column",
+                line + 1
+            );
+            (line + 1, Some(offset), text)
+        } else {
+            let (line, column) = parse_line_col(input)?;
+            let line = line.clamp(1, buffer.last_line() + 1);
+            match column {
+                Some(column) => {
+                    let content = buffer.line_content(line - 1);
+                    let content =
+                        content.trim_end_matches(|c: char| c == '\r' || c == '\n');
+                    let column = column.clamp(1, content.chars().count() + 1);
+                    let offset = content
+                        .char_indices()
+                        .nth(column - 1)
+                        .map(|(i, _)| i)
+                        .unwrap_or(content.len());
+                    let text = format!("Go to line {line}, column {column}");
+                    (line, Some(offset), text)
                 }
+                None => (line, None, format!("Go to line {line}")),
+            }
+        };
 
-                c.kind.desc().as_ref().map(|m| PaletteItem {
-                    content: PaletteItemContent::Command(c.clone()),
-                    filter_text: m.to_string(),
-                    score: 0,
-                    indices: vec![],
-                })
-            })
-            .collect();
+        Some(PaletteItem {
+            content: PaletteItemContent::GoToLine {
+                line,
+                column,
+                text: text.clone(),
+            },
+            filter_text: text,
+            score: 0,
+            indices: vec![],
+        })
     }
 
     fn get_lines(&mut self, _ctx: &mut EventCtx) {
@@ -1199,7 +1738,7 @@ fn filter_items(
                     matcher.fuzzy_indices(&i.filter_text, input)
                 {
                     let mut item = i.clone();
-                    item.score = score;
+                    item.score += score;
                     item.indices = indices;
                     Some(item)
                 } else {
@@ -1212,3 +1751,124 @@ fn filter_items(
         items
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_go_to_line_input() {
+        assert_eq!(
+            PaletteType::get_palette_type(&PaletteType::Command, ":12"),
+            PaletteType::GoToLine
+        );
+        assert_eq!(
+            PaletteType::get_palette_type(&PaletteType::GoToLine, ":go"),
+            PaletteType::Command
+        );
+
+        assert_eq!(parse_line_col("12"), Some((12, None)));
+        assert_eq!(parse_line_col("12:"), Some((12, None)));
+        assert_eq!(parse_line_col("12:5"), Some((12, Some(5))));
+        assert_eq!(parse_line_col("12:x"), None);
+        assert_eq!(parse_line_col("x"), None);
+    }
+
+    #[test]
+    fn test_recent_commands_first() {
+        let commands = crate::command::lapce_internal_commands();
+        let names: Vec<String> = commands
+            .values()
+            .filter(|c| c.kind.desc().is_some() && c.kind.str() != "palette.command")
+            .map(|c| c.kind.str().to_string())
+            .take(3)
+            .collect();
+        let recent = im::vector![names[2].clone(), names[0].clone()];
+
+        let items = command_items(&commands, &recent);
+        let command = |item: &PaletteItem| match &item.content {
+            PaletteItemContent::Command(c) => c.kind.str().to_string(),
+            _ => panic!("not a command"),
+        };
+        assert_eq!(command(&items[0]), names[2]);
+        assert_eq!(items[0].score, RECENT_COMMAND_BONUS);
+        assert_eq!(command(&items[1]), names[0]);
+        assert_eq!(items[1].score, RECENT_COMMAND_BONUS);
+        assert_eq!(command(&items[2]), names[1]);
+        assert_eq!(items[2].score, 0);
+        // The recent commands aren't listed twice
+        assert_eq!(
+            items
+                .iter()
+                .filter(|item| command(item) == names[0])
+                .count(),
+            1
+        );
+        assert!(items.iter().all(|item| command(item) != "palette.command"));
+    }
+
+    #[test]
+    fn test_switch_items() {
+        let workspace = |path: &str| LapceWorkspace {
+            kind: LapceWorkspaceType::Local,
+            path: Some(PathBuf::from(path)),
+            last_open: 0,
+        };
+        let recent_files = HashMap::from([
+            (
+                PathBuf::from("/work/src/main.rs"),
+                FileFrecency {
+                    count: 3,
+                    last_open: 30,
+                },
+            ),
+            (
+                PathBuf::from("/work/README.md"),
+                FileFrecency {
+                    count: 1,
+                    last_open: 10,
+                },
+            ),
+            (
+                PathBuf::from("/work/Cargo.toml"),
+                FileFrecency {
+                    count: 1,
+                    last_open: 20,
+                },
+            ),
+        ]);
+
+        let items = switch_items(
+            &workspace("/work"),
+            vec![PathBuf::from("/work/src/lib.rs")],
+            recent_files,
+            vec![workspace("/work"), workspace("/other")],
+        );
+        let texts = |items: &im::Vector<PaletteItem>| {
+            items
+                .iter()
+                .map(|item| item.filter_text.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            texts(&items),
+            vec![
+                "src/lib.rs",
+                "src/main.rs",
+                "Cargo.toml",
+                "README.md",
+                "/other"
+            ]
+        );
+
+        let matcher = SkimMatcherV2::default().ignore_case();
+        let filtered =
+            PaletteViewData::filter_items("", "main", items.clone(), &matcher);
+        assert_eq!(texts(&filtered), vec!["src/main.rs"]);
+        let filtered = PaletteViewData::filter_items("", "other", items, &matcher);
+        assert!(matches!(
+            filtered[0].content,
+            PaletteItemContent::Workspace(_)
+        ));
+    }
+}
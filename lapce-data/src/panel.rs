@@ -11,6 +11,8 @@ pub enum PanelKind {
     Terminal,
     Search,
     Problem,
+    CallHierarchy,
+    TypeHierarchy,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -32,6 +34,8 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::Terminal => "terminal.svg",
             PanelKind::Search => "search.svg",
             PanelKind::Problem => "error.svg",
+            PanelKind::CallHierarchy => "symbol-method.svg",
+            PanelKind::TypeHierarchy => "symbol-class.svg",
         }
     }
 }
@@ -4,7 +4,10 @@
 use std::path::Path;
 use std::process::{Command, Stdio};
 use std::thread;
-use std::{collections::HashMap, io::Write};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::Write,
+};
 use std::{path::PathBuf, str::FromStr, sync::Arc};
 
 use anyhow::{anyhow, Result};
@@ -16,13 +19,15 @@
 use lapce_proxy::dispatch::Dispatcher;
 use lapce_proxy::APPLICATION_NAME;
 pub use lapce_proxy::VERSION;
-use lapce_rpc::core::{CoreHandler, CoreNotification, CoreRequest, CoreRpcHandler};
+use lapce_rpc::core::{
+    CoreHandler, CoreNotification, CoreRequest, CoreResponse, CoreRpcHandler,
+};
 use lapce_rpc::proxy::{ProxyRpc, ProxyRpcHandler};
 use lapce_rpc::stdio::stdio_transport;
 use lapce_rpc::terminal::TermId;
 use lapce_rpc::RequestId;
 use lapce_rpc::RpcMessage;
-use lsp_types::Url;
+use lsp_types::{LogMessageParams, MessageType, Url};
 use parking_lot::Mutex;
 use serde_json::Value;
 use thiserror::Error;
@@ -31,6 +36,7 @@
 use crate::command::LapceUICommand;
 use crate::command::LAPCE_UI_COMMAND;
 use crate::data::{LapceWorkspace, LapceWorkspaceType};
+use crate::editor::EditorLocation;
 use crate::terminal::RawTerminal;
 
 const UNIX_PROXY_SCRIPT: &[u8] = include_bytes!("../../extra/proxy.sh");
@@ -97,6 +103,9 @@ pub struct LapceProxy {
     pub core_rpc: CoreRpcHandler,
     term_tx: Sender<(TermId, TermEvent)>,
     event_sink: ExtEventSink,
+    /// The log files of the language servers which logged something since
+    /// the proxy started
+    lsp_logs: Arc<Mutex<HashMap<String, (PathBuf, std::fs::File)>>>,
 }
 
 impl CoreHandler for LapceProxy {
@@ -172,6 +181,13 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
                     Target::Widget(self.tab_id),
                 );
             }
+            CodeLensRefresh {} => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RefreshCodeLens,
+                    Target::Widget(self.tab_id),
+                );
+            }
             HomeDir { path } => {
                 let _ = self.event_sink.submit_command(
                     LAPCE_UI_COMMAND,
@@ -248,6 +264,58 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
                     Target::Widget(self.tab_id),
                 );
             }
+            RegisterPaletteProvider {
+                volt_id,
+                plugin_id,
+                provider,
+            } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RegisterPaletteProvider(
+                        volt_id, plugin_id, provider,
+                    ),
+                    Target::Widget(self.tab_id),
+                );
+            }
+            ShowMessage { title, message } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::ShowNotification {
+                        title,
+                        message: message.message,
+                        typ: message.typ,
+                        actions: Vec::new(),
+                        request_id: None,
+                    },
+                    Target::Widget(self.tab_id),
+                );
+            }
+            ShowDocument { params } => {
+                let command = if params.external == Some(true) {
+                    LapceUICommand::OpenURI(params.uri.to_string())
+                } else {
+                    LapceUICommand::JumpToLspLocation(
+                        None,
+                        EditorLocation {
+                            path: path_from_url(&params.uri),
+                            position: params.selection.map(|range| range.start),
+                            scroll_offset: None,
+                            history: None,
+                        },
+                        false,
+                    )
+                };
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    command,
+                    Target::Widget(self.tab_id),
+                );
+            }
+            LogMessage { title, message } => {
+                if let Err(err) = self.append_lsp_log(&title, &message) {
+                    log::error!("failed to write log of {title}: {err}");
+                }
+            }
             Log { level, message } => {
                 if let Ok(level) = log::Level::from_str(&level) {
                     log::log!(level, "{}", message);
@@ -256,7 +324,51 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
         }
     }
 
-    fn handle_request(&mut self, _id: RequestId, _rpc: CoreRequest) {}
+    fn handle_request(&mut self, id: RequestId, rpc: CoreRequest) {
+        match rpc {
+            CoreRequest::ShowMessageRequest { title, message } => {
+                let shown = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::ShowNotification {
+                        title,
+                        message: message.message,
+                        typ: message.typ,
+                        actions: message.actions.unwrap_or_default(),
+                        request_id: Some(id),
+                    },
+                    Target::Widget(self.tab_id),
+                );
+                // Nobody is going to pick an action, so the server shouldn't
+                // wait for one
+                if shown.is_err() {
+                    self.core_rpc.handle_response(
+                        id,
+                        Ok(CoreResponse::ShowMessageRequest { action: None }),
+                    );
+                }
+            }
+            CoreRequest::ApplyWorkspaceEdit { edit } => {
+                let submitted = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::ApplyWorkspaceEditRequest {
+                        edit,
+                        request_id: id,
+                    },
+                    Target::Widget(self.tab_id),
+                );
+                if submitted.is_err() {
+                    self.core_rpc.handle_response(
+                        id,
+                        Ok(CoreResponse::ApplyWorkspaceEdit {
+                            failure_reason: Some(
+                                "the editor has been closed".to_string(),
+                            ),
+                        }),
+                    );
+                }
+            }
+        }
+    }
 }
 
 impl LapceProxy {
@@ -278,6 +390,7 @@ pub fn new(
             core_rpc,
             term_tx,
             event_sink: event_sink.clone(),
+            lsp_logs: Arc::new(Mutex::new(HashMap::new())),
         };
 
         let local_proxy = proxy.clone();
@@ -733,6 +846,50 @@ pub fn stop(&self) {
         self.proxy_rpc.shutdown();
         self.core_rpc.shutdown();
     }
+
+    pub fn lsp_log_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self
+            .lsp_logs
+            .lock()
+            .values()
+            .map(|(path, _)| path.clone())
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Appends the message to the log file of the server, which is emptied
+    /// the first time the server logs something after the proxy started
+    fn append_lsp_log(&self, name: &str, message: &LogMessageParams) -> Result<()> {
+        let mut lsp_logs = self.lsp_logs.lock();
+        let file = match lsp_logs.entry(name.to_string()) {
+            Entry::Occupied(entry) => &mut entry.into_mut().1,
+            Entry::Vacant(entry) => {
+                let file_name =
+                    format!("lsp-{}.log", name.replace(['/', '\\'], "_"));
+                let path = Directory::logs_directory()
+                    .ok_or_else(|| anyhow!("can't find logs directory"))?
+                    .join(file_name);
+                // The file stays open for the next messages of the server
+                let file = std::fs::File::create(&path)?;
+                &mut entry.insert((path, file)).1
+            }
+        };
+
+        let level = match message.typ {
+            MessageType::ERROR => "Error",
+            MessageType::WARNING => "Warn",
+            MessageType::INFO => "Info",
+            _ => "Log",
+        };
+        writeln!(
+            file,
+            "[{} {level}] {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            message.message
+        )?;
+        Ok(())
+    }
 }
 
 fn new_command(program: &str) -> Command {
@@ -0,0 +1,389 @@
+//! A bundle of the settings, keymaps, volts and custom themes, to move a
+//! whole setup from one machine to another.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicU64, Arc},
+};
+
+use anyhow::Result;
+use lapce_core::buffer::{rope_diff, DiffLines};
+use lapce_proxy::directory::Directory;
+use serde::{Deserialize, Serialize};
+use xi_rope::Rope;
+
+use crate::{config::LapceConfig, db::LapceDb, plugin::PluginData};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleVolt {
+    pub id: String,
+    pub version: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingsBundle {
+    /// The `settings.toml`, which is left as it is when the bundle doesn't
+    /// have one
+    #[serde(default)]
+    pub settings: Option<String>,
+    /// The `keymaps.toml`, which is left as it is when the bundle doesn't
+    /// have one
+    #[serde(default)]
+    pub keymaps: Option<String>,
+    #[serde(default)]
+    pub volts: Vec<BundleVolt>,
+    #[serde(default)]
+    pub disabled_volts: Vec<String>,
+    /// The custom themes, by their file name in the themes directory
+    #[serde(default)]
+    pub themes: BTreeMap<String, String>,
+}
+
+impl SettingsBundle {
+    /// Bundles up the setup of this machine
+    pub fn collect(plugin: &PluginData, db: &LapceDb) -> Self {
+        let mut volts: Vec<BundleVolt> = plugin
+            .installed
+            .values()
+            .map(|meta| BundleVolt {
+                id: meta.id(),
+                version: meta.version.clone(),
+            })
+            .collect();
+        volts.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut disabled_volts = db.get_disabled_volts().unwrap_or_default();
+        disabled_volts.sort();
+
+        Self {
+            settings: read_config_file(LapceConfig::settings_file()),
+            keymaps: read_config_file(LapceConfig::keymaps_file()),
+            volts,
+            disabled_volts,
+            themes: read_themes(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// What applying the bundle would change on this machine, for the
+    /// bundle to be looked over before it's applied
+    pub fn review(&self, plugin: &PluginData) -> String {
+        let mut review = String::new();
+        let files = [
+            (
+                "settings.toml",
+                &self.settings,
+                LapceConfig::settings_file(),
+            ),
+            ("keymaps.toml", &self.keymaps, LapceConfig::keymaps_file()),
+        ];
+        for (name, content, path) in files {
+            match content {
+                Some(content) => review.push_str(&diff_file(
+                    name,
+                    &read_config_file(path).unwrap_or_default(),
+                    content,
+                )),
+                None => review.push_str(&format!("# {name} (not in the bundle)\n")),
+            }
+            review.push('\n');
+        }
+
+        review.push_str("# Volts to install\n");
+        let mut installs = 0;
+        for volt in self.volts.iter() {
+            if plugin.installed.contains_key(&volt.id) {
+                continue;
+            }
+            installs += 1;
+            let line = match plugin.volts.volts.get(&volt.id) {
+                Some(info) if info.version == volt.version => {
+                    format!("+ {} {}\n", volt.id, volt.version)
+                }
+                Some(info) => format!(
+                    "+ {} {} ({} from the registry)\n",
+                    volt.id, volt.version, info.version
+                ),
+                None => {
+                    format!("! {} {} (not in the registry)\n", volt.id, volt.version)
+                }
+            };
+            review.push_str(&line);
+        }
+        if installs == 0 {
+            review.push_str("  none\n");
+        }
+
+        review.push_str("\n# Disabled volts\n");
+        let disabled: HashSet<&String> = self.disabled_volts.iter().collect();
+        let mut current: Vec<&String> = plugin.disabled.iter().collect();
+        current.sort();
+        for id in current.iter() {
+            if !disabled.contains(id) {
+                review.push_str(&format!("- {id}\n"));
+            }
+        }
+        for id in self.disabled_volts.iter() {
+            let sign = if plugin.disabled.contains(id) {
+                ' '
+            } else {
+                '+'
+            };
+            review.push_str(&format!("{sign} {id}\n"));
+        }
+        if current.is_empty() && self.disabled_volts.is_empty() {
+            review.push_str("  none\n");
+        }
+
+        review.push_str("\n# Themes\n");
+        let themes = if self.themes.is_empty() {
+            BTreeMap::new()
+        } else {
+            read_themes()
+        };
+        for (name, content) in self.themes.iter() {
+            let line = match themes.get(name) {
+                Some(current) if current == content => format!("  {name}\n"),
+                Some(_) => format!("~ {name} (replaced)\n"),
+                None => format!("+ {name}\n"),
+            };
+            review.push_str(&line);
+        }
+        if self.themes.is_empty() {
+            review.push_str("  none\n");
+        }
+
+        review
+    }
+
+    /// Writes the settings, keymaps and themes of the bundle, which the
+    /// config watcher then picks up
+    pub fn write_files(&self) -> Result<()> {
+        if let Some(settings) = self.settings.as_ref() {
+            let path = LapceConfig::settings_file()
+                .ok_or_else(|| anyhow::anyhow!("can't find the settings file"))?;
+            std::fs::write(path, settings)?;
+        }
+        if let Some(keymaps) = self.keymaps.as_ref() {
+            let path = LapceConfig::keymaps_file()
+                .ok_or_else(|| anyhow::anyhow!("can't find the keymaps file"))?;
+            std::fs::write(path, keymaps)?;
+        }
+
+        if !self.themes.is_empty() {
+            let folder = Directory::themes_directory()
+                .ok_or_else(|| anyhow::anyhow!("can't find the themes directory"))?;
+            for (name, content) in self.themes.iter() {
+                // Only a file name, so that a theme stays in the directory
+                let name = Path::new(name)
+                    .file_name()
+                    .ok_or_else(|| anyhow::anyhow!("invalid theme name {name}"))?;
+                std::fs::write(folder.join(name), content)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_config_file(path: Option<PathBuf>) -> Option<String> {
+    path.and_then(|path| std::fs::read_to_string(path).ok())
+}
+
+fn read_themes() -> BTreeMap<String, String> {
+    let mut themes = BTreeMap::new();
+    let entries = Directory::themes_directory()
+        .and_then(|folder| std::fs::read_dir(folder).ok());
+    for entry in entries.into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("toml") {
+            continue;
+        }
+        if let (Some(name), Ok(content)) =
+            (path.file_name(), std::fs::read_to_string(&path))
+        {
+            themes.insert(name.to_string_lossy().to_string(), content);
+        }
+    }
+    themes
+}
+
+/// A diff of the lines of a file, with the unchanged lines away from the
+/// changes left out
+fn diff_file(name: &str, old: &str, new: &str) -> String {
+    if old == new {
+        return format!("# {name} (unchanged)\n");
+    }
+
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let changes = rope_diff(
+        Rope::from(old),
+        Rope::from(new),
+        0,
+        Arc::new(AtomicU64::new(0)),
+    )
+    .unwrap_or_default();
+
+    let mut diff = format!("# {name}\n");
+    for change in changes.iter() {
+        match change {
+            DiffLines::Both(range, _) => {
+                push_lines(&mut diff, ' ', &old_lines, range.clone())
+            }
+            DiffLines::Left(range) => {
+                push_lines(&mut diff, '-', &old_lines, range.clone())
+            }
+            DiffLines::Right(range) => {
+                push_lines(&mut diff, '+', &new_lines, range.clone())
+            }
+            DiffLines::Skip(range, _) => {
+                diff.push_str(&format!("@@ {} unchanged lines @@\n", range.len()));
+            }
+        }
+    }
+    diff
+}
+
+fn push_lines(diff: &mut String, sign: char, lines: &[&str], range: Range<usize>) {
+    for line in range {
+        let line = lines.get(line).copied().unwrap_or_default();
+        diff.push_str(&format!("{sign} {line}\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid::WidgetId;
+    use indexmap::IndexMap;
+    use lapce_rpc::plugin::{VoltInfo, VoltMetadata};
+
+    use super::*;
+    use crate::plugin::{PluginLoadStatus, VoltsList};
+
+    #[test]
+    fn test_diff_file() {
+        assert_eq!(diff_file("a", "x\n", "x\n"), "# a (unchanged)\n");
+
+        let old = "a\nb\nc\nd\ne\nf\ng\n";
+        let new = "a\nb\nc\nd\ne\nf\nh\ni\n";
+        assert_eq!(
+            diff_file("settings.toml", old, new),
+            "# settings.toml\n\
+             @@ 3 unchanged lines @@\n  \
+             d\n  \
+             e\n  \
+             f\n\
+             - g\n\
+             + h\n\
+             + i\n"
+        );
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let bundle: SettingsBundle =
+            serde_json::from_str(r#"{"settings": "[core]\n"}"#).unwrap();
+        assert_eq!(bundle.settings.as_deref(), Some("[core]\n"));
+        assert_eq!(bundle.keymaps, None);
+        assert!(bundle.volts.is_empty());
+
+        let bundle = SettingsBundle {
+            volts: vec![BundleVolt {
+                id: "lapce.lapce-rust".to_string(),
+                version: "0.3.0".to_string(),
+            }],
+            disabled_volts: vec!["lapce.lapce-rust".to_string()],
+            ..bundle
+        };
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SettingsBundle>(&json).unwrap(),
+            bundle
+        );
+    }
+
+    #[test]
+    fn test_review() {
+        let info = |name: &str, version: &str| VoltInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+            display_name: name.to_string(),
+            author: "lapce".to_string(),
+            description: String::new(),
+            meta: String::new(),
+        };
+        let rust = VoltMetadata {
+            name: "lapce-rust".to_string(),
+            version: "0.3.0".to_string(),
+            display_name: "Rust".to_string(),
+            author: "lapce".to_string(),
+            description: String::new(),
+            wasm: None,
+            themes: None,
+            dir: None,
+            activation: None,
+        };
+        let plugin = PluginData {
+            widget_id: WidgetId::next(),
+            installed_id: WidgetId::next(),
+            uninstalled_id: WidgetId::next(),
+            volts: VoltsList {
+                volts: [info("lapce-go", "0.2.0"), info("lapce-cpp", "0.1.0")]
+                    .into_iter()
+                    .map(|info| (format!("lapce.{}", info.name), info))
+                    .collect(),
+                status: PluginLoadStatus::Success,
+            },
+            installing: IndexMap::new(),
+            installed: [(rust.id(), rust)].into_iter().collect(),
+            disabled: ["lapce.lapce-rust", "lapce.old"]
+                .into_iter()
+                .map(|id| id.to_string())
+                .collect(),
+            workspace_disabled: HashSet::new(),
+        };
+
+        let volt = |id: &str, version: &str| BundleVolt {
+            id: id.to_string(),
+            version: version.to_string(),
+        };
+        let bundle = SettingsBundle {
+            volts: vec![
+                volt("lapce.lapce-rust", "0.3.0"),
+                volt("lapce.lapce-go", "0.2.0"),
+                volt("lapce.lapce-cpp", "0.2.0"),
+                volt("lapce.gone", "1.0.0"),
+            ],
+            disabled_volts: vec![
+                "lapce.lapce-go".to_string(),
+                "lapce.lapce-rust".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            bundle.review(&plugin),
+            "# settings.toml (not in the bundle)\n\n\
+             # keymaps.toml (not in the bundle)\n\n\
+             # Volts to install\n\
+             + lapce.lapce-go 0.2.0\n\
+             + lapce.lapce-cpp 0.2.0 (0.1.0 from the registry)\n\
+             ! lapce.gone 1.0.0 (not in the registry)\n\n\
+             # Disabled volts\n\
+             - lapce.old\n\
+             + lapce.lapce-go\n  \
+             lapce.lapce-rust\n\n\
+             # Themes\n  \
+             none\n"
+        );
+    }
+}
@@ -1,59 +1,200 @@
-use lsp_types::ParameterLabel;
-use lsp_types::SignatureHelp;
+use std::{path::PathBuf, sync::Arc};
+
+use druid::{ExtEventSink, Target, WidgetId};
+use lapce_core::encoding::offset_utf16_to_utf8_str;
+use lapce_rpc::{buffer::BufferId, proxy::ProxyResponse};
+use lsp_types::{
+    ParameterLabel, Position, SignatureHelp, SignatureHelpContext,
+    SignatureInformation,
+};
+
+use crate::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    proxy::LapceProxy,
+};
 
 #[derive(Clone)]
 pub struct SignatureState {
+    pub id: WidgetId,
+    /// The editor view id that the signature help is displayed for
+    pub editor_view_id: WidgetId,
+    /// The buffer that the signature help is for
+    pub buffer_id: BufferId,
+    /// The offset the displayed signature help was first requested at, which
+    /// is where it's displayed from
     pub offset: Option<usize>,
+    /// A counter to keep track of the active requests
+    pub request_id: usize,
+    /// The newest request that got a response, anything older is outdated.
+    /// Requests for characters that don't trigger the signature help never
+    /// get one, so they don't make a pending request outdated
+    pub received_id: usize,
     pub signature: Option<SignatureHelp>,
+    /// The index of the overload that is displayed
+    pub active_signature: usize,
+    /// The byte range of the active parameter in the label of the displayed
+    /// overload
     pub active: Option<(usize, usize)>,
 }
 
 impl SignatureState {
     pub fn new() -> Self {
         Self {
+            id: WidgetId::next(),
+            editor_view_id: WidgetId::next(),
+            buffer_id: BufferId(0),
             offset: None,
+            request_id: 0,
+            received_id: 0,
             signature: None,
+            active_signature: 0,
             active: None,
         }
     }
 
-    pub fn update(&mut self, offset: usize, commas: Vec<usize>) -> Option<bool> {
-        let signature = self.signature.as_ref()?;
-        let params = signature.signatures[0].parameters.as_ref()?;
-        if params.is_empty() {
-            return None;
+    /// Whether there is a signature being displayed
+    pub fn is_active(&self) -> bool {
+        self.signature.is_some()
+    }
+
+    /// The overload that is displayed
+    pub fn current_signature(&self) -> Option<&SignatureInformation> {
+        self.signature
+            .as_ref()?
+            .signatures
+            .get(self.active_signature)
+    }
+
+    /// The number of overloads the signature help has
+    pub fn len(&self) -> usize {
+        self.signature
+            .as_ref()
+            .map(|s| s.signatures.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Send a request to update the signature help at the given offset
+    pub fn request(
+        &mut self,
+        proxy: Arc<LapceProxy>,
+        path: PathBuf,
+        offset: usize,
+        position: Position,
+        context: SignatureHelpContext,
+        event_sink: ExtEventSink,
+    ) {
+        self.request_id += 1;
+        let signature_widget_id = self.id;
+        proxy.proxy_rpc.get_signature(
+            self.request_id,
+            path,
+            position,
+            context,
+            move |result| {
+                if let Ok(ProxyResponse::GetSignature {
+                    request_id,
+                    signature,
+                }) = result
+                {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::UpdateSignature {
+                            request_id,
+                            offset,
+                            signature,
+                        },
+                        Target::Widget(signature_widget_id),
+                    );
+                }
+            },
+        );
+    }
+
+    /// Receive the result of a signature help request
+    pub fn receive(
+        &mut self,
+        request_id: usize,
+        offset: usize,
+        signature: Option<SignatureHelp>,
+    ) {
+        // The signature help was cancelled or a newer one was received
+        if request_id <= self.received_id {
+            return;
         }
-        let mut index = commas.len();
-        for (i, c) in commas.iter().enumerate() {
-            if offset <= *c {
-                index = i;
-                break;
+        self.received_id = request_id;
+
+        match signature.filter(|s| !s.signatures.is_empty()) {
+            Some(signature) => {
+                // An update keeps the signature help where it was
+                if self.signature.is_none() {
+                    self.offset = Some(offset);
+                }
+                self.active_signature = signature
+                    .active_signature
+                    .map(|i| i as usize)
+                    .filter(|i| *i < signature.signatures.len())
+                    .unwrap_or(0);
+                self.signature = Some(signature);
+                self.update_active();
             }
+            None => self.clear(),
+        }
+    }
+
+    /// The signature help to give the server as context when it's asked for
+    /// an update, with the overload that the user picked as the active one
+    pub fn active_signature_help(&self) -> Option<SignatureHelp> {
+        let mut signature = self.signature.clone()?;
+        signature.active_signature = Some(self.active_signature as u32);
+        Some(signature)
+    }
+
+    pub fn next_signature(&mut self) {
+        if self.len() > 1 {
+            self.active_signature = (self.active_signature + 1) % self.len();
+            self.update_active();
+        }
+    }
+
+    pub fn previous_signature(&mut self) {
+        if self.len() > 1 {
+            self.active_signature =
+                (self.active_signature + self.len() - 1) % self.len();
+            self.update_active();
         }
+    }
 
-        let label = signature.signatures[0].label.clone();
-        let active = if index >= params.len() {
-            None
-        } else {
-            match &params[index].label {
+    fn update_active(&mut self) {
+        self.active = self.signature.as_ref().and_then(|help| {
+            let signature = help.signatures.get(self.active_signature)?;
+            let index = signature.active_parameter.or(help.active_parameter)?;
+            let param = signature.parameters.as_ref()?.get(index as usize)?;
+            let label = &signature.label;
+            match &param.label {
                 ParameterLabel::Simple(s) => {
-                    let start = label.find(s)?;
+                    // Skip the function name which could contain the
+                    // parameter name as well
+                    let params_start = label.find('(').map(|i| i + 1).unwrap_or(0);
+                    let start = params_start + label[params_start..].find(s)?;
                     Some((start, start + s.len()))
                 }
-                ParameterLabel::LabelOffsets(offsets) => {
-                    Some((offsets[0] as usize, offsets[1] as usize))
-                }
+                ParameterLabel::LabelOffsets([start, end]) => Some((
+                    offset_utf16_to_utf8_str(label, *start as usize),
+                    offset_utf16_to_utf8_str(label, *end as usize),
+                )),
             }
-        };
-
-        let changed = self.active != active;
-        self.active = active;
-        Some(changed)
+        });
     }
 
     pub fn clear(&mut self) {
+        self.received_id = self.request_id;
         self.offset = None;
         self.signature = None;
+        self.active_signature = 0;
         self.active = None;
     }
 }
@@ -63,3 +204,121 @@ fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use lsp_types::ParameterInformation;
+
+    use super::*;
+
+    fn signature(label: &str, params: Vec<ParameterLabel>) -> SignatureInformation {
+        SignatureInformation {
+            label: label.to_string(),
+            documentation: None,
+            parameters: Some(
+                params
+                    .into_iter()
+                    .map(|label| ParameterInformation {
+                        label,
+                        documentation: None,
+                    })
+                    .collect(),
+            ),
+            active_parameter: None,
+        }
+    }
+
+    fn received(signatures: Vec<SignatureInformation>) -> SignatureState {
+        let mut state = SignatureState::new();
+        state.request_id = 1;
+        state.receive(
+            1,
+            0,
+            Some(SignatureHelp {
+                signatures,
+                active_signature: None,
+                active_parameter: Some(1),
+            }),
+        );
+        state
+    }
+
+    #[test]
+    fn test_active_parameter() {
+        let state = received(vec![signature(
+            "fn a(a: usize, b: usize)",
+            vec![
+                ParameterLabel::Simple("a: usize".to_string()),
+                ParameterLabel::Simple("b: usize".to_string()),
+            ],
+        )]);
+        assert_eq!(state.active, Some((15, 23)));
+
+        // The offsets are in UTF-16 code units
+        let state = received(vec![signature(
+            "fn ä(x: ä, y: ä)",
+            vec![
+                ParameterLabel::LabelOffsets([5, 9]),
+                ParameterLabel::LabelOffsets([11, 15]),
+            ],
+        )]);
+        assert_eq!(state.active, Some((13, 18)));
+    }
+
+    #[test]
+    fn test_overloads() {
+        let mut state = received(vec![
+            signature("f()", vec![]),
+            signature(
+                "f(a, b)",
+                vec![
+                    ParameterLabel::Simple("a".to_string()),
+                    ParameterLabel::Simple("b".to_string()),
+                ],
+            ),
+        ]);
+        assert_eq!(state.len(), 2);
+        assert_eq!(state.current_signature().unwrap().label, "f()");
+        assert_eq!(state.active, None);
+
+        state.next_signature();
+        assert_eq!(state.current_signature().unwrap().label, "f(a, b)");
+        assert_eq!(state.active, Some((5, 6)));
+        assert_eq!(
+            state.active_signature_help().unwrap().active_signature,
+            Some(1)
+        );
+
+        state.next_signature();
+        assert_eq!(state.active_signature, 0);
+        state.previous_signature();
+        assert_eq!(state.active_signature, 1);
+    }
+
+    #[test]
+    fn test_receive_outdated_or_empty() {
+        let help = SignatureHelp {
+            signatures: vec![signature("f()", vec![])],
+            active_signature: None,
+            active_parameter: None,
+        };
+        let mut state = received(help.signatures.clone());
+        assert_eq!(state.offset, Some(0));
+        state.request_id = 4;
+        state.receive(1, 5, None);
+        assert!(state.is_active());
+
+        // An update doesn't move the signature help
+        state.receive(2, 5, Some(help.clone()));
+        assert_eq!(state.offset, Some(0));
+
+        state.receive(3, 5, None);
+        assert!(!state.is_active());
+        assert_eq!(state.offset, None);
+
+        // Clearing drops the responses that are still on the way
+        state.clear();
+        state.receive(4, 5, Some(help));
+        assert!(!state.is_active());
+    }
+}
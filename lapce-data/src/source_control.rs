@@ -1,10 +1,12 @@
+use std::path::PathBuf;
+
 use druid::{Command, Env, EventCtx, Modifiers, Target, WidgetId};
 use lapce_core::{
     command::{FocusCommand, MoveCommand},
     mode::Mode,
     movement::Movement,
 };
-use lapce_rpc::source_control::FileDiff;
+use lapce_rpc::source_control::{FileDiff, StashInfo, UpstreamStatus};
 
 use crate::{
     command::{CommandExecuted, CommandKind, LapceUICommand, LAPCE_UI_COMMAND},
@@ -15,6 +17,24 @@
 pub const SOURCE_CONTROL_BUFFER: &str = "[Source Control Buffer]";
 pub const SEARCH_BUFFER: &str = "[Search Buffer]";
 
+/// The branch operation the palette is collecting input for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GitBranchAction {
+    Create,
+    Delete,
+    Rename,
+}
+
+/// The stash operation the palette is collecting input for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GitStashAction {
+    Save,
+    SaveIncludeUntracked,
+    Apply,
+    Pop,
+    Drop,
+}
+
 #[derive(Clone)]
 pub struct SourceControlData {
     pub active: WidgetId,
@@ -23,11 +43,22 @@ pub struct SourceControlData {
     pub split_direction: SplitDirection,
     pub file_list_id: WidgetId,
     pub file_list_index: usize,
+    pub conflict_list_id: WidgetId,
+    pub stash_list_id: WidgetId,
     pub editor_view_id: WidgetId,
     pub commit_button_id: WidgetId,
     pub file_diffs: Vec<(FileDiff, bool)>,
+    /// Files with merge conflicts that still need to be resolved
+    pub conflicts: im::Vector<PathBuf>,
+    pub stashes: im::Vector<StashInfo>,
     pub branch: String,
     pub branches: im::Vector<String>,
+    pub remote_branches: im::Vector<String>,
+    pub upstream: Option<UpstreamStatus>,
+    /// Whether committing replaces the last commit
+    pub amend: bool,
+    /// Whether commits get a `Signed-off-by` trailer
+    pub sign_off: bool,
 }
 
 impl SourceControlData {
@@ -40,12 +71,20 @@ pub fn new() -> Self {
             editor_view_id,
             file_list_id,
             file_list_index: 0,
+            conflict_list_id: WidgetId::next(),
+            stash_list_id: WidgetId::next(),
             commit_button_id: WidgetId::next(),
             split_id: WidgetId::next(),
             split_direction: SplitDirection::Horizontal,
             file_diffs: Vec::new(),
+            conflicts: im::Vector::new(),
+            stashes: im::Vector::new(),
             branch: "".to_string(),
             branches: im::Vector::new(),
+            remote_branches: im::Vector::new(),
+            upstream: None,
+            amend: false,
+            sign_off: false,
         }
     }
 }
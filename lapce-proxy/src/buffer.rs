@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 use lapce_core::buffer::rope_text::CharIndicesJoin;
 use lapce_core::encoding::offset_utf8_to_utf16;
-use lapce_rpc::buffer::BufferId;
+use lapce_rpc::buffer::{BufferId, Charset, EditorConfigProperties};
 use lsp_types::*;
 use std::ffi::OsString;
 use std::fs;
@@ -11,6 +11,8 @@
 use std::{borrow::Cow, path::Path, time::SystemTime};
 use xi_rope::{interval::IntervalBounds, rope::Rope, RopeDelta};
 
+use crate::editorconfig::editorconfig_properties;
+
 #[derive(Clone)]
 pub struct Buffer {
     pub language_id: &'static str,
@@ -19,11 +21,14 @@ pub struct Buffer {
     pub path: PathBuf,
     pub rev: u64,
     pub mod_time: Option<SystemTime>,
+    pub editorconfig: EditorConfigProperties,
 }
 
 impl Buffer {
     pub fn new(id: BufferId, path: PathBuf) -> Buffer {
-        let rope = Rope::from(load_file(&path).unwrap_or_default());
+        let editorconfig = editorconfig_properties(&path);
+        let rope =
+            Rope::from(load_file(&path, editorconfig.charset).unwrap_or_default());
         let rev = if rope.is_empty() { 0 } else { 1 };
         let language_id = language_id_from_path(&path).unwrap_or("");
         let mod_time = get_mod_time(&path);
@@ -34,6 +39,7 @@ pub fn new(id: BufferId, path: PathBuf) -> Buffer {
             language_id,
             rev,
             mod_time,
+            editorconfig,
         }
     }
 
@@ -41,6 +47,8 @@ pub fn save(&mut self, rev: u64) -> Result<()> {
         if self.rev != rev {
             return Err(anyhow!("not the right rev"));
         }
+        // The `.editorconfig` files might have changed since the file was opened
+        self.editorconfig = editorconfig_properties(&self.path);
         let tmp_extension = self.path.extension().map_or_else(
             || OsString::from("swp"),
             |ext| {
@@ -51,9 +59,24 @@ pub fn save(&mut self, rev: u64) -> Result<()> {
         );
         let tmp_path = &self.path.with_extension(tmp_extension);
 
+        // The text is encoded before anything is written, so that a text the
+        // charset can't hold leaves the file as it is
+        let encoded = match self.editorconfig.charset {
+            None | Some(Charset::Utf8) | Some(Charset::Utf8Bom) => None,
+            Some(charset) => Some(encode(&self.rope.to_string(), charset)?),
+        };
+
         let mut f = File::create(tmp_path)?;
-        for chunk in self.rope.iter_chunks(..self.rope.len()) {
-            f.write_all(chunk.as_bytes())?;
+        match encoded {
+            Some(bytes) => f.write_all(&bytes)?,
+            None => {
+                if self.editorconfig.charset == Some(Charset::Utf8Bom) {
+                    f.write_all(UTF8_BOM)?;
+                }
+                for chunk in self.rope.iter_chunks(..self.rope.len()) {
+                    f.write_all(chunk.as_bytes())?;
+                }
+            }
         }
 
         if let Ok(metadata) = fs::metadata(&self.path) {
@@ -140,8 +163,78 @@ pub fn is_empty(&self) -> bool {
     }
 }
 
-pub fn load_file(path: &Path) -> Result<String> {
-    Ok(read_path_to_string_lossy(path)?)
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// Reads the file in the charset the `.editorconfig` files set, or as UTF-8
+pub fn load_file(path: &Path, charset: Option<Charset>) -> Result<String> {
+    match charset {
+        None | Some(Charset::Utf8) => Ok(read_path_to_string_lossy(path)?),
+        Some(charset) => Ok(decode(&fs::read(path)?, charset)),
+    }
+}
+
+/// Decodes the bytes in the charset, where the bytes that aren't valid in
+/// it become replacement characters
+fn decode(bytes: &[u8], charset: Charset) -> String {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units = bytes.chunks_exact(2);
+        let odd_byte = !units.remainder().is_empty();
+        let units = units
+            .map(|unit| from_bytes([unit[0], unit[1]]))
+            .collect::<Vec<u16>>();
+        let mut text = String::from_utf16_lossy(&units);
+        if odd_byte {
+            text.push(char::REPLACEMENT_CHARACTER);
+        }
+        text
+    };
+    match charset {
+        Charset::Latin1 => bytes.iter().map(|b| *b as char).collect(),
+        Charset::Utf8 => String::from_utf8_lossy(bytes).to_string(),
+        Charset::Utf8Bom => {
+            String::from_utf8_lossy(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes))
+                .to_string()
+        }
+        Charset::Utf16Be => utf16(
+            bytes.strip_prefix(&[0xFE, 0xFF]).unwrap_or(bytes),
+            u16::from_be_bytes,
+        ),
+        Charset::Utf16Le => utf16(
+            bytes.strip_prefix(&[0xFF, 0xFE]).unwrap_or(bytes),
+            u16::from_le_bytes,
+        ),
+    }
+}
+
+/// Encodes the text in the charset, which fails on the first character
+/// Latin 1 doesn't have rather than writing something else in its place
+fn encode(text: &str, charset: Charset) -> Result<Vec<u8>> {
+    Ok(match charset {
+        Charset::Latin1 => {
+            let mut bytes = Vec::with_capacity(text.len());
+            for (line, content) in text.split('\n').enumerate() {
+                for (column, c) in content.chars().enumerate() {
+                    let byte = u8::try_from(c as u32).map_err(|_| {
+                        anyhow!(
+                            "{c:?} at line {}, column {} isn't in {}",
+                            line + 1,
+                            column + 1,
+                            charset.name()
+                        )
+                    })?;
+                    bytes.push(byte);
+                }
+                bytes.push(b'\n');
+            }
+            // The last line doesn't end with a newline
+            bytes.pop();
+            bytes
+        }
+        Charset::Utf8 => text.as_bytes().to_vec(),
+        Charset::Utf8Bom => [UTF8_BOM, text.as_bytes()].concat(),
+        Charset::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        Charset::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+    })
 }
 
 pub fn read_path_to_string_lossy<P: AsRef<Path>>(
@@ -295,3 +388,47 @@ pub fn get_mod_time<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
         .and_then(|meta| meta.modified())
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charset_round_trip() {
+        let text = "héllo\r\nwörld ÿ\n";
+        for charset in [
+            Charset::Latin1,
+            Charset::Utf8,
+            Charset::Utf8Bom,
+            Charset::Utf16Be,
+            Charset::Utf16Le,
+        ] {
+            let bytes = encode(text, charset).unwrap();
+            assert_eq!(decode(&bytes, charset), text, "{}", charset.name());
+        }
+
+        let text = "€ and 😀";
+        for charset in [
+            Charset::Utf8,
+            Charset::Utf8Bom,
+            Charset::Utf16Be,
+            Charset::Utf16Le,
+        ] {
+            let bytes = encode(text, charset).unwrap();
+            assert_eq!(decode(&bytes, charset), text, "{}", charset.name());
+        }
+    }
+
+    #[test]
+    fn test_charset_errors() {
+        let err = encode("a\nb €", Charset::Latin1).unwrap_err();
+        assert_eq!(err.to_string(), "'€' at line 2, column 3 isn't in Latin 1");
+
+        // An odd trailing byte isn't dropped
+        assert_eq!(decode(&[0, b'a', 0], Charset::Utf16Be), "a\u{FFFD}");
+        assert_eq!(decode(&[b'a', 0, 0], Charset::Utf16Le), "a\u{FFFD}");
+        // The byte order mark is left out
+        assert_eq!(decode(&[0xFF, 0xFE, b'a', 0], Charset::Utf16Le), "a");
+        assert_eq!(decode(&[0xEF, 0xBB, 0xBF, b'a'], Charset::Utf8Bom), "a");
+    }
+}
@@ -1,4 +1,6 @@
 use crate::buffer::{get_mod_time, load_file, Buffer};
+use crate::editorconfig::editorconfig_properties;
+use crate::file_index::{FileChanges, FileIndex};
 use crate::plugin::catalog::PluginCatalog;
 use crate::plugin::{remove_volt, PluginCatalogRpcHandler};
 use crate::terminal::Terminal;
@@ -6,31 +8,42 @@
 use alacritty_terminal::event_loop::Msg;
 use alacritty_terminal::term::SizeInfo;
 use anyhow::{anyhow, Context, Result};
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender};
 use git2::build::CheckoutBuilder;
-use git2::{DiffOptions, Repository};
+use git2::{
+    BranchType, Cred, CredentialType, DiffOptions, ErrorCode, FetchOptions,
+    PushOptions, RemoteCallbacks, Repository, StashApplyOptions, StashFlags,
+};
 use grep_matcher::Matcher;
 use grep_regex::RegexMatcherBuilder;
 use grep_searcher::sinks::UTF8;
 use grep_searcher::SearcherBuilder;
+use indexmap::IndexMap;
+use lapce_core::conflict::find_conflicts;
 use lapce_rpc::core::{CoreNotification, CoreRpcHandler};
 use lapce_rpc::file::FileNodeItem;
 use lapce_rpc::proxy::{
     ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse, ProxyRpcHandler,
 };
-use lapce_rpc::source_control::{DiffInfo, FileDiff};
+use lapce_rpc::source_control::{DiffInfo, FileDiff, StashInfo, UpstreamStatus};
 use lapce_rpc::style::{LineStyle, SemanticStyles};
 use lapce_rpc::terminal::TermId;
 use lapce_rpc::{RequestId, RpcError};
-use lsp_types::{Position, Range, TextDocumentItem, Url};
+use lsp_types::{
+    FileChangeType, FileEvent, GotoDefinitionResponse, Location, MessageType,
+    NumberOrString, Position, ProgressParams, ProgressParamsValue, Range,
+    ShowMessageParams, TextDocumentItem, Url, WorkDoneProgress,
+    WorkDoneProgressBegin, WorkDoneProgressEnd, WorkDoneProgressReport,
+};
 use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use xi_rope::Rope;
 
 const OPEN_FILE_EVENT_TOKEN: WatchToken = WatchToken(1);
@@ -45,6 +58,7 @@ pub struct Dispatcher {
     #[allow(deprecated)]
     terminals: HashMap<TermId, mio::channel::Sender<Msg>>,
     file_watcher: FileWatcher,
+    file_index: Arc<Mutex<FileIndex>>,
 
     window_id: usize,
     tab_id: usize,
@@ -68,10 +82,19 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     self.workspace.clone(),
                     self.core_rpc.clone(),
                     self.proxy_rpc.clone(),
+                    self.catalog_rpc.clone(),
+                    self.file_index.clone(),
                 ));
                 if let Some(workspace) = self.workspace.as_ref() {
                     self.file_watcher
                         .watch(workspace, true, WORKSPACE_EVENT_TOKEN);
+
+                    let workspace = workspace.clone();
+                    let file_index = self.file_index.clone();
+                    thread::spawn(move || {
+                        let files = FileIndex::walk(&workspace);
+                        file_index.lock().set_files(files);
+                    });
                 }
 
                 let plugin_rpc = self.catalog_rpc.clone();
@@ -95,11 +118,14 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 });
             }
             OpenFileChanged { path } => {
-                if let Some(buffer) = self.buffers.get(&path) {
+                if let Some(buffer) = self.buffers.get_mut(&path) {
                     if get_mod_time(&buffer.path) == buffer.mod_time {
                         return;
                     }
-                    if let Ok(content) = load_file(&buffer.path) {
+                    buffer.editorconfig = editorconfig_properties(&buffer.path);
+                    if let Ok(content) =
+                        load_file(&buffer.path, buffer.editorconfig.charset)
+                    {
                         self.core_rpc.open_file_changed(path, content);
                     }
                 }
@@ -195,20 +221,115 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             EnableVolt { volt } => {
                 let _ = self.catalog_rpc.enable_volt(volt);
             }
-            GitCommit { message, diffs } => {
+            SelectPaletteItem {
+                plugin_id,
+                provider,
+                data,
+            } => {
+                self.catalog_rpc
+                    .select_palette_item(plugin_id, provider, data);
+            }
+            GitCheckout { branch } => {
                 if let Some(workspace) = self.workspace.as_ref() {
-                    match git_commit(workspace, &message, diffs) {
-                        Ok(()) => (),
-                        Err(e) => eprintln!("{e:?}"),
-                    }
+                    let result = git_checkout(workspace, &branch);
+                    report_git_result(&self.core_rpc, result);
                 }
             }
-            GitCheckout { branch } => {
+            GitCreateBranch {
+                name,
+                start_point,
+                checkout,
+            } => {
                 if let Some(workspace) = self.workspace.as_ref() {
-                    match git_checkout(workspace, &branch) {
-                        Ok(()) => (),
-                        Err(e) => eprintln!("{e:?}"),
-                    }
+                    let result = git_create_branch(
+                        workspace,
+                        &name,
+                        start_point.as_deref(),
+                        checkout,
+                    );
+                    report_git_result(&self.core_rpc, result);
+                }
+            }
+            GitDeleteBranch { branch, force } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    let result = git_delete_branch(workspace, &branch, force);
+                    report_git_result(&self.core_rpc, result);
+                }
+            }
+            GitRenameBranch { branch, new_name } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    let result = git_rename_branch(workspace, &branch, &new_name);
+                    report_git_result(&self.core_rpc, result);
+                }
+            }
+            GitFetch {} => {
+                if let Some(workspace) = self.workspace.clone() {
+                    let core_rpc = self.core_rpc.clone();
+                    thread::spawn(move || {
+                        let progress = GitProgress::begin(&core_rpc, "Git Fetch");
+                        let result = git_fetch(&workspace, &progress);
+                        progress.end();
+                        report_git_result(&core_rpc, result);
+                    });
+                }
+            }
+            GitPull { rebase } => {
+                if let Some(workspace) = self.workspace.clone() {
+                    let core_rpc = self.core_rpc.clone();
+                    thread::spawn(move || {
+                        let progress = GitProgress::begin(&core_rpc, "Git Pull");
+                        let result = git_pull(&workspace, rebase, &progress);
+                        progress.end();
+                        report_git_result(&core_rpc, result);
+                    });
+                }
+            }
+            GitPush {} => {
+                if let Some(workspace) = self.workspace.clone() {
+                    let core_rpc = self.core_rpc.clone();
+                    thread::spawn(move || {
+                        let progress = GitProgress::begin(&core_rpc, "Git Push");
+                        let result = git_push(&workspace, &progress);
+                        progress.end();
+                        report_git_result(&core_rpc, result);
+                    });
+                }
+            }
+            GitStashSave {
+                message,
+                include_untracked,
+            } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    let result = git_stash_save(
+                        workspace,
+                        message.as_deref(),
+                        include_untracked,
+                    );
+                    report_git_result(&self.core_rpc, result);
+                }
+            }
+            GitStashApply { index } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    let result = git_stash_apply(workspace, index, false);
+                    report_git_result(&self.core_rpc, result);
+                }
+            }
+            GitStashPop { index } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    let result = git_stash_apply(workspace, index, true);
+                    report_git_result(&self.core_rpc, result);
+                }
+            }
+            GitStashDrop { index } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    let result = git_stash_drop(workspace, index);
+                    report_git_result(&self.core_rpc, result);
+                }
+            }
+            GitResolveConflict { path } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    let result = git_resolve_conflict(workspace, &path);
+                    report_git_result(&self.core_rpc, result);
                 }
             }
             GitDiscardFilesChanges { files } => {
@@ -247,6 +368,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
             NewBuffer { buffer_id, path } => {
                 let buffer = Buffer::new(buffer_id, path.clone());
                 let content = buffer.rope.to_string();
+                let editorconfig = buffer.editorconfig.clone();
                 self.catalog_rpc.did_open_document(
                     &path,
                     buffer.language_id.to_string(),
@@ -257,17 +379,61 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(
                     id,
-                    Ok(ProxyResponse::NewBufferResponse { content }),
+                    Ok(ProxyResponse::NewBufferResponse {
+                        content,
+                        editorconfig,
+                    }),
                 );
             }
-            BufferHead { path } => {
+            GitCommit {
+                message,
+                diffs,
+                amend,
+                sign_off,
+            } => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                // The hooks can take a while to run
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace.as_ref() {
+                        git_commit(workspace, &message, diffs, amend, sign_off)
+                            .map(|_| ProxyResponse::Success {})
+                            .map_err(|e| RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            })
+                    } else {
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitGetCommitMessage { amend } => {
                 let result = if let Some(workspace) = self.workspace.as_ref() {
-                    let result = file_get_head(workspace, &path);
-                    if let Ok((_blob_id, content)) = result {
-                        Ok(ProxyResponse::BufferHeadResponse {
-                            version: "head".to_string(),
-                            content,
+                    git_commit_message(workspace, amend)
+                        .map(|message| ProxyResponse::GitGetCommitMessage {
+                            message,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
                         })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            BufferHead { path, version } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    let result = file_get_version(workspace, &path, &version);
+                    if let Ok((_blob_id, content)) = result {
+                        Ok(ProxyResponse::BufferHeadResponse { version, content })
                     } else {
                         Err(RpcError {
                             code: 0,
@@ -364,7 +530,27 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     proxy_rpc.handle_response(id, result);
                 });
             }
-            GetSignature { .. } => {}
+            GetSignature {
+                request_id,
+                path,
+                position,
+                context,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.signature_help(
+                    &path,
+                    position,
+                    context,
+                    move |_, result| {
+                        let result =
+                            result.map(|signature| ProxyResponse::GetSignature {
+                                request_id,
+                                signature,
+                            });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetReferences { path, position } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc.get_references(
@@ -378,6 +564,21 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            GetDocumentHighlights { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_document_highlights(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|highlights| {
+                            ProxyResponse::GetDocumentHighlights {
+                                highlights: highlights.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetDefinition {
                 request_id,
                 path,
@@ -418,6 +619,113 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            GetImplementation { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_implementation(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|response| {
+                            ProxyResponse::GetImplementation {
+                                locations: goto_locations(response),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetDeclaration { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_declaration(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result =
+                            result.map(|response| ProxyResponse::GetDeclaration {
+                                locations: goto_locations(response),
+                            });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            PrepareCallHierarchy { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.prepare_call_hierarchy(
+                    &path,
+                    position,
+                    move |plugin_id, result| {
+                        let result =
+                            result.map(|items| ProxyResponse::PrepareHierarchy {
+                                plugin_id,
+                                items: items.unwrap_or_default(),
+                            });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            PrepareTypeHierarchy { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.prepare_type_hierarchy(
+                    &path,
+                    position,
+                    move |plugin_id, result| {
+                        let result =
+                            result.map(|items| ProxyResponse::PrepareHierarchy {
+                                plugin_id,
+                                items: items.unwrap_or_default(),
+                            });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            CallHierarchyIncomingCalls { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.call_hierarchy_incoming_calls(
+                    plugin_id,
+                    *item,
+                    move |result| {
+                        let result = result
+                            .map(|items| ProxyResponse::HierarchyItems { items });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            CallHierarchyOutgoingCalls { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.call_hierarchy_outgoing_calls(
+                    plugin_id,
+                    *item,
+                    move |result| {
+                        let result = result
+                            .map(|items| ProxyResponse::HierarchyItems { items });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            TypeHierarchySupertypes { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.type_hierarchy_supertypes(
+                    plugin_id,
+                    *item,
+                    move |result| {
+                        let result = result
+                            .map(|items| ProxyResponse::HierarchyItems { items });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            TypeHierarchySubtypes { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.type_hierarchy_subtypes(
+                    plugin_id,
+                    *item,
+                    move |result| {
+                        let result = result
+                            .map(|items| ProxyResponse::HierarchyItems { items });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetInlayHints { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 let buffer = self.buffers.get(&path).unwrap();
@@ -432,6 +740,43 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         proxy_rpc.handle_response(id, result);
                     });
             }
+            GetFoldingRanges { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .get_folding_ranges(&path, move |_, result| {
+                        let result =
+                            result.map(|ranges| ProxyResponse::GetFoldingRanges {
+                                ranges: ranges.unwrap_or_default(),
+                            });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
+            GetCodeLens { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .get_code_lens(&path, move |plugin_id, result| {
+                        let result =
+                            result.map(|lenses| ProxyResponse::GetCodeLens {
+                                plugin_id,
+                                lenses: lenses.unwrap_or_default(),
+                            });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
+            CodeLensResolve { plugin_id, lens } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.code_lens_resolve(
+                    plugin_id,
+                    *lens,
+                    move |result| {
+                        let result =
+                            result.map(|lens| ProxyResponse::CodeLensResolve {
+                                lens: Box::new(lens),
+                            });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetSemanticTokens { path } => {
                 let buffer = self.buffers.get(&path).unwrap();
                 let text = buffer.rope.clone();
@@ -484,9 +829,64 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 self.catalog_rpc.get_code_actions(
                     &path,
                     position,
-                    move |_, result| {
+                    move |plugin_id, result| {
                         let result = result.map(|resp| {
-                            ProxyResponse::GetCodeActionsResponse { resp }
+                            ProxyResponse::GetCodeActionsResponse { plugin_id, resp }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetCodeActionsOfKind { path, kind } => {
+                let buffer = self.buffers.get(&path).unwrap();
+                let range = Range {
+                    start: Position::new(0, 0),
+                    end: buffer.offset_to_position(buffer.len()),
+                };
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_code_actions_of_kind(
+                    &path,
+                    range,
+                    kind,
+                    move |responses| {
+                        let actions = responses
+                            .into_iter()
+                            .flat_map(|(plugin_id, resp)| {
+                                resp.into_iter()
+                                    .map(move |action| (plugin_id, action))
+                            })
+                            .collect();
+                        proxy_rpc.handle_response(
+                            id,
+                            Ok(ProxyResponse::GetCodeActionsOfKindResponse {
+                                actions,
+                            }),
+                        );
+                    },
+                );
+            }
+            CodeActionResolve { plugin_id, action } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.code_action_resolve(
+                    plugin_id,
+                    *action,
+                    move |result| {
+                        let result =
+                            result.map(|action| ProxyResponse::CodeActionResolve {
+                                action: Box::new(action),
+                            });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            ExecuteCommand { plugin_id, command } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.execute_command(
+                    plugin_id,
+                    command,
+                    move |result| {
+                        let result = result.map(|result| {
+                            ProxyResponse::ExecuteCommandResponse { result }
                         });
                         proxy_rpc.handle_response(id, result);
                     },
@@ -511,38 +911,92 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         proxy_rpc.handle_response(id, result);
                     });
             }
+            GetPaletteItems {
+                plugin_id,
+                provider,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_palette_items(
+                    plugin_id,
+                    provider,
+                    move |result| {
+                        let result = result
+                            .map(|items| ProxyResponse::GetPaletteItems { items });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetDocumentFormatting { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                self.catalog_rpc
-                    .get_document_formatting(&path, move |_, result| {
+                let buffer = self.buffers.get(&path).unwrap();
+                self.catalog_rpc.get_document_formatting(
+                    &path,
+                    &buffer.editorconfig,
+                    move |_, result| {
                         let result = result.map(|edits| {
                             ProxyResponse::GetDocumentFormatting { edits }
                         });
                         proxy_rpc.handle_response(id, result);
-                    });
+                    },
+                );
             }
-            PrepareRename { path, position } => {
+            GetDocumentRangeFormatting { path, range } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                self.catalog_rpc.prepare_rename(
+                let buffer = self.buffers.get(&path).unwrap();
+                self.catalog_rpc.get_document_range_formatting(
                     &path,
-                    position,
+                    &buffer.editorconfig,
+                    range,
                     move |_, result| {
-                        let result =
-                            result.map(|resp| ProxyResponse::PrepareRename { resp });
+                        let result = result.map(|edits| {
+                            ProxyResponse::GetDocumentFormatting {
+                                edits: edits.unwrap_or_default(),
+                            }
+                        });
                         proxy_rpc.handle_response(id, result);
                     },
                 );
             }
-            Rename {
-                path,
-                position,
-                new_name,
-            } => {
+            GetOnTypeFormatting { path, position, ch } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                self.catalog_rpc.rename(
+                let buffer = self.buffers.get(&path).unwrap();
+                self.catalog_rpc.get_on_type_formatting(
                     &path,
+                    &buffer.editorconfig,
                     position,
-                    new_name,
+                    ch,
+                    move |_, result| {
+                        let result = result.map(|edits| {
+                            ProxyResponse::GetDocumentFormatting {
+                                edits: edits.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            PrepareRename { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.prepare_rename(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result =
+                            result.map(|resp| ProxyResponse::PrepareRename { resp });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            Rename {
+                path,
+                position,
+                new_name,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.rename(
+                    &path,
+                    position,
+                    new_name,
                     move |_, result| {
                         let result =
                             result.map(|edit| ProxyResponse::Rename { edit });
@@ -551,6 +1005,16 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 );
             }
             GetFiles { .. } => {
+                if let Some(items) = self.file_index.lock().files() {
+                    self.respond_rpc(
+                        id,
+                        Ok(ProxyResponse::GetFilesResponse { items }),
+                    );
+                    return;
+                }
+
+                // The index is still being built, so the files are walked
+                // for this request
                 let workspace = self.workspace.clone();
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
@@ -740,6 +1204,7 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             buffers: HashMap::new(),
             terminals: HashMap::new(),
             file_watcher,
+            file_index: Arc::new(Mutex::new(FileIndex::default())),
             window_id: 1,
             tab_id: 1,
         }
@@ -753,9 +1218,17 @@ fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
 struct FileWatchNotifier {
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
+    catalog_rpc: PluginCatalogRpcHandler,
     workspace: Option<PathBuf>,
     workspace_fs_change_handler: Arc<Mutex<Option<Sender<bool>>>>,
+    /// The file events for the language servers
+    watched_file_events: Sender<Vec<FileEvent>>,
     last_diff: Arc<Mutex<DiffInfo>>,
+    /// The files of the stashes by the id of the stash commit, which never
+    /// changes them, so that a refresh only diffs the new stashes
+    stash_files: Arc<Mutex<HashMap<git2::Oid, Vec<PathBuf>>>>,
+    /// The created, removed or renamed paths for the file index
+    file_index_changes: Sender<PathBuf>,
 }
 
 impl Notify for FileWatchNotifier {
@@ -769,20 +1242,40 @@ fn new(
         workspace: Option<PathBuf>,
         core_rpc: CoreRpcHandler,
         proxy_rpc: ProxyRpcHandler,
+        catalog_rpc: PluginCatalogRpcHandler,
+        file_index: Arc<Mutex<FileIndex>>,
     ) -> Self {
+        let (file_index_changes, receiver) = crossbeam_channel::unbounded();
+        if let Some(workspace) = workspace.clone() {
+            thread::spawn(move || {
+                update_file_index(receiver, workspace, file_index)
+            });
+        }
+        let (watched_file_events, receiver) = crossbeam_channel::unbounded();
+        let watched_catalog_rpc = catalog_rpc.clone();
+        thread::spawn(move || {
+            send_watched_file_events(receiver, watched_catalog_rpc)
+        });
+
         let notifier = Self {
             workspace,
             core_rpc,
             proxy_rpc,
+            catalog_rpc,
             workspace_fs_change_handler: Arc::new(Mutex::new(None)),
+            watched_file_events,
             last_diff: Arc::new(Mutex::new(DiffInfo::default())),
+            stash_files: Arc::new(Mutex::new(HashMap::new())),
+            file_index_changes,
         };
 
         if let Some(workspace) = notifier.workspace.clone() {
             let core_rpc = notifier.core_rpc.clone();
             let last_diff = notifier.last_diff.clone();
+            let stash_files = notifier.stash_files.clone();
             thread::spawn(move || {
-                if let Some(diff) = git_diff_new(&workspace) {
+                if let Some(diff) = git_diff_new(&workspace, &mut stash_files.lock())
+                {
                     core_rpc.diff_info(diff.clone());
                     *last_diff.lock() = diff;
                 }
@@ -820,6 +1313,14 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             _ => return,
         };
 
+        self.queue_watched_file_events(&event);
+
+        if explorer_change {
+            for path in event.paths.iter() {
+                let _ = self.file_index_changes.send(path.clone());
+            }
+        }
+
         let mut handler = self.workspace_fs_change_handler.lock();
         if let Some(sender) = handler.as_mut() {
             if explorer_change {
@@ -838,6 +1339,7 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
         let core_rpc = self.core_rpc.clone();
         let workspace = self.workspace.clone().unwrap();
         let last_diff = self.last_diff.clone();
+        let stash_files = self.stash_files.clone();
         thread::spawn(move || {
             thread::sleep(Duration::from_millis(500));
 
@@ -855,7 +1357,7 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             if explorer_change {
                 core_rpc.workspace_file_change();
             }
-            if let Some(diff) = git_diff_new(&workspace) {
+            if let Some(diff) = git_diff_new(&workspace, &mut stash_files.lock()) {
                 let mut last_diff = last_diff.lock();
                 if diff != *last_diff {
                     core_rpc.diff_info(diff.clone());
@@ -865,6 +1367,195 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
         });
         *handler = Some(sender);
     }
+
+    fn queue_watched_file_events(&self, event: &notify::Event) {
+        let events = watched_file_events(event);
+        if !events.is_empty() {
+            let _ = self.watched_file_events.send(events);
+        }
+    }
+}
+
+/// Sends the file events to the language servers in batches, each of which
+/// ends once the files stop changing for a moment, leaving out the repeated
+/// events
+fn send_watched_file_events(
+    receiver: Receiver<Vec<FileEvent>>,
+    catalog_rpc: PluginCatalogRpcHandler,
+) {
+    while let Ok(events) = receiver.recv() {
+        // The change types of each file, in the order they came in
+        let mut pending: IndexMap<Url, Vec<FileChangeType>> = IndexMap::new();
+        let mut add = |events: Vec<FileEvent>| {
+            for event in events {
+                let types = pending.entry(event.uri).or_default();
+                if !types.contains(&event.typ) {
+                    types.push(event.typ);
+                }
+            }
+        };
+        add(events);
+        // A batch doesn't wait for ever on files that keep changing
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            match receiver.recv_timeout(Duration::from_millis(300)) {
+                Ok(events) => add(events),
+                Err(_) => break,
+            }
+        }
+        let events = pending
+            .into_iter()
+            .flat_map(|(uri, types)| {
+                types.into_iter().map(move |typ| FileEvent {
+                    uri: uri.clone(),
+                    typ,
+                })
+            })
+            .collect();
+        catalog_rpc.did_change_watched_files(events);
+    }
+}
+
+/// Updates the file index with the changed paths in batches, each of which
+/// ends once the paths stop coming for a moment, so that the disk is read
+/// away from the watcher thread and the lock of the index
+fn update_file_index(
+    receiver: Receiver<PathBuf>,
+    workspace: PathBuf,
+    file_index: Arc<Mutex<FileIndex>>,
+) {
+    while let Ok(path) = receiver.recv() {
+        let mut paths = vec![path];
+        // A batch doesn't wait for ever on files that keep changing
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            match receiver.recv_timeout(Duration::from_millis(300)) {
+                Ok(path) => paths.push(path),
+                Err(_) => break,
+            }
+        }
+        let changes = FileChanges::scan(&workspace, paths);
+        file_index.lock().update(changes);
+    }
+}
+
+/// The `workspace/didChangeWatchedFiles` events for a change on disk, which
+/// leave out the internals of the git directory
+fn watched_file_events(event: &notify::Event) -> Vec<FileEvent> {
+    use notify::event::{ModifyKind, RenameMode};
+    use notify::EventKind;
+
+    event
+        .paths
+        .iter()
+        .enumerate()
+        .filter(|(_, path)| !path.components().any(|c| c.as_os_str() == ".git"))
+        .filter_map(|(i, path)| {
+            let typ = match event.kind {
+                EventKind::Create(_) => FileChangeType::CREATED,
+                EventKind::Remove(_) => FileChangeType::DELETED,
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                    if i == 0 {
+                        FileChangeType::DELETED
+                    } else {
+                        FileChangeType::CREATED
+                    }
+                }
+                EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                    FileChangeType::DELETED
+                }
+                EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                    FileChangeType::CREATED
+                }
+                EventKind::Modify(ModifyKind::Name(_)) => {
+                    if path.exists() {
+                        FileChangeType::CREATED
+                    } else {
+                        FileChangeType::DELETED
+                    }
+                }
+                EventKind::Modify(_) => FileChangeType::CHANGED,
+                _ => return None,
+            };
+            Some(FileEvent::new(Url::from_file_path(path).ok()?, typ))
+        })
+        .collect()
+}
+
+/// The locations of a goto response, which can also come as links
+fn goto_locations(response: Option<GotoDefinitionResponse>) -> Vec<Location> {
+    match response {
+        Some(GotoDefinitionResponse::Scalar(location)) => vec![location],
+        Some(GotoDefinitionResponse::Array(locations)) => locations,
+        Some(GotoDefinitionResponse::Link(links)) => links
+            .into_iter()
+            .map(|link| Location {
+                uri: link.target_uri,
+                range: link.target_selection_range,
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Let the user know when a git operation they started has failed
+fn report_git_result(core_rpc: &CoreRpcHandler, result: Result<()>) {
+    if let Err(e) = result {
+        core_rpc.show_message(
+            "Git".to_string(),
+            ShowMessageParams {
+                typ: MessageType::ERROR,
+                message: e.to_string(),
+            },
+        );
+    }
+}
+
+/// Reports the progress of a git network operation to the core the same way
+/// a language server reports its work done progress
+struct GitProgress {
+    core_rpc: CoreRpcHandler,
+    token: NumberOrString,
+}
+
+impl GitProgress {
+    fn begin(core_rpc: &CoreRpcHandler, title: &str) -> Self {
+        static PROGRESS_ID: AtomicU64 = AtomicU64::new(0);
+        let token = NumberOrString::String(format!(
+            "lapce-git-{}",
+            PROGRESS_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let progress = Self {
+            core_rpc: core_rpc.clone(),
+            token,
+        };
+        progress.send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: title.to_string(),
+            cancellable: Some(false),
+            message: None,
+            percentage: None,
+        }));
+        progress
+    }
+
+    fn report(&self, message: String, percentage: Option<u32>) {
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: None,
+            message: Some(message),
+            percentage,
+        }));
+    }
+
+    fn end(self) {
+        self.send(WorkDoneProgress::End(WorkDoneProgressEnd { message: None }));
+    }
+
+    fn send(&self, progress: WorkDoneProgress) {
+        self.core_rpc.work_done_progress(ProgressParams {
+            token: self.token.clone(),
+            value: ProgressParamsValue::WorkDone(progress),
+        });
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -885,12 +1576,15 @@ fn git_commit(
     workspace_path: &Path,
     message: &str,
     diffs: Vec<FileDiff>,
+    amend: bool,
+    sign_off: bool,
 ) -> Result<()> {
     let repo = Repository::open(
         workspace_path
             .to_str()
             .ok_or_else(|| anyhow!("workspace path can't changed to str"))?,
     )?;
+    let config = repo.config()?;
     let mut index = repo.index()?;
     for diff in diffs {
         match diff {
@@ -907,18 +1601,204 @@ fn git_commit(
         }
     }
     index.write()?;
+
+    git_run_hook(&repo, "pre-commit", &[])?;
+    // The hook is free to stage more changes
+    index.read(true)?;
     let tree = index.write_tree()?;
     let tree = repo.find_tree(tree)?;
-    let signature = repo.signature()?;
-    let parent = repo.head()?.peel_to_commit()?;
-    repo.commit(
-        Some("HEAD"),
-        &signature,
-        &signature,
-        message,
-        &tree,
-        &[&parent],
-    )?;
+
+    // Comment lines are there for the template to explain itself
+    let mut message = git2::message_prettify(message, Some(b'#'))?;
+    if message.is_empty() {
+        return Err(anyhow!("aborting commit due to empty commit message"));
+    }
+    let committer = repo.signature()?;
+    if sign_off {
+        let trailer = format!(
+            "Signed-off-by: {} <{}>",
+            committer.name().unwrap_or(""),
+            committer.email().unwrap_or("")
+        );
+        if !message.lines().any(|line| line == trailer) {
+            let last_line = message.lines().last().unwrap_or("");
+            if !last_line.starts_with("Signed-off-by:") {
+                message.push('\n');
+            }
+            message.push_str(&trailer);
+            message.push('\n');
+        }
+    }
+    let message_path = repo.path().join("COMMIT_EDITMSG");
+    fs::write(&message_path, &message)?;
+    git_run_hook(&repo, "commit-msg", &[&message_path])?;
+    let message = fs::read_to_string(&message_path)?;
+    if message.trim().is_empty() {
+        return Err(anyhow!("aborting commit due to empty commit message"));
+    }
+
+    let head = repo
+        .head()
+        .ok()
+        .map(|head| head.peel_to_commit())
+        .transpose()?;
+    let (author, parents) = match head {
+        Some(head) if amend => {
+            (head.author().to_owned(), head.parents().collect::<Vec<_>>())
+        }
+        Some(head) => (committer.clone(), vec![head]),
+        None if amend => return Err(anyhow!("there is no commit to amend")),
+        None => (committer.clone(), Vec::new()),
+    };
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+
+    let id = if config.get_bool("commit.gpgsign").unwrap_or(false) {
+        let buffer = repo
+            .commit_create_buffer(&author, &committer, &message, &tree, &parents)?;
+        let content = buffer
+            .as_str()
+            .ok_or_else(|| anyhow!("commit isn't valid utf-8"))?;
+        let signature = git_sign(&config, &committer, content)?;
+        repo.commit_signed(content, &signature, None)?
+    } else {
+        repo.commit(None, &author, &committer, &message, &tree, &parents)?
+    };
+
+    let summary = message.lines().next().unwrap_or("");
+    let log_message = if amend {
+        format!("commit (amend): {summary}")
+    } else {
+        format!("commit: {summary}")
+    };
+    // HEAD is moved by hand because a signed commit isn't created on a ref
+    // and an amended one doesn't have the current tip as its parent
+    let head = repo.find_reference("HEAD")?;
+    match head.symbolic_target() {
+        Some(branch) => {
+            repo.reference(branch, id, true, &log_message)?;
+        }
+        None => {
+            repo.set_head_detached(id)?;
+        }
+    }
+    Ok(())
+}
+
+/// The message the commit input starts with, either the message of the
+/// commit being amended or the configured `commit.template`
+fn git_commit_message(workspace_path: &Path, amend: bool) -> Result<String> {
+    let repo = Repository::open(workspace_path)?;
+    if amend {
+        let head = repo.head()?.peel_to_commit()?;
+        return Ok(head.message().unwrap_or("").to_string());
+    }
+    let template = match repo.config()?.get_path("commit.template") {
+        Ok(path) => path,
+        Err(_) => return Ok(String::new()),
+    };
+    let template = repo
+        .workdir()
+        .map(|workdir| workdir.join(&template))
+        .unwrap_or(template);
+    fs::read_to_string(&template).with_context(|| {
+        format!("can't read commit template {}", template.display())
+    })
+}
+
+/// Sign the commit with gpg the same way `git commit -S` does
+fn git_sign(
+    config: &git2::Config,
+    committer: &git2::Signature,
+    content: &str,
+) -> Result<String> {
+    let format = config
+        .get_string("gpg.format")
+        .unwrap_or_else(|_| "openpgp".to_string());
+    if format != "openpgp" {
+        return Err(anyhow!("signing commits with {format} isn't supported"));
+    }
+    let program = config
+        .get_string("gpg.program")
+        .unwrap_or_else(|_| "gpg".to_string());
+    let key = config.get_string("user.signingkey").unwrap_or_else(|_| {
+        format!(
+            "{} <{}>",
+            committer.name().unwrap_or(""),
+            committer.email().unwrap_or("")
+        )
+    });
+
+    let mut command = std::process::Command::new(&program);
+    command
+        .args(["--status-fd=2", "-bsau", &key])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    #[cfg(target_os = "windows")]
+    std::os::windows::process::CommandExt::creation_flags(&mut command, 0x08000000);
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("can't run {program} to sign the commit"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        std::io::Write::write_all(&mut stdin, content.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{program} failed to sign the commit:\n{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Run the hook if the repository has it, failing with whatever it printed
+/// when it exits with an error
+fn git_run_hook(repo: &Repository, name: &str, args: &[&Path]) -> Result<()> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("can't run hooks in a bare repository"))?;
+    let hooks = match repo.config()?.get_path("core.hooksPath") {
+        Ok(path) => workdir.join(path),
+        Err(_) => repo.path().join("hooks"),
+    };
+    let hook = hooks.join(name);
+    if !hook.is_file() {
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    let mut command = {
+        use std::os::unix::fs::PermissionsExt;
+        // Git skips the sample hooks and any others that aren't executable
+        if fs::metadata(&hook)?.permissions().mode() & 0o111 == 0 {
+            return Ok(());
+        }
+        std::process::Command::new(&hook)
+    };
+    // Hooks are shell scripts that Windows can't run on their own
+    #[cfg(not(unix))]
+    let mut command = {
+        let mut command = std::process::Command::new("sh");
+        command.arg(&hook);
+        command
+    };
+    command.args(args).current_dir(workdir);
+    #[cfg(target_os = "windows")]
+    std::os::windows::process::CommandExt::creation_flags(&mut command, 0x08000000);
+
+    let output = command
+        .output()
+        .with_context(|| format!("can't run the {name} hook"))?;
+    if !output.status.success() {
+        let output = [&output.stdout, &output.stderr]
+            .iter()
+            .map(|out| String::from_utf8_lossy(out).trim().to_string())
+            .filter(|out| !out.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(anyhow!("the {name} hook failed:\n{output}"));
+    }
     Ok(())
 }
 
@@ -928,12 +1808,531 @@ fn git_checkout(workspace_path: &Path, branch: &str) -> Result<()> {
             .to_str()
             .ok_or_else(|| anyhow!("workspace path can't changed to str"))?,
     )?;
-    let (object, reference) = repo.revparse_ext(branch)?;
-    repo.checkout_tree(&object, None)?;
-    repo.set_head(reference.unwrap().name().unwrap())?;
+    git_checkout_branch(&repo, branch)
+}
+
+fn git_checkout_branch(repo: &Repository, branch: &str) -> Result<()> {
+    // Checking out a remote branch goes through a local branch tracking it,
+    // the same as `git checkout <branch>` does when only a remote has it
+    let branch = if repo.find_branch(branch, BranchType::Local).is_err() {
+        match repo.find_branch(branch, BranchType::Remote) {
+            Ok(remote_branch) => git_track_remote_branch(repo, &remote_branch)?,
+            Err(_) => branch.to_string(),
+        }
+    } else {
+        branch.to_string()
+    };
+
+    let (object, reference) = repo.revparse_ext(&branch)?;
+    repo.checkout_tree(&object, None).map_err(|e| {
+        if e.code() == ErrorCode::Conflict {
+            anyhow!(
+                "checking out {branch} would overwrite local changes, commit or stash them first"
+            )
+        } else {
+            e.into()
+        }
+    })?;
+    match reference.as_ref().and_then(|r| r.name()) {
+        Some(name) => repo.set_head(name)?,
+        None => repo.set_head_detached(object.id())?,
+    }
+    Ok(())
+}
+
+/// Create a local branch which tracks `remote_branch` and return its name.
+/// An existing local branch of the same name is left as it is.
+fn git_track_remote_branch(
+    repo: &Repository,
+    remote_branch: &git2::Branch,
+) -> Result<String> {
+    let remote_ref = remote_branch
+        .get()
+        .name()
+        .ok_or_else(|| anyhow!("remote branch name is not valid utf-8"))?;
+    let remote = repo.branch_remote_name(remote_ref)?;
+    let remote = remote
+        .as_str()
+        .ok_or_else(|| anyhow!("remote name is not valid utf-8"))?;
+    let remote_name = remote_branch
+        .name()?
+        .ok_or_else(|| anyhow!("remote branch name is not valid utf-8"))?;
+    let local_name = remote_name
+        .strip_prefix(remote)
+        .and_then(|name| name.strip_prefix('/'))
+        .ok_or_else(|| anyhow!("can't get a local name for {remote_name}"))?;
+
+    if repo.find_branch(local_name, BranchType::Local).is_err() {
+        let commit = remote_branch.get().peel_to_commit()?;
+        let mut local_branch = repo.branch(local_name, &commit, false)?;
+        local_branch.set_upstream(Some(remote_name))?;
+    }
+    Ok(local_name.to_string())
+}
+
+fn git_create_branch(
+    workspace_path: &Path,
+    name: &str,
+    start_point: Option<&str>,
+    checkout: bool,
+) -> Result<()> {
+    let repo = Repository::open(workspace_path)?;
+    let commit = match start_point {
+        Some(start_point) => repo.revparse_single(start_point)?.peel_to_commit()?,
+        None => repo.head()?.peel_to_commit()?,
+    };
+    let mut branch = repo.branch(name, &commit, false)?;
+    if let Some(start_point) = start_point {
+        // Starting from a remote branch sets it as the upstream, like git's
+        // default `branch.autoSetupMerge` behaviour
+        if repo.find_branch(start_point, BranchType::Remote).is_ok() {
+            branch.set_upstream(Some(start_point))?;
+        }
+    }
+    if checkout {
+        git_checkout_branch(&repo, name)?;
+    }
+    Ok(())
+}
+
+fn git_delete_branch(workspace_path: &Path, name: &str, force: bool) -> Result<()> {
+    let repo = Repository::open(workspace_path)?;
+    let mut branch = repo.find_branch(name, BranchType::Local)?;
+    if branch.is_head() {
+        return Err(anyhow!("can't delete the checked out branch {name}"));
+    }
+    if !force {
+        // Like git, a branch is merged once its upstream has it, or the
+        // checked out branch for a branch without one
+        let tip = branch.get().peel_to_commit()?.id();
+        let base = match branch.upstream() {
+            Ok(upstream) => upstream.get().peel_to_commit()?.id(),
+            Err(_) => repo.head()?.peel_to_commit()?.id(),
+        };
+        if tip != base && !repo.graph_descendant_of(base, tip)? {
+            return Err(anyhow!("branch {name} is not fully merged"));
+        }
+    }
+    branch.delete()?;
+    Ok(())
+}
+
+fn git_rename_branch(
+    workspace_path: &Path,
+    name: &str,
+    new_name: &str,
+) -> Result<()> {
+    let repo = Repository::open(workspace_path)?;
+    let mut branch = repo.find_branch(name, BranchType::Local)?;
+    branch.rename(new_name, false)?;
+    Ok(())
+}
+
+/// Callbacks for talking to a remote, which authenticate through the
+/// ssh-agent or the configured credential helper and report the transfer
+/// progress
+fn git_remote_callbacks<'a>(
+    repo: &Repository,
+    progress: &'a GitProgress,
+) -> Result<RemoteCallbacks<'a>> {
+    let config = repo.config()?;
+    let mut tried_ssh_agent = false;
+    let mut tried_credential_helper = false;
+    let mut callbacks = RemoteCallbacks::new();
+    // libgit2 keeps asking for credentials while they are rejected, so each
+    // source is only offered once
+    callbacks.credentials(move |url, username, allowed_types| {
+        if allowed_types.contains(CredentialType::SSH_KEY) && !tried_ssh_agent {
+            tried_ssh_agent = true;
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+        if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT)
+            && !tried_credential_helper
+        {
+            tried_credential_helper = true;
+            return Cred::credential_helper(&config, url, username);
+        }
+        if allowed_types.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+        Err(git2::Error::from_str(&format!(
+            "no credentials for {url} from the ssh-agent or credential helper"
+        )))
+    });
+
+    let mut last_percentage = None;
+    callbacks.transfer_progress(move |stats| {
+        if stats.total_objects() > 0 {
+            let percentage =
+                (stats.received_objects() * 100 / stats.total_objects()) as u32;
+            if last_percentage != Some(percentage) {
+                last_percentage = Some(percentage);
+                progress.report(
+                    format!(
+                        "{}/{} objects",
+                        stats.received_objects(),
+                        stats.total_objects()
+                    ),
+                    Some(percentage),
+                );
+            }
+        }
+        true
+    });
+
+    let mut last_percentage = None;
+    callbacks.push_transfer_progress(move |current, total, _bytes| {
+        if total > 0 {
+            let percentage = (current * 100 / total) as u32;
+            if last_percentage != Some(percentage) {
+                last_percentage = Some(percentage);
+                progress
+                    .report(format!("{current}/{total} objects"), Some(percentage));
+            }
+        }
+    });
+
+    // A ref the remote refused to update is reported here rather than as
+    // an error of the push itself
+    callbacks.push_update_reference(|refname, status| match status {
+        Some(status) => Err(git2::Error::from_str(&format!(
+            "failed to push {refname}: {status}"
+        ))),
+        None => Ok(()),
+    });
+
+    Ok(callbacks)
+}
+
+/// The remote to talk to for the current branch, which is the remote of its
+/// upstream, or `origin` or the only remote there is when it has none
+fn git_current_remote(repo: &Repository) -> Result<String> {
+    if let Ok(head) = repo.head() {
+        if let Some(refname) = head.name() {
+            if let Ok(remote) = repo.branch_upstream_remote(refname) {
+                if let Some(remote) = remote.as_str() {
+                    return Ok(remote.to_string());
+                }
+            }
+        }
+    }
+
+    let remotes = repo.remotes()?;
+    let remotes: Vec<&str> = remotes.iter().flatten().collect();
+    match remotes.as_slice() {
+        [] => Err(anyhow!("no remote is configured")),
+        [remote] => Ok(remote.to_string()),
+        _ if remotes.contains(&"origin") => Ok("origin".to_string()),
+        _ => Err(anyhow!(
+            "the current branch has no upstream and there's more than one remote"
+        )),
+    }
+}
+
+fn git_fetch(workspace_path: &Path, progress: &GitProgress) -> Result<()> {
+    let repo = Repository::open(workspace_path)?;
+    git_fetch_remote(&repo, progress)
+}
+
+fn git_fetch_remote(repo: &Repository, progress: &GitProgress) -> Result<()> {
+    let mut remote = repo.find_remote(&git_current_remote(repo)?)?;
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(git_remote_callbacks(repo, progress)?);
+    // No refspecs means the ones configured for the remote are used
+    remote.fetch::<&str>(&[], Some(&mut fetch_options), None)?;
+    Ok(())
+}
+
+/// Fetch and bring the current branch up to date with its upstream, either
+/// by fast-forwarding it or by rebasing the local commits on top
+fn git_pull(
+    workspace_path: &Path,
+    rebase: bool,
+    progress: &GitProgress,
+) -> Result<()> {
+    let repo = Repository::open(workspace_path)?;
+    git_fetch_remote(&repo, progress)?;
+
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Err(anyhow!("can't pull without a branch checked out"));
+    }
+    let name = head
+        .shorthand()
+        .ok_or_else(|| anyhow!("branch name is not valid utf-8"))?;
+    let refname = head
+        .name()
+        .ok_or_else(|| anyhow!("branch name is not valid utf-8"))?;
+    let upstream = repo
+        .find_branch(name, BranchType::Local)?
+        .upstream()
+        .map_err(|_| anyhow!("branch {name} has no upstream"))?;
+    let upstream_commit = repo.reference_to_annotated_commit(upstream.get())?;
+
+    let (analysis, _) = repo.merge_analysis(&[&upstream_commit])?;
+    if analysis.is_up_to_date() {
+        return Ok(());
+    }
+
+    if analysis.is_fast_forward() {
+        // Check out the new tree before moving the branch, so that local
+        // changes conflicting with it stop the pull instead of being lost
+        let target = repo.find_object(upstream_commit.id(), None)?;
+        repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))?;
+        repo.find_reference(refname)?
+            .set_target(upstream_commit.id(), "pull: fast-forward")?;
+        return Ok(());
+    }
+
+    if !rebase {
+        return Err(anyhow!(
+            "branch {name} has diverged from its upstream, pull with rebase instead"
+        ));
+    }
+
+    let signature = repo.signature()?;
+    let mut git_rebase = repo.rebase(None, Some(&upstream_commit), None, None)?;
+    // A rebase that stops halfway mustn't leave the repository in the middle
+    // of it
+    if let Err(err) = git_rebase_commits(&repo, &mut git_rebase, &signature, name) {
+        if let Err(abort_err) = git_rebase.abort() {
+            return Err(anyhow!(
+                "{err}, and the rebase couldn't be aborted: {abort_err}"
+            ));
+        }
+        return Err(anyhow!("{err}, the pull was aborted"));
+    }
+    Ok(())
+}
+
+fn git_rebase_commits(
+    repo: &Repository,
+    git_rebase: &mut git2::Rebase,
+    signature: &git2::Signature,
+    name: &str,
+) -> Result<()> {
+    while let Some(operation) = git_rebase.next() {
+        operation?;
+        let index = repo.index()?;
+        if index.has_conflicts() {
+            let mut paths = Vec::new();
+            for conflict in index.conflicts()? {
+                let conflict = conflict?;
+                if let Some(entry) =
+                    conflict.our.or(conflict.their).or(conflict.ancestor)
+                {
+                    paths.push(String::from_utf8_lossy(&entry.path).to_string());
+                }
+            }
+            return Err(anyhow!(
+                "rebasing {name} onto its upstream conflicts in {}",
+                paths.join(", ")
+            ));
+        }
+        if let Err(e) = git_rebase.commit(None, signature, None) {
+            // The upstream already has this change
+            if e.code() != ErrorCode::Applied {
+                return Err(e.into());
+            }
+        }
+    }
+    git_rebase.finish(Some(signature))?;
+    Ok(())
+}
+
+/// Push the current branch to the branch it tracks, setting the upstream to
+/// a branch of the same name when there's none yet
+fn git_push(workspace_path: &Path, progress: &GitProgress) -> Result<()> {
+    let repo = Repository::open(workspace_path)?;
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Err(anyhow!("can't push without a branch checked out"));
+    }
+    let name = head
+        .shorthand()
+        .ok_or_else(|| anyhow!("branch name is not valid utf-8"))?;
+    let refname = head
+        .name()
+        .ok_or_else(|| anyhow!("branch name is not valid utf-8"))?;
+
+    let remote_name = git_current_remote(&repo)?;
+    let mut branch = repo.find_branch(name, BranchType::Local)?;
+    let has_upstream = branch.upstream().is_ok();
+    let remote_refname = if has_upstream {
+        repo.config()?.get_string(&format!("branch.{name}.merge"))?
+    } else {
+        refname.to_string()
+    };
+
+    let mut remote = repo.find_remote(&remote_name)?;
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(git_remote_callbacks(&repo, progress)?);
+    remote.push(
+        &[format!("{refname}:{remote_refname}")],
+        Some(&mut push_options),
+    )?;
+
+    if !has_upstream {
+        branch.set_upstream(Some(&format!("{remote_name}/{name}")))?;
+    }
+    Ok(())
+}
+
+fn git_stash_save(
+    workspace_path: &Path,
+    message: Option<&str>,
+    include_untracked: bool,
+) -> Result<()> {
+    let mut repo = Repository::open(workspace_path)?;
+    let signature = repo.signature()?;
+    let mut flags = StashFlags::DEFAULT;
+    if include_untracked {
+        flags |= StashFlags::INCLUDE_UNTRACKED;
+    }
+    match repo.stash_save2(&signature, message, Some(flags)) {
+        Ok(_) => Ok(()),
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            Err(anyhow!("there are no local changes to stash"))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Apply the stash at `index` to the working tree, dropping it afterwards
+/// when `pop` is set
+fn git_stash_apply(workspace_path: &Path, index: usize, pop: bool) -> Result<()> {
+    let mut repo = Repository::open(workspace_path)?;
+    let mut options = StashApplyOptions::new();
+    let result = if pop {
+        repo.stash_pop(index, Some(&mut options))
+    } else {
+        repo.stash_apply(index, Some(&mut options))
+    };
+    result.map_err(|e| {
+        if e.code() == ErrorCode::Conflict {
+            anyhow!("applying the stash would overwrite local changes")
+        } else {
+            e.into()
+        }
+    })
+}
+
+fn git_stash_drop(workspace_path: &Path, index: usize) -> Result<()> {
+    let mut repo = Repository::open(workspace_path)?;
+    repo.stash_drop(index)?;
+    Ok(())
+}
+
+/// The stashes of the repository, whose files are kept in `stash_files`
+/// for the next refresh, which drops the ones of the stashes that are gone
+fn git_stashes(
+    repo: &mut Repository,
+    workspace_path: &Path,
+    stash_files: &mut HashMap<git2::Oid, Vec<PathBuf>>,
+) -> Vec<StashInfo> {
+    let mut stashes = Vec::new();
+    let _ = repo.stash_foreach(|index, message, id| {
+        stashes.push((index, message.to_string(), *id));
+        true
+    });
+
+    stash_files.retain(|id, _| stashes.iter().any(|(_, _, stash)| stash == id));
+    stashes
+        .into_iter()
+        .map(|(index, message, id)| StashInfo {
+            index,
+            message,
+            id: id.to_string(),
+            files: stash_files
+                .entry(id)
+                .or_insert_with(|| {
+                    git_stash_files(repo, workspace_path, id).unwrap_or_default()
+                })
+                .clone(),
+        })
+        .collect()
+}
+
+/// The files a stash changed, compared to the commit it was made on
+fn git_stash_files(
+    repo: &Repository,
+    workspace_path: &Path,
+    id: git2::Oid,
+) -> Result<Vec<PathBuf>> {
+    let commit = repo.find_commit(id)?;
+    let base = commit.parent(0)?.tree()?;
+    let mut files = Vec::new();
+    let diff = repo.diff_tree_to_tree(Some(&base), Some(&commit.tree()?), None)?;
+    // A stash keeps the untracked files it saved in a third parent
+    let untracked = match commit.parent(2) {
+        Ok(untracked) => {
+            Some(repo.diff_tree_to_tree(None, Some(&untracked.tree()?), None)?)
+        }
+        Err(_) => None,
+    };
+    for diff in std::iter::once(&diff).chain(untracked.as_ref()) {
+        for delta in diff.deltas() {
+            if let Some(path) = delta.new_file().path().or(delta.old_file().path()) {
+                files.push(workspace_path.join(path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Stage a conflicted file to mark it resolved, as long as there are no
+/// conflict markers left in it
+fn git_resolve_conflict(workspace_path: &Path, path: &Path) -> Result<()> {
+    let repo = Repository::open(workspace_path)?;
+    let relative = path.strip_prefix(workspace_path)?;
+    let mut index = repo.index()?;
+    // Index entries always use forward slashes
+    let entry_path = relative
+        .to_str()
+        .ok_or_else(|| anyhow!("path is not valid utf-8"))?
+        .replace('\\', "/");
+    let conflicted = index.conflicts()?.flatten().any(|conflict| {
+        [conflict.ancestor, conflict.our, conflict.their]
+            .iter()
+            .flatten()
+            .any(|entry| entry.path == entry_path.as_bytes())
+    });
+    if !conflicted {
+        return Ok(());
+    }
+
+    if path.exists() {
+        let content = fs::read_to_string(path)?;
+        if !find_conflicts(&Rope::from(content)).is_empty() {
+            return Err(anyhow!(
+                "{} still has conflict markers",
+                relative.display()
+            ));
+        }
+        index.add_path(relative)?;
+    } else {
+        // Resolved by deleting the file
+        index.remove_path(relative)?;
+    }
+    index.write()?;
     Ok(())
 }
 
+fn git_upstream_status(repo: &Repository, head: &str) -> Option<UpstreamStatus> {
+    let branch = repo.find_branch(head, BranchType::Local).ok()?;
+    let upstream = branch.upstream().ok()?;
+    let name = upstream.name().ok()??.to_string();
+    let (ahead, behind) = repo
+        .graph_ahead_behind(branch.get().target()?, upstream.get().target()?)
+        .ok()?;
+    Some(UpstreamStatus {
+        name,
+        ahead,
+        behind,
+    })
+}
+
 fn git_discard_files_changes<'a>(
     workspace_path: &Path,
     files: impl Iterator<Item = &'a Path>,
@@ -994,20 +2393,40 @@ fn git_delta_format(
             delta.new_file().id(),
             delta.new_file().path().map(|p| workspace_path.join(p))?,
         )),
+        git2::Delta::Conflicted => Some((
+            git2::Delta::Conflicted,
+            delta.new_file().id(),
+            delta.new_file().path().map(|p| workspace_path.join(p))?,
+        )),
         _ => None,
     }
 }
 
-fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
-    let repo = Repository::open(workspace_path.to_str()?).ok()?;
+fn git_diff_new(
+    workspace_path: &Path,
+    stash_files: &mut HashMap<git2::Oid, Vec<PathBuf>>,
+) -> Option<DiffInfo> {
+    let mut repo = Repository::open(workspace_path.to_str()?).ok()?;
+    let stashes = git_stashes(&mut repo, workspace_path, stash_files);
     let head = repo.head().ok()?;
     let name = head.shorthand()?.to_string();
 
     let mut branches = Vec::new();
-    for branch in repo.branches(None).ok()? {
+    for branch in repo.branches(Some(BranchType::Local)).ok()? {
         branches.push(branch.ok()?.0.name().ok()??.to_string());
     }
 
+    let mut remote_branches = Vec::new();
+    for branch in repo.branches(Some(BranchType::Remote)).ok()? {
+        let name = branch.ok()?.0.name().ok()??.to_string();
+        // Skip symbolic refs like `origin/HEAD`
+        if !name.ends_with("/HEAD") {
+            remote_branches.push(name);
+        }
+    }
+
+    let upstream = git_upstream_status(&repo, &name);
+
     let mut deltas = Vec::new();
     let mut diff_options = DiffOptions::new();
     let diff = repo
@@ -1048,6 +2467,15 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         }
     }
 
+    // A conflicted file shows up in both diffs
+    let mut conflicts: Vec<PathBuf> = deltas
+        .iter()
+        .filter(|delta| delta.0 == git2::Delta::Conflicted)
+        .map(|delta| delta.2.clone())
+        .collect();
+    conflicts.sort();
+    conflicts.dedup();
+
     let mut file_diffs = Vec::new();
     for (i, j) in renames.iter() {
         file_diffs.push(FileDiff::Renamed(
@@ -1076,19 +2504,38 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
     Some(DiffInfo {
         head: name,
         branches,
+        remote_branches,
+        upstream,
         diffs: file_diffs,
+        conflicts,
+        stashes,
     })
 }
 
-fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
+fn file_get_version(
+    workspace_path: &Path,
+    path: &Path,
+    version: &str,
+) -> Result<(String, String)> {
     let repo = Repository::open(
         workspace_path
             .to_str()
             .ok_or_else(|| anyhow!("can't to str"))?,
     )?;
-    let head = repo.head()?;
-    let tree = head.peel_to_tree()?;
-    let tree_entry = tree.get_path(path.strip_prefix(workspace_path)?)?;
+    let relative_path = path.strip_prefix(workspace_path)?;
+    let tree_entry = if version == "head" {
+        repo.head()?.peel_to_tree()?.get_path(relative_path)?
+    } else {
+        let commit = repo.revparse_single(version)?.peel_to_commit()?;
+        match commit.tree()?.get_path(relative_path) {
+            Ok(tree_entry) => tree_entry,
+            // A stash keeps the untracked files it saved in a third parent
+            Err(e) => match commit.parent(2) {
+                Ok(untracked) => untracked.tree()?.get_path(relative_path)?,
+                Err(_) => return Err(e.into()),
+            },
+        }
+    };
     let blob = repo.find_blob(tree_entry.id())?;
     let id = blob.id().to_string();
     let content = std::str::from_utf8(blob.content())
@@ -1096,3 +2543,466 @@ fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)>
         .to_string();
     Ok((id, content))
 }
+
+#[cfg(test)]
+mod tests {
+    use git2::{RepositoryInitOptions, Signature};
+
+    use super::*;
+
+    /// A scratch directory holding a bare remote and the clones of it
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("lapce-git-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn init_remote(dir: &Path) -> Url {
+        let path = dir.join("remote.git");
+        Repository::init_opts(
+            &path,
+            RepositoryInitOptions::new().bare(true).initial_head("main"),
+        )
+        .unwrap();
+        Url::from_file_path(path).unwrap()
+    }
+
+    fn clone(url: &Url, path: &Path) -> Repository {
+        let repo = Repository::clone(url.as_str(), path).unwrap();
+        // Cloning an empty remote doesn't tell which branch to start on
+        if repo.is_empty().unwrap() {
+            repo.set_head("refs/heads/main").unwrap();
+        }
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Lapce").unwrap();
+        config.set_str("user.email", "lapce@example.com").unwrap();
+        repo
+    }
+
+    fn commit_file(repo: &Repository, name: &str, content: &str) -> git2::Oid {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join(name), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Lapce", "lapce@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &format!("update {name}"),
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    fn head_id(repo: &Repository) -> git2::Oid {
+        repo.head().unwrap().target().unwrap()
+    }
+
+    #[test]
+    fn test_git_push_sets_upstream() {
+        let dir = TestDir::new("push");
+        let url = init_remote(&dir.0);
+        let local = clone(&url, &dir.0.join("local"));
+        let commit = commit_file(&local, "a.txt", "a");
+
+        let progress = GitProgress::begin(&CoreRpcHandler::new(), "test");
+        git_push(&dir.0.join("local"), &progress).unwrap();
+
+        let remote = Repository::open_bare(dir.0.join("remote.git")).unwrap();
+        let pushed = remote.find_reference("refs/heads/main").unwrap();
+        assert_eq!(pushed.target(), Some(commit));
+        let upstream = local
+            .find_branch("main", BranchType::Local)
+            .unwrap()
+            .upstream()
+            .unwrap();
+        assert_eq!(upstream.name().unwrap(), Some("origin/main"));
+    }
+
+    #[test]
+    fn test_git_branches() {
+        let dir = TestDir::new("branches");
+        let url = init_remote(&dir.0);
+        let path = dir.0.join("local");
+        let local = clone(&url, &path);
+        commit_file(&local, "a.txt", "a");
+        let progress = GitProgress::begin(&CoreRpcHandler::new(), "test");
+        git_push(&path, &progress).unwrap();
+
+        // A branch from a remote branch tracks it
+        git_create_branch(&path, "tracked", Some("origin/main"), false).unwrap();
+        let tracked = local.find_branch("tracked", BranchType::Local).unwrap();
+        assert_eq!(
+            tracked.upstream().unwrap().name().unwrap(),
+            Some("origin/main")
+        );
+        assert!(!tracked.is_head());
+
+        git_create_branch(&path, "feature", None, true).unwrap();
+        assert_eq!(local.head().unwrap().shorthand(), Some("feature"));
+        let feature = commit_file(&local, "b.txt", "b");
+        git_checkout(&path, "main").unwrap();
+
+        assert!(git_delete_branch(&path, "main", false).is_err());
+        assert!(git_delete_branch(&path, "feature", false).is_err());
+
+        git_rename_branch(&path, "feature", "topic").unwrap();
+        assert!(local.find_branch("feature", BranchType::Local).is_err());
+        let topic = local.find_branch("topic", BranchType::Local).unwrap();
+        assert_eq!(topic.get().target(), Some(feature));
+
+        // The upstream having the commits is enough, even when the checked
+        // out branch doesn't
+        local
+            .reference("refs/remotes/origin/topic", feature, true, "test")
+            .unwrap();
+        local
+            .find_branch("topic", BranchType::Local)
+            .unwrap()
+            .set_upstream(Some("origin/topic"))
+            .unwrap();
+        git_delete_branch(&path, "topic", false).unwrap();
+        assert!(local.find_branch("topic", BranchType::Local).is_err());
+
+        // Checking out a remote branch makes a local branch tracking it
+        local
+            .reference("refs/remotes/origin/dev", feature, true, "test")
+            .unwrap();
+        git_checkout(&path, "origin/dev").unwrap();
+        assert_eq!(local.head().unwrap().shorthand(), Some("dev"));
+        assert_eq!(head_id(&local), feature);
+        let dev = local.find_branch("dev", BranchType::Local).unwrap();
+        assert_eq!(dev.upstream().unwrap().name().unwrap(), Some("origin/dev"));
+
+        git_checkout(&path, "main").unwrap();
+        git_delete_branch(&path, "tracked", false).unwrap();
+        commit_file(&local, "c.txt", "c");
+        git_create_branch(&path, "unmerged", None, false).unwrap();
+        git_checkout(&path, "dev").unwrap();
+        assert!(git_delete_branch(&path, "unmerged", false).is_err());
+        git_delete_branch(&path, "unmerged", true).unwrap();
+    }
+
+    #[test]
+    fn test_git_pull_fast_forward() {
+        let dir = TestDir::new("pull-ff");
+        let url = init_remote(&dir.0);
+        let progress = GitProgress::begin(&CoreRpcHandler::new(), "test");
+
+        let local = clone(&url, &dir.0.join("local"));
+        commit_file(&local, "a.txt", "a");
+        git_push(&dir.0.join("local"), &progress).unwrap();
+
+        let other = clone(&url, &dir.0.join("other"));
+        let commit = commit_file(&other, "b.txt", "b");
+        git_push(&dir.0.join("other"), &progress).unwrap();
+
+        git_pull(&dir.0.join("local"), false, &progress).unwrap();
+        assert_eq!(head_id(&local), commit);
+        assert!(dir.0.join("local").join("b.txt").exists());
+    }
+
+    #[test]
+    fn test_git_pull_diverged() {
+        let dir = TestDir::new("pull-rebase");
+        let url = init_remote(&dir.0);
+        let progress = GitProgress::begin(&CoreRpcHandler::new(), "test");
+
+        let local = clone(&url, &dir.0.join("local"));
+        commit_file(&local, "a.txt", "a");
+        git_push(&dir.0.join("local"), &progress).unwrap();
+
+        let other = clone(&url, &dir.0.join("other"));
+        let remote_commit = commit_file(&other, "b.txt", "b");
+        git_push(&dir.0.join("other"), &progress).unwrap();
+
+        commit_file(&local, "c.txt", "c");
+        // Without rebasing the diverged branch is left alone
+        assert!(git_pull(&dir.0.join("local"), false, &progress).is_err());
+
+        git_pull(&dir.0.join("local"), true, &progress).unwrap();
+        let head = local.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_id(0).unwrap(), remote_commit);
+        assert!(dir.0.join("local").join("b.txt").exists());
+        assert!(dir.0.join("local").join("c.txt").exists());
+    }
+
+    #[test]
+    fn test_git_pull_conflict_aborts() {
+        let dir = TestDir::new("pull-conflict");
+        let url = init_remote(&dir.0);
+        let progress = GitProgress::begin(&CoreRpcHandler::new(), "test");
+
+        let local = clone(&url, &dir.0.join("local"));
+        commit_file(&local, "a.txt", "a");
+        git_push(&dir.0.join("local"), &progress).unwrap();
+
+        let other = clone(&url, &dir.0.join("other"));
+        commit_file(&other, "a.txt", "remote");
+        git_push(&dir.0.join("other"), &progress).unwrap();
+
+        let local_commit = commit_file(&local, "a.txt", "local");
+        let err = git_pull(&dir.0.join("local"), true, &progress).unwrap_err();
+        assert!(err.to_string().contains("a.txt"));
+        assert_eq!(local.state(), git2::RepositoryState::Clean);
+        assert_eq!(head_id(&local), local_commit);
+        assert_eq!(
+            std::fs::read_to_string(dir.0.join("local").join("a.txt")).unwrap(),
+            "local"
+        );
+    }
+
+    #[test]
+    fn test_git_fetch_updates_remote_branch() {
+        let dir = TestDir::new("fetch");
+        let url = init_remote(&dir.0);
+        let progress = GitProgress::begin(&CoreRpcHandler::new(), "test");
+
+        let local = clone(&url, &dir.0.join("local"));
+        let local_commit = commit_file(&local, "a.txt", "a");
+        git_push(&dir.0.join("local"), &progress).unwrap();
+
+        let other = clone(&url, &dir.0.join("other"));
+        let commit = commit_file(&other, "b.txt", "b");
+        git_push(&dir.0.join("other"), &progress).unwrap();
+
+        git_fetch(&dir.0.join("local"), &progress).unwrap();
+        let remote_branch = local
+            .find_branch("origin/main", BranchType::Remote)
+            .unwrap();
+        assert_eq!(remote_branch.get().target(), Some(commit));
+        // Fetching leaves the local branch where it was
+        assert_eq!(head_id(&local), local_commit);
+    }
+
+    #[test]
+    fn test_git_stash() {
+        let dir = TestDir::new("stash");
+        let url = init_remote(&dir.0);
+        let path = dir.0.join("local");
+        let local = clone(&url, &path);
+        commit_file(&local, "a.txt", "a\n");
+
+        std::fs::write(path.join("a.txt"), "changed\n").unwrap();
+        std::fs::write(path.join("b.txt"), "untracked\n").unwrap();
+        git_stash_save(&path, Some("wip"), true).unwrap();
+        assert_eq!(std::fs::read_to_string(path.join("a.txt")).unwrap(), "a\n");
+        assert!(!path.join("b.txt").exists());
+        // There's nothing left to stash
+        assert!(git_stash_save(&path, None, true).is_err());
+
+        let mut stash_files = HashMap::new();
+        let stashes = git_diff_new(&path, &mut stash_files).unwrap().stashes;
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].index, 0);
+        assert!(stashes[0].message.ends_with("wip"));
+        assert_eq!(
+            stashes[0].files,
+            vec![path.join("a.txt"), path.join("b.txt")]
+        );
+        let (_, content) =
+            file_get_version(&path, &path.join("b.txt"), &stashes[0].id).unwrap();
+        assert_eq!(content, "untracked\n");
+
+        git_stash_apply(&path, 0, true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path.join("a.txt")).unwrap(),
+            "changed\n"
+        );
+        assert!(path.join("b.txt").exists());
+        assert!(git_diff_new(&path, &mut stash_files)
+            .unwrap()
+            .stashes
+            .is_empty());
+        // The files of the stash that is gone are dropped
+        assert!(stash_files.is_empty());
+    }
+
+    #[test]
+    fn test_git_resolve_conflict() {
+        let dir = TestDir::new("conflict");
+        let url = init_remote(&dir.0);
+        let path = dir.0.join("local");
+        let local = clone(&url, &path);
+        let base = commit_file(&local, "a.txt", "a\n");
+
+        let base = local.find_commit(base).unwrap();
+        local.branch("feature", &base, false).unwrap();
+        commit_file(&local, "a.txt", "ours\n");
+        local.set_head("refs/heads/feature").unwrap();
+        local
+            .checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+        let theirs = commit_file(&local, "a.txt", "theirs\n");
+        local.set_head("refs/heads/main").unwrap();
+        local
+            .checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+
+        let theirs = local.find_annotated_commit(theirs).unwrap();
+        local.merge(&[&theirs], None, None).unwrap();
+        let diff = git_diff_new(&path, &mut HashMap::new()).unwrap();
+        assert_eq!(diff.conflicts, vec![path.join("a.txt")]);
+
+        // The markers are still there
+        assert!(git_resolve_conflict(&path, &path.join("a.txt")).is_err());
+
+        std::fs::write(path.join("a.txt"), "ours\ntheirs\n").unwrap();
+        git_resolve_conflict(&path, &path.join("a.txt")).unwrap();
+        let index = Repository::open(&path).unwrap().index().unwrap();
+        assert!(!index.has_conflicts());
+        assert!(git_diff_new(&path, &mut HashMap::new())
+            .unwrap()
+            .conflicts
+            .is_empty());
+    }
+
+    #[test]
+    fn test_git_commit_amend_and_sign_off() {
+        let dir = TestDir::new("commit-amend");
+        let url = init_remote(&dir.0);
+        let path = dir.0.join("local");
+        let local = clone(&url, &path);
+        let first = commit_file(&local, "a.txt", "a");
+
+        std::fs::write(path.join("b.txt"), "b").unwrap();
+        let diffs = vec![FileDiff::Added(path.join("b.txt"))];
+        git_commit(&path, "# a comment\nadd b\n", diffs, false, true).unwrap();
+        let head = local.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_id(0).unwrap(), first);
+        assert_eq!(
+            head.message().unwrap(),
+            "add b\n\nSigned-off-by: Lapce <lapce@example.com>\n"
+        );
+        assert_eq!(
+            git_commit_message(&path, true).unwrap(),
+            head.message().unwrap()
+        );
+
+        git_commit(&path, "add b and c", Vec::new(), true, false).unwrap();
+        let amended = local.head().unwrap().peel_to_commit().unwrap();
+        assert_ne!(amended.id(), head.id());
+        assert_eq!(amended.parent_id(0).unwrap(), first);
+        assert_eq!(amended.message().unwrap(), "add b and c\n");
+        assert_eq!(amended.tree_id(), head.tree_id());
+    }
+
+    #[test]
+    fn test_git_commit_message_template() {
+        let dir = TestDir::new("commit-template");
+        let url = init_remote(&dir.0);
+        let path = dir.0.join("local");
+        let local = clone(&url, &path);
+        assert_eq!(git_commit_message(&path, false).unwrap(), "");
+
+        std::fs::write(path.join("template.txt"), "feat: \n# explain why\n")
+            .unwrap();
+        local
+            .config()
+            .unwrap()
+            .set_str("commit.template", "template.txt")
+            .unwrap();
+        assert_eq!(
+            git_commit_message(&path, false).unwrap(),
+            "feat: \n# explain why\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_git_commit_hooks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TestDir::new("commit-hooks");
+        let url = init_remote(&dir.0);
+        let path = dir.0.join("local");
+        let local = clone(&url, &path);
+        let first = commit_file(&local, "a.txt", "a");
+        let hooks = local.path().join("hooks");
+        std::fs::create_dir_all(&hooks).unwrap();
+        let write_hook = |name: &str, script: &str| {
+            let hook = hooks.join(name);
+            std::fs::write(&hook, script).unwrap();
+            std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))
+                .unwrap();
+        };
+
+        write_hook("pre-commit", "#!/bin/sh\necho 'lint failed'\nexit 1\n");
+        std::fs::write(path.join("b.txt"), "b").unwrap();
+        let diffs = vec![FileDiff::Added(path.join("b.txt"))];
+        let err = git_commit(&path, "add b", diffs.clone(), false, false)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "the pre-commit hook failed:\nlint failed");
+        assert_eq!(head_id(&local), first);
+
+        write_hook("pre-commit", "#!/bin/sh\nexit 0\n");
+        write_hook("commit-msg", "#!/bin/sh\necho 'Change-Id: 1' >> \"$1\"\n");
+        git_commit(&path, "add b", diffs, false, false).unwrap();
+        let head = local.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message().unwrap(), "add b\nChange-Id: 1\n");
+    }
+
+    #[test]
+    fn test_watched_file_events() {
+        use notify::event::{
+            CreateKind, DataChange, EventKind, ModifyKind, RenameMode,
+        };
+
+        let dir = std::env::temp_dir();
+        let uri = |name: &str| Url::from_file_path(dir.join(name)).unwrap();
+
+        let event = notify::Event::new(EventKind::Create(CreateKind::File))
+            .add_path(dir.join("a.rs"))
+            .add_path(dir.join(".git").join("index"));
+        assert_eq!(
+            watched_file_events(&event),
+            vec![FileEvent::new(uri("a.rs"), FileChangeType::CREATED)]
+        );
+
+        let event = notify::Event::new(EventKind::Modify(ModifyKind::Name(
+            RenameMode::Both,
+        )))
+        .add_path(dir.join("a.rs"))
+        .add_path(dir.join("b.rs"));
+        assert_eq!(
+            watched_file_events(&event),
+            vec![
+                FileEvent::new(uri("a.rs"), FileChangeType::DELETED),
+                FileEvent::new(uri("b.rs"), FileChangeType::CREATED),
+            ]
+        );
+
+        let event = notify::Event::new(EventKind::Modify(ModifyKind::Data(
+            DataChange::Content,
+        )))
+        .add_path(dir.join("b.rs"));
+        assert_eq!(
+            watched_file_events(&event),
+            vec![FileEvent::new(uri("b.rs"), FileChangeType::CHANGED)]
+        );
+
+        let event =
+            notify::Event::new(EventKind::Access(notify::event::AccessKind::Any))
+                .add_path(dir.join("b.rs"));
+        assert!(watched_file_events(&event).is_empty());
+    }
+}
@@ -0,0 +1,391 @@
+//! Reads the `.editorconfig` files that apply to a file, following
+//! <https://spec.editorconfig.org>
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobMatcher};
+use lapce_rpc::buffer::{
+    Charset, EditorConfigIndentStyle, EditorConfigProperties, LineEnding,
+};
+
+struct Section {
+    glob: Option<GlobMatcher>,
+    /// A name without a slash matches the file name in any subdirectory
+    file_name_only: bool,
+    properties: Vec<(String, String)>,
+}
+
+impl Section {
+    fn new(name: &str) -> Self {
+        let file_name_only = !name.contains('/');
+        let name = name.strip_prefix('/').unwrap_or(name);
+        let glob = GlobBuilder::new(&glob(name))
+            .literal_separator(true)
+            .build()
+            .ok()
+            .map(|glob| glob.compile_matcher());
+        Self {
+            glob,
+            file_name_only,
+            properties: Vec::new(),
+        }
+    }
+
+    /// Whether the section is for the path relative to the directory of the
+    /// `.editorconfig` file
+    fn matches(&self, relative_path: &Path) -> bool {
+        let path = if self.file_name_only {
+            match relative_path.file_name() {
+                Some(file_name) => Path::new(file_name),
+                None => return false,
+            }
+        } else {
+            relative_path
+        };
+        self.glob
+            .as_ref()
+            .map(|glob| glob.is_match(path))
+            .unwrap_or(false)
+    }
+}
+
+/// A parsed `.editorconfig` file
+struct EditorConfigFile {
+    /// Whether the files in the parent directories are ignored
+    root: bool,
+    sections: Vec<Section>,
+}
+
+impl EditorConfigFile {
+    fn parse(content: &str) -> Self {
+        let mut root = false;
+        let mut sections: Vec<Section> = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                sections.push(Section::new(&line[1..line.len() - 1]));
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim().to_lowercase();
+                let value = value.trim().to_string();
+                match sections.last_mut() {
+                    Some(section) => section.properties.push((key, value)),
+                    None if key == "root" => {
+                        root = value.eq_ignore_ascii_case("true")
+                    }
+                    None => {}
+                }
+            }
+        }
+        Self { root, sections }
+    }
+}
+
+/// Turns a section name into a globset glob, where `*` doesn't match a
+/// slash but `**` does, unless it is in the middle of a name
+fn glob(name: &str) -> String {
+    let name = expand_numeric_ranges(name);
+    let mut glob = String::new();
+    let mut chars = name.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                glob.push(c);
+                if let Some(c) = chars.next() {
+                    glob.push(c);
+                }
+            }
+            '[' => {
+                in_class = true;
+                glob.push(c);
+            }
+            ']' => {
+                in_class = false;
+                glob.push(c);
+            }
+            '*' if !in_class && chars.peek() == Some(&'*') => {
+                chars.next();
+                let next = chars.peek().copied();
+                // globset only lets a `**` that is a whole name cross
+                // slashes, so the rest of the name goes after a slash, and
+                // one in the middle of a name is left a `*`
+                if glob.is_empty() || glob.ends_with('/') {
+                    glob.push_str("**");
+                    if next.is_some() && next != Some('/') {
+                        glob.push_str("/*");
+                    }
+                } else if next == Some('/') {
+                    glob.push_str("*/**");
+                } else {
+                    glob.push('*');
+                }
+            }
+            c => glob.push(c),
+        }
+    }
+    glob
+}
+
+/// Expands the `{num1..num2}` ranges into alternatives, which globset
+/// doesn't know about
+fn expand_numeric_ranges(name: &str) -> String {
+    let mut glob = String::new();
+    let mut rest = name;
+    while let Some(start) = rest.find('{') {
+        glob.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+        let range = rest[1..end].split_once("..").and_then(|(from, to)| {
+            Some((from.parse::<i64>().ok()?, to.parse::<i64>().ok()?))
+        });
+        match range {
+            Some((from, to)) if from <= to && to - from <= 1000 => {
+                let numbers = (from..=to).map(|n| n.to_string()).collect::<Vec<_>>();
+                glob.push('{');
+                glob.push_str(&numbers.join(","));
+                glob.push('}');
+            }
+            _ => glob.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    glob.push_str(rest);
+    glob
+}
+
+/// The `.editorconfig` properties of the file, where the closer files and
+/// the later sections win
+pub fn editorconfig_properties(path: &Path) -> EditorConfigProperties {
+    let mut files: Vec<(PathBuf, EditorConfigFile)> = Vec::new();
+    for dir in path.ancestors().skip(1) {
+        if let Ok(content) = fs::read_to_string(dir.join(".editorconfig")) {
+            let file = EditorConfigFile::parse(&content);
+            let root = file.root;
+            files.push((dir.to_path_buf(), file));
+            if root {
+                break;
+            }
+        }
+    }
+
+    let mut values = HashMap::new();
+    for (dir, file) in files.iter().rev() {
+        let relative_path = match path.strip_prefix(dir) {
+            Ok(relative_path) => relative_path,
+            Err(_) => continue,
+        };
+        for section in file.sections.iter() {
+            if section.matches(relative_path) {
+                for (key, value) in section.properties.iter() {
+                    values.insert(key.clone(), value.to_lowercase());
+                }
+            }
+        }
+    }
+    values.retain(|_, value| value != "unset");
+
+    resolve_properties(&values)
+}
+
+fn resolve_properties(values: &HashMap<String, String>) -> EditorConfigProperties {
+    let value = |key: &str| values.get(key).map(|value| value.as_str());
+    let number = |key: &str| value(key).and_then(|value| value.parse().ok());
+    let boolean = |key: &str| match value(key) {
+        Some("true") => Some(true),
+        Some("false") => Some(false),
+        _ => None,
+    };
+
+    let indent_style = match value("indent_style") {
+        Some("tab") => Some(EditorConfigIndentStyle::Tab),
+        Some("space") => Some(EditorConfigIndentStyle::Space),
+        _ => None,
+    };
+    let tab_width: Option<usize> = number("tab_width");
+    let indent_size = match value("indent_size") {
+        Some("tab") => tab_width,
+        Some(_) => number("indent_size"),
+        None if indent_style == Some(EditorConfigIndentStyle::Tab) => tab_width,
+        None => None,
+    };
+
+    EditorConfigProperties {
+        indent_style,
+        indent_size,
+        tab_width: tab_width.or(indent_size),
+        end_of_line: match value("end_of_line") {
+            Some("lf") => Some(LineEnding::Lf),
+            Some("crlf") => Some(LineEnding::CrLf),
+            Some("cr") => Some(LineEnding::Cr),
+            _ => None,
+        },
+        charset: match value("charset") {
+            Some("latin1") => Some(Charset::Latin1),
+            Some("utf-8") => Some(Charset::Utf8),
+            Some("utf-8-bom") => Some(Charset::Utf8Bom),
+            Some("utf-16be") => Some(Charset::Utf16Be),
+            Some("utf-16le") => Some(Charset::Utf16Le),
+            _ => None,
+        },
+        trim_trailing_whitespace: boolean("trim_trailing_whitespace"),
+        insert_final_newline: boolean("insert_final_newline"),
+        max_line_length: number("max_line_length"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch directory holding the `.editorconfig` files of a test
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("lapce-editorconfig-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, dir: &str, content: &str) {
+            let dir = self.0.join(dir);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(".editorconfig"), content).unwrap();
+        }
+
+        fn properties(&self, path: &str) -> EditorConfigProperties {
+            editorconfig_properties(&self.0.join(path))
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_editorconfig_sections() {
+        let dir = TestDir::new("sections");
+        dir.write(
+            "",
+            r#"
+root = true
+
+[*]
+indent_style = space
+indent_size = 4
+end_of_line = lf
+insert_final_newline = true
+
+# Makefiles need tabs
+[Makefile]
+indent_style = tab
+tab_width = 8
+
+[src/**.{rs,toml}]
+max_line_length = 85
+Trim_Trailing_Whitespace = TRUE
+
+[test{1..3}.md]
+insert_final_newline = unset
+"#,
+        );
+
+        let rust = dir.properties("src/plugin/lsp.rs");
+        assert_eq!(rust.indent_style, Some(EditorConfigIndentStyle::Space));
+        assert_eq!(rust.indent_size, Some(4));
+        assert_eq!(rust.tab_width, Some(4));
+        assert_eq!(rust.end_of_line, Some(LineEnding::Lf));
+        assert_eq!(rust.max_line_length, Some(85));
+        assert_eq!(rust.trim_trailing_whitespace, Some(true));
+
+        let makefile = dir.properties("build/Makefile");
+        assert_eq!(makefile.indent_style, Some(EditorConfigIndentStyle::Tab));
+        assert_eq!(makefile.indent_size, Some(4));
+        assert_eq!(makefile.tab_width, Some(8));
+        assert_eq!(makefile.max_line_length, None);
+
+        assert_eq!(dir.properties("test2.md").insert_final_newline, None);
+        assert_eq!(dir.properties("test4.md").insert_final_newline, Some(true));
+    }
+
+    #[test]
+    fn test_editorconfig_path_sections() {
+        let dir = TestDir::new("path-sections");
+        dir.write(
+            "",
+            "root = true\n[lib/*.js]\nindent_size = 2\n[docs/**]\ncharset = utf-8\n",
+        );
+
+        // A `*` doesn't go into subdirectories, and may match nothing
+        assert_eq!(dir.properties("lib/main.js").indent_size, Some(2));
+        assert_eq!(dir.properties("lib/.js").indent_size, Some(2));
+        assert_eq!(dir.properties("lib/sub/deep.js").indent_size, None);
+        assert_eq!(dir.properties("main.js").indent_size, None);
+
+        assert_eq!(dir.properties("docs/a/b/c.md").charset, Some(Charset::Utf8));
+        assert_eq!(dir.properties("lib/c.md").charset, None);
+
+        // The section of a nested file is relative to its directory
+        dir.write("app", "[src/*.rs]\nmax_line_length = 85\n");
+        assert_eq!(dir.properties("app/src/main.rs").max_line_length, Some(85));
+        assert_eq!(dir.properties("app/src/bin/a.rs").max_line_length, None);
+        assert_eq!(dir.properties("src/main.rs").max_line_length, None);
+    }
+
+    #[test]
+    fn test_editorconfig_indent_size_tab() {
+        let dir = TestDir::new("indent-size-tab");
+        dir.write("", "root = true\n[*]\nindent_style = tab\ntab_width = 2\n");
+        let properties = dir.properties("main.c");
+        assert_eq!(properties.indent_size, Some(2));
+        assert_eq!(properties.tab_width, Some(2));
+    }
+
+    #[test]
+    fn test_editorconfig_nested_files() {
+        let dir = TestDir::new("nested");
+        dir.write("", "[*]\nmax_line_length = 100\ncharset = latin1\n");
+        dir.write(
+            "project",
+            "root = true\n[*]\nindent_size = 4\ninsert_final_newline = true\n",
+        );
+        dir.write(
+            "project/src",
+            "[*.rs]\nindent_size = 2\ninsert_final_newline = unset\n",
+        );
+
+        // The nearest file wins, and nothing above the root file is read
+        let rust = dir.properties("project/src/main.rs");
+        assert_eq!(rust.indent_size, Some(2));
+        assert_eq!(rust.insert_final_newline, None);
+        assert_eq!(rust.max_line_length, None);
+        assert_eq!(rust.charset, None);
+
+        let toml = dir.properties("project/src/config.toml");
+        assert_eq!(toml.indent_size, Some(4));
+        assert_eq!(toml.insert_final_newline, Some(true));
+
+        // A section of a file in a parent directory matches relative to it
+        let outside = dir.properties("other/main.rs");
+        assert_eq!(outside.max_line_length, Some(100));
+        assert_eq!(outside.indent_size, None);
+    }
+}
@@ -0,0 +1,251 @@
+//! The files of the workspace for the file palette, which are walked once
+//! and then kept up to date with the changes the workspace watcher sees.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use ignore::WalkBuilder;
+
+#[derive(Default)]
+pub struct FileIndex {
+    /// The files that aren't ignored, which is `None` until the first walk
+    /// of the workspace is done
+    files: Option<BTreeSet<PathBuf>>,
+    /// The changes that came in while the workspace was being walked
+    changed: Vec<FileChanges>,
+}
+
+/// A batch of paths that were created, removed or renamed, with the files
+/// under them as they are on disk now
+pub struct FileChanges {
+    paths: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+impl FileChanges {
+    /// Reads the files under the changed paths of the workspace, which is
+    /// meant to be done away from the lock of the index. Each directory a
+    /// changed path is in is listed once for the whole batch, to tell which
+    /// paths are ignored.
+    pub fn scan(workspace: &Path, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let paths: BTreeSet<PathBuf> = paths
+            .into_iter()
+            .filter(|path| path.starts_with(workspace))
+            .filter(|path| !path.components().any(|c| c.as_os_str() == ".git"))
+            .collect();
+
+        // A path in a changed directory is covered by the directory
+        let mut changed: Vec<PathBuf> = Vec::new();
+        for path in paths {
+            if !changed
+                .last()
+                .map(|dir| path.starts_with(dir))
+                .unwrap_or(false)
+            {
+                changed.push(path);
+            }
+        }
+
+        let mut visible: HashMap<PathBuf, HashSet<PathBuf>> = HashMap::new();
+        let mut files = Vec::new();
+        for path in changed.iter() {
+            if path.exists() && is_visible(workspace, path, &mut visible) {
+                files.extend(walk_files(path));
+            }
+        }
+
+        Self {
+            paths: changed,
+            files,
+        }
+    }
+}
+
+impl FileIndex {
+    /// Walks the workspace, which is meant to be done away from the lock of
+    /// the index so that the changes keep coming in meanwhile
+    pub fn walk(workspace: &Path) -> BTreeSet<PathBuf> {
+        walk_files(workspace).collect()
+    }
+
+    /// Sets the files of the first walk, and applies the changes that were
+    /// made during it
+    pub fn set_files(&mut self, files: BTreeSet<PathBuf>) {
+        self.files = Some(files);
+        for changes in std::mem::take(&mut self.changed) {
+            self.update(changes);
+        }
+    }
+
+    pub fn files(&self) -> Option<Vec<PathBuf>> {
+        self.files
+            .as_ref()
+            .map(|files| files.iter().cloned().collect())
+    }
+
+    /// Replaces the files under the changed paths with the ones on disk
+    pub fn update(&mut self, changes: FileChanges) {
+        let files = match self.files.as_mut() {
+            Some(files) => files,
+            None => {
+                self.changed.push(changes);
+                return;
+            }
+        };
+
+        // A directory takes the files in it along
+        for path in changes.paths.iter() {
+            let removed: Vec<PathBuf> = files
+                .range(path.to_path_buf()..)
+                .take_while(|file| file.starts_with(path))
+                .cloned()
+                .collect();
+            for file in removed {
+                files.remove(&file);
+            }
+        }
+        files.extend(changes.files);
+    }
+}
+
+fn walk_files(path: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkBuilder::new(path)
+        .build()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_type()
+                .map(|file_type| file_type.is_file())
+                .unwrap_or(false)
+        })
+        .map(|entry| entry.into_path())
+}
+
+/// Whether a walk of the workspace gets to the path, which it does when
+/// neither the path nor any directory above it is ignored or hidden. The
+/// directories are checked from the workspace down, so that the ones in an
+/// ignored directory aren't listed.
+fn is_visible(
+    workspace: &Path,
+    path: &Path,
+    visible: &mut HashMap<PathBuf, HashSet<PathBuf>>,
+) -> bool {
+    let mut ancestors: Vec<&Path> = path
+        .ancestors()
+        .take_while(|ancestor| *ancestor != workspace)
+        .collect();
+    ancestors.reverse();
+    ancestors
+        .into_iter()
+        .all(|ancestor| match ancestor.parent() {
+            Some(parent) => visible
+                .entry(parent.to_path_buf())
+                .or_insert_with(|| visible_entries(parent))
+                .contains(ancestor),
+            None => false,
+        })
+}
+
+/// The entries of the directory that the ignore files, or being hidden,
+/// don't keep out of a walk of it
+fn visible_entries(dir: &Path) -> HashSet<PathBuf> {
+    WalkBuilder::new(dir)
+        .max_depth(Some(1))
+        .build()
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_file_index_update() {
+        let dir = std::env::temp_dir()
+            .join(format!("lapce-file-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join(".ignore"), "target\n").unwrap();
+        fs::write(dir.join("src/main.rs"), "").unwrap();
+
+        let mut index = FileIndex::default();
+        // A change before the first walk is done is applied after it
+        fs::write(dir.join("src/lib.rs"), "").unwrap();
+        index.update(FileChanges::scan(&dir, [dir.join("src/lib.rs")]));
+        assert_eq!(index.files(), None);
+        index.set_files(FileIndex::walk(&dir));
+        assert_eq!(
+            index.files().unwrap(),
+            vec![dir.join("src/lib.rs"), dir.join("src/main.rs")]
+        );
+
+        // The events of a batch, with ones inside a new directory
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("target/out.rs"), "").unwrap();
+        fs::create_dir_all(dir.join("tests")).unwrap();
+        fs::write(dir.join("tests/a.rs"), "").unwrap();
+        index.update(FileChanges::scan(
+            &dir,
+            [
+                dir.join("target"),
+                dir.join("target/out.rs"),
+                dir.join("tests/a.rs"),
+                dir.join("tests"),
+            ],
+        ));
+        assert_eq!(
+            index.files().unwrap(),
+            vec![
+                dir.join("src/lib.rs"),
+                dir.join("src/main.rs"),
+                dir.join("tests/a.rs")
+            ]
+        );
+
+        fs::remove_dir_all(dir.join("src")).unwrap();
+        index.update(FileChanges::scan(
+            &dir,
+            [dir.join("src"), dir.join("src/lib.rs")],
+        ));
+        assert_eq!(index.files().unwrap(), vec![dir.join("tests/a.rs")]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_index_update_in_ignored_dir() {
+        let dir = std::env::temp_dir()
+            .join(format!("lapce-file-index-ignored-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("target/debug")).unwrap();
+        fs::create_dir_all(dir.join(".hidden/sub")).unwrap();
+        fs::create_dir_all(dir.join("src/bin")).unwrap();
+        fs::write(dir.join(".ignore"), "target/\n").unwrap();
+
+        let mut index = FileIndex::default();
+        index.set_files(FileIndex::walk(&dir));
+        assert_eq!(index.files().unwrap(), Vec::<PathBuf>::new());
+
+        // The events only name the files, deep in directories that were there
+        fs::write(dir.join("target/debug/out.rlib"), "").unwrap();
+        fs::write(dir.join(".hidden/sub/a.rs"), "").unwrap();
+        fs::write(dir.join("src/bin/main.rs"), "").unwrap();
+        index.update(FileChanges::scan(
+            &dir,
+            [
+                dir.join("target/debug/out.rlib"),
+                dir.join(".hidden/sub/a.rs"),
+                dir.join("src/bin/main.rs"),
+            ],
+        ));
+        assert_eq!(index.files().unwrap(), vec![dir.join("src/bin/main.rs")]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
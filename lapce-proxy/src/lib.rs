@@ -1,6 +1,8 @@
 pub mod buffer;
 pub mod directory;
 pub mod dispatch;
+pub mod editorconfig;
+pub mod file_index;
 pub mod plugin;
 pub mod terminal;
 pub mod watcher;
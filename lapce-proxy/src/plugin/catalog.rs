@@ -15,8 +15,9 @@
     RpcError,
 };
 use lsp_types::{
-    notification::DidOpenTextDocument, DidOpenTextDocumentParams, SemanticTokens,
-    TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
+    notification::DidOpenTextDocument, DidOpenTextDocumentParams, FileEvent,
+    SemanticTokens, TextDocumentIdentifier, TextDocumentItem,
+    VersionedTextDocumentIdentifier,
 };
 use parking_lot::Mutex;
 use psp_types::Notification;
@@ -253,6 +254,12 @@ pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
                 path.clone(),
                 true,
             );
+            if let Some(path) = path.clone() {
+                plugin.handle_rpc(PluginServerRpc::PullDiagnostics {
+                    language_id: language_id.clone(),
+                    path,
+                });
+            }
         }
     }
 
@@ -273,6 +280,14 @@ pub fn handle_did_save_text_document(
         }
     }
 
+    pub fn handle_did_change_watched_files(&mut self, events: Vec<FileEvent>) {
+        for (_, plugin) in self.plugins.iter() {
+            plugin.handle_rpc(PluginServerRpc::DidChangeWatchedFiles {
+                events: events.clone(),
+            });
+        }
+    }
+
     pub fn handle_did_change_text_document(
         &mut self,
         language_id: String,
@@ -331,17 +346,23 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     self.plugin_rpc.proxy_rpc.get_open_files_content()
                 {
                     for item in items {
-                        let language_id = Some(item.language_id.clone());
+                        let language_id = item.language_id.clone();
                         let path = item.uri.to_file_path().ok();
                         plugin.server_notification(
                             DidOpenTextDocument::METHOD,
                             DidOpenTextDocumentParams {
                                 text_document: item,
                             },
-                            language_id,
-                            path,
+                            Some(language_id.clone()),
+                            path.clone(),
                             true,
                         );
+                        if let Some(path) = path {
+                            plugin.handle_rpc(PluginServerRpc::PullDiagnostics {
+                                language_id,
+                                path,
+                            });
+                        }
                     }
                 }
                 self.plugins.insert(plugin.plugin_id, plugin);
@@ -64,6 +64,14 @@ fn method_registered(&mut self, method: &'static str) -> bool {
         self.host.method_registered(method)
     }
 
+    fn request_params(
+        &mut self,
+        method: &'static str,
+        params: Params,
+    ) -> Option<Params> {
+        self.host.request_params(method, params)
+    }
+
     fn document_supported(
         &mut self,
         lanaguage_id: Option<&str>,
@@ -142,6 +150,14 @@ fn format_semantic_tokens(
     ) {
         self.host.format_semantic_tokens(tokens, text, f);
     }
+
+    fn handle_did_change_watched_files(&self, events: Vec<FileEvent>) {
+        self.host.handle_did_change_watched_files(events);
+    }
+
+    fn handle_pull_diagnostics(&self, language_id: String, path: PathBuf) {
+        self.host.pull_document_diagnostics(&language_id, &path);
+    }
 }
 
 impl LspClient {
@@ -235,6 +251,7 @@ fn new(
             workspace.clone(),
             pwd,
             volt_id,
+            options.clone(),
             document_selector,
             server_rpc.clone(),
             plugin_rpc.clone(),
@@ -309,9 +326,33 @@ fn initialize(&mut self) {
                     ]),
                     ..Default::default()
                 }),
+                signature_help: Some(SignatureHelpClientCapabilities {
+                    signature_information: Some(SignatureInformationSettings {
+                        documentation_format: Some(vec![
+                            MarkupKind::Markdown,
+                            MarkupKind::PlainText,
+                        ]),
+                        parameter_information: Some(ParameterInformationSettings {
+                            label_offset_support: Some(true),
+                        }),
+                        active_parameter_support: Some(true),
+                    }),
+                    context_support: Some(true),
+                    ..Default::default()
+                }),
                 inlay_hint: Some(InlayHintClientCapabilities {
                     ..Default::default()
                 }),
+                folding_range: Some(FoldingRangeClientCapabilities {
+                    line_folding_only: Some(true),
+                    ..Default::default()
+                }),
+                code_lens: Some(CodeLensClientCapabilities {
+                    dynamic_registration: Some(false),
+                }),
+                document_highlight: Some(DocumentHighlightClientCapabilities {
+                    dynamic_registration: Some(false),
+                }),
                 code_action: Some(CodeActionClientCapabilities {
                     code_action_literal_support: Some(CodeActionLiteralSupport {
                         code_action_kind: CodeActionKindLiteralSupport {
@@ -330,9 +371,14 @@ fn initialize(&mut self) {
                                 CodeActionKind::SOURCE_ORGANIZE_IMPORTS
                                     .as_str()
                                     .to_string(),
+                                "source.fixAll".to_string(),
                             ],
                         },
                     }),
+                    data_support: Some(true),
+                    resolve_support: Some(CodeActionCapabilityResolveSupport {
+                        properties: vec!["edit".to_string()],
+                    }),
                     ..Default::default()
                 }),
                 semantic_tokens: Some(SemanticTokensClientCapabilities {
@@ -344,6 +390,36 @@ fn initialize(&mut self) {
                     link_support: Some(false),
                     ..Default::default()
                 }),
+                implementation: Some(GotoCapability {
+                    link_support: Some(false),
+                    ..Default::default()
+                }),
+                declaration: Some(GotoCapability {
+                    link_support: Some(false),
+                    ..Default::default()
+                }),
+                call_hierarchy: Some(CallHierarchyClientCapabilities {
+                    dynamic_registration: Some(false),
+                }),
+                range_formatting: Some(DocumentRangeFormattingClientCapabilities {
+                    dynamic_registration: Some(false),
+                }),
+                on_type_formatting: Some(
+                    DocumentOnTypeFormattingClientCapabilities {
+                        dynamic_registration: Some(false),
+                    },
+                ),
+                publish_diagnostics: Some(PublishDiagnosticsClientCapabilities {
+                    related_information: Some(true),
+                    tag_support: Some(TagSupport {
+                        value_set: vec![
+                            DiagnosticTag::UNNECESSARY,
+                            DiagnosticTag::DEPRECATED,
+                        ],
+                    }),
+                    code_description_support: Some(true),
+                    ..Default::default()
+                }),
                 ..Default::default()
             }),
             window: Some(WindowClientCapabilities {
@@ -353,13 +429,29 @@ fn initialize(&mut self) {
                         additional_properties_support: Some(true),
                     }),
                 }),
+                show_document: Some(ShowDocumentClientCapabilities {
+                    support: true,
+                }),
                 ..Default::default()
             }),
             workspace: Some(WorkspaceClientCapabilities {
                 symbol: Some(WorkspaceSymbolClientCapabilities {
                     ..Default::default()
                 }),
-                configuration: Some(false),
+                configuration: Some(true),
+                workspace_folders: Some(true),
+                did_change_watched_files: Some(
+                    DidChangeWatchedFilesClientCapabilities {
+                        dynamic_registration: Some(true),
+                    },
+                ),
+                apply_edit: Some(true),
+                code_lens: Some(CodeLensWorkspaceClientCapabilities {
+                    refresh_support: Some(true),
+                }),
+                execute_command: Some(DynamicRegistrationClientCapabilities {
+                    dynamic_registration: Some(false),
+                }),
                 ..Default::default()
             }),
 
@@ -386,6 +478,24 @@ fn initialize(&mut self) {
             locale: None,
             root_path: None,
         };
+        // lsp-types doesn't have the type hierarchy and pull diagnostics
+        // capabilities yet
+        let mut params = serde_json::to_value(params).unwrap();
+        if let Some(text_document) = params.pointer_mut("/capabilities/textDocument")
+        {
+            text_document["typeHierarchy"] = json!({
+                "dynamicRegistration": false,
+            });
+            text_document["diagnostic"] = json!({
+                "dynamicRegistration": true,
+                "relatedDocumentSupport": true,
+            });
+        }
+        if let Some(workspace) = params.pointer_mut("/capabilities/workspace") {
+            workspace["diagnostics"] = json!({
+                "refreshSupport": true,
+            });
+        }
         if let Ok(value) = self.server_rpc.server_request(
             Initialize::METHOD,
             params,
@@ -393,6 +503,13 @@ fn initialize(&mut self) {
             None,
             false,
         ) {
+            self.host.type_hierarchy_provider = value
+                .pointer("/capabilities/typeHierarchyProvider")
+                .map(|provider| !provider.is_null() && provider != &json!(false))
+                .unwrap_or(false);
+            self.host.diagnostic_provider = value
+                .pointer("/capabilities/diagnosticProvider")
+                .and_then(|provider| serde_json::from_value(provider.clone()).ok());
             let result: InitializeResult = serde_json::from_value(value).unwrap();
             self.host.server_capabilities = result.capabilities;
             self.server_rpc.server_notification(
@@ -402,6 +519,7 @@ fn initialize(&mut self) {
                 None,
                 false,
             );
+            self.host.pull_workspace_diagnostics();
             if self
                 .plugin_rpc
                 .plugin_server_loaded(self.server_rpc.clone())
@@ -1,34 +1,48 @@
 pub mod catalog;
+pub mod diagnostic;
 pub mod lsp;
+pub mod palette;
 pub mod psp;
+pub mod type_hierarchy;
 pub mod wasi;
 
 use anyhow::{anyhow, Result};
 use crossbeam_channel::{Receiver, Sender};
 use dyn_clone::DynClone;
+use lapce_rpc::buffer::{EditorConfigIndentStyle, EditorConfigProperties};
 use lapce_rpc::core::CoreRpcHandler;
-use lapce_rpc::plugin::{PluginId, VoltInfo, VoltMetadata};
+use lapce_rpc::plugin::{PluginId, PluginPaletteItem, VoltInfo, VoltMetadata};
 use lapce_rpc::proxy::ProxyRpcHandler;
 use lapce_rpc::style::LineStyle;
 use lapce_rpc::{RequestId, RpcError};
 use lsp_types::request::{
-    CodeActionRequest, Completion, DocumentSymbolRequest, Formatting,
-    GotoDefinition, GotoTypeDefinition, GotoTypeDefinitionParams,
-    GotoTypeDefinitionResponse, HoverRequest, InlayHintRequest,
-    PrepareRenameRequest, References, Rename, Request, ResolveCompletionItem,
-    SelectionRangeRequest, SemanticTokensFullRequest, WorkspaceSymbol,
+    CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare,
+    CodeActionRequest, CodeActionResolveRequest, CodeLensRequest, CodeLensResolve,
+    Completion, DocumentHighlightRequest, DocumentSymbolRequest, ExecuteCommand,
+    FoldingRangeRequest, Formatting, GotoDeclaration, GotoDefinition,
+    GotoImplementation, GotoTypeDefinition, GotoTypeDefinitionParams,
+    GotoTypeDefinitionResponse, HoverRequest, InlayHintRequest, OnTypeFormatting,
+    PrepareRenameRequest, RangeFormatting, References, Rename, Request,
+    ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullRequest,
+    SignatureHelpRequest, WorkspaceSymbol,
 };
 use lsp_types::{
-    CodeActionContext, CodeActionParams, CodeActionResponse, CompletionItem,
-    CompletionParams, CompletionResponse, DocumentFormattingParams,
-    DocumentSymbolParams, DocumentSymbolResponse, FormattingOptions,
-    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverParams, InlayHint,
-    InlayHintParams, Location, PartialResultParams, Position, PrepareRenameResponse,
-    Range, ReferenceContext, ReferenceParams, RenameParams, SelectionRange,
-    SelectionRangeParams, SemanticTokens, SemanticTokensParams, SymbolInformation,
-    TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams, TextEdit,
-    Url, VersionedTextDocumentIdentifier, WorkDoneProgressParams, WorkspaceEdit,
-    WorkspaceSymbolParams,
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyPrepareParams, CodeAction,
+    CodeActionContext, CodeActionKind, CodeActionParams, CodeActionResponse,
+    CodeLens, CodeLensParams, Command, CompletionItem, CompletionParams,
+    CompletionResponse, DocumentFormattingParams, DocumentHighlight,
+    DocumentHighlightParams, DocumentOnTypeFormattingParams,
+    DocumentRangeFormattingParams, DocumentSymbolParams, DocumentSymbolResponse,
+    ExecuteCommandParams, FileEvent, FoldingRange, FoldingRangeParams,
+    FormattingOptions, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverParams, InlayHint, InlayHintParams, Location, PartialResultParams,
+    Position, PrepareRenameResponse, Range, ReferenceContext, ReferenceParams,
+    RenameParams, SelectionRange, SelectionRangeParams, SemanticTokens,
+    SemanticTokensParams, SignatureHelp, SignatureHelpContext, SignatureHelpParams,
+    SymbolInformation, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, TextEdit, Url, VersionedTextDocumentIdentifier,
+    WorkDoneProgressParams, WorkspaceEdit, WorkspaceSymbolParams,
 };
 use parking_lot::Mutex;
 use serde::de::DeserializeOwned;
@@ -47,7 +61,13 @@
 use crate::directory::Directory;
 
 use self::catalog::PluginCatalog;
+use self::palette::{
+    PaletteItems, PaletteItemsParams, SelectPaletteItem, SelectPaletteItemParams,
+};
 use self::psp::{ClonableCallback, PluginServerRpcHandler, RpcCallback};
+use self::type_hierarchy::{
+    TypeHierarchyPrepare, TypeHierarchySubtypes, TypeHierarchySupertypes,
+};
 use self::wasi::{load_volt, start_volt};
 
 pub type PluginName = String;
@@ -90,6 +110,9 @@ pub enum PluginCatalogRpc {
         text_document: TextDocumentIdentifier,
         text: Rope,
     },
+    DidChangeWatchedFiles {
+        events: Vec<FileEvent>,
+    },
     Handler(PluginCatalogNotification),
     Shutdown,
 }
@@ -198,6 +221,9 @@ pub fn mainloop(&self, plugin: &mut PluginCatalog) {
                         text,
                     );
                 }
+                PluginCatalogRpc::DidChangeWatchedFiles { events } => {
+                    plugin.handle_did_change_watched_files(events);
+                }
                 PluginCatalogRpc::DidChangeTextDocument {
                     language_id,
                     document,
@@ -286,6 +312,44 @@ fn send_request_to_all_plugins<P, Resp>(
         );
     }
 
+    /// Sends the request to every plugin, and hands over the results of
+    /// the plugins that answered once all of them have
+    fn send_request_to_all_plugins_and_collect<P, Resp>(
+        &self,
+        method: &'static str,
+        params: P,
+        language_id: Option<String>,
+        path: Option<PathBuf>,
+        cb: impl FnOnce(Vec<(PluginId, Resp)>) + Clone + Send + 'static,
+    ) where
+        P: Serialize,
+        Resp: DeserializeOwned + Send + 'static,
+    {
+        let request_sent = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(AtomicUsize::new(0));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        self.send_request(
+            None,
+            Some(request_sent.clone()),
+            method,
+            params,
+            language_id,
+            path,
+            move |plugin_id, result| {
+                if let Some(item) = result
+                    .ok()
+                    .and_then(|value| serde_json::from_value::<Resp>(value).ok())
+                {
+                    results.lock().push((plugin_id, item));
+                }
+                let rx = received.fetch_add(1, Ordering::Relaxed) + 1;
+                if request_sent.load(Ordering::Acquire) == rx {
+                    cb(std::mem::take(&mut *results.lock()))
+                }
+            },
+        );
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn send_request<P: Serialize>(
         &self,
@@ -337,6 +401,13 @@ pub fn did_save_text_document(&self, path: &Path, text: Rope) {
         });
     }
 
+    /// Lets the servers know about the files which changed on disk
+    pub fn did_change_watched_files(&self, events: Vec<FileEvent>) {
+        let _ = self
+            .plugin_tx
+            .send(PluginCatalogRpc::DidChangeWatchedFiles { events });
+    }
+
     pub fn did_change_text_document(
         &self,
         path: &Path,
@@ -423,6 +494,263 @@ pub fn get_type_definition(
         );
     }
 
+    pub fn get_implementation(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<GotoDefinitionResponse>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = GotoImplementation::METHOD;
+        let params = GotoDefinitionParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_declaration(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<GotoDefinitionResponse>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = GotoDeclaration::METHOD;
+        let params = GotoDefinitionParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn prepare_call_hierarchy(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<CallHierarchyItem>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        self.prepare_hierarchy(CallHierarchyPrepare::METHOD, path, position, cb);
+    }
+
+    pub fn prepare_type_hierarchy(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<CallHierarchyItem>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        self.prepare_hierarchy(TypeHierarchyPrepare::METHOD, path, position, cb);
+    }
+
+    fn prepare_hierarchy(
+        &self,
+        method: &'static str,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<CallHierarchyItem>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let params = CallHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn call_hierarchy_incoming_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        cb: impl FnOnce(Result<Vec<CallHierarchyItem>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        self.hierarchy_items(
+            plugin_id,
+            CallHierarchyIncomingCalls::METHOD,
+            item,
+            move |value| {
+                serde_json::from_value::<Option<Vec<CallHierarchyIncomingCall>>>(
+                    value,
+                )
+                .map(|calls| {
+                    calls
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|call| call.from)
+                        .collect()
+                })
+            },
+            cb,
+        );
+    }
+
+    pub fn call_hierarchy_outgoing_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        cb: impl FnOnce(Result<Vec<CallHierarchyItem>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        self.hierarchy_items(
+            plugin_id,
+            CallHierarchyOutgoingCalls::METHOD,
+            item,
+            move |value| {
+                serde_json::from_value::<Option<Vec<CallHierarchyOutgoingCall>>>(
+                    value,
+                )
+                .map(|calls| {
+                    calls
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|call| call.to)
+                        .collect()
+                })
+            },
+            cb,
+        );
+    }
+
+    pub fn type_hierarchy_supertypes(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        cb: impl FnOnce(Result<Vec<CallHierarchyItem>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        self.hierarchy_items(
+            plugin_id,
+            TypeHierarchySupertypes::METHOD,
+            item,
+            |value| {
+                serde_json::from_value::<Option<Vec<CallHierarchyItem>>>(value)
+                    .map(|items| items.unwrap_or_default())
+            },
+            cb,
+        );
+    }
+
+    pub fn type_hierarchy_subtypes(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        cb: impl FnOnce(Result<Vec<CallHierarchyItem>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        self.hierarchy_items(
+            plugin_id,
+            TypeHierarchySubtypes::METHOD,
+            item,
+            |value| {
+                serde_json::from_value::<Option<Vec<CallHierarchyItem>>>(value)
+                    .map(|items| items.unwrap_or_default())
+            },
+            cb,
+        );
+    }
+
+    /// Asks the plugin which prepared the hierarchy item for the items next to
+    /// it, which are the callers, callees, supertypes or subtypes
+    fn hierarchy_items(
+        &self,
+        plugin_id: PluginId,
+        method: &'static str,
+        item: CallHierarchyItem,
+        parse: impl FnOnce(Value) -> serde_json::Result<Vec<CallHierarchyItem>>
+            + Send
+            + Clone
+            + 'static,
+        cb: impl FnOnce(Result<Vec<CallHierarchyItem>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        let params = CallHierarchyIncomingCallsParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            params,
+            None,
+            None,
+            move |_, result| {
+                let result = match result {
+                    Ok(value) => parse(value).map_err(|_| RpcError {
+                        code: 0,
+                        message: "hierarchy items deserialize error".to_string(),
+                    }),
+                    Err(e) => Err(e),
+                };
+                cb(result)
+            },
+        );
+    }
+
     pub fn get_references(
         &self,
         path: &Path,
@@ -457,6 +785,36 @@ pub fn get_references(
         );
     }
 
+    pub fn get_document_highlights(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<DocumentHighlight>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentHighlightRequest::METHOD;
+        let params = DocumentHighlightParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
     pub fn get_code_actions(
         &self,
         path: &Path,
@@ -489,6 +847,139 @@ pub fn get_code_actions(
         );
     }
 
+    /// The code actions of the kind for the range, such as the
+    /// `source.organizeImports` ones for the whole document
+    pub fn get_code_actions_of_kind(
+        &self,
+        path: &Path,
+        range: Range,
+        kind: CodeActionKind,
+        cb: impl FnOnce(Vec<(PluginId, CodeActionResponse)>) + Clone + Send + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = CodeActionRequest::METHOD;
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            context: CodeActionContext {
+                only: Some(vec![kind]),
+                ..Default::default()
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins_and_collect(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn code_action_resolve(
+        &self,
+        plugin_id: PluginId,
+        action: CodeAction,
+        cb: impl FnOnce(Result<CodeAction, RpcError>) + Send + Clone + 'static,
+    ) {
+        let method = CodeActionResolveRequest::METHOD;
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            action,
+            None,
+            None,
+            move |_, result| {
+                let result = match result {
+                    Ok(value) => serde_json::from_value::<CodeAction>(value)
+                        .map_err(|_| RpcError {
+                            code: 0,
+                            message: "code action deserialize error".to_string(),
+                        }),
+                    Err(e) => Err(e),
+                };
+                cb(result)
+            },
+        );
+    }
+
+    pub fn execute_command(
+        &self,
+        plugin_id: PluginId,
+        command: Command,
+        cb: impl FnOnce(Result<Option<Value>, RpcError>) + Send + Clone + 'static,
+    ) {
+        let method = ExecuteCommand::METHOD;
+        let params = ExecuteCommandParams {
+            command: command.command,
+            arguments: command.arguments.unwrap_or_default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            params,
+            None,
+            None,
+            move |_, result| {
+                cb(result.map(|value| Some(value).filter(|v| !v.is_null())))
+            },
+        );
+    }
+
+    pub fn get_palette_items(
+        &self,
+        plugin_id: PluginId,
+        provider: String,
+        cb: impl FnOnce(Result<Vec<PluginPaletteItem>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        self.send_request(
+            Some(plugin_id),
+            None,
+            PaletteItems::METHOD,
+            PaletteItemsParams { provider },
+            None,
+            None,
+            move |_, result| {
+                let result = match result {
+                    Ok(value) => {
+                        serde_json::from_value(value).map_err(|_| RpcError {
+                            code: 0,
+                            message: "palette items deserialize error".to_string(),
+                        })
+                    }
+                    Err(e) => Err(e),
+                };
+                cb(result)
+            },
+        );
+    }
+
+    pub fn select_palette_item(
+        &self,
+        plugin_id: PluginId,
+        provider: String,
+        data: Option<Value>,
+    ) {
+        self.send_request(
+            Some(plugin_id),
+            None,
+            SelectPaletteItem::METHOD,
+            SelectPaletteItemParams { provider, data },
+            None,
+            None,
+            |_, _| {},
+        );
+    }
+
     pub fn get_inlay_hints(
         &self,
         path: &Path,
@@ -516,6 +1007,87 @@ pub fn get_inlay_hints(
         );
     }
 
+    pub fn get_folding_ranges(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<FoldingRange>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = FoldingRangeRequest::METHOD;
+        let params = FoldingRangeParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_code_lens(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<CodeLens>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = CodeLensRequest::METHOD;
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn code_lens_resolve(
+        &self,
+        plugin_id: PluginId,
+        lens: CodeLens,
+        cb: impl FnOnce(Result<CodeLens, RpcError>) + Send + Clone + 'static,
+    ) {
+        let method = CodeLensResolve::METHOD;
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            lens,
+            None,
+            None,
+            move |_, result| {
+                let result =
+                    match result {
+                        Ok(value) => serde_json::from_value::<CodeLens>(value)
+                            .map_err(|_| RpcError {
+                                code: 0,
+                                message: "code lens deserialize error".to_string(),
+                            }),
+                        Err(e) => Err(e),
+                    };
+                cb(result)
+            },
+        );
+    }
+
     pub fn get_document_symbols(
         &self,
         path: &Path,
@@ -562,6 +1134,7 @@ pub fn get_workspace_symbols(
     pub fn get_document_formatting(
         &self,
         path: &Path,
+        editorconfig: &EditorConfigProperties,
         cb: impl FnOnce(PluginId, Result<Vec<TextEdit>, RpcError>)
             + Clone
             + Send
@@ -571,11 +1144,7 @@ pub fn get_document_formatting(
         let method = Formatting::METHOD;
         let params = DocumentFormattingParams {
             text_document: TextDocumentIdentifier { uri },
-            options: FormattingOptions {
-                tab_size: 4,
-                insert_spaces: true,
-                ..Default::default()
-            },
+            options: formatting_options(editorconfig),
             work_done_progress_params: WorkDoneProgressParams::default(),
         };
         let language_id =
@@ -589,6 +1158,67 @@ pub fn get_document_formatting(
         );
     }
 
+    pub fn get_document_range_formatting(
+        &self,
+        path: &Path,
+        editorconfig: &EditorConfigProperties,
+        range: Range,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<TextEdit>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = RangeFormatting::METHOD;
+        let params = DocumentRangeFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            options: formatting_options(editorconfig),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_on_type_formatting(
+        &self,
+        path: &Path,
+        editorconfig: &EditorConfigProperties,
+        position: Position,
+        ch: String,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<TextEdit>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = OnTypeFormatting::METHOD;
+        let params = DocumentOnTypeFormattingParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            ch,
+            options: formatting_options(editorconfig),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
     pub fn prepare_rename(
         &self,
         path: &Path,
@@ -727,6 +1357,38 @@ pub fn hover(
         );
     }
 
+    pub fn signature_help(
+        &self,
+        path: &Path,
+        position: Position,
+        context: SignatureHelpContext,
+        cb: impl FnOnce(PluginId, Result<Option<SignatureHelp>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = SignatureHelpRequest::METHOD;
+        let params = SignatureHelpParams {
+            context: Some(context),
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
     pub fn completion(
         &self,
         request_id: usize,
@@ -870,6 +1532,24 @@ pub enum PluginNotification {
     },
 }
 
+/// The formatting options of a file, which follow its `.editorconfig`
+/// properties so that a formatter keeps the indentation they set
+fn formatting_options(editorconfig: &EditorConfigProperties) -> FormattingOptions {
+    let tab = editorconfig.indent_style == Some(EditorConfigIndentStyle::Tab);
+    let tab_size = if tab {
+        editorconfig.tab_width
+    } else {
+        editorconfig.indent_size
+    };
+    FormattingOptions {
+        tab_size: tab_size.unwrap_or(4) as u32,
+        insert_spaces: !tab,
+        trim_trailing_whitespace: editorconfig.trim_trailing_whitespace,
+        insert_final_newline: editorconfig.insert_final_newline,
+        ..Default::default()
+    }
+}
+
 pub fn download_volt(
     volt: VoltInfo,
     wasm: bool,
@@ -0,0 +1,44 @@
+//! The palettes of volts, which aren't part of PSP. A volt registers one
+//! with a notification, gets asked for its items when it's opened, and is
+//! handed back the data of the item that gets picked.
+
+use lapce_rpc::plugin::{PaletteProvider, PluginPaletteItem};
+use lsp_types::{notification::Notification, request::Request};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub enum RegisterPaletteProvider {}
+
+impl Notification for RegisterPaletteProvider {
+    type Params = PaletteProvider;
+    const METHOD: &'static str = "lapce/registerPaletteProvider";
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PaletteItemsParams {
+    pub provider: String,
+}
+
+pub enum PaletteItems {}
+
+impl Request for PaletteItems {
+    type Params = PaletteItemsParams;
+    type Result = Vec<PluginPaletteItem>;
+    const METHOD: &'static str = "lapce/paletteItems";
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectPaletteItemParams {
+    pub provider: String,
+    pub data: Option<Value>,
+}
+
+/// A request rather than a notification, so that it goes to the volt which
+/// registered the palette only
+pub enum SelectPaletteItem {}
+
+impl Request for SelectPaletteItem {
+    type Params = SelectPaletteItemParams;
+    type Result = ();
+    const METHOD: &'static str = "lapce/selectPaletteItem";
+}
@@ -2,7 +2,7 @@
     collections::HashMap,
     path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
     thread,
@@ -14,31 +14,45 @@
 use jsonrpc_lite::{Id, JsonRpc, Params};
 use lapce_core::{buffer::rope_text::RopeText, encoding::offset_utf16_to_utf8};
 use lapce_rpc::{
-    plugin::PluginId,
+    core::CoreRpcHandler,
+    plugin::{PaletteProvider, PluginId},
     style::{LineStyle, Style},
     RpcError,
 };
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
-        Initialized, Notification, Progress, PublishDiagnostics,
+        DidChangeTextDocument, DidChangeWatchedFiles, DidOpenTextDocument,
+        DidSaveTextDocument, Initialized, LogMessage, Notification, Progress,
+        PublishDiagnostics, ShowMessage,
     },
     request::{
-        CodeActionRequest, Completion, DocumentSymbolRequest, Formatting,
-        GotoDefinition, GotoTypeDefinition, HoverRequest, Initialize,
-        InlayHintRequest, PrepareRenameRequest, References, RegisterCapability,
-        Rename, ResolveCompletionItem, SelectionRangeRequest,
-        SemanticTokensFullRequest, WorkDoneProgressCreate, WorkspaceSymbol,
+        ApplyWorkspaceEdit, CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls,
+        CallHierarchyPrepare, CodeActionRequest, CodeActionResolveRequest,
+        CodeLensRefresh, CodeLensRequest, CodeLensResolve, Completion,
+        DocumentHighlightRequest, DocumentSymbolRequest, ExecuteCommand,
+        FoldingRangeRequest, Formatting, GotoDeclaration, GotoDefinition,
+        GotoImplementation, GotoTypeDefinition, HoverRequest, Initialize,
+        InlayHintRequest, OnTypeFormatting, PrepareRenameRequest, RangeFormatting,
+        References, RegisterCapability, Rename, ResolveCompletionItem,
+        SelectionRangeRequest, SemanticTokensFullRequest, ShowDocument,
+        ShowMessageRequest, SignatureHelpRequest, UnregisterCapability,
+        WorkDoneProgressCreate, WorkspaceConfiguration, WorkspaceFoldersRequest,
+        WorkspaceSymbol,
     },
-    CodeActionProviderCapability, DidChangeTextDocumentParams,
-    DidSaveTextDocumentParams, DocumentSelector, HoverProviderCapability, OneOf,
-    ProgressParams, PublishDiagnosticsParams, Range, Registration,
-    RegistrationParams, SemanticTokens, SemanticTokensLegend,
-    SemanticTokensServerCapabilities, ServerCapabilities,
+    ApplyWorkspaceEditParams, ApplyWorkspaceEditResponse,
+    CodeActionProviderCapability, ConfigurationParams, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesParams, DidChangeWatchedFilesRegistrationOptions,
+    DidSaveTextDocumentParams, DocumentOnTypeFormattingParams, DocumentSelector,
+    FileChangeType, FileEvent, FoldingRangeProviderCapability,
+    HoverProviderCapability, LogMessageParams, OneOf, ProgressParams,
+    PublishDiagnosticsParams, Range, Registration, RegistrationParams,
+    SemanticTokens, SemanticTokensLegend, SemanticTokensServerCapabilities,
+    ServerCapabilities, ShowDocumentParams, ShowDocumentResult, ShowMessageParams,
+    ShowMessageRequestParams, SignatureHelpParams, SignatureHelpTriggerKind,
     TextDocumentContentChangeEvent, TextDocumentIdentifier,
     TextDocumentSaveRegistrationOptions, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextDocumentSyncSaveOptions,
-    VersionedTextDocumentIdentifier,
+    TextDocumentSyncKind, TextDocumentSyncSaveOptions, UnregistrationParams, Url,
+    VersionedTextDocumentIdentifier, WatchKind, WorkspaceFolder,
 };
 use parking_lot::Mutex;
 use psp_types::{Request, StartLspServer, StartLspServerParams};
@@ -47,7 +61,17 @@
 use xi_rope::{Rope, RopeDelta};
 
 use super::{
+    diagnostic::{
+        DiagnosticOptions, DocumentDiagnosticParams, DocumentDiagnosticReport,
+        DocumentDiagnosticReportKind, DocumentDiagnosticRequest, PreviousResultId,
+        WorkspaceDiagnosticParams, WorkspaceDiagnosticRefresh,
+        WorkspaceDiagnosticReport, WorkspaceDiagnosticRequest,
+    },
     lsp::{DocumentFilter, LspClient},
+    palette::{PaletteItems, RegisterPaletteProvider, SelectPaletteItem},
+    type_hierarchy::{
+        TypeHierarchyPrepare, TypeHierarchySubtypes, TypeHierarchySupertypes,
+    },
     PluginCatalogRpcHandler,
 };
 
@@ -144,6 +168,13 @@ pub enum PluginServerRpc {
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     },
+    DidChangeWatchedFiles {
+        events: Vec<FileEvent>,
+    },
+    PullDiagnostics {
+        language_id: String,
+        path: PathBuf,
+    },
 }
 
 #[derive(Clone)]
@@ -164,6 +195,11 @@ fn document_supported(
         path: Option<&Path>,
     ) -> bool;
     fn method_registered(&mut self, method: &'static str) -> bool;
+    fn request_params(
+        &mut self,
+        method: &'static str,
+        params: Params,
+    ) -> Option<Params>;
     fn handle_host_notification(&mut self, method: String, params: Params);
     fn handle_host_request(&mut self, id: Id, method: String, params: Params);
     fn handle_handler_notification(
@@ -197,6 +233,8 @@ fn format_semantic_tokens(
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     );
+    fn handle_did_change_watched_files(&self, events: Vec<FileEvent>);
+    fn handle_pull_diagnostics(&self, language_id: String, path: PathBuf);
 }
 
 impl PluginServerRpcHandler {
@@ -399,10 +437,15 @@ pub fn mainloop<H>(&self, handler: &mut H)
                     path,
                     rh,
                 } => {
-                    if handler
+                    let params = if handler
                         .document_supported(language_id.as_deref(), path.as_deref())
                         && handler.method_registered(method)
                     {
+                        handler.request_params(method, params)
+                    } else {
+                        None
+                    };
+                    if let Some(params) = params {
                         self.send_server_request(id, method, params, rh);
                     } else {
                         rh.invoke(Err(RpcError {
@@ -463,6 +506,12 @@ pub fn mainloop<H>(&self, handler: &mut H)
                 PluginServerRpc::FormatSemanticTokens { tokens, text, f } => {
                     handler.format_semantic_tokens(tokens, text, f);
                 }
+                PluginServerRpc::DidChangeWatchedFiles { events } => {
+                    handler.handle_did_change_watched_files(events);
+                }
+                PluginServerRpc::PullDiagnostics { language_id, path } => {
+                    handler.handle_pull_diagnostics(language_id, path);
+                }
                 PluginServerRpc::Handler(notification) => {
                     handler.handle_handler_notification(notification)
                 }
@@ -519,20 +568,75 @@ struct SaveRegistration {
     filters: Vec<DocumentFilter>,
 }
 
+/// A glob registered with `workspace/didChangeWatchedFiles`
+struct FileSystemWatcher {
+    pattern: globset::GlobMatcher,
+    kind: WatchKind,
+}
+
+impl FileSystemWatcher {
+    /// Relative patterns are matched against the path in the workspace
+    fn matches(&self, event: &FileEvent, workspace: Option<&Path>) -> bool {
+        let kind = match event.typ {
+            FileChangeType::CREATED => WatchKind::Create,
+            FileChangeType::CHANGED => WatchKind::Change,
+            FileChangeType::DELETED => WatchKind::Delete,
+            _ => return false,
+        };
+        if !self.kind.contains(kind) {
+            return false;
+        }
+        let path = match event.uri.to_file_path() {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        self.pattern.is_match(&path)
+            || workspace
+                .and_then(|workspace| path.strip_prefix(workspace).ok())
+                .map(|path| self.pattern.is_match(path))
+                .unwrap_or(false)
+    }
+}
+
+/// The state of the diagnostics pulled for a document
+#[derive(Default)]
+struct PulledDiagnostics {
+    /// Set for the open documents, which are pulled on their own
+    language_id: Option<String>,
+    /// Counts the requests, so that the answer to an older one is dropped
+    request: u64,
+    result_id: Option<String>,
+}
+
 #[derive(Default)]
 struct ServerRegistrations {
     save: Option<SaveRegistration>,
+    /// The file watchers by the id of the registration which added them
+    watched_files: HashMap<String, Vec<FileSystemWatcher>>,
 }
 
 pub struct PluginHostHandler {
     volt_id: String,
     pwd: Option<PathBuf>,
     pub(crate) workspace: Option<PathBuf>,
+    /// The settings the volt was started with, which answer the server's
+    /// `workspace/configuration` requests
+    configurations: Option<Value>,
     document_selector: Vec<DocumentFilter>,
     catalog_rpc: PluginCatalogRpcHandler,
     pub server_rpc: PluginServerRpcHandler,
     pub server_capabilities: ServerCapabilities,
+    /// lsp-types doesn't know `typeHierarchyProvider` yet, so it's read from
+    /// the raw initialize result
+    pub type_hierarchy_provider: bool,
+    /// lsp-types doesn't know `diagnosticProvider` yet either
+    pub diagnostic_provider: Option<DiagnosticOptions>,
+    pulled_diagnostics: Arc<Mutex<HashMap<Url, PulledDiagnostics>>>,
+    /// Whether a workspace pull is waiting for its answer
+    workspace_diagnostics_pending: Arc<AtomicBool>,
     server_registrations: ServerRegistrations,
+    /// The ids of the palettes the volt registered
+    palette_providers: Vec<String>,
 }
 
 impl PluginHostHandler {
@@ -540,6 +644,7 @@ pub fn new(
         workspace: Option<PathBuf>,
         pwd: Option<PathBuf>,
         volt_id: String,
+        configurations: Option<Value>,
         document_selector: DocumentSelector,
         server_rpc: PluginServerRpcHandler,
         catalog_rpc: PluginCatalogRpcHandler,
@@ -552,11 +657,17 @@ pub fn new(
             pwd,
             workspace,
             volt_id,
+            configurations,
             document_selector,
             catalog_rpc,
             server_rpc,
             server_capabilities: ServerCapabilities::default(),
+            type_hierarchy_provider: false,
+            diagnostic_provider: None,
+            pulled_diagnostics: Arc::new(Mutex::new(HashMap::new())),
+            workspace_diagnostics_pending: Arc::new(AtomicBool::new(false)),
             server_registrations: ServerRegistrations::default(),
+            palette_providers: Vec::new(),
         }
     }
 
@@ -649,6 +760,23 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
             GotoTypeDefinition::METHOD => {
                 self.server_capabilities.type_definition_provider.is_some()
             }
+            GotoImplementation::METHOD => {
+                self.server_capabilities.implementation_provider.is_some()
+            }
+            GotoDeclaration::METHOD => {
+                self.server_capabilities.declaration_provider.is_some()
+            }
+            CallHierarchyPrepare::METHOD
+            | CallHierarchyIncomingCalls::METHOD
+            | CallHierarchyOutgoingCalls::METHOD => {
+                self.server_capabilities.call_hierarchy_provider.is_some()
+            }
+            TypeHierarchyPrepare::METHOD
+            | TypeHierarchySupertypes::METHOD
+            | TypeHierarchySubtypes::METHOD => self.type_hierarchy_provider,
+            PaletteItems::METHOD | SelectPaletteItem::METHOD => {
+                !self.palette_providers.is_empty()
+            }
             References::METHOD => self
                 .server_capabilities
                 .references_provider
@@ -658,6 +786,15 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
                     OneOf::Right(_) => true,
                 })
                 .unwrap_or(false),
+            DocumentHighlightRequest::METHOD => self
+                .server_capabilities
+                .document_highlight_provider
+                .as_ref()
+                .map(|h| match h {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
             CodeActionRequest::METHOD => self
                 .server_capabilities
                 .code_action_provider
@@ -667,6 +804,17 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
                     CodeActionProviderCapability::Options(_) => true,
                 })
                 .unwrap_or(false),
+            CodeActionResolveRequest::METHOD => self
+                .server_capabilities
+                .code_action_provider
+                .as_ref()
+                .and_then(|a| match a {
+                    CodeActionProviderCapability::Simple(_) => None,
+                    CodeActionProviderCapability::Options(options) => {
+                        options.resolve_provider
+                    }
+                })
+                .unwrap_or(false),
             Formatting::METHOD => self
                 .server_capabilities
                 .document_formatting_provider
@@ -676,6 +824,19 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
                     OneOf::Right(_) => true,
                 })
                 .unwrap_or(false),
+            RangeFormatting::METHOD => self
+                .server_capabilities
+                .document_range_formatting_provider
+                .as_ref()
+                .map(|f| match f {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
+            OnTypeFormatting::METHOD => self
+                .server_capabilities
+                .document_on_type_formatting_provider
+                .is_some(),
             SemanticTokensFullRequest::METHOD => {
                 self.server_capabilities.semantic_tokens_provider.is_some()
             }
@@ -695,10 +856,112 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
             SelectionRangeRequest::METHOD => {
                 self.server_capabilities.selection_range_provider.is_some()
             }
+            SignatureHelpRequest::METHOD => {
+                self.server_capabilities.signature_help_provider.is_some()
+            }
+            FoldingRangeRequest::METHOD => self
+                .server_capabilities
+                .folding_range_provider
+                .as_ref()
+                .map(|f| match f {
+                    FoldingRangeProviderCapability::Simple(is_capable) => {
+                        *is_capable
+                    }
+                    _ => true,
+                })
+                .unwrap_or(false),
+            ExecuteCommand::METHOD => {
+                self.server_capabilities.execute_command_provider.is_some()
+            }
+            CodeLensRequest::METHOD => {
+                self.server_capabilities.code_lens_provider.is_some()
+            }
+            CodeLensResolve::METHOD => self
+                .server_capabilities
+                .code_lens_provider
+                .as_ref()
+                .and_then(|c| c.resolve_provider)
+                .unwrap_or(false),
             _ => false,
         }
     }
 
+    /// The params to send the request with, or `None` if the server only
+    /// wants the request for some characters and it wasn't one of them
+    pub fn request_params(
+        &self,
+        method: &'static str,
+        params: Params,
+    ) -> Option<Params> {
+        match method {
+            SignatureHelpRequest::METHOD => self.signature_help_params(params),
+            OnTypeFormatting::METHOD => self.on_type_formatting_params(params),
+            _ => Some(params),
+        }
+    }
+
+    /// The core asks for on-type formatting on every typed character, and
+    /// only the server's trigger characters get through
+    fn on_type_formatting_params(&self, params: Params) -> Option<Params> {
+        let options = self
+            .server_capabilities
+            .document_on_type_formatting_provider
+            .as_ref()?;
+        let formatting_params: DocumentOnTypeFormattingParams =
+            serde_json::from_value(serde_json::to_value(&params).ok()?).ok()?;
+        let ch = &formatting_params.ch;
+        if &options.first_trigger_character == ch
+            || options
+                .more_trigger_character
+                .as_ref()
+                .map(|characters| characters.contains(ch))
+                .unwrap_or(false)
+        {
+            Some(params)
+        } else {
+            None
+        }
+    }
+
+    /// The core asks for signature help on every typed character, and only
+    /// the server knows which of them should show or update it
+    fn signature_help_params(&self, params: Params) -> Option<Params> {
+        let options = self.server_capabilities.signature_help_provider.as_ref()?;
+        let mut help_params: SignatureHelpParams =
+            serde_json::from_value(serde_json::to_value(&params).ok()?).ok()?;
+        let context = match help_params.context.as_mut() {
+            Some(context)
+                if context.trigger_kind
+                    == SignatureHelpTriggerKind::TRIGGER_CHARACTER =>
+            {
+                context
+            }
+            _ => return Some(params),
+        };
+
+        let character = context.trigger_character.clone().unwrap_or_default();
+        let contains = |characters: &Option<Vec<String>>| {
+            characters
+                .as_ref()
+                .map(|characters| characters.contains(&character))
+                .unwrap_or(false)
+        };
+        if contains(&options.trigger_characters)
+            || (context.is_retrigger && contains(&options.retrigger_characters))
+        {
+            return Some(params);
+        }
+        if !context.is_retrigger {
+            return None;
+        }
+
+        // Any other edit while the signature help is showing still updates it
+        // so it goes away once the cursor leaves the call
+        context.trigger_kind = SignatureHelpTriggerKind::CONTENT_CHANGE;
+        context.trigger_character = None;
+        Some(Params::from(serde_json::to_value(help_params).ok()?))
+    }
+
     fn check_save_capability(&self, language_id: &str, path: &Path) -> (bool, bool) {
         if self.document_supported(Some(language_id), Some(path)) {
             let (should_send, include_text) = self
@@ -764,6 +1027,39 @@ fn register_capability(&mut self, registration: Registration) -> Result<()> {
                         .unwrap_or_default(),
                 });
             }
+            DidChangeWatchedFiles::METHOD => {
+                let options = registration
+                    .register_options
+                    .ok_or_else(|| anyhow!("don't have options"))?;
+                let options: DidChangeWatchedFilesRegistrationOptions =
+                    serde_json::from_value(options)?;
+                let watchers = options
+                    .watchers
+                    .iter()
+                    .filter_map(|watcher| {
+                        let pattern =
+                            globset::GlobBuilder::new(&watcher.glob_pattern)
+                                .literal_separator(true)
+                                .build()
+                                .ok()?
+                                .compile_matcher();
+                        Some(FileSystemWatcher {
+                            pattern,
+                            kind: watcher.kind.unwrap_or_else(WatchKind::all),
+                        })
+                    })
+                    .collect();
+                self.server_registrations
+                    .watched_files
+                    .insert(registration.id, watchers);
+            }
+            DocumentDiagnosticRequest::METHOD => {
+                let options = match registration.register_options {
+                    Some(options) => serde_json::from_value(options)?,
+                    None => DiagnosticOptions::default(),
+                };
+                self.diagnostic_provider = Some(options);
+            }
             _ => {
                 eprintln!(
                     "don't handle register capability for {}",
@@ -788,6 +1084,94 @@ pub fn handle_request(
                 let params: RegistrationParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 self.register_capabilities(params.registrations);
+                self.server_rpc.send_host_success(id, Value::Null);
+            }
+            UnregisterCapability::METHOD => {
+                let params: UnregistrationParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                for unregistration in params.unregisterations {
+                    if unregistration.method == DidChangeWatchedFiles::METHOD {
+                        self.server_registrations
+                            .watched_files
+                            .remove(&unregistration.id);
+                    }
+                }
+                self.server_rpc.send_host_success(id, Value::Null);
+            }
+            WorkspaceConfiguration::METHOD => {
+                let params: ConfigurationParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let result: Vec<Value> = params
+                    .items
+                    .iter()
+                    .map(|item| {
+                        configuration_section(
+                            self.configurations.as_ref(),
+                            item.section.as_deref(),
+                        )
+                    })
+                    .collect();
+                self.server_rpc.send_host_success(id, result);
+            }
+            WorkspaceFoldersRequest::METHOD => {
+                let folders = self.workspace.as_ref().and_then(|path| {
+                    let uri = Url::from_directory_path(path).ok()?;
+                    let name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_else(|| path.to_string_lossy().to_string());
+                    Some(vec![WorkspaceFolder { name, uri }])
+                });
+                self.server_rpc.send_host_success(id, folders);
+            }
+            ShowMessageRequest::METHOD => {
+                let message: ShowMessageRequestParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let core_rpc = self.catalog_rpc.core_rpc.clone();
+                let server_rpc = self.server_rpc.clone();
+                let title = self.volt_id.clone();
+                // Waiting for the user mustn't hold up the other messages of
+                // the server
+                thread::spawn(move || {
+                    let result = core_rpc.show_message_request(title, message);
+                    server_rpc.send_host_response(id, result);
+                });
+            }
+            ShowDocument::METHOD => {
+                let params: ShowDocumentParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc.core_rpc.show_document(params);
+                self.server_rpc
+                    .send_host_success(id, ShowDocumentResult { success: true });
+            }
+            CodeLensRefresh::METHOD => {
+                self.catalog_rpc.core_rpc.code_lens_refresh();
+                self.server_rpc.send_host_success(id, Value::Null);
+            }
+            WorkspaceDiagnosticRefresh::METHOD => {
+                self.server_rpc.send_host_success(id, Value::Null);
+                self.pull_all_diagnostics();
+            }
+            ApplyWorkspaceEdit::METHOD => {
+                let params: ApplyWorkspaceEditParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let core_rpc = self.catalog_rpc.core_rpc.clone();
+                let server_rpc = self.server_rpc.clone();
+                // The editor may have to open files to apply the edit, which
+                // mustn't hold up the other messages of the server
+                thread::spawn(move || {
+                    let failure_reason = core_rpc
+                        .apply_workspace_edit(params.edit)
+                        .unwrap_or_else(|err| Some(err.message));
+                    server_rpc.send_host_success(
+                        id,
+                        ApplyWorkspaceEditResponse {
+                            applied: failure_reason.is_none(),
+                            failure_reason,
+                            failed_change: None,
+                        },
+                    );
+                });
             }
             _ => {
                 self.server_rpc.send_host_error(
@@ -838,6 +1222,32 @@ pub fn handle_notification(
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 self.catalog_rpc.core_rpc.work_done_progress(progress);
             }
+            ShowMessage::METHOD => {
+                let message: ShowMessageParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc
+                    .core_rpc
+                    .show_message(self.volt_id.clone(), message);
+            }
+            LogMessage::METHOD => {
+                let message: LogMessageParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc
+                    .core_rpc
+                    .log_message(self.volt_id.clone(), message);
+            }
+            RegisterPaletteProvider::METHOD => {
+                let provider: PaletteProvider =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                if !self.palette_providers.contains(&provider.id) {
+                    self.palette_providers.push(provider.id.clone());
+                }
+                self.catalog_rpc.core_rpc.register_palette_provider(
+                    self.volt_id.clone(),
+                    self.server_rpc.plugin_id,
+                    provider,
+                );
+            }
             _ => {
                 eprintln!("host notificaton {method} not handled");
             }
@@ -845,6 +1255,31 @@ pub fn handle_notification(
         Ok(())
     }
 
+    /// Sends the events which match the globs the server registered
+    pub fn handle_did_change_watched_files(&self, events: Vec<FileEvent>) {
+        let workspace = self.workspace.as_deref();
+        let changes: Vec<FileEvent> = events
+            .into_iter()
+            .filter(|event| {
+                self.server_registrations
+                    .watched_files
+                    .values()
+                    .flatten()
+                    .any(|watcher| watcher.matches(event, workspace))
+            })
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
+        self.server_rpc.server_notification(
+            DidChangeWatchedFiles::METHOD,
+            DidChangeWatchedFilesParams { changes },
+            None,
+            None,
+            false,
+        );
+    }
+
     pub fn handle_did_save_text_document(
         &self,
         language_id: String,
@@ -854,24 +1289,35 @@ pub fn handle_did_save_text_document(
     ) {
         let (should_send, include_text) =
             self.check_save_capability(language_id.as_str(), &path);
-        if !should_send {
-            return;
+        if should_send {
+            let params = DidSaveTextDocumentParams {
+                text_document,
+                text: if include_text {
+                    Some(text.to_string())
+                } else {
+                    None
+                },
+            };
+            self.server_rpc.server_notification(
+                DidSaveTextDocument::METHOD,
+                params,
+                Some(language_id),
+                Some(path),
+                false,
+            );
+        }
+
+        // A saved file can change the diagnostics of the files using it
+        let inter_file_dependencies = self
+            .diagnostic_provider
+            .as_ref()
+            .map(|options| options.inter_file_dependencies)
+            .unwrap_or(false);
+        if inter_file_dependencies {
+            self.pull_all_diagnostics();
+        } else {
+            self.pull_workspace_diagnostics();
         }
-        let params = DidSaveTextDocumentParams {
-            text_document,
-            text: if include_text {
-                Some(text.to_string())
-            } else {
-                None
-            },
-        };
-        self.server_rpc.server_notification(
-            DidSaveTextDocument::METHOD,
-            params,
-            Some(language_id),
-            Some(path),
-            false,
-        );
     }
 
     pub fn handle_did_change_text_document(
@@ -939,10 +1385,148 @@ pub fn handle_did_change_text_document(
         self.server_rpc.server_notification(
             DidChangeTextDocument::METHOD,
             params,
-            Some(lanaguage_id),
-            path,
+            Some(lanaguage_id.clone()),
+            path.clone(),
             false,
         );
+
+        if let Some(path) = path {
+            self.pull_document_diagnostics(&lanaguage_id, &path);
+        }
+    }
+
+    /// Pulls the diagnostics of a document from a server which prefers
+    /// `textDocument/diagnostic` to publishing them
+    pub fn pull_document_diagnostics(&self, language_id: &str, path: &Path) {
+        let options = match self.diagnostic_provider.as_ref() {
+            Some(options) => options,
+            None => return,
+        };
+        if !self.document_supported(Some(language_id), Some(path)) {
+            return;
+        }
+        let uri = match Url::from_file_path(path) {
+            Ok(uri) => uri,
+            Err(_) => return,
+        };
+
+        let (request, previous_result_id) = {
+            let mut pulled = self.pulled_diagnostics.lock();
+            let pulled = pulled.entry(uri.clone()).or_default();
+            pulled.language_id = Some(language_id.to_string());
+            pulled.request += 1;
+            (pulled.request, pulled.result_id.clone())
+        };
+        let params = DocumentDiagnosticParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            identifier: options.identifier.clone(),
+            previous_result_id,
+        };
+
+        let pulled_diagnostics = self.pulled_diagnostics.clone();
+        let core_rpc = self.catalog_rpc.core_rpc.clone();
+        self.server_rpc.server_request_async(
+            DocumentDiagnosticRequest::METHOD,
+            params,
+            Some(language_id.to_string()),
+            Some(path.to_path_buf()),
+            false,
+            move |result: Result<Value, RpcError>| {
+                let report: DocumentDiagnosticReport =
+                    match result.map(serde_json::from_value) {
+                        Ok(Ok(report)) => report,
+                        _ => return,
+                    };
+                let mut pulled = pulled_diagnostics.lock();
+                if pulled.get(&uri).map(|pulled| pulled.request) != Some(request) {
+                    return;
+                }
+                apply_diagnostic_report(&core_rpc, &mut pulled, uri, report);
+            },
+        );
+    }
+
+    /// Pulls the diagnostics of the whole workspace, unless the last pull is
+    /// still open, which servers can keep until something changes
+    pub fn pull_workspace_diagnostics(&self) {
+        let options = match self.diagnostic_provider.as_ref() {
+            Some(options) if options.workspace_diagnostics => options,
+            _ => return,
+        };
+        if self
+            .workspace_diagnostics_pending
+            .swap(true, Ordering::Relaxed)
+        {
+            return;
+        }
+
+        let previous_result_ids = self
+            .pulled_diagnostics
+            .lock()
+            .iter()
+            .filter_map(|(uri, pulled)| {
+                Some(PreviousResultId {
+                    uri: uri.clone(),
+                    value: pulled.result_id.clone()?,
+                })
+            })
+            .collect();
+        let params = WorkspaceDiagnosticParams {
+            identifier: options.identifier.clone(),
+            previous_result_ids,
+        };
+
+        let pending = self.workspace_diagnostics_pending.clone();
+        let pulled_diagnostics = self.pulled_diagnostics.clone();
+        let core_rpc = self.catalog_rpc.core_rpc.clone();
+        self.server_rpc.server_request_async(
+            WorkspaceDiagnosticRequest::METHOD,
+            params,
+            None,
+            None,
+            false,
+            move |result: Result<Value, RpcError>| {
+                pending.store(false, Ordering::Relaxed);
+                let report: WorkspaceDiagnosticReport =
+                    match result.map(serde_json::from_value) {
+                        Ok(Ok(report)) => report,
+                        _ => return,
+                    };
+                let mut pulled = pulled_diagnostics.lock();
+                for item in report.items {
+                    // The report of an open document could be older than the
+                    // one pulled for it on its last change
+                    let open = pulled
+                        .get(&item.uri)
+                        .map(|pulled| pulled.language_id.is_some())
+                        .unwrap_or(false);
+                    if !open {
+                        apply_diagnostic_report(
+                            &core_rpc,
+                            &mut pulled,
+                            item.uri,
+                            item.report,
+                        );
+                    }
+                }
+            },
+        );
+    }
+
+    /// Pulls the diagnostics of the open documents and of the workspace again
+    fn pull_all_diagnostics(&self) {
+        let documents: Vec<(String, PathBuf)> = self
+            .pulled_diagnostics
+            .lock()
+            .iter()
+            .filter_map(|(uri, pulled)| {
+                Some((pulled.language_id.clone()?, uri.to_file_path().ok()?))
+            })
+            .collect();
+        for (language_id, path) in documents {
+            self.pull_document_diagnostics(&language_id, &path);
+        }
+        self.pull_workspace_diagnostics();
     }
 
     pub fn format_semantic_tokens(
@@ -1067,3 +1651,158 @@ fn semantic_tokens_legend(
         ) => &options.semantic_tokens_options.legend,
     }
 }
+
+/// Publishes a full report like pushed diagnostics, and keeps the result ids
+/// for the next pull
+fn apply_diagnostic_report(
+    core_rpc: &CoreRpcHandler,
+    pulled: &mut HashMap<Url, PulledDiagnostics>,
+    uri: Url,
+    report: DocumentDiagnosticReport,
+) {
+    pulled.entry(uri.clone()).or_default().result_id = report.result_id;
+    if report.kind == DocumentDiagnosticReportKind::Full {
+        core_rpc.publish_diagnostics(PublishDiagnosticsParams {
+            uri,
+            diagnostics: report.items,
+            version: None,
+        });
+    }
+    for (uri, report) in report.related_documents {
+        apply_diagnostic_report(core_rpc, pulled, uri, report);
+    }
+}
+
+/// The part of the volt's settings asked for by a `workspace/configuration`
+/// item, whose dotted section can either be nested in the settings or be a
+/// key of its own. Settings which aren't put under the first part of the
+/// section, usually the name of the server, are taken to be that part.
+fn configuration_section(
+    configurations: Option<&Value>,
+    section: Option<&str>,
+) -> Value {
+    let mut value = match configurations {
+        Some(value) => value,
+        None => return Value::Null,
+    };
+    let mut section = match section {
+        Some(section) if !section.is_empty() => section,
+        _ => return value.clone(),
+    };
+    let (first, rest) = section.split_once('.').unwrap_or((section, ""));
+    if value.get(section).is_none() && value.get(first).is_none() {
+        if rest.is_empty() {
+            return value.clone();
+        }
+        section = rest;
+    }
+    loop {
+        if let Some(value) = value.get(section) {
+            return value.clone();
+        }
+        match section.split_once('.') {
+            Some((first, rest)) => match value.get(first) {
+                Some(nested) => {
+                    value = nested;
+                    section = rest;
+                }
+                None => return Value::Null,
+            },
+            None => return Value::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_configuration_section() {
+        let configurations = json!({
+            "rust-analyzer": {
+                "cargo": { "features": ["a"] },
+                "checkOnSave.command": "clippy",
+            },
+        });
+        let section =
+            |section| configuration_section(Some(&configurations), section);
+        assert_eq!(section(None), configurations);
+        assert_eq!(
+            section(Some("rust-analyzer.cargo")),
+            json!({ "features": ["a"] })
+        );
+        assert_eq!(
+            section(Some("rust-analyzer.checkOnSave.command")),
+            json!("clippy")
+        );
+        assert_eq!(section(Some("rust-analyzer.procMacro")), Value::Null);
+
+        let configurations = json!({ "cargo": { "features": ["a"] } });
+        let section =
+            |section| configuration_section(Some(&configurations), section);
+        assert_eq!(section(Some("rust-analyzer")), configurations);
+        assert_eq!(section(Some("rust-analyzer.cargo.features")), json!(["a"]));
+        assert_eq!(section(Some("cargo")), json!({ "features": ["a"] }));
+
+        assert_eq!(
+            configuration_section(None, Some("rust-analyzer")),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_apply_diagnostic_report() {
+        use lapce_rpc::core::{CoreNotification, CoreRpc};
+
+        let report: DocumentDiagnosticReport = serde_json::from_value(json!({
+            "kind": "full",
+            "resultId": "1",
+            "items": [{
+                "range": {
+                    "start": { "line": 0, "character": 4 },
+                    "end": { "line": 0, "character": 5 },
+                },
+                "message": "unused variable",
+                "tags": [1],
+            }],
+            "relatedDocuments": {
+                "file:///b.rs": { "kind": "unchanged", "resultId": "2" },
+            },
+        }))
+        .unwrap();
+
+        let core_rpc = CoreRpcHandler::new();
+        let mut pulled = HashMap::new();
+        let uri = Url::parse("file:///a.rs").unwrap();
+        apply_diagnostic_report(&core_rpc, &mut pulled, uri.clone(), report);
+
+        let result_id = |uri: &str| {
+            pulled
+                .get(&Url::parse(uri).unwrap())
+                .and_then(|pulled: &PulledDiagnostics| pulled.result_id.clone())
+        };
+        assert_eq!(result_id("file:///a.rs").as_deref(), Some("1"));
+        assert_eq!(result_id("file:///b.rs").as_deref(), Some("2"));
+
+        // Only the full report is published
+        let published: Vec<PublishDiagnosticsParams> = core_rpc
+            .rx()
+            .try_iter()
+            .filter_map(|rpc| match rpc {
+                CoreRpc::Notification(notification) => match *notification {
+                    CoreNotification::PublishDiagnostics { diagnostics } => {
+                        Some(diagnostics)
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].uri, uri);
+        assert_eq!(published[0].diagnostics[0].message, "unused variable");
+    }
+}
@@ -0,0 +1,31 @@
+//! The type hierarchy requests of LSP 3.17, which lsp-types doesn't have yet.
+//! Their params and items have the same shape as the call hierarchy ones.
+
+use lsp_types::{
+    request::Request, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyPrepareParams,
+};
+
+pub enum TypeHierarchyPrepare {}
+
+impl Request for TypeHierarchyPrepare {
+    type Params = CallHierarchyPrepareParams;
+    type Result = Option<Vec<CallHierarchyItem>>;
+    const METHOD: &'static str = "textDocument/prepareTypeHierarchy";
+}
+
+pub enum TypeHierarchySupertypes {}
+
+impl Request for TypeHierarchySupertypes {
+    type Params = CallHierarchyIncomingCallsParams;
+    type Result = Option<Vec<CallHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/supertypes";
+}
+
+pub enum TypeHierarchySubtypes {}
+
+impl Request for TypeHierarchySubtypes {
+    type Params = CallHierarchyIncomingCallsParams;
+    type Result = Option<Vec<CallHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/subtypes";
+}
@@ -16,7 +16,7 @@
     RpcError,
 };
 use lsp_types::{
-    request::Initialize, ClientCapabilities, InitializeParams,
+    request::Initialize, ClientCapabilities, FileEvent, InitializeParams,
     TextDocumentContentChangeEvent, TextDocumentIdentifier, Url,
     VersionedTextDocumentIdentifier,
 };
@@ -89,6 +89,14 @@ fn method_registered(&mut self, method: &'static str) -> bool {
         self.host.method_registered(method)
     }
 
+    fn request_params(
+        &mut self,
+        method: &'static str,
+        params: Params,
+    ) -> Option<Params> {
+        self.host.request_params(method, params)
+    }
+
     fn document_supported(
         &mut self,
         language_id: Option<&str>,
@@ -167,6 +175,14 @@ fn format_semantic_tokens(
     ) {
         self.host.format_semantic_tokens(tokens, text, f);
     }
+
+    fn handle_did_change_watched_files(&self, events: Vec<FileEvent>) {
+        self.host.handle_did_change_watched_files(events);
+    }
+
+    fn handle_pull_diagnostics(&self, language_id: String, path: PathBuf) {
+        self.host.pull_document_diagnostics(&language_id, &path);
+    }
 }
 
 impl Plugin {
@@ -400,6 +416,7 @@ pub fn start_volt(
             workspace,
             meta.dir.clone(),
             meta.id(),
+            configurations.clone(),
             Vec::new(),
             rpc.clone(),
             plugin_rpc.clone(),
@@ -1,5 +1,9 @@
 use crossbeam_channel::{Receiver, Sender};
-use lsp_types::{CompletionResponse, ProgressParams, PublishDiagnosticsParams};
+use lsp_types::{
+    CompletionResponse, LogMessageParams, MessageActionItem, ProgressParams,
+    PublishDiagnosticsParams, ShowDocumentParams, ShowMessageParams,
+    ShowMessageRequestParams, WorkspaceEdit,
+};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -13,7 +17,7 @@
 
 use crate::{
     file::FileNodeItem,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{PaletteProvider, PluginId, VoltInfo, VoltMetadata},
     source_control::DiffInfo,
     terminal::TermId,
     RequestId, RpcError, RpcMessage,
@@ -57,6 +61,8 @@ pub enum CoreNotification {
     WorkDoneProgress {
         progress: ProgressParams,
     },
+    /// A server asked for the code lenses to be requested again
+    CodeLensRefresh {},
     HomeDir {
         path: PathBuf,
     },
@@ -92,6 +98,25 @@ pub enum CoreNotification {
     CloseTerminal {
         term_id: TermId,
     },
+    /// A volt registered a palette of its own
+    RegisterPaletteProvider {
+        volt_id: String,
+        plugin_id: PluginId,
+        provider: PaletteProvider,
+    },
+    ShowMessage {
+        title: String,
+        message: ShowMessageParams,
+    },
+    /// A server asked for a document to be shown with `window/showDocument`
+    ShowDocument {
+        params: ShowDocumentParams,
+    },
+    /// A `window/logMessage` from the server named `title`
+    LogMessage {
+        title: String,
+        message: LogMessageParams,
+    },
     Log {
         level: String,
         message: String,
@@ -99,12 +124,27 @@ pub enum CoreNotification {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum CoreRequest {}
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "method", content = "params")]
+pub enum CoreRequest {
+    /// A message which waits for the user to pick one of its actions
+    ShowMessageRequest {
+        title: String,
+        message: ShowMessageRequestParams,
+    },
+    /// An edit a server asked to apply with `workspace/applyEdit`
+    ApplyWorkspaceEdit { edit: WorkspaceEdit },
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
-pub enum CoreResponse {}
+pub enum CoreResponse {
+    /// `None` when the message was closed without picking an action
+    ShowMessageRequest { action: Option<MessageActionItem> },
+    /// `failure_reason` is only set when the edit wasn't applied
+    ApplyWorkspaceEdit { failure_reason: Option<String> },
+}
 
 pub type CoreMessage = RpcMessage<CoreRequest, CoreNotification, CoreResponse>;
 
@@ -244,6 +284,46 @@ pub fn volt_removed(&self, volt: VoltInfo, only_installing: bool) {
         });
     }
 
+    pub fn register_palette_provider(
+        &self,
+        volt_id: String,
+        plugin_id: PluginId,
+        provider: PaletteProvider,
+    ) {
+        self.notification(CoreNotification::RegisterPaletteProvider {
+            volt_id,
+            plugin_id,
+            provider,
+        });
+    }
+
+    pub fn show_message(&self, title: String, message: ShowMessageParams) {
+        self.notification(CoreNotification::ShowMessage { title, message });
+    }
+
+    /// Blocks until the user picked an action or closed the message
+    pub fn show_message_request(
+        &self,
+        title: String,
+        message: ShowMessageRequestParams,
+    ) -> Result<Option<MessageActionItem>, RpcError> {
+        match self.request(CoreRequest::ShowMessageRequest { title, message })? {
+            CoreResponse::ShowMessageRequest { action } => Ok(action),
+            _ => Err(RpcError {
+                code: 0,
+                message: "wrong response".to_string(),
+            }),
+        }
+    }
+
+    pub fn show_document(&self, params: ShowDocumentParams) {
+        self.notification(CoreNotification::ShowDocument { params });
+    }
+
+    pub fn log_message(&self, title: String, message: LogMessageParams) {
+        self.notification(CoreNotification::LogMessage { title, message });
+    }
+
     pub fn log(&self, level: log::Level, message: String) {
         self.notification(CoreNotification::Log {
             level: level.as_str().to_string(),
@@ -259,6 +339,27 @@ pub fn work_done_progress(&self, progress: ProgressParams) {
         self.notification(CoreNotification::WorkDoneProgress { progress });
     }
 
+    /// Blocks until the edit has been applied, and gives the reason it
+    /// couldn't be if it wasn't
+    pub fn apply_workspace_edit(
+        &self,
+        edit: WorkspaceEdit,
+    ) -> Result<Option<String>, RpcError> {
+        match self.request(CoreRequest::ApplyWorkspaceEdit { edit })? {
+            CoreResponse::ApplyWorkspaceEdit { failure_reason } => {
+                Ok(failure_reason)
+            }
+            _ => Err(RpcError {
+                code: 0,
+                message: "wrong response".to_string(),
+            }),
+        }
+    }
+
+    pub fn code_lens_refresh(&self) {
+        self.notification(CoreNotification::CodeLensRefresh {});
+    }
+
     pub fn close_terminal(&self, term_id: TermId) {
         self.notification(CoreNotification::CloseTerminal { term_id });
     }
@@ -77,3 +77,23 @@ pub fn info(&self) -> VoltInfo {
         }
     }
 }
+
+/// A palette of a volt, which it registers with `lapce/registerPaletteProvider`
+/// and which is listed in the command palette
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PaletteProvider {
+    /// The id the volt knows the palette by
+    pub id: String,
+    pub name: String,
+}
+
+/// An item of a volt's palette, whose `data` is handed back to the volt when
+/// the item is picked
+#[derive(Deserialize, Clone, Debug, PartialEq, Serialize)]
+pub struct PluginPaletteItem {
+    pub label: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub data: Option<Value>,
+}
@@ -9,19 +9,22 @@
 
 use crossbeam_channel::{Receiver, Sender};
 use lsp_types::{
-    request::GotoTypeDefinitionResponse, CodeActionResponse, CompletionItem,
-    DocumentSymbolResponse, GotoDefinitionResponse, Hover, InlayHint, Location,
-    Position, PrepareRenameResponse, SelectionRange, SymbolInformation,
-    TextDocumentItem, TextEdit, WorkspaceEdit,
+    request::GotoTypeDefinitionResponse, CallHierarchyItem, CodeAction,
+    CodeActionKind, CodeActionOrCommand, CodeActionResponse, CodeLens, Command,
+    CompletionItem, DocumentHighlight, DocumentSymbolResponse, FoldingRange,
+    GotoDefinitionResponse, Hover, InlayHint, Location, Position,
+    PrepareRenameResponse, Range, SelectionRange, SignatureHelp,
+    SignatureHelpContext, SymbolInformation, TextDocumentItem, TextEdit,
+    WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use xi_rope::RopeDelta;
 
 use crate::{
-    buffer::BufferId,
+    buffer::{BufferId, EditorConfigProperties},
     file::FileNodeItem,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{PluginId, PluginPaletteItem, VoltInfo, VoltMetadata},
     source_control::FileDiff,
     style::SemanticStyles,
     terminal::TermId,
@@ -44,10 +47,26 @@ pub enum ProxyRequest {
     },
     BufferHead {
         path: PathBuf,
+        /// Either `head` or the id of the commit to get the file from
+        version: String,
     },
     GlobalSearch {
         pattern: String,
     },
+    /// Commit the changes, failing with the output of any hook rejecting them
+    GitCommit {
+        message: String,
+        diffs: Vec<FileDiff>,
+        /// Replace the last commit instead of creating a new one
+        amend: bool,
+        /// Add a `Signed-off-by` trailer for the committer
+        sign_off: bool,
+    },
+    /// The message to start the commit input with, which is the last commit's
+    /// message when amending and the configured commit template otherwise
+    GitGetCommitMessage {
+        amend: bool,
+    },
     CompletionResolve {
         plugin_id: PluginId,
         completion_item: Box<CompletionItem>,
@@ -58,8 +77,10 @@ pub enum ProxyRequest {
         position: Position,
     },
     GetSignature {
-        buffer_id: BufferId,
+        request_id: usize,
+        path: PathBuf,
         position: Position,
+        context: SignatureHelpContext,
     },
     GetSelectionRange {
         path: PathBuf,
@@ -74,14 +95,62 @@ pub enum ProxyRequest {
         path: PathBuf,
         position: Position,
     },
+    GetDocumentHighlights {
+        path: PathBuf,
+        position: Position,
+    },
     GetTypeDefinition {
         request_id: usize,
         path: PathBuf,
         position: Position,
     },
+    GetImplementation {
+        path: PathBuf,
+        position: Position,
+    },
+    GetDeclaration {
+        path: PathBuf,
+        position: Position,
+    },
+    PrepareCallHierarchy {
+        path: PathBuf,
+        position: Position,
+    },
+    CallHierarchyIncomingCalls {
+        plugin_id: PluginId,
+        item: Box<CallHierarchyItem>,
+    },
+    CallHierarchyOutgoingCalls {
+        plugin_id: PluginId,
+        item: Box<CallHierarchyItem>,
+    },
+    /// The items of the type hierarchy have the same shape as the ones of the
+    /// call hierarchy
+    PrepareTypeHierarchy {
+        path: PathBuf,
+        position: Position,
+    },
+    TypeHierarchySupertypes {
+        plugin_id: PluginId,
+        item: Box<CallHierarchyItem>,
+    },
+    TypeHierarchySubtypes {
+        plugin_id: PluginId,
+        item: Box<CallHierarchyItem>,
+    },
     GetInlayHints {
         path: PathBuf,
     },
+    GetFoldingRanges {
+        path: PathBuf,
+    },
+    GetCodeLens {
+        path: PathBuf,
+    },
+    CodeLensResolve {
+        plugin_id: PluginId,
+        lens: Box<CodeLens>,
+    },
     GetSemanticTokens {
         path: PathBuf,
     },
@@ -98,6 +167,23 @@ pub enum ProxyRequest {
         path: PathBuf,
         position: Position,
     },
+    /// The code actions of a kind for the whole document, which are run on
+    /// save
+    GetCodeActionsOfKind {
+        path: PathBuf,
+        kind: CodeActionKind,
+    },
+    /// Resolve the edit of a code action that came without one
+    CodeActionResolve {
+        plugin_id: PluginId,
+        action: Box<CodeAction>,
+    },
+    /// Run a command of a code action or code lens with `workspace/executeCommand`
+    /// on the plugin that provided it
+    ExecuteCommand {
+        plugin_id: PluginId,
+        command: Command,
+    },
     GetDocumentSymbols {
         path: PathBuf,
     },
@@ -105,9 +191,25 @@ pub enum ProxyRequest {
         /// The search query
         query: String,
     },
+    /// The items of a palette that a volt registered
+    GetPaletteItems {
+        plugin_id: PluginId,
+        provider: String,
+    },
     GetDocumentFormatting {
         path: PathBuf,
     },
+    GetDocumentRangeFormatting {
+        path: PathBuf,
+        range: Range,
+    },
+    /// Sent for every typed character, the proxy only passes it on to the
+    /// servers which format on that character
+    GetOnTypeFormatting {
+        path: PathBuf,
+        position: Position,
+        ch: String,
+    },
     GetOpenFilesContent {},
     GetFiles {
         path: String,
@@ -186,13 +288,52 @@ pub enum ProxyNotification {
     EnableVolt {
         volt: VoltInfo,
     },
-    GitCommit {
-        message: String,
-        diffs: Vec<FileDiff>,
+    /// Hand the data of an item of a volt's palette back to it when it's picked
+    SelectPaletteItem {
+        plugin_id: PluginId,
+        provider: String,
+        data: Option<serde_json::Value>,
     },
     GitCheckout {
         branch: String,
     },
+    GitCreateBranch {
+        name: String,
+        /// The ref the branch starts from, HEAD if not given
+        start_point: Option<String>,
+        checkout: bool,
+    },
+    GitDeleteBranch {
+        branch: String,
+        force: bool,
+    },
+    GitRenameBranch {
+        branch: String,
+        new_name: String,
+    },
+    GitFetch {},
+    GitPull {
+        /// Rebase local commits onto the upstream when it can't fast-forward
+        rebase: bool,
+    },
+    GitPush {},
+    GitStashSave {
+        message: Option<String>,
+        include_untracked: bool,
+    },
+    GitStashApply {
+        index: usize,
+    },
+    GitStashPop {
+        index: usize,
+    },
+    GitStashDrop {
+        index: usize,
+    },
+    /// Mark a file that no longer has conflict markers as resolved
+    GitResolveConflict {
+        path: PathBuf,
+    },
     GitDiscardFilesChanges {
         files: Vec<PathBuf>,
     },
@@ -218,11 +359,15 @@ pub enum ProxyNotification {
 pub enum ProxyResponse {
     NewBufferResponse {
         content: String,
+        editorconfig: EditorConfigProperties,
     },
     BufferHeadResponse {
         version: String,
         content: String,
     },
+    GitGetCommitMessage {
+        message: String,
+    },
     ReadDirResponse {
         items: HashMap<PathBuf, FileNodeItem>,
     },
@@ -233,6 +378,10 @@ pub enum ProxyResponse {
         request_id: usize,
         hover: Hover,
     },
+    GetSignature {
+        request_id: usize,
+        signature: Option<SignatureHelp>,
+    },
     GetDefinitionResponse {
         request_id: usize,
         definition: GotoDefinitionResponse,
@@ -244,12 +393,38 @@ pub enum ProxyResponse {
     GetReferencesResponse {
         references: Vec<Location>,
     },
+    GetImplementation {
+        locations: Vec<Location>,
+    },
+    GetDeclaration {
+        locations: Vec<Location>,
+    },
+    PrepareHierarchy {
+        plugin_id: PluginId,
+        items: Vec<CallHierarchyItem>,
+    },
+    /// The callers, callees, supertypes or subtypes of a hierarchy item
+    HierarchyItems {
+        items: Vec<CallHierarchyItem>,
+    },
     GetCodeActionsResponse {
+        plugin_id: PluginId,
         resp: CodeActionResponse,
     },
+    /// The code actions of a kind from every plugin that has some
+    GetCodeActionsOfKindResponse {
+        actions: Vec<(PluginId, CodeActionOrCommand)>,
+    },
+    CodeActionResolve {
+        action: Box<CodeAction>,
+    },
+    ExecuteCommandResponse {
+        result: Option<serde_json::Value>,
+    },
     GetFilesResponse {
         items: Vec<PathBuf>,
     },
+    /// The edits of the whole document, range or on-type formatting
     GetDocumentFormatting {
         edits: Vec<TextEdit>,
     },
@@ -259,12 +434,28 @@ pub enum ProxyResponse {
     GetWorkspaceSymbols {
         symbols: Vec<SymbolInformation>,
     },
+    GetPaletteItems {
+        items: Vec<PluginPaletteItem>,
+    },
     GetSelectionRange {
         ranges: Vec<SelectionRange>,
     },
     GetInlayHints {
         hints: Vec<InlayHint>,
     },
+    GetFoldingRanges {
+        ranges: Vec<FoldingRange>,
+    },
+    GetDocumentHighlights {
+        highlights: Vec<DocumentHighlight>,
+    },
+    GetCodeLens {
+        plugin_id: PluginId,
+        lenses: Vec<CodeLens>,
+    },
+    CodeLensResolve {
+        lens: Box<CodeLens>,
+    },
     GetSemanticTokens {
         styles: SemanticStyles,
     },
@@ -406,14 +597,93 @@ pub fn git_init(&self) {
         self.notification(ProxyNotification::GitInit {});
     }
 
-    pub fn git_commit(&self, message: String, diffs: Vec<FileDiff>) {
-        self.notification(ProxyNotification::GitCommit { message, diffs });
+    pub fn git_commit(
+        &self,
+        message: String,
+        diffs: Vec<FileDiff>,
+        amend: bool,
+        sign_off: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GitCommit {
+                message,
+                diffs,
+                amend,
+                sign_off,
+            },
+            f,
+        );
+    }
+
+    pub fn git_get_commit_message(
+        &self,
+        amend: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitGetCommitMessage { amend }, f);
     }
 
     pub fn git_checkout(&self, branch: String) {
         self.notification(ProxyNotification::GitCheckout { branch });
     }
 
+    pub fn git_create_branch(
+        &self,
+        name: String,
+        start_point: Option<String>,
+        checkout: bool,
+    ) {
+        self.notification(ProxyNotification::GitCreateBranch {
+            name,
+            start_point,
+            checkout,
+        });
+    }
+
+    pub fn git_delete_branch(&self, branch: String, force: bool) {
+        self.notification(ProxyNotification::GitDeleteBranch { branch, force });
+    }
+
+    pub fn git_rename_branch(&self, branch: String, new_name: String) {
+        self.notification(ProxyNotification::GitRenameBranch { branch, new_name });
+    }
+
+    pub fn git_fetch(&self) {
+        self.notification(ProxyNotification::GitFetch {});
+    }
+
+    pub fn git_pull(&self, rebase: bool) {
+        self.notification(ProxyNotification::GitPull { rebase });
+    }
+
+    pub fn git_push(&self) {
+        self.notification(ProxyNotification::GitPush {});
+    }
+
+    pub fn git_stash_save(&self, message: Option<String>, include_untracked: bool) {
+        self.notification(ProxyNotification::GitStashSave {
+            message,
+            include_untracked,
+        });
+    }
+
+    pub fn git_stash_apply(&self, index: usize) {
+        self.notification(ProxyNotification::GitStashApply { index });
+    }
+
+    pub fn git_stash_pop(&self, index: usize) {
+        self.notification(ProxyNotification::GitStashPop { index });
+    }
+
+    pub fn git_stash_drop(&self, index: usize) {
+        self.notification(ProxyNotification::GitStashDrop { index });
+    }
+
+    pub fn git_resolve_conflict(&self, path: PathBuf) {
+        self.notification(ProxyNotification::GitResolveConflict { path });
+    }
+
     pub fn install_volt(&self, volt: VoltInfo) {
         self.notification(ProxyNotification::InstallVolt { volt });
     }
@@ -430,6 +700,19 @@ pub fn enable_volt(&self, volt: VoltInfo) {
         self.notification(ProxyNotification::EnableVolt { volt });
     }
 
+    pub fn select_palette_item(
+        &self,
+        plugin_id: PluginId,
+        provider: String,
+        data: Option<serde_json::Value>,
+    ) {
+        self.notification(ProxyNotification::SelectPaletteItem {
+            plugin_id,
+            provider,
+            data,
+        });
+    }
+
     pub fn shutdown(&self) {
         self.notification(ProxyNotification::Shutdown {});
         let _ = self.tx.send(ProxyRpc::Shutdown);
@@ -512,9 +795,10 @@ pub fn get_buffer_head(
         &self,
         _buffer_id: BufferId,
         path: PathBuf,
+        version: String,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::BufferHead { path }, f);
+        self.request_async(ProxyRequest::BufferHead { path, version }, f);
     }
 
     pub fn create_file(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
@@ -614,6 +898,25 @@ pub fn get_hover(
         );
     }
 
+    pub fn get_signature(
+        &self,
+        request_id: usize,
+        path: PathBuf,
+        position: Position,
+        context: SignatureHelpContext,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetSignature {
+                request_id,
+                path,
+                position,
+                context,
+            },
+            f,
+        );
+    }
+
     pub fn get_definition(
         &self,
         request_id: usize,
@@ -648,6 +951,102 @@ pub fn get_type_definition(
         );
     }
 
+    pub fn get_implementation(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetImplementation { path, position }, f);
+    }
+
+    pub fn get_declaration(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetDeclaration { path, position }, f);
+    }
+
+    pub fn prepare_call_hierarchy(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::PrepareCallHierarchy { path, position }, f);
+    }
+
+    pub fn call_hierarchy_incoming_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CallHierarchyIncomingCalls {
+                plugin_id,
+                item: Box::new(item),
+            },
+            f,
+        );
+    }
+
+    pub fn call_hierarchy_outgoing_calls(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CallHierarchyOutgoingCalls {
+                plugin_id,
+                item: Box::new(item),
+            },
+            f,
+        );
+    }
+
+    pub fn prepare_type_hierarchy(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::PrepareTypeHierarchy { path, position }, f);
+    }
+
+    pub fn type_hierarchy_supertypes(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::TypeHierarchySupertypes {
+                plugin_id,
+                item: Box::new(item),
+            },
+            f,
+        );
+    }
+
+    pub fn type_hierarchy_subtypes(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::TypeHierarchySubtypes {
+                plugin_id,
+                item: Box::new(item),
+            },
+            f,
+        );
+    }
+
     pub fn get_references(
         &self,
         path: PathBuf,
@@ -657,6 +1056,18 @@ pub fn get_references(
         self.request_async(ProxyRequest::GetReferences { path, position }, f);
     }
 
+    pub fn get_document_highlights(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetDocumentHighlights { path, position },
+            f,
+        );
+    }
+
     pub fn get_code_actions(
         &self,
         path: PathBuf,
@@ -666,6 +1077,39 @@ pub fn get_code_actions(
         self.request_async(ProxyRequest::GetCodeActions { path, position }, f);
     }
 
+    pub fn get_code_actions_of_kind(
+        &self,
+        path: PathBuf,
+        kind: CodeActionKind,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetCodeActionsOfKind { path, kind }, f);
+    }
+
+    pub fn code_action_resolve(
+        &self,
+        plugin_id: PluginId,
+        action: CodeAction,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CodeActionResolve {
+                plugin_id,
+                action: Box::new(action),
+            },
+            f,
+        );
+    }
+
+    pub fn execute_command(
+        &self,
+        plugin_id: PluginId,
+        command: Command,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ExecuteCommand { plugin_id, command }, f);
+    }
+
     pub fn get_document_formatting(
         &self,
         path: PathBuf,
@@ -674,6 +1118,31 @@ pub fn get_document_formatting(
         self.request_async(ProxyRequest::GetDocumentFormatting { path }, f);
     }
 
+    pub fn get_document_range_formatting(
+        &self,
+        path: PathBuf,
+        range: Range,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetDocumentRangeFormatting { path, range },
+            f,
+        );
+    }
+
+    pub fn get_on_type_formatting(
+        &self,
+        path: PathBuf,
+        position: Position,
+        ch: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetOnTypeFormatting { path, position, ch },
+            f,
+        );
+    }
+
     pub fn get_semantic_tokens(
         &self,
         path: PathBuf,
@@ -698,6 +1167,21 @@ pub fn get_workspace_symbols(
         self.request_async(ProxyRequest::GetWorkspaceSymbols { query }, f);
     }
 
+    pub fn get_palette_items(
+        &self,
+        plugin_id: PluginId,
+        provider: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetPaletteItems {
+                plugin_id,
+                provider,
+            },
+            f,
+        );
+    }
+
     pub fn prepare_rename(
         &self,
         path: PathBuf,
@@ -728,6 +1212,33 @@ pub fn get_inlay_hints(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::GetInlayHints { path }, f);
     }
 
+    pub fn get_folding_ranges(
+        &self,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetFoldingRanges { path }, f);
+    }
+
+    pub fn get_code_lens(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GetCodeLens { path }, f);
+    }
+
+    pub fn code_lens_resolve(
+        &self,
+        plugin_id: PluginId,
+        lens: CodeLens,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CodeLensResolve {
+                plugin_id,
+                lens: Box::new(lens),
+            },
+            f,
+        );
+    }
+
     pub fn update(&self, path: PathBuf, delta: RopeDelta, rev: u64) {
         self.notification(ProxyNotification::Update { path, delta, rev });
     }
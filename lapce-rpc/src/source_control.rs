@@ -6,7 +6,34 @@
 pub struct DiffInfo {
     pub head: String,
     pub branches: Vec<String>,
+    pub remote_branches: Vec<String>,
+    /// The upstream of the current branch, if it tracks one
+    pub upstream: Option<UpstreamStatus>,
     pub diffs: Vec<FileDiff>,
+    /// Files with merge conflicts that still need to be resolved
+    pub conflicts: Vec<PathBuf>,
+    pub stashes: Vec<StashInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StashInfo {
+    /// The position in the stash list, as in `stash@{index}`
+    pub index: usize,
+    pub message: String,
+    /// The id of the stash commit, which unlike the index doesn't change
+    /// when other stashes are added or dropped
+    pub id: String,
+    /// The files the stash has changes for
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpstreamStatus {
+    pub name: String,
+    /// Number of local commits not yet in the upstream
+    pub ahead: usize,
+    /// Number of upstream commits not yet in the local branch
+    pub behind: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -29,14 +29,15 @@
 use lapce_data::selection_range::SyntaxSelectionRanges;
 use lapce_data::{
     command::{
-        LapceCommand, LapceUICommand, LapceWorkbenchCommand, LAPCE_UI_COMMAND,
+        LapceCommand, LapceUICommand, LapceWorkbenchCommand, LAPCE_COMMAND,
+        LAPCE_UI_COMMAND,
     },
     config::{LapceConfig, LapceTheme},
     data::LapceTabData,
     editor::{LapceEditorBufferData, Syntax},
     menu::MenuItem,
 };
-use lsp_types::{CodeActionOrCommand, DiagnosticSeverity};
+use lsp_types::{CodeActionOrCommand, DiagnosticSeverity, DocumentHighlightKind};
 
 pub mod bread_crumb;
 pub mod container;
@@ -70,6 +71,12 @@ pub struct LapceEditor {
     /// of hover info (if there is any)
     mouse_hover_timer: TimerToken,
     drag_timer: TimerToken,
+    /// Where the actions of the visible merge conflicts were painted, so that
+    /// a click on one of them can run it
+    conflict_actions: Vec<(Rect, FocusCommand)>,
+    /// Where the visible code lenses were painted, with their index, so that a
+    /// click on one of them can run its command
+    code_lens_actions: Vec<(Rect, usize)>,
 }
 
 impl LapceEditor {
@@ -82,6 +89,8 @@ pub fn new(view_id: WidgetId, editor_id: WidgetId) -> Self {
             mouse_mods: Modifiers::empty(),
             mouse_hover_timer: TimerToken::INVALID,
             drag_timer: TimerToken::INVALID,
+            conflict_actions: Vec::new(),
+            code_lens_actions: Vec::new(),
         }
     }
 
@@ -220,6 +229,28 @@ fn mouse_down(
         match mouse_event.button {
             MouseButton::Left => {
                 self.left_click(ctx, mouse_event, editor_data, config);
+                if let Some((_, command)) = self
+                    .conflict_actions
+                    .iter()
+                    .find(|(rect, _)| rect.contains(mouse_event.pos))
+                {
+                    // The click has put the cursor on the conflict already
+                    ctx.submit_command(Command::new(
+                        LAPCE_COMMAND,
+                        LapceCommand {
+                            kind: CommandKind::Focus(command.clone()),
+                            data: None,
+                        },
+                        Target::Widget(editor_data.view_id),
+                    ));
+                }
+                if let Some((_, index)) = self
+                    .code_lens_actions
+                    .iter()
+                    .find(|(rect, _)| rect.contains(mouse_event.pos))
+                {
+                    editor_data.run_code_lens(*index);
+                }
                 editor_data.get_code_actions(ctx);
                 editor_data.cancel_completion();
                 // TODO: Don't cancel over here, because it would good to allow the user to
@@ -412,18 +443,16 @@ pub fn get_size(
                         },
                     )
                 } else {
+                    let num_lines = data.doc.num_visual_lines();
                     Size::new(
                         (width * data.doc.buffer().max_len() as f64)
                             .max(data.doc.text_layouts.borrow().max_width)
                             .max(editor_size.width),
                         if data.config.editor.scroll_beyond_last_line {
-                            (line_height * data.doc.buffer().num_lines() as f64
-                                - line_height)
-                                .max(0.0)
+                            (line_height * num_lines as f64 - line_height).max(0.0)
                                 + editor_size.height
                         } else {
-                            (line_height * data.doc.buffer().num_lines() as f64)
-                                .max(editor_size.height)
+                            (line_height * num_lines as f64).max(editor_size.height)
                         },
                     )
                 }
@@ -796,16 +825,22 @@ fn paint_content(
                 let start_line = (rect.y0 / line_height).floor() as usize;
                 let end_line = (rect.y1 / line_height).ceil() as usize;
 
+                // The lines hidden by folds are skipped, and the virtual lines
+                // of the code lenses are painted on their own
                 let mut lines = Vec::new();
                 let mut info = HashMap::new();
-                for line in start_line..end_line + 1 {
+                for visual_line in start_line..end_line + 1 {
+                    if data.doc.code_lens_line(visual_line).is_some() {
+                        continue;
+                    }
+                    let line = data.doc.actual_line(visual_line);
                     lines.push(line);
                     info.insert(
                         line,
                         LineInfo {
                             font_size,
                             x: 0.0,
-                            y: line as f64 * line_height + line_padding,
+                            y: visual_line as f64 * line_height + line_padding,
                             line_height,
                         },
                     );
@@ -823,9 +858,14 @@ fn paint_content(
         };
 
         Self::paint_current_line(ctx, data, &screen_lines);
+        Self::paint_max_line_length(ctx, data);
+        self.paint_conflicts(ctx, data, &screen_lines);
+        Self::paint_document_highlights(ctx, data, &screen_lines);
         Self::paint_cursor_new(ctx, data, &screen_lines, is_focused, env);
         Self::paint_find(ctx, data, &screen_lines);
         Self::paint_text(ctx, data, &screen_lines, env);
+        Self::paint_folds(ctx, data, &screen_lines);
+        self.paint_code_lens(ctx, data, env);
         Self::paint_diagnostics(ctx, data, &screen_lines);
         Self::paint_snippet(ctx, data, &screen_lines);
         Self::paint_sticky_headers(ctx, data, env);
@@ -897,6 +937,84 @@ fn paint_text(
             }
 
             ctx.draw_text(&text_layout.text, Point::new(info.x, y));
+
+            for (x0, x1, style) in text_layout.extra_style.iter() {
+                if let Some(color) = &style.strike_through {
+                    let x1 = x1.unwrap_or(self_size.width);
+                    let y = y + height / 2.0;
+                    ctx.stroke(
+                        Line::new(
+                            Point::new(*x0 + info.x, y),
+                            Point::new(x1 + info.x, y),
+                        ),
+                        color,
+                        1.0,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Show that there are hidden lines after the lines that are folded
+    fn paint_folds(
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        if data.doc.folded_lines().is_empty() {
+            return;
+        }
+
+        let char_width = data.config.editor_char_width(ctx.text());
+        for line in &screen_lines.lines {
+            let line = *line;
+            if line > data.doc.buffer().last_line() {
+                break;
+            }
+            if !data.doc.is_folded(line) {
+                continue;
+            }
+
+            let info = screen_lines.info.get(&line).unwrap();
+            let x = data
+                .doc
+                .line_point_of_line_col(
+                    ctx.text(),
+                    line,
+                    data.doc.buffer().line_end_col(line, true),
+                    info.font_size,
+                    &data.config,
+                )
+                .x;
+            let text_layout = ctx
+                .text()
+                .new_text_layout("\u{22ef}")
+                .font(data.config.editor.font_family(), info.font_size as f64)
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let rect = Size::new(
+                text_layout.size().width + char_width,
+                text_layout.size().height,
+            )
+            .to_rect()
+            .with_origin(Point::new(
+                info.x + x + char_width,
+                info.y + text_layout.y_offset(info.line_height),
+            ));
+            ctx.fill(
+                rect.to_rounded_rect(3.0),
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_CURRENT_LINE),
+            );
+            ctx.draw_text(
+                &text_layout,
+                Point::new(rect.x0 + char_width / 2.0, rect.y0),
+            );
         }
     }
 
@@ -1023,6 +1141,247 @@ fn paint_current_line(
         }
     }
 
+    /// Paint a ruler at the `max_line_length` the `.editorconfig` files set
+    fn paint_max_line_length(ctx: &mut PaintCtx, data: &LapceEditorBufferData) {
+        if data.editor.content.is_input() {
+            return;
+        }
+        if let Some(max_line_length) = data.doc.editorconfig().max_line_length {
+            let char_width = data.config.editor_char_width(ctx.text());
+            let x = (max_line_length as f64 * char_width).round() + 0.5;
+            let rect = ctx.region().bounding_box();
+            ctx.stroke(
+                Line::new(Point::new(x, rect.y0), Point::new(x, rect.y1)),
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_VISIBLE_WHITESPACE),
+                1.0,
+            );
+        }
+    }
+
+    /// Highlight the merge conflicts, and paint the actions to resolve each
+    /// one after its `<<<<<<<` marker
+    fn paint_conflicts(
+        &mut self,
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        self.conflict_actions.clear();
+        let (start_line, end_line) =
+            match (screen_lines.lines.first(), screen_lines.lines.last()) {
+                (Some(start), Some(end)) => (*start, *end),
+                _ => return,
+            };
+        let self_size = ctx.size();
+        let current_color = data
+            .config
+            .get_color_unchecked(LapceTheme::SOURCE_CONTROL_ADDED);
+        let incoming_color = data
+            .config
+            .get_color_unchecked(LapceTheme::SOURCE_CONTROL_MODIFIED);
+        let base_color = data
+            .config
+            .get_color_unchecked(LapceTheme::SOURCE_CONTROL_REMOVED);
+
+        for conflict in data.doc.conflicts.iter() {
+            if conflict.end < start_line || conflict.start > end_line {
+                continue;
+            }
+
+            for line in conflict.start..conflict.end + 1 {
+                let info = match screen_lines.info.get(&line) {
+                    Some(info) => info,
+                    None => continue,
+                };
+                let color = if line < conflict.base.unwrap_or(conflict.separator) {
+                    current_color
+                } else if line < conflict.separator {
+                    base_color
+                } else {
+                    incoming_color
+                };
+                let is_marker = line == conflict.start
+                    || Some(line) == conflict.base
+                    || line == conflict.separator
+                    || line == conflict.end;
+                let color = if is_marker {
+                    // Make the marker lines stand out from the changes, as
+                    // dark as the color painted twice
+                    let (r, g, b, a) = color.as_rgba();
+                    Color::rgba(r, g, b, 1.0 - (1.0 - a).powi(2))
+                } else {
+                    color.clone()
+                };
+                let rect = Size::new(self_size.width, info.line_height)
+                    .to_rect()
+                    .with_origin(Point::new(0.0, info.y));
+                ctx.fill(rect, &color);
+            }
+
+            let info = match screen_lines.info.get(&conflict.start) {
+                Some(info) => info,
+                None => continue,
+            };
+            let text_layout = data.doc.get_text_layout(
+                ctx.text(),
+                conflict.start,
+                info.font_size,
+                &data.config,
+            );
+            let mut x = info.x + text_layout.text.size().width + 20.0;
+            for (i, (label, command)) in [
+                ("Accept Current Change", FocusCommand::AcceptConflictCurrent),
+                (
+                    "Accept Incoming Change",
+                    FocusCommand::AcceptConflictIncoming,
+                ),
+                ("Accept Both Changes", FocusCommand::AcceptConflictBoth),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                if i > 0 {
+                    let separator = ctx
+                        .text()
+                        .new_text_layout(" | ")
+                        .font(
+                            data.config.ui.font_family(),
+                            data.config.ui.font_size() as f64,
+                        )
+                        .text_color(
+                            data.config
+                                .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                                .clone(),
+                        )
+                        .build()
+                        .unwrap();
+                    ctx.draw_text(
+                        &separator,
+                        Point::new(x, info.y + separator.y_offset(info.line_height)),
+                    );
+                    x += separator.size().width;
+                }
+
+                let text_layout = ctx
+                    .text()
+                    .new_text_layout(label)
+                    .font(
+                        data.config.ui.font_family(),
+                        data.config.ui.font_size() as f64,
+                    )
+                    .text_color(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                            .clone(),
+                    )
+                    .build()
+                    .unwrap();
+                ctx.draw_text(
+                    &text_layout,
+                    Point::new(x, info.y + text_layout.y_offset(info.line_height)),
+                );
+                let width = text_layout.size().width;
+                self.conflict_actions.push((
+                    Size::new(width, info.line_height)
+                        .to_rect()
+                        .with_origin(Point::new(x, info.y)),
+                    command,
+                ));
+                x += width;
+            }
+        }
+    }
+
+    /// Paint the code lenses on the virtual lines above the lines they are
+    /// for, aligned with the text of the line
+    fn paint_code_lens(
+        &mut self,
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        env: &Env,
+    ) {
+        self.code_lens_actions.clear();
+        if !data.editor.view.is_normal() || data.doc.code_lens().is_empty() {
+            return;
+        }
+
+        let line_height = Self::line_height(data, env);
+        let rect = ctx.region().bounding_box();
+        let start_row = (rect.y0 / line_height).floor() as usize;
+        let end_row = (rect.y1 / line_height).ceil() as usize;
+        let font_family = data.config.editor.inlay_hint_font_family();
+        let font_size = data.config.editor.inlay_hint_font_size() as f64;
+        let color = data.config.get_color_unchecked(LapceTheme::EDITOR_DIM);
+
+        for row in start_row..end_row + 1 {
+            let line = match data.doc.code_lens_line(row) {
+                Some(line) => line,
+                None => continue,
+            };
+
+            let y = row as f64 * line_height;
+            let col = data.doc.buffer().first_non_blank_character_on_line(line)
+                - data.doc.buffer().offset_of_line(line);
+            let mut x = data
+                .doc
+                .line_point_of_line_col(
+                    ctx.text(),
+                    line,
+                    col,
+                    data.config.editor.font_size,
+                    &data.config,
+                )
+                .x;
+            let lenses = data
+                .doc
+                .code_lens()
+                .line_lenses(data.doc.buffer().text(), line);
+            for (i, (index, command)) in lenses
+                .filter_map(|(index, lens)| Some((index, lens.command.as_ref()?)))
+                .enumerate()
+            {
+                if i > 0 {
+                    let separator = ctx
+                        .text()
+                        .new_text_layout(" | ")
+                        .font(font_family.clone(), font_size)
+                        .text_color(color.clone())
+                        .build()
+                        .unwrap();
+                    ctx.draw_text(
+                        &separator,
+                        Point::new(x, y + separator.y_offset(line_height)),
+                    );
+                    x += separator.size().width;
+                }
+
+                let text_layout = ctx
+                    .text()
+                    .new_text_layout(command.title.clone())
+                    .font(font_family.clone(), font_size)
+                    .text_color(color.clone())
+                    .build()
+                    .unwrap();
+                ctx.draw_text(
+                    &text_layout,
+                    Point::new(x, y + text_layout.y_offset(line_height)),
+                );
+                let width = text_layout.size().width;
+                // A lens without a command is only a label
+                if !command.command.is_empty() {
+                    self.code_lens_actions.push((
+                        Size::new(width, line_height)
+                            .to_rect()
+                            .with_origin(Point::new(x, y)),
+                        index,
+                    ));
+                }
+                x += width;
+            }
+        }
+    }
+
     fn paint_cursor_new(
         ctx: &mut PaintCtx,
         data: &LapceEditorBufferData,
@@ -1385,6 +1744,72 @@ fn paint_find(
         }
     }
 
+    /// Fill the occurrences of the symbol under the cursor, with the ones
+    /// where it's written standing out from the ones where it's read
+    fn paint_document_highlights(
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        if screen_lines.lines.is_empty() {
+            return;
+        }
+        let first_line = *screen_lines.lines.first().unwrap();
+        let last_line = *screen_lines.lines.last().unwrap();
+        let visible_start = data.doc.buffer().offset_of_line(first_line);
+        let visible_end = data.doc.buffer().offset_of_line(last_line + 1);
+
+        for (start, end, kind) in
+            data.doc.document_highlights().ranges().iter().filter(
+                |(start, end, _)| *end >= visible_start && *start <= visible_end,
+            )
+        {
+            let color = if *kind == DocumentHighlightKind::WRITE {
+                LapceTheme::EDITOR_HIGHLIGHT_WRITE
+            } else {
+                LapceTheme::EDITOR_HIGHLIGHT_READ
+            };
+            let color = data.config.get_color_unchecked(color);
+            let (start_line, start_col) =
+                data.doc.buffer().offset_to_line_col(*start);
+            let (end_line, end_col) = data.doc.buffer().offset_to_line_col(*end);
+            for line in &screen_lines.lines {
+                let line = *line;
+                if line < start_line {
+                    continue;
+                }
+                if line > end_line {
+                    break;
+                }
+
+                let info = screen_lines.info.get(&line).unwrap();
+
+                let left_col = if line == start_line { start_col } else { 0 };
+                let right_col = if line == end_line {
+                    end_col
+                } else {
+                    data.doc.buffer().line_end_col(line, true) + 1
+                };
+
+                let phantom_text = data.doc.line_phantom_text(&data.config, line);
+                let left_col = phantom_text.col_at(left_col);
+                let right_col = phantom_text.col_at(right_col);
+
+                let text_layout = data.doc.get_text_layout(
+                    ctx.text(),
+                    line,
+                    info.font_size,
+                    &data.config,
+                );
+                let x0 = text_layout.text.hit_test_text_position(left_col).point.x;
+                let x1 = text_layout.text.hit_test_text_position(right_col).point.x;
+                let y0 = info.y;
+                let y1 = info.y + info.line_height;
+                ctx.fill(Rect::new(x0 + info.x, y0, x1 + info.x, y1), color);
+            }
+        }
+    }
+
     fn paint_sticky_headers(
         ctx: &mut PaintCtx,
         data: &LapceEditorBufferData,
@@ -1412,15 +1837,16 @@ fn paint_sticky_headers(
         let rect = ctx.region().bounding_box();
         let x0 = rect.x0;
         let y0 = rect.y0;
-        let start_line = (rect.y0 / line_height).floor() as usize;
-        let y_diff = y0 - start_line as f64 * line_height;
+        let start_visual_line = (rect.y0 / line_height).floor() as usize;
+        let y_diff = y0 - start_visual_line as f64 * line_height;
         let mut last_sticky_should_scroll = false;
 
+        let start_line = data.doc.actual_line(start_visual_line);
         let mut sticky_lines = Vec::new();
         if let Some(lines) = data.doc.sticky_headers(start_line) {
             let total_lines = lines.len();
             if total_lines > 0 {
-                let line = start_line + total_lines;
+                let line = data.doc.actual_line(start_visual_line + total_lines);
                 if let Some(new_lines) = data.doc.sticky_headers(line) {
                     if new_lines.len() > total_lines {
                         sticky_lines = new_lines;
@@ -1429,7 +1855,9 @@ fn paint_sticky_headers(
                         last_sticky_should_scroll = new_lines.len() < total_lines;
                         if new_lines.len() < total_lines {
                             if let Some(new_new_lines) =
-                                data.doc.sticky_headers(start_line + total_lines - 1)
+                                data.doc.sticky_headers(data.doc.actual_line(
+                                    start_visual_line + total_lines - 1,
+                                ))
                             {
                                 if new_new_lines.len() < total_lines {
                                     sticky_lines.pop();
@@ -1453,8 +1881,8 @@ fn paint_sticky_headers(
         let paint_last_line = total_sticky_lines > 0
             && (last_sticky_should_scroll
                 || y_diff != 0.0
-                || start_line + total_sticky_lines - 1
-                    != *sticky_lines.last().unwrap());
+                || start_visual_line + total_sticky_lines - 1
+                    != data.doc.visual_line(*sticky_lines.last().unwrap()));
 
         // Fix up the line count in case we don't need to paint the last one.
         let total_sticky_lines = if paint_last_line {
@@ -1915,7 +2343,9 @@ fn event(
                 match cmd.get_unchecked(LAPCE_UI_COMMAND) {
                     LapceUICommand::ShowCodeActions(point) => {
                         let editor_data = data.editor_view_content(self.view_id);
-                        if let Some(actions) = editor_data.current_code_actions() {
+                        if let Some((plugin_id, actions)) =
+                            editor_data.current_code_actions()
+                        {
                             if !actions.is_empty() {
                                 let mut menu = druid::Menu::new("");
 
@@ -1933,6 +2363,7 @@ fn event(
                                         LAPCE_UI_COMMAND,
                                         LapceUICommand::RunCodeAction(
                                             action.clone(),
+                                            *plugin_id,
                                         ),
                                         Target::Widget(editor_data.view_id),
                                     ));
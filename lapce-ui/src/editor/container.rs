@@ -1,9 +1,10 @@
 use druid::{
     kurbo::Line, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, Point, RenderContext, Size, UpdateCtx, Widget, WidgetId,
-    WidgetPod,
+    LifeCycleCtx, PaintCtx, Point, Rect, RenderContext, Size, UpdateCtx, Widget,
+    WidgetId, WidgetPod,
 };
 use lapce_data::{config::LapceTheme, data::LapceTabData};
+use lsp_types::DocumentHighlightKind;
 
 use crate::{
     editor::{gutter::LapceEditorGutter, LapceEditor},
@@ -61,6 +62,49 @@ fn show_bread_crumbs(&self, data: &LapceTabData) -> bool {
 
         false
     }
+
+    /// Mark where the occurrences of the symbol under the cursor are on the
+    /// vertical scroll bar of the editor
+    fn paint_scroll_bar_markers(&self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        let editor_data = data.editor_view_content(self.view_id);
+        let doc = &editor_data.doc;
+        if doc.document_highlights().is_empty()
+            || !editor_data.editor.view.is_normal()
+        {
+            return;
+        }
+
+        let rect = self.editor.layout_rect();
+        let content_height = self.editor.widget().inner().child_size().height;
+        if content_height <= 0.0 {
+            return;
+        }
+        let line_height = data.config.editor.line_height() as f64;
+        let scroll_width = data.config.ui.scroll_width() as f64;
+        for (start, _, kind) in doc.document_highlights().ranges() {
+            let line = doc.buffer().line_of_offset(*start);
+            let y = doc.visual_line(line) as f64 * line_height / content_height
+                * rect.height();
+            let color = if *kind == DocumentHighlightKind::WRITE {
+                LapceTheme::EDITOR_HIGHLIGHT_WRITE
+            } else {
+                LapceTheme::EDITOR_HIGHLIGHT_READ
+            };
+            ctx.fill(
+                Rect::new(
+                    rect.x1 - scroll_width,
+                    rect.y0 + y,
+                    rect.x1,
+                    rect.y0 + y + 2.0,
+                ),
+                &data
+                    .config
+                    .get_color_unchecked(color)
+                    .clone()
+                    .with_alpha(1.0),
+            );
+        }
+    }
 }
 
 impl Widget<LapceTabData> for LapceEditorContainer {
@@ -186,6 +230,7 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
         let show_bread_crumbs = self.show_bread_crumbs(data);
 
         self.editor.paint(ctx, data, env);
+        self.paint_scroll_bar_markers(ctx, data);
         if self.display_gutter {
             self.gutter.paint(ctx, data, env);
         }
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use crate::svg::get_svg;
 use druid::{
     piet::{PietText, Text, TextLayout, TextLayoutBuilder},
@@ -20,6 +22,9 @@ pub struct LapceEditorGutter {
 }
 
 impl LapceEditorGutter {
+    /// The width of the column of the fold markers
+    const FOLD_WIDTH: f64 = 14.0;
+
     pub fn new(view_id: WidgetId) -> Self {
         Self {
             view_id,
@@ -43,7 +48,21 @@ fn event(
             }
             Event::MouseUp(mouse_event) => {
                 let data = data.editor_view_content(self.view_id);
-                if let Some(actions) = data.current_code_actions() {
+                if let Some(line) =
+                    self.fold_marker_line(ctx.text(), &data, self.mouse_down_pos)
+                {
+                    if Some(line)
+                        == self.fold_marker_line(ctx.text(), &data, mouse_event.pos)
+                    {
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::ToggleFold(line),
+                            Target::Widget(self.view_id),
+                        ));
+                        return;
+                    }
+                }
+                if let Some((_, actions)) = data.current_code_actions() {
                     if !actions.is_empty() {
                         let rect = self.code_actions_rect(ctx.text(), &data);
                         if rect.contains(self.mouse_down_pos)
@@ -54,6 +73,7 @@ fn event(
                             let offset = data.editor.cursor.offset();
                             let (line, _) =
                                 data.doc.buffer().offset_to_line_col(offset);
+                            let line = data.doc.visual_line(line);
                             ctx.submit_command(Command::new(
                                 LAPCE_UI_COMMAND,
                                 LapceUICommand::ShowCodeActions(Some(
@@ -128,7 +148,7 @@ fn layout(
         let last_line = data.doc.buffer().last_line() + 1;
         let char_width = data.config.editor_char_width(ctx.text());
         self.width = (char_width * last_line.to_string().len() as f64).ceil();
-        let mut width = self.width + 16.0 + char_width * 2.0;
+        let mut width = self.width + 16.0 + char_width * 2.0 + Self::FOLD_WIDTH;
         if data.editor.compare.is_some() {
             width += self.width + char_width * 2.0;
         }
@@ -488,6 +508,7 @@ fn code_actions_rect(
         let line_height = data.config.editor.line_height() as f64;
         let offset = data.editor.cursor.offset();
         let (line, _) = data.doc.buffer().offset_to_line_col(offset);
+        let line = data.doc.visual_line(line);
 
         let width = 16.0;
         let height = 16.0;
@@ -499,12 +520,92 @@ fn code_actions_rect(
         ))
     }
 
+    /// The line of the fold marker at the point, if there's one there
+    fn fold_marker_line(
+        &self,
+        text: &mut PietText,
+        data: &LapceEditorBufferData,
+        pos: Point,
+    ) -> Option<usize> {
+        if !data.editor.view.is_normal() || data.editor.is_code_lens() {
+            return None;
+        }
+        let char_width = data.config.editor_char_width(text);
+        let x0 = self.width + 16.0 + char_width * 2.0;
+        if pos.x < x0 || pos.x > x0 + Self::FOLD_WIDTH {
+            return None;
+        }
+
+        let line_height = data.config.editor.line_height() as f64;
+        let visual_line =
+            ((pos.y + data.editor.scroll_offset.y) / line_height).floor() as usize;
+        if data.doc.code_lens_line(visual_line).is_some() {
+            return None;
+        }
+        let line = data.doc.actual_line(visual_line);
+        if data.doc.is_folded(line)
+            || data
+                .doc
+                .folding_ranges()
+                .iter()
+                .any(|range| range.start_line == line)
+        {
+            Some(line)
+        } else {
+            None
+        }
+    }
+
+    /// Paint the markers of the regions that can be folded and of the ones
+    /// that are folded
+    fn paint_fold_markers(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        lines: &[(usize, f64)],
+    ) {
+        let (first, last) = match (lines.first(), lines.last()) {
+            (Some(first), Some(last)) => (first.0, last.0),
+            _ => return,
+        };
+        let foldable: HashSet<usize> = data
+            .doc
+            .folding_ranges()
+            .iter()
+            .map(|range| range.start_line)
+            .filter(|line| first <= *line && *line <= last)
+            .collect();
+
+        let char_width = data.config.editor_char_width(ctx.text());
+        let line_height = data.config.editor.line_height() as f64;
+        let size = 12.0;
+        let x =
+            self.width + 16.0 + char_width * 2.0 + (Self::FOLD_WIDTH - size) / 2.0;
+        for (line, y) in lines.iter().copied() {
+            let (svg, color) = if data.doc.is_folded(line) {
+                ("chevron-right.svg", LapceTheme::EDITOR_FOREGROUND)
+            } else if foldable.contains(&line) {
+                ("chevron-down.svg", LapceTheme::EDITOR_DIM)
+            } else {
+                continue;
+            };
+            let rect = Size::new(size, size)
+                .to_rect()
+                .with_origin(Point::new(x, y + (line_height - size) / 2.0));
+            ctx.draw_svg(
+                &get_svg(svg).unwrap(),
+                rect,
+                Some(data.config.get_color_unchecked(color)),
+            );
+        }
+    }
+
     fn paint_code_actions_hint(
         &self,
         data: &LapceEditorBufferData,
         ctx: &mut PaintCtx,
     ) {
-        if let Some(actions) = data.current_code_actions() {
+        if let Some((_, actions)) = data.current_code_actions() {
             if !actions.is_empty() {
                 let svg = get_svg("lightbulb.svg").unwrap();
                 let rect = self.code_actions_rect(ctx.text(), data);
@@ -592,9 +693,10 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
             }
             let line_height = data.config.editor.line_height() as f64;
             let scroll_offset = data.editor.scroll_offset;
-            let start_line = (scroll_offset.y / line_height).floor() as usize;
+            let start_visual_line = (scroll_offset.y / line_height).floor() as usize;
             let num_lines = (ctx.size().height / line_height).floor() as usize;
             let last_line = data.doc.buffer().last_line();
+            let start_line = data.doc.actual_line(start_visual_line);
             let current_line = data
                 .doc
                 .buffer()
@@ -603,7 +705,10 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
 
             let line_label_length =
                 (last_line + 1).to_string().len() as f64 * char_width;
-            let last_displayed_line = (start_line + num_lines + 1).min(last_line);
+            let last_displayed_line = data
+                .doc
+                .actual_line(start_visual_line + num_lines + 1)
+                .min(last_line);
 
             let sequential_line_numbers = *data.main_split.active
                 != Some(data.view_id)
@@ -612,7 +717,22 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
 
             let font_family = data.config.editor.font_family();
 
-            for line in start_line..last_displayed_line + 1 {
+            // The lines that are displayed with their y, which leaves out the
+            // virtual lines of the code lenses
+            let lines: Vec<(usize, f64)> = (start_visual_line..)
+                .filter(|visual_line| {
+                    data.doc.code_lens_line(*visual_line).is_none()
+                })
+                .map(|visual_line| {
+                    (
+                        data.doc.actual_line(visual_line),
+                        line_height * visual_line as f64 - scroll_offset.y,
+                    )
+                })
+                .take_while(|(line, _)| *line <= last_displayed_line)
+                .collect();
+
+            for (line, line_y) in lines.iter().copied() {
                 let line_no = if sequential_line_numbers || line == current_line {
                     line + 1
                 } else {
@@ -646,15 +766,17 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
                 let x = line_label_length as f64 - text_layout.size().width;
 
                 // Vertically centered
-                let y = line_height * line as f64 - scroll_offset.y
-                    + text_layout.y_offset(line_height);
+                let y = line_y + text_layout.y_offset(line_height);
 
                 ctx.draw_text(&text_layout, Point::new(x, y));
             }
 
+            self.paint_fold_markers(ctx, data, &lines);
+
             if let Some(history) = data.doc.get_history("head") {
-                let end_line =
-                    (scroll_offset.y + rect.height() / line_height).ceil() as usize;
+                let end_line = data.doc.actual_line(
+                    (scroll_offset.y + rect.height() / line_height).ceil() as usize,
+                );
 
                 let mut line = 0;
                 let mut last_change = None;
@@ -698,8 +820,15 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
                     if let Some(color) = color.cloned() {
                         let removed_height = 10.0;
                         let x = self.width + char_width;
+                        let start_visual_line = data.doc.visual_line(line - len);
                         let mut y =
-                            (line - len) as f64 * line_height - scroll_offset.y;
+                            start_visual_line as f64 * line_height - scroll_offset.y;
+                        // The lines hidden by folds don't take any space
+                        let len = if len == 0 {
+                            0
+                        } else {
+                            data.doc.visual_line(line - 1) + 1 - start_visual_line
+                        };
                         if len == 0 {
                             y -= removed_height / 2.0;
                         }
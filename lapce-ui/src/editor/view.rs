@@ -44,6 +44,12 @@ pub struct LapceEditorView {
     cursor_blink_timer: TimerToken,
     autosave_timer: TimerToken,
     last_idle_timer: TimerToken,
+    /// Started when the cursor moves, to highlight the occurrences of the
+    /// symbol under it once it stays there
+    document_highlight_timer: TimerToken,
+    /// Started when the text changes, to ask for the code lenses once the
+    /// typing stops
+    code_lens_timer: TimerToken,
     display_border: bool,
     background_color_name: &'static str,
 }
@@ -97,6 +103,8 @@ pub fn new(
             cursor_blink_timer: TimerToken::INVALID,
             autosave_timer: TimerToken::INVALID,
             last_idle_timer: TimerToken::INVALID,
+            document_highlight_timer: TimerToken::INVALID,
+            code_lens_timer: TimerToken::INVALID,
             display_border: true,
             background_color_name: LapceTheme::EDITOR_BACKGROUND,
         }
@@ -209,15 +217,25 @@ pub fn handle_lapce_ui_command(
         env: &Env,
     ) {
         match cmd {
-            LapceUICommand::RunCodeAction(action) => {
-                data.run_code_action(ctx, action);
+            LapceUICommand::RunCodeAction(action, plugin_id) => {
+                data.run_code_action(ctx, action, *plugin_id);
             }
             LapceUICommand::ApplyWorkspaceEdit(edit) => {
                 data.apply_workspace_edit(ctx, edit);
             }
+            LapceUICommand::CodeActionsOnSave { rev, result, exit } => {
+                data.apply_code_actions_on_save(ctx, *rev, result, *exit);
+            }
+            LapceUICommand::FormatAndSave { exit } => {
+                data.format_and_save(ctx, *exit);
+            }
             LapceUICommand::EnsureCursorVisible(position) => {
+                data.reveal_cursor();
                 self.ensure_cursor_visible(ctx, data, panel, position.as_ref(), env);
             }
+            LapceUICommand::ToggleFold(line) => {
+                data.toggle_fold(*line);
+            }
             LapceUICommand::EnsureCursorPosition(position) => {
                 self.ensure_cursor_position(ctx, data, panel, position, env);
             }
@@ -521,7 +539,7 @@ fn cursor_region(data: &LapceEditorBufferData, text: &mut PietText) -> Rect {
             let line = if let EditorView::Diff(version) = &data.editor.view {
                 data.doc.history_visual_line(version, line)
             } else {
-                line
+                data.doc.visual_line(line)
             };
             line as f64 * line_height
         };
@@ -638,6 +656,25 @@ fn event(
                     }
                 }
             }
+            Event::Timer(id) if self.document_highlight_timer == *id => {
+                ctx.set_handled();
+                let offset = data
+                    .main_split
+                    .editors
+                    .get(&self.view_id)
+                    .map(|editor| editor.cursor.offset());
+                let doc = data.main_split.editor_doc(self.view_id);
+                // The occurrences are kept while the cursor moves among them
+                if let Some(offset) = offset
+                    .filter(|offset| !doc.document_highlights().contains(*offset))
+                {
+                    doc.get_document_highlights(offset);
+                }
+            }
+            Event::Timer(id) if self.code_lens_timer == *id => {
+                ctx.set_handled();
+                data.main_split.editor_doc(self.view_id).get_code_lens();
+            }
             Event::Timer(id) if self.autosave_timer == *id => {
                 ctx.set_handled();
                 if let Some(editor) = data
@@ -687,6 +724,7 @@ fn event(
                     &mut editor_data,
                     env,
                 ) {
+                    editor_data.reveal_cursor();
                     self.ensure_cursor_visible(
                         ctx,
                         &editor_data,
@@ -715,6 +753,7 @@ fn event(
                 {
                     ctx.set_handled();
                 }
+                editor_data.reveal_cursor();
                 self.ensure_cursor_visible(
                     ctx,
                     &editor_data,
@@ -859,6 +898,13 @@ fn update(
         let old_editor_data = old_data.editor_view_content(self.view_id);
         let editor_data = data.editor_view_content(self.view_id);
 
+        if !old_editor_data.doc.same(&editor_data.doc)
+            || old_editor_data.editor.scroll_offset
+                != editor_data.editor.scroll_offset
+        {
+            editor_data.resolve_visible_code_lens();
+        }
+
         if let BufferContent::SettingsValue(..) = &editor_data.editor.content {
             if !editor_data.doc.buffer().is_pristine()
                 && (editor_data.doc.buffer().len()
@@ -897,6 +943,24 @@ fn update(
             }
         }
 
+        if *data.focus == self.view_id
+            && (editor_data.editor.cursor.offset()
+                != old_editor_data.editor.cursor.offset()
+                || editor_data.doc.rev() != old_editor_data.doc.rev())
+        {
+            self.document_highlight_timer = ctx.request_timer(
+                Duration::from_millis(data.config.editor.document_highlight_delay),
+                None,
+            );
+        }
+
+        if editor_data.doc.rev() != old_editor_data.doc.rev()
+            && editor_data.doc.content().is_file()
+        {
+            self.code_lens_timer =
+                ctx.request_timer(Duration::from_millis(500), None);
+        }
+
         if data.config.editor.autosave_interval > 0
             && editor_data.doc.rev() != old_editor_data.doc.rev()
         {
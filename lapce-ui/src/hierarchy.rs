@@ -0,0 +1,350 @@
+use druid::{
+    piet::{Text, TextLayout as PietTextLayout, TextLayoutBuilder},
+    BoxConstraints, Command, Cursor, Data, Env, Event, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point, RenderContext, Size,
+    Target, UpdateCtx, Widget, WidgetExt,
+};
+use lapce_data::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    config::LapceTheme,
+    data::LapceTabData,
+    editor::EditorLocation,
+    hierarchy::{HierarchyData, HierarchyKind},
+    panel::PanelKind,
+    proxy::path_from_url,
+};
+
+use crate::{
+    panel::{LapcePanel, PanelHeaderKind, PanelSizing},
+    svg::{get_svg, symbol_svg},
+};
+
+pub fn new_hierarchy_panel(data: &HierarchyData) -> LapcePanel {
+    let (kind, title) = match data.kind {
+        HierarchyKind::Call => (PanelKind::CallHierarchy, "Call Hierarchy"),
+        HierarchyKind::Type => (PanelKind::TypeHierarchy, "Type Hierarchy"),
+    };
+    LapcePanel::new(
+        kind,
+        data.widget_id,
+        data.split_id,
+        vec![(
+            data.tree_id,
+            PanelHeaderKind::Simple(title.into()),
+            HierarchyContent::new(data.kind).boxed(),
+            PanelSizing::Flex(true),
+        )],
+    )
+}
+
+fn hierarchy(data: &LapceTabData, kind: HierarchyKind) -> &HierarchyData {
+    match kind {
+        HierarchyKind::Call => data.call_hierarchy.as_ref(),
+        HierarchyKind::Type => data.type_hierarchy.as_ref(),
+    }
+}
+
+/// The tree of the hierarchy, below a first line which shows and switches
+/// the direction of the tree
+struct HierarchyContent {
+    kind: HierarchyKind,
+    mouse_pos: Point,
+    content_height: f64,
+}
+
+impl HierarchyContent {
+    pub fn new(kind: HierarchyKind) -> Self {
+        Self {
+            kind,
+            mouse_pos: Point::ZERO,
+            content_height: 0.0,
+        }
+    }
+
+    fn mouse_down(
+        &self,
+        ctx: &mut EventCtx,
+        mouse_event: &MouseEvent,
+        data: &LapceTabData,
+    ) {
+        if !ctx.is_hot() {
+            return;
+        }
+
+        let hierarchy = hierarchy(data, self.kind);
+        if hierarchy.roots.is_empty() {
+            return;
+        }
+
+        let line_height = data.config.editor.line_height() as f64;
+        let click_line = (mouse_event.pos.y / line_height).floor() as usize;
+        if click_line == 0 {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::ToggleHierarchyDirection(self.kind),
+                Target::Widget(data.id),
+            ));
+            return;
+        }
+
+        let rows = hierarchy.rows();
+        let row = match rows.get(click_line - 1) {
+            Some(row) => row,
+            None => return,
+        };
+
+        // The arrow in front of the item expands or collapses it, and the rest
+        // of the line jumps to it
+        if mouse_event.pos.x < (row.depth + 1) as f64 * line_height {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::ToggleHierarchyItem {
+                    kind: self.kind,
+                    path: row.path.clone(),
+                },
+                Target::Widget(data.id),
+            ));
+        } else {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::JumpToLspLocation(
+                    None,
+                    EditorLocation {
+                        path: path_from_url(&row.node.item.uri),
+                        position: Some(row.node.item.selection_range.start),
+                        scroll_offset: None,
+                        history: None,
+                    },
+                    false,
+                ),
+                Target::Widget(data.id),
+            ));
+        }
+    }
+}
+
+impl Widget<LapceTabData> for HierarchyContent {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(mouse_event) => {
+                self.mouse_pos = mouse_event.pos;
+
+                if mouse_event.pos.y < self.content_height {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+
+                ctx.request_paint();
+            }
+            Event::MouseDown(mouse_event) => {
+                self.mouse_down(ctx, mouse_event, data);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        let changed = match self.kind {
+            HierarchyKind::Call => {
+                !data.call_hierarchy.same(&old_data.call_hierarchy)
+            }
+            HierarchyKind::Type => {
+                !data.type_hierarchy.same(&old_data.type_hierarchy)
+            }
+        };
+        if changed {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let hierarchy = hierarchy(data, self.kind);
+        let lines = if hierarchy.roots.is_empty() {
+            0
+        } else {
+            hierarchy.rows().len() + 1 /* direction header */
+        };
+        let line_height = data.config.editor.line_height() as f64;
+        self.content_height = line_height * lines as f64;
+
+        Size::new(bc.max().width, self.content_height.max(bc.max().height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        let hierarchy = hierarchy(data, self.kind);
+        if hierarchy.roots.is_empty() {
+            return;
+        }
+
+        let line_height = data.config.editor.line_height() as f64;
+        let padding = (line_height - 14.0) / 2.0;
+        let size = ctx.size();
+        let mouse_line = (self.mouse_pos.y / line_height).floor() as usize;
+
+        let rect = ctx.region().bounding_box();
+        let min = (rect.y0 / line_height).floor() as usize;
+        let max = (rect.y1 / line_height) as usize + 2;
+
+        let ui_font_family = data.config.ui.font_family();
+        let ui_font_size = data.config.ui.font_size() as f64;
+
+        let rows = hierarchy.rows();
+        if ctx.is_hot() && mouse_line <= rows.len() {
+            ctx.fill(
+                Size::new(size.width, line_height)
+                    .to_rect()
+                    .with_origin(Point::new(0.0, line_height * mouse_line as f64)),
+                data.config.get_color_unchecked(LapceTheme::PANEL_HOVERED),
+            );
+        }
+
+        let text_layout = ctx
+            .text()
+            .new_text_layout(format!(
+                "{}  (click to show {})",
+                hierarchy.direction.label(),
+                hierarchy.direction.toggled().label().to_lowercase()
+            ))
+            .font(ui_font_family.clone(), ui_font_size)
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(padding, text_layout.y_offset(line_height)),
+        );
+
+        for (i, row) in rows.iter().enumerate() {
+            let line = i + 1;
+            if line < min {
+                continue;
+            }
+            if line > max {
+                break;
+            }
+
+            let y = line_height * line as f64;
+            let x = row.depth as f64 * line_height;
+            let item = &row.node.item;
+
+            // Items without children never show an arrow once they are known
+            let has_children = row
+                .node
+                .children
+                .as_ref()
+                .map(|children| !children.is_empty())
+                .unwrap_or(true);
+            if has_children {
+                let icon_name = if row.node.expanded {
+                    "chevron-down.svg"
+                } else {
+                    "chevron-right.svg"
+                };
+                let rect = Size::new(line_height, line_height)
+                    .to_rect()
+                    .with_origin(Point::new(x, y))
+                    .inflate(-padding, -padding);
+                ctx.draw_svg(
+                    &get_svg(icon_name).unwrap(),
+                    rect,
+                    Some(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                    ),
+                );
+            }
+
+            let x = x + line_height;
+            if let Some(svg) = symbol_svg(&item.kind) {
+                let rect = Size::new(line_height, line_height)
+                    .to_rect()
+                    .with_origin(Point::new(x, y))
+                    .inflate(-padding, -padding);
+                ctx.draw_svg(
+                    &svg,
+                    rect,
+                    Some(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                    ),
+                );
+            }
+
+            let x = x + line_height;
+            let text_layout = ctx
+                .text()
+                .new_text_layout(item.name.clone())
+                .font(ui_font_family.clone(), ui_font_size)
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, y + text_layout.y_offset(line_height)),
+            );
+
+            let path = path_from_url(&item.uri);
+            let location = format!(
+                "{}{}:{}",
+                item.detail
+                    .as_ref()
+                    .map(|detail| format!("{detail} "))
+                    .unwrap_or_default(),
+                path.file_name().and_then(|f| f.to_str()).unwrap_or(""),
+                item.selection_range.start.line + 1,
+            );
+            let x = x + text_layout.size().width + 5.0;
+            let text_layout = ctx
+                .text()
+                .new_text_layout(location)
+                .font(ui_font_family.clone(), ui_font_size)
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+}
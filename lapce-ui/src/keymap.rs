@@ -95,6 +95,7 @@ fn mouse_down(&mut self, ctx: &mut EventCtx, pos: Point, data: &LapceTabData) {
                         key: Vec::new(),
                         modes: Modes::empty(),
                         when: None,
+                        args: None,
                     },
                     Vec::new(),
                 ));
@@ -272,11 +273,26 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
                             size.width / 2.0 - keypress_width,
                             (i + 1) as f64 * self.line_height,
                         ));
-                        let text_layout = ctx
+                        let desc = cmd.kind.desc().unwrap_or_else(|| cmd.kind.str());
+                        // The arguments follow the command, with the reason
+                        // they're invalid first when they are
+                        let args = keymap.args.as_ref().map(|args| {
+                            match data.keypress.args_error(keymap) {
+                                Some(err) => (
+                                    format!(" {err}: {args}"),
+                                    LapceTheme::LAPCE_ERROR,
+                                ),
+                                None => (format!(" {args}"), LapceTheme::EDITOR_DIM),
+                            }
+                        });
+                        let mut text_layout = ctx
                             .text()
-                            .new_text_layout(
-                                cmd.kind.desc().unwrap_or_else(|| cmd.kind.str()),
-                            )
+                            .new_text_layout(format!(
+                                "{desc}{}",
+                                args.as_ref()
+                                    .map(|(args, _)| args.as_str())
+                                    .unwrap_or("")
+                            ))
                             .font(
                                 data.config.ui.font_family(),
                                 data.config.ui.font_size() as f64,
@@ -287,9 +303,16 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
                                         LapceTheme::EDITOR_FOREGROUND,
                                     )
                                     .clone(),
-                            )
-                            .build()
-                            .unwrap();
+                            );
+                        if let Some((args, color)) = args.as_ref() {
+                            text_layout = text_layout.range_attribute(
+                                desc.len()..desc.len() + args.len(),
+                                TextAttribute::TextColor(
+                                    data.config.get_color_unchecked(color).clone(),
+                                ),
+                            );
+                        }
+                        let text_layout = text_layout.build().unwrap();
                         ctx.draw_text(
                             &text_layout,
                             Point::new(
@@ -476,6 +499,7 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
                 modes: keymap.modes,
                 when: keymap.when.clone(),
                 command: keymap.command.clone(),
+                args: keymap.args.clone(),
             }
             .paint(ctx, rect.center(), Alignment::Center, &data.config);
 
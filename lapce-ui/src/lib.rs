@@ -6,10 +6,12 @@
 pub mod editor;
 pub mod explorer;
 pub mod find;
+pub mod hierarchy;
 pub mod hover;
 pub mod keymap;
 pub mod list;
 mod logging;
+pub mod notification;
 pub mod palette;
 pub mod panel;
 pub mod picker;
@@ -18,6 +20,7 @@
 pub mod scroll;
 pub mod search;
 pub mod settings;
+pub mod signature;
 pub mod source_control;
 pub mod split;
 pub mod status;
@@ -0,0 +1,295 @@
+use druid::{
+    piet::{PietTextLayout, Text, TextAttribute, TextLayout, TextLayoutBuilder},
+    BoxConstraints, Command, Cursor, Env, Event, EventCtx, FontWeight, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Point, Rect, RenderContext, Size, Target,
+    UpdateCtx, Widget, WidgetId,
+};
+use lapce_data::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    config::LapceTheme,
+    data::LapceTabData,
+};
+use lsp_types::MessageType;
+
+use crate::svg::get_svg;
+
+/// The layout of a notification, relative to the list
+struct NotificationLayout {
+    id: u64,
+    rect: Rect,
+    icon: Option<(&'static str, &'static str, Rect)>,
+    title: PietTextLayout,
+    title_origin: Point,
+    message: PietTextLayout,
+    message_origin: Point,
+    close: Rect,
+    actions: Vec<(PietTextLayout, Rect)>,
+}
+
+/// The notifications stacked in the bottom right corner, newest last
+pub struct NotificationList {
+    widget_id: WidgetId,
+    width: f64,
+    padding: f64,
+    icon_size: f64,
+    button_height: f64,
+    layouts: Vec<NotificationLayout>,
+}
+
+impl NotificationList {
+    pub fn new(data: &LapceTabData) -> Self {
+        Self {
+            widget_id: data.notifications.widget_id,
+            width: 360.0,
+            padding: 10.0,
+            icon_size: 14.0,
+            button_height: 24.0,
+            layouts: Vec::new(),
+        }
+    }
+
+    /// The notification and the action under the point, where no action means
+    /// the close button was hit
+    fn hit_test(&self, pos: Point) -> Option<(u64, Option<usize>)> {
+        let layout = self.layouts.iter().find(|l| l.rect.contains(pos))?;
+        if layout.close.contains(pos) {
+            return Some((layout.id, None));
+        }
+        let action = layout
+            .actions
+            .iter()
+            .position(|(_, rect)| rect.contains(pos))?;
+        Some((layout.id, Some(action)))
+    }
+}
+
+impl Widget<LapceTabData> for NotificationList {
+    fn id(&self) -> Option<WidgetId> {
+        Some(self.widget_id)
+    }
+
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(mouse_event) => {
+                if !ctx.is_hot() {
+                    return;
+                }
+                if self.hit_test(mouse_event.pos).is_some() {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+                ctx.set_handled();
+            }
+            Event::MouseDown(mouse_event) => {
+                if !ctx.is_hot() {
+                    return;
+                }
+                if let Some((id, action)) = self.hit_test(mouse_event.pos) {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::CloseNotification { id, action },
+                        Target::Widget(data.id),
+                    ));
+                }
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if old_data.notifications.items != data.notifications.items {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let width = self.width.min(bc.max().width);
+        let text_width = width - self.padding * 3.0 - self.icon_size * 2.0;
+        let font_family = data.config.ui.font_family();
+        let font_size = data.config.ui.font_size() as f64;
+        let foreground = data
+            .config
+            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+            .clone();
+
+        self.layouts.clear();
+        let mut y = 0.0;
+        for notification in data.notifications.items.iter() {
+            let top = y;
+            y += self.padding;
+
+            let icon = match notification.typ {
+                MessageType::ERROR => Some(("error.svg", LapceTheme::LAPCE_ERROR)),
+                MessageType::WARNING => {
+                    Some(("warning.svg", LapceTheme::LAPCE_WARN))
+                }
+                _ => None,
+            }
+            .map(|(svg, color)| {
+                let rect = Size::new(self.icon_size, self.icon_size)
+                    .to_rect()
+                    .with_origin(Point::new(self.padding, y + 2.0));
+                (svg, color, rect)
+            });
+            let text_x = self.padding * 2.0 + self.icon_size;
+
+            let title = ctx
+                .text()
+                .new_text_layout(notification.title.clone())
+                .font(font_family.clone(), font_size)
+                .default_attribute(TextAttribute::Weight(FontWeight::BOLD))
+                .max_width(text_width)
+                .text_color(foreground.clone())
+                .build()
+                .unwrap();
+            let title_origin = Point::new(text_x, y);
+            let close = Size::new(self.icon_size, self.icon_size)
+                .to_rect()
+                .with_origin(Point::new(
+                    width - self.padding - self.icon_size,
+                    y + 2.0,
+                ));
+            y += title.size().height + self.padding / 2.0;
+
+            let message = ctx
+                .text()
+                .new_text_layout(notification.message.clone())
+                .font(font_family.clone(), font_size - 1.0)
+                .set_line_height(1.2)
+                .max_width(text_width)
+                .text_color(foreground.clone())
+                .build()
+                .unwrap();
+            let message_origin = Point::new(text_x, y);
+            y += message.size().height;
+
+            // The buttons are put in rows which fit the width of the box
+            let mut actions = Vec::new();
+            let mut x = text_x;
+            if !notification.actions.is_empty() {
+                y += self.padding;
+            }
+            for action in notification.actions.iter() {
+                let text = ctx
+                    .text()
+                    .new_text_layout(action.title.clone())
+                    .font(font_family.clone(), font_size)
+                    .text_color(foreground.clone())
+                    .build()
+                    .unwrap();
+                let button_width = text.size().width + self.padding * 2.0;
+                if x > text_x && x + button_width > width - self.padding {
+                    x = text_x;
+                    y += self.button_height + self.padding / 2.0;
+                }
+                let rect = Size::new(button_width, self.button_height)
+                    .to_rect()
+                    .with_origin(Point::new(x, y));
+                actions.push((text, rect));
+                x += button_width + self.padding / 2.0;
+            }
+            if !notification.actions.is_empty() {
+                y += self.button_height;
+            }
+
+            y += self.padding;
+            self.layouts.push(NotificationLayout {
+                id: notification.id,
+                rect: Rect::new(0.0, top, width, y),
+                icon,
+                title,
+                title_origin,
+                message,
+                message_origin,
+                close,
+                actions,
+            });
+            y += self.padding;
+        }
+
+        Size::new(width, (y - self.padding).max(0.0))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        let shadow_width = data.config.ui.drop_shadow_width() as f64;
+        for layout in self.layouts.iter() {
+            if shadow_width > 0.0 {
+                ctx.blurred_rect(
+                    layout.rect,
+                    shadow_width,
+                    data.config
+                        .get_color_unchecked(LapceTheme::LAPCE_DROPDOWN_SHADOW),
+                );
+            } else {
+                ctx.stroke(
+                    layout.rect.inflate(0.5, 0.5),
+                    data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+                    1.0,
+                );
+            }
+            ctx.fill(
+                layout.rect,
+                data.config
+                    .get_color_unchecked(LapceTheme::PANEL_BACKGROUND),
+            );
+
+            if let Some((svg, color, rect)) = layout.icon {
+                ctx.draw_svg(
+                    &get_svg(svg).unwrap(),
+                    rect,
+                    Some(data.config.get_color_unchecked(color)),
+                );
+            }
+            ctx.draw_text(&layout.title, layout.title_origin);
+            ctx.draw_text(&layout.message, layout.message_origin);
+            ctx.draw_svg(
+                &get_svg("close.svg").unwrap(),
+                layout.close,
+                Some(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                ),
+            );
+
+            for (text, rect) in layout.actions.iter() {
+                ctx.stroke(
+                    *rect,
+                    data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+                    1.0,
+                );
+                let point =
+                    rect.center() - (text.size().width / 2.0, text.cap_center());
+                ctx.draw_text(text, point);
+            }
+        }
+    }
+}
@@ -21,6 +21,7 @@
     data::LapceTabData,
     keypress::KeyPressFocus,
     palette::{PaletteStatus, PaletteType, PaletteViewData},
+    source_control::{GitBranchAction, GitStashAction},
 };
 use lsp_types::SymbolKind;
 
@@ -496,37 +497,64 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &PaletteViewData, _env: &Env) {
         let text = data.palette.input.clone();
         let cursor = data.palette.cursor;
 
-        let text_layout = if text.is_empty()
-            && data.palette.palette_type == PaletteType::SshHost
-        {
-            ctx.text()
-                .new_text_layout("Enter your SSH details, like user@host")
-                .font(
-                    data.config.ui.font_family(),
-                    data.config.ui.font_size() as f64,
-                )
-                .text_color(
-                    data.config
-                        .get_color_unchecked(LapceTheme::EDITOR_DIM)
-                        .clone(),
-                )
-                .build()
-                .unwrap()
-        } else {
-            ctx.text()
-                .new_text_layout(text)
-                .font(
-                    data.config.ui.font_family(),
-                    data.config.ui.font_size() as f64,
-                )
-                .text_color(
-                    data.config
-                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
-                        .clone(),
-                )
-                .build()
-                .unwrap()
+        let placeholder = match data.palette.palette_type {
+            PaletteType::SshHost => Some("Enter your SSH details, like user@host"),
+            PaletteType::GitBranch(GitBranchAction::Create) => Some(
+                "Enter a branch name, optionally followed by the ref to start from",
+            ),
+            PaletteType::GitBranch(GitBranchAction::Rename) => {
+                Some("Enter a new name for the current branch")
+            }
+            PaletteType::GitBranch(GitBranchAction::Delete) => {
+                Some("Select a branch to delete")
+            }
+            PaletteType::GitStash(
+                GitStashAction::Save | GitStashAction::SaveIncludeUntracked,
+            ) => Some("Enter a stash message, or leave it empty"),
+            PaletteType::GitStash(GitStashAction::Apply) => {
+                Some("Select a stash to apply")
+            }
+            PaletteType::GitStash(GitStashAction::Pop) => {
+                Some("Select a stash to pop")
+            }
+            PaletteType::GitStash(GitStashAction::Drop) => {
+                Some("Select a stash to drop")
+            }
+            PaletteType::Switch => {
+                Some("Search open editors, recent files and recent workspaces")
+            }
+            _ => None,
         };
+        let text_layout =
+            if let Some(placeholder) = placeholder.filter(|_| text.is_empty()) {
+                ctx.text()
+                    .new_text_layout(placeholder)
+                    .font(
+                        data.config.ui.font_family(),
+                        data.config.ui.font_size() as f64,
+                    )
+                    .text_color(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                            .clone(),
+                    )
+                    .build()
+                    .unwrap()
+            } else {
+                ctx.text()
+                    .new_text_layout(text)
+                    .font(
+                        data.config.ui.font_family(),
+                        data.config.ui.font_size() as f64,
+                    )
+                    .text_color(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                            .clone(),
+                    )
+                    .build()
+                    .unwrap()
+            };
 
         let pos = text_layout.hit_test_text_position(cursor);
         let line_metric = text_layout.line_metric(0).unwrap();
@@ -696,7 +724,8 @@ fn paint(
                 name.as_str(),
                 *kind,
             ),
-            PaletteItemContent::Line(_, text) => {
+            PaletteItemContent::Line(_, text)
+            | PaletteItemContent::GoToLine { text, .. } => {
                 PaletteItemPaintInfo::new_text(text.clone(), self.indices.to_vec())
             }
             PaletteItemContent::ReferenceLocation(rel_path, _location) => {
@@ -721,7 +750,22 @@ fn paint(
                     .desc()
                     .map(|m| m.to_string())
                     .unwrap_or_else(|| "".to_string());
-                PaletteItemPaintInfo::new_text(text, self.indices.to_vec())
+                let recent = data
+                    .data
+                    .recent_commands
+                    .iter()
+                    .any(|name| name == command.kind.str());
+                PaletteItemPaintInfo {
+                    svg: None,
+                    text,
+                    text_indices: self.indices.to_vec(),
+                    hint: if recent {
+                        "recently used".to_string()
+                    } else {
+                        String::new()
+                    },
+                    hint_indices: Vec::new(),
+                }
             }
             PaletteItemContent::Theme(theme) => PaletteItemPaintInfo::new_text(
                 theme.to_string(),
@@ -743,6 +787,19 @@ fn paint(
                     self.indices.to_vec(),
                 )
             }
+            PaletteItemContent::PluginProvider(_, provider) => {
+                PaletteItemPaintInfo::new_text(
+                    provider.name.clone(),
+                    self.indices.to_vec(),
+                )
+            }
+            PaletteItemContent::PluginItem { item, .. } => PaletteItemPaintInfo {
+                svg: None,
+                text: item.label.clone(),
+                text_indices: self.indices.to_vec(),
+                hint: item.description.clone().unwrap_or_default(),
+                hint_indices: Vec::new(),
+            },
         };
 
         let line_height = data.line_height() as f64;
@@ -758,9 +815,11 @@ fn paint(
         }
 
         let svg_x = match &self.content {
-            &PaletteItemContent::Line(_, _) | &PaletteItemContent::Workspace(_) => {
-                0.0
-            }
+            &PaletteItemContent::Line(_, _)
+            | &PaletteItemContent::GoToLine { .. }
+            | &PaletteItemContent::Workspace(_)
+            | &PaletteItemContent::PluginProvider(..)
+            | &PaletteItemContent::PluginItem { .. } => 0.0,
             _ => line_height,
         };
 
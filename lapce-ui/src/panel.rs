@@ -943,6 +943,12 @@ fn panel_icon(kind: &PanelKind, data: &LapceTabData) -> (PanelKind, LapceIcon) {
             PanelKind::Terminal => LapceWorkbenchCommand::ToggleTerminalVisual,
             PanelKind::Search => LapceWorkbenchCommand::ToggleSearchVisual,
             PanelKind::Problem => LapceWorkbenchCommand::ToggleProblemVisual,
+            PanelKind::CallHierarchy => {
+                LapceWorkbenchCommand::ToggleCallHierarchyVisual
+            }
+            PanelKind::TypeHierarchy => {
+                LapceWorkbenchCommand::ToggleTypeHierarchyVisual
+            }
         };
         (
             *kind,
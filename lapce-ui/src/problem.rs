@@ -15,7 +15,7 @@
     problem::ProblemData,
     proxy::path_from_url,
 };
-use lsp_types::DiagnosticSeverity;
+use lsp_types::{DiagnosticSeverity, NumberOrString};
 
 use crate::{
     panel::{LapcePanel, PanelHeaderKind, PanelSizing},
@@ -177,6 +177,21 @@ fn mouse_down(
         }
         line_cursor += msg_lines;
 
+        // The link to the description of the diagnostic's code.
+        if let Some(code_description) =
+            file_diagnostic.diagnostic.code_description.as_ref()
+        {
+            if line_cursor == click_line {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::OpenURI(code_description.href.to_string()),
+                    Target::Widget(data.id),
+                ));
+                return;
+            }
+            line_cursor += 1;
+        }
+
         // Skip to clicked related information.
         let related = file_diagnostic
             .diagnostic
@@ -186,7 +201,8 @@ fn mouse_down(
 
         let mut clicked_related = None;
         for related in related {
-            let lines = related.message.lines().count();
+            // The location header and the message.
+            let lines = related.message.lines().count() + 1;
             let item_line_range = line_cursor..(line_cursor + lines);
 
             // Is the current line the clicked one?
@@ -399,7 +415,8 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
                     return;
                 }
                 let msg_lines = message_lines(d);
-                let related_lines = related_line_count(d);
+                let related_lines =
+                    code_description_lines(d) + related_line_count(d);
                 if current_line + 1 + msg_lines + related_lines < min {
                     current_line += msg_lines + related_lines;
                     continue;
@@ -464,6 +481,71 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
                     );
                 }
 
+                if let Some(code_description) =
+                    d.diagnostic.code_description.as_ref()
+                {
+                    current_line += 1;
+
+                    if ctx.is_hot() && mouse_line == current_line {
+                        ctx.fill(
+                            Size::new(size.width, line_height)
+                                .to_rect()
+                                .with_origin(Point::new(
+                                    0.0,
+                                    line_height * current_line as f64,
+                                )),
+                            data.config.get_color_unchecked(
+                                LapceTheme::EDITOR_CURRENT_LINE,
+                            ),
+                        );
+                    }
+
+                    let svg = get_svg("link.svg").unwrap();
+                    let rect = Size::new(line_height, line_height)
+                        .to_rect()
+                        .with_origin(Point::new(
+                            2.0 * line_height,
+                            line_height * current_line as f64,
+                        ))
+                        .inflate(-padding, -padding);
+                    ctx.draw_svg(
+                        &svg,
+                        rect,
+                        Some(
+                            data.config
+                                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                        ),
+                    );
+                    let text = match d.diagnostic.code.as_ref() {
+                        Some(NumberOrString::Number(code)) => {
+                            format!("{code}: {}", code_description.href)
+                        }
+                        Some(NumberOrString::String(code)) => {
+                            format!("{code}: {}", code_description.href)
+                        }
+                        None => code_description.href.to_string(),
+                    };
+                    let text_layout = ctx
+                        .text()
+                        .new_text_layout(text)
+                        .font(ui_font_family.clone(), ui_font_size)
+                        .text_color(
+                            data.config
+                                .get_color_unchecked(LapceTheme::EDITOR_LINK)
+                                .clone(),
+                        )
+                        .build()
+                        .unwrap();
+                    ctx.draw_text(
+                        &text_layout,
+                        Point::new(
+                            3.0 * line_height,
+                            line_height * current_line as f64
+                                + text_layout.y_offset(line_height),
+                        ),
+                    );
+                }
+
                 for related in
                     d.diagnostic.related_information.as_deref().unwrap_or(&[])
                 {
@@ -562,6 +644,11 @@ fn message_lines(diagnostic: &EditorDiagnostic) -> usize {
     diagnostic.diagnostic.message.lines().count()
 }
 
+/// The line with the link to the description of the diagnostic's code
+fn code_description_lines(diagnostic: &EditorDiagnostic) -> usize {
+    usize::from(diagnostic.diagnostic.code_description.is_some())
+}
+
 fn related_line_count(diagnostic: &EditorDiagnostic) -> usize {
     diagnostic
         .diagnostic
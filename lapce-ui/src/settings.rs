@@ -14,6 +14,7 @@
 use itertools::Itertools;
 use lapce_core::{
     command::{EditCommand, MoveCommand},
+    language::LapceLanguage,
     mode::Mode,
 };
 use lapce_data::{
@@ -344,18 +345,155 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
     }
 }
 
+/// Picks the language whose `[editor.lang.<language>]` settings are shown,
+/// where no language stands for the settings of every language
+struct LanguageSelector {
+    language: Option<String>,
+    open: bool,
+    header: Rect,
+    header_text: Option<PietTextLayout>,
+    /// The languages to pick from, while the list is open
+    items: Vec<(Option<String>, PietTextLayout, Rect)>,
+}
+
+impl LanguageSelector {
+    const ALL_LANGUAGES: &'static str = "All Languages";
+
+    fn new() -> Self {
+        Self {
+            language: None,
+            open: false,
+            header: Rect::ZERO,
+            header_text: None,
+            items: Vec::new(),
+        }
+    }
+
+    /// The settings file table the settings are written to
+    fn settings_kind(&self) -> String {
+        match self.language.as_ref() {
+            Some(language) => format!("editor.lang.{}", language.to_lowercase()),
+            None => "editor".to_string(),
+        }
+    }
+
+    /// Lays out the selector from the top, and returns its height
+    fn layout(
+        &mut self,
+        text: &mut PietText,
+        width: f64,
+        data: &LapceTabData,
+    ) -> f64 {
+        let font_family = data.config.ui.font_family();
+        let font_size = data.config.ui.font_size() as f64;
+        let color = data
+            .config
+            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+            .clone();
+
+        let header_text = text
+            .new_text_layout(format!(
+                "Language: {}",
+                self.language.as_deref().unwrap_or(Self::ALL_LANGUAGES)
+            ))
+            .font(font_family.clone(), font_size + 1.0)
+            .default_attribute(TextAttribute::Weight(FontWeight::BOLD))
+            .text_color(color.clone())
+            .build()
+            .unwrap();
+        self.header = Size::new(
+            header_text.size().width + 20.0,
+            header_text.size().height + 10.0,
+        )
+        .to_rect()
+        .with_origin(Point::new(0.0, 10.0));
+        self.header_text = Some(header_text);
+
+        let mut y = self.header.y1 + 10.0;
+        self.items.clear();
+        if self.open {
+            let row_height = font_size * 2.0;
+            let mut x = 0.0;
+            let languages = std::iter::once(None)
+                .chain(LapceLanguage::languages().into_iter().map(Some));
+            for language in languages {
+                let item_text = text
+                    .new_text_layout(
+                        language
+                            .clone()
+                            .unwrap_or_else(|| Self::ALL_LANGUAGES.to_string()),
+                    )
+                    .font(font_family.clone(), font_size)
+                    .text_color(color.clone())
+                    .build()
+                    .unwrap();
+                let item_width = item_text.size().width + 20.0;
+                if x > 0.0 && x + item_width > width {
+                    x = 0.0;
+                    y += row_height;
+                }
+                let rect = Size::new(item_width, row_height)
+                    .to_rect()
+                    .with_origin(Point::new(x, y));
+                self.items.push((language, item_text, rect));
+                x += item_width;
+            }
+            y += row_height + 10.0;
+        }
+        y
+    }
+
+    fn paint(&self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        ctx.stroke(
+            self.header,
+            data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+            1.0,
+        );
+        if let Some(text) = self.header_text.as_ref() {
+            ctx.draw_text(
+                text,
+                Point::new(
+                    self.header.x0 + 10.0,
+                    self.header.y0 + text.y_offset(self.header.height()),
+                ),
+            );
+        }
+
+        for (language, text, rect) in self.items.iter() {
+            if *language == self.language {
+                ctx.fill(
+                    rect,
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_CURRENT_LINE),
+                );
+            }
+            ctx.draw_text(
+                text,
+                Point::new(rect.x0 + 10.0, rect.y0 + text.y_offset(rect.height())),
+            );
+        }
+    }
+}
+
 struct LapceSettings {
     widget_id: WidgetId,
     kind: LapceSettingsKind,
+    /// Only the editor settings can be set for each language
+    language_selector: Option<LanguageSelector>,
     children: Vec<WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>>,
 }
 
 impl LapceSettings {
     pub fn new_split(kind: LapceSettingsKind, data: &LapceTabData) -> LapceSplit {
+        let language_selector = match kind {
+            LapceSettingsKind::Editor => Some(LanguageSelector::new()),
+            _ => None,
+        };
         let settings = LapceScroll::new(
             Self {
                 widget_id: WidgetId::next(),
                 kind,
+                language_selector,
                 children: Vec::new(),
             }
             .boxed(),
@@ -391,25 +529,33 @@ fn into_settings_map(
 
         let (kind, fields, descs, mut settings) = match self.kind {
             LapceSettingsKind::Core => (
-                "core",
+                "core".to_string(),
                 &CoreConfig::FIELDS[..],
                 &CoreConfig::DESCS[..],
                 into_settings_map(&data.config.core),
             ),
             LapceSettingsKind::UI => (
-                "ui",
+                "ui".to_string(),
                 &UIConfig::FIELDS[..],
                 &UIConfig::DESCS[..],
                 into_settings_map(&data.config.ui),
             ),
-            LapceSettingsKind::Editor => (
-                "editor",
-                &EditorConfig::FIELDS[..],
-                &EditorConfig::DESCS[..],
-                into_settings_map(&data.config.editor),
-            ),
+            LapceSettingsKind::Editor => {
+                let selector = self.language_selector.as_ref();
+                let language = selector
+                    .and_then(|selector| selector.language.as_deref())
+                    .and_then(LapceLanguage::from_name);
+                (
+                    selector
+                        .map(LanguageSelector::settings_kind)
+                        .unwrap_or_else(|| "editor".to_string()),
+                    &EditorConfig::FIELDS[..],
+                    &EditorConfig::DESCS[..],
+                    into_settings_map(data.config.editor_config(language)),
+                )
+            }
             LapceSettingsKind::Terminal => (
-                "terminal",
+                "terminal".to_string(),
                 &TerminalConfig::FIELDS[..],
                 &TerminalConfig::DESCS[..],
                 into_settings_map(&data.config.terminal),
@@ -425,7 +571,7 @@ fn into_settings_map(
                     (10.0, 10.0),
                     LapceSettingsItem::new(
                         data,
-                        kind.to_string(),
+                        kind.clone(),
                         field,
                         desc.to_string(),
                         value,
@@ -450,6 +596,31 @@ fn event(
         data: &mut LapceTabData,
         env: &Env,
     ) {
+        if let (Event::MouseDown(mouse_event), Some(selector)) =
+            (event, self.language_selector.as_mut())
+        {
+            if selector.header.contains(mouse_event.pos) {
+                selector.open = !selector.open;
+                ctx.request_layout();
+                ctx.set_handled();
+                return;
+            }
+            let picked = selector
+                .items
+                .iter()
+                .find(|(_, _, rect)| rect.contains(mouse_event.pos))
+                .map(|(language, _, _)| language.clone());
+            if let Some(language) = picked {
+                selector.language = language;
+                selector.open = false;
+                self.children.clear();
+                self.update_children(ctx, data);
+                ctx.children_changed();
+                ctx.set_handled();
+                return;
+            }
+        }
+
         for child in self.children.iter_mut() {
             child.event(ctx, event, data, env);
         }
@@ -499,6 +670,9 @@ fn layout(
         }
 
         let mut y = 0.0;
+        if let Some(selector) = self.language_selector.as_mut() {
+            y = selector.layout(ctx.text(), bc.max().width, data);
+        }
         for child in self.children.iter_mut() {
             let size = child.layout(ctx, bc, data, env);
             child.set_origin(ctx, data, env, Point::new(0.0, y));
@@ -509,6 +683,9 @@ fn layout(
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
+        if let Some(selector) = self.language_selector.as_ref() {
+            selector.paint(ctx, data);
+        }
         for child in self.children.iter_mut() {
             child.paint(ctx, data, env);
         }
@@ -0,0 +1,229 @@
+use std::sync::Arc;
+
+use druid::{
+    piet::{PietTextLayout, Text, TextAttribute, TextLayout, TextLayoutBuilder},
+    BoxConstraints, Env, Event, EventCtx, FontWeight, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, RenderContext, Size, UpdateCtx, Widget, WidgetId,
+};
+use lapce_data::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    config::LapceTheme,
+    data::LapceTabData,
+    signature::SignatureState,
+};
+use lsp_types::Documentation;
+
+/// Shows the signature of the call the cursor is in, with the parameter the
+/// cursor is at highlighted
+pub struct SignatureContainer {
+    id: WidgetId,
+    label: Option<PietTextLayout>,
+    documentation: Option<PietTextLayout>,
+}
+
+impl SignatureContainer {
+    const PADDING: f64 = 8.0;
+    const MAX_WIDTH: f64 = 600.0;
+
+    pub fn new(data: &SignatureState) -> Self {
+        Self {
+            id: data.id,
+            label: None,
+            documentation: None,
+        }
+    }
+
+    fn documentation(documentation: &Documentation) -> &str {
+        match documentation {
+            Documentation::String(s) => s,
+            Documentation::MarkupContent(content) => &content.value,
+        }
+    }
+}
+
+impl Widget<LapceTabData> for SignatureContainer {
+    fn id(&self) -> Option<WidgetId> {
+        Some(self.id)
+    }
+
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(LAPCE_UI_COMMAND) => {
+                let command = cmd.get_unchecked(LAPCE_UI_COMMAND);
+                if let LapceUICommand::UpdateSignature {
+                    request_id,
+                    offset,
+                    signature,
+                } = command
+                {
+                    let state = Arc::make_mut(&mut data.signature);
+                    state.receive(*request_id, *offset, signature.clone());
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseMove(_) => {
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if !Arc::ptr_eq(&old_data.signature, &data.signature) {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        _bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let state = &data.signature;
+        let signature = match state.current_signature() {
+            Some(signature) => signature,
+            None => {
+                self.label = None;
+                self.documentation = None;
+                return Size::ZERO;
+            }
+        };
+
+        let max_width = Self::MAX_WIDTH - Self::PADDING * 2.0;
+        let focus_color = data.config.get_color_unchecked(LapceTheme::EDITOR_FOCUS);
+        let dim_color = data.config.get_color_unchecked(LapceTheme::EDITOR_DIM);
+
+        // The overloads are cycled through with the next and previous
+        // signature commands
+        let overloads = if state.len() > 1 {
+            format!("{}/{}  ", state.active_signature + 1, state.len())
+        } else {
+            String::new()
+        };
+        let mut label = ctx
+            .text()
+            .new_text_layout(format!("{overloads}{}", signature.label))
+            .font(
+                data.config.editor.font_family(),
+                data.config.editor.font_size as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                    .clone(),
+            )
+            .max_width(max_width)
+            .range_attribute(
+                0..overloads.len(),
+                TextAttribute::TextColor(dim_color.clone()),
+            );
+        if let Some((start, end)) = state.active {
+            let range = overloads.len() + start..overloads.len() + end;
+            label = label
+                .range_attribute(
+                    range.clone(),
+                    TextAttribute::TextColor(focus_color.clone()),
+                )
+                .range_attribute(range, TextAttribute::Weight(FontWeight::BOLD));
+        }
+        let label = label.build().unwrap();
+
+        // The documentation of the active parameter is more useful while
+        // typing it than the one of the whole function
+        let active_parameter = state.active.and_then(|_| {
+            let index = signature
+                .active_parameter
+                .or(state.signature.as_ref()?.active_parameter)?;
+            signature.parameters.as_ref()?.get(index as usize)
+        });
+        let documentation = active_parameter
+            .and_then(|param| param.documentation.as_ref())
+            .or(signature.documentation.as_ref())
+            .map(Self::documentation)
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                ctx.text()
+                    .new_text_layout(s.to_string())
+                    .font(
+                        data.config.ui.hover_font_family(),
+                        data.config.ui.hover_font_size() as f64,
+                    )
+                    .text_color(dim_color.clone())
+                    .max_width(max_width)
+                    .build()
+                    .unwrap()
+            });
+
+        let label_size = label.size();
+        let mut width = label_size.width;
+        let mut height = label_size.height;
+        if let Some(documentation) = documentation.as_ref() {
+            let size = documentation.size();
+            width = width.max(size.width);
+            height += Self::PADDING + size.height;
+        }
+        self.label = Some(label);
+        self.documentation = documentation;
+        ctx.set_paint_insets((10.0, 10.0, 10.0, 10.0));
+        Size::new(width + Self::PADDING * 2.0, height + Self::PADDING * 2.0)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        let label = match self.label.as_ref() {
+            Some(label) if data.signature.is_active() => label,
+            _ => return,
+        };
+
+        let rect = ctx.size().to_rect();
+        let shadow_width = data.config.ui.drop_shadow_width() as f64;
+        if shadow_width > 0.0 {
+            ctx.blurred_rect(
+                rect,
+                shadow_width,
+                data.config
+                    .get_color_unchecked(LapceTheme::LAPCE_DROPDOWN_SHADOW),
+            );
+        } else {
+            ctx.stroke(
+                rect.inflate(0.5, 0.5),
+                data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+                1.0,
+            );
+        }
+        ctx.fill(
+            rect,
+            data.config
+                .get_color_unchecked(LapceTheme::HOVER_BACKGROUND),
+        );
+
+        ctx.draw_text(label, Point::new(Self::PADDING, Self::PADDING));
+        if let Some(documentation) = self.documentation.as_ref() {
+            let y = Self::PADDING * 2.0 + label.size().height;
+            ctx.draw_text(documentation, Point::new(Self::PADDING, y));
+        }
+    }
+}
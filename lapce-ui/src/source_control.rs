@@ -1,4 +1,4 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
 
 use druid::{
     kurbo::BezPath,
@@ -14,9 +14,10 @@
     },
     config::LapceTheme,
     data::{FocusArea, LapceTabData},
+    menu::{MenuItem, MenuKind},
     panel::PanelKind,
 };
-use lapce_rpc::source_control::FileDiff;
+use lapce_rpc::source_control::{FileDiff, StashInfo};
 
 use crate::{
     button::Button,
@@ -38,24 +39,35 @@ pub fn new_source_control_panel(data: &LapceTabData) -> LapcePanel {
             .set_placeholder("Commit Message".to_string())
             .padding((15.0, 15.0));
 
-    let commit_button = Button::new(data, "Commit")
-        .on_click(|ctx, data, _env| {
-            ctx.submit_command(Command::new(
-                LAPCE_COMMAND,
-                LapceCommand {
-                    kind: CommandKind::Workbench(
-                        LapceWorkbenchCommand::SourceControlCommit,
-                    ),
-                    data: None,
-                },
-                Target::Widget(data.id),
-            ));
-        })
-        .expand_width()
-        .with_id(data.source_control.commit_button_id)
-        .padding((10.0, 0.0, 10.0, 10.0));
+    let commit_button = Button::new(data, |data: &LapceTabData, _env: &Env| {
+        let source_control = &data.source_control;
+        match (source_control.amend, source_control.sign_off) {
+            (false, false) => "Commit",
+            (false, true) => "Commit (Signed-off)",
+            (true, false) => "Amend Last Commit",
+            (true, true) => "Amend Last Commit (Signed-off)",
+        }
+    })
+    .on_click(|ctx, data, _env| {
+        ctx.submit_command(Command::new(
+            LAPCE_COMMAND,
+            LapceCommand {
+                kind: CommandKind::Workbench(
+                    LapceWorkbenchCommand::SourceControlCommit,
+                ),
+                data: None,
+            },
+            Target::Widget(data.id),
+        ));
+    })
+    .expand_width()
+    .with_id(data.source_control.commit_button_id)
+    .padding((10.0, 0.0, 10.0, 10.0));
 
+    let conflicts =
+        SourceControlConflictList::new(data.source_control.conflict_list_id);
     let content = SourceControlFileList::new(data.source_control.file_list_id);
+    let stashes = SourceControlStashList::new(data.source_control.stash_list_id);
 
     LapcePanel::new(
         PanelKind::SourceControl,
@@ -74,12 +86,24 @@ pub fn new_source_control_panel(data: &LapceTabData) -> LapcePanel {
                 commit_button.boxed(),
                 PanelSizing::Flex(false),
             ),
+            (
+                data.source_control.conflict_list_id,
+                PanelHeaderKind::Simple("Merge Changes".into()),
+                conflicts.boxed(),
+                PanelSizing::Flex(false),
+            ),
             (
                 data.source_control.file_list_id,
                 PanelHeaderKind::Simple("Changes".into()),
                 content.boxed(),
                 PanelSizing::Flex(false),
             ),
+            (
+                data.source_control.stash_list_id,
+                PanelHeaderKind::Simple("Stashes".into()),
+                stashes.boxed(),
+                PanelSizing::Flex(false),
+            ),
         ],
     )
 }
@@ -387,3 +411,390 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
         }
     }
 }
+
+/// The files git couldn't merge, which are opened to resolve their conflicts
+struct SourceControlConflictList {
+    widget_id: WidgetId,
+    line_height: f64,
+}
+
+impl SourceControlConflictList {
+    pub fn new(widget_id: WidgetId) -> Self {
+        Self {
+            widget_id,
+            line_height: 25.0,
+        }
+    }
+}
+
+impl Widget<LapceTabData> for SourceControlConflictList {
+    fn id(&self) -> Option<WidgetId> {
+        Some(self.widget_id)
+    }
+
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(_mouse_event) => {
+                ctx.set_cursor(&druid::Cursor::Pointer);
+            }
+            Event::MouseDown(mouse_event) => {
+                let line = (mouse_event.pos.y / self.line_height).floor() as usize;
+                if let Some(path) = data.source_control.conflicts.get(line) {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::OpenFile(path.clone(), false),
+                        Target::Widget(data.id),
+                    ));
+                }
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if data.source_control.conflicts != old_data.source_control.conflicts {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let height = self.line_height * data.source_control.conflicts.len() as f64;
+        Size::new(bc.max().width, height)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        let self_size = ctx.size();
+        for (line, path) in data.source_control.conflicts.iter().enumerate() {
+            let y = self.line_height * line as f64;
+            let mut path: PathBuf = path.clone();
+            if let Some(workspace_path) = data.workspace.path.as_ref() {
+                path = path
+                    .strip_prefix(workspace_path)
+                    .unwrap_or(&path)
+                    .to_path_buf();
+            }
+
+            let (svg, svg_color) = file_svg(&path);
+            let width = 13.0;
+            let height = 13.0;
+            let rect = Size::new(width, height).to_rect().with_origin(Point::new(
+                (self.line_height - width) / 2.0 + 5.0,
+                (self.line_height - height) / 2.0 + y,
+            ));
+            ctx.draw_svg(&svg, rect, svg_color);
+
+            let file_name = path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string();
+            let text_layout = ctx
+                .text()
+                .new_text_layout(file_name)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(
+                    self.line_height + 5.0,
+                    y + text_layout.y_offset(self.line_height),
+                ),
+            );
+            let folder = path
+                .parent()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string();
+            if !folder.is_empty() {
+                let x = text_layout.size().width;
+                let text_layout = ctx
+                    .text()
+                    .new_text_layout(folder)
+                    .font(
+                        data.config.ui.font_family(),
+                        data.config.ui.font_size() as f64,
+                    )
+                    .text_color(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                            .clone(),
+                    )
+                    .build()
+                    .unwrap();
+                ctx.draw_text(
+                    &text_layout,
+                    Point::new(
+                        self.line_height + 5.0 + x + 5.0,
+                        y + text_layout.y_offset(self.line_height),
+                    ),
+                );
+            }
+
+            let svg = get_svg("diff-modified.svg").unwrap();
+            let svg_size = 15.0;
+            let rect =
+                Size::new(svg_size, svg_size)
+                    .to_rect()
+                    .with_origin(Point::new(
+                        self_size.width - svg_size - 10.0,
+                        y + (self.line_height - svg_size) / 2.0,
+                    ));
+            let color = data
+                .config
+                .get_color_unchecked(LapceTheme::SOURCE_CONTROL_REMOVED);
+            ctx.draw_svg(&svg, rect, Some(&color.clone().with_alpha(0.9)));
+        }
+    }
+}
+
+enum StashRow<'a> {
+    Stash(&'a StashInfo),
+    File(&'a StashInfo, &'a PathBuf),
+}
+
+/// The stashes, which expand to the files they changed. Those open in the
+/// diff view against the stashed version.
+struct SourceControlStashList {
+    widget_id: WidgetId,
+    line_height: f64,
+    /// The ids of the stashes that are expanded
+    expanded: HashSet<String>,
+}
+
+impl SourceControlStashList {
+    pub fn new(widget_id: WidgetId) -> Self {
+        Self {
+            widget_id,
+            line_height: 25.0,
+            expanded: HashSet::new(),
+        }
+    }
+
+    fn rows<'a>(&self, data: &'a LapceTabData) -> Vec<StashRow<'a>> {
+        let mut rows = Vec::new();
+        for stash in data.source_control.stashes.iter() {
+            rows.push(StashRow::Stash(stash));
+            if self.expanded.contains(&stash.id) {
+                rows.extend(stash.files.iter().map(|f| StashRow::File(stash, f)));
+            }
+        }
+        rows
+    }
+
+    fn show_menu(
+        &self,
+        ctx: &mut EventCtx,
+        tab_id: WidgetId,
+        pos: Point,
+        stash: &StashInfo,
+    ) {
+        let menu_items = [
+            LapceWorkbenchCommand::SourceControlStashApply,
+            LapceWorkbenchCommand::SourceControlStashPop,
+            LapceWorkbenchCommand::SourceControlStashDrop,
+        ]
+        .into_iter()
+        .map(|command| {
+            MenuKind::Item(MenuItem {
+                desc: None,
+                command: LapceCommand {
+                    kind: CommandKind::Workbench(command),
+                    data: Some(serde_json::json!(stash.index)),
+                },
+                enabled: true,
+            })
+        })
+        .collect();
+        ctx.submit_command(Command::new(
+            LAPCE_UI_COMMAND,
+            LapceUICommand::ShowMenu(ctx.to_window(pos), Arc::new(menu_items)),
+            Target::Widget(tab_id),
+        ));
+    }
+}
+
+impl Widget<LapceTabData> for SourceControlStashList {
+    fn id(&self) -> Option<WidgetId> {
+        Some(self.widget_id)
+    }
+
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(_mouse_event) => {
+                ctx.set_cursor(&druid::Cursor::Pointer);
+            }
+            Event::MouseDown(mouse_event) => {
+                let line = (mouse_event.pos.y / self.line_height).floor() as usize;
+                match self.rows(data).get(line) {
+                    Some(StashRow::Stash(stash)) => {
+                        if mouse_event.button.is_right() {
+                            self.show_menu(ctx, data.id, mouse_event.pos, stash);
+                        } else if !self.expanded.remove(&stash.id) {
+                            self.expanded.insert(stash.id.clone());
+                        }
+                        ctx.request_layout();
+                    }
+                    Some(StashRow::File(stash, path)) => {
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::OpenFileDiff(
+                                path.to_path_buf(),
+                                stash.id.clone(),
+                            ),
+                            Target::Widget(data.id),
+                        ));
+                    }
+                    None => {}
+                }
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if data.source_control.stashes != old_data.source_control.stashes {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let height = self.line_height * self.rows(data).len() as f64;
+        Size::new(bc.max().width, height)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        for (line, row) in self.rows(data).into_iter().enumerate() {
+            let y = self.line_height * line as f64;
+            let (x, text, color) = match row {
+                StashRow::Stash(stash) => {
+                    let svg = if self.expanded.contains(&stash.id) {
+                        get_svg("chevron-down.svg")
+                    } else {
+                        get_svg("chevron-right.svg")
+                    }
+                    .unwrap();
+                    let size = 10.0;
+                    let rect =
+                        Size::new(size, size).to_rect().with_origin(Point::new(
+                            (self.line_height - size) / 2.0 + 5.0,
+                            (self.line_height - size) / 2.0 + y,
+                        ));
+                    ctx.draw_svg(
+                        &svg,
+                        rect,
+                        Some(
+                            data.config
+                                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                        ),
+                    );
+                    (
+                        self.line_height + 5.0,
+                        format!("stash@{{{}}}: {}", stash.index, stash.message),
+                        LapceTheme::EDITOR_FOREGROUND,
+                    )
+                }
+                StashRow::File(_, path) => {
+                    let path = match data.workspace.path.as_ref() {
+                        Some(workspace_path) => {
+                            path.strip_prefix(workspace_path).unwrap_or(path)
+                        }
+                        None => path,
+                    };
+                    let (svg, svg_color) = file_svg(path);
+                    let size = 13.0;
+                    let rect =
+                        Size::new(size, size).to_rect().with_origin(Point::new(
+                            (self.line_height - size) / 2.0 + self.line_height,
+                            (self.line_height - size) / 2.0 + y,
+                        ));
+                    ctx.draw_svg(&svg, rect, svg_color);
+                    (
+                        self.line_height * 2.0 + 5.0,
+                        path.to_str().unwrap_or("").to_string(),
+                        LapceTheme::EDITOR_DIM,
+                    )
+                }
+            };
+
+            let text_layout = ctx
+                .text()
+                .new_text_layout(text)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(data.config.get_color_unchecked(color).clone())
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, y + text_layout.y_offset(self.line_height)),
+            );
+        }
+    }
+}
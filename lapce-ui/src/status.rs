@@ -4,11 +4,12 @@
     Command, Data, Event, EventCtx, MouseEvent, PaintCtx, Point, Rect,
     RenderContext, Size, Target, Widget,
 };
-use lapce_core::mode::Mode;
+use lapce_core::{indent::IndentStyle, mode::Mode};
 use lapce_data::{
     command::{CommandKind, LapceCommand, LapceWorkbenchCommand, LAPCE_COMMAND},
     config::{LapceConfig, LapceTheme},
     data::{FocusArea, LapceTabData},
+    document::Document,
     panel::{PanelContainerPosition, PanelKind},
 };
 
@@ -164,6 +165,25 @@ fn paint_icon_with_label(
         (left + text_layout.size().width, svg, (point, text_layout))
     }
 
+    /// The effective indentation, line ending and charset of the document,
+    /// which the `.editorconfig` files can set
+    fn document_settings_label(doc: &Document, config: &LapceConfig) -> String {
+        let editorconfig = doc.editorconfig();
+        let mut label = match doc.buffer().indent_style() {
+            IndentStyle::Tabs => format!("Tab Size: {}", doc.tab_width(config)),
+            IndentStyle::Spaces(spaces) => format!("Spaces: {spaces}"),
+        };
+        label += &format!("  {}", doc.line_ending().name());
+        label += &format!(
+            "  {}",
+            editorconfig.charset.map(|c| c.name()).unwrap_or("UTF-8")
+        );
+        if let Some(max_line_length) = editorconfig.max_line_length {
+            label += &format!("  Max Line Length: {max_line_length}");
+        }
+        label
+    }
+
     fn paint_icon_with_label_from_right(
         &self,
         right: f64,
@@ -273,6 +293,13 @@ fn update(
             _ => ctx.request_paint(),
         }
 
+        if old_data.source_control.branch != data.source_control.branch
+            || old_data.source_control.upstream != data.source_control.upstream
+        {
+            ctx.request_paint();
+            return;
+        }
+
         if old_data.main_split.warning_count != data.main_split.warning_count
             || old_data.main_split.error_count != data.main_split.error_count
         {
@@ -437,12 +464,42 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &druid::Env)
             ),
         ));
 
+        if !data.source_control.branch.is_empty() {
+            let mut label = data.source_control.branch.clone();
+            if let Some(upstream) = data.source_control.upstream.as_ref() {
+                label += &format!(" ↑{} ↓{}", upstream.ahead, upstream.behind);
+            }
+            let (new_left, svg, (point, text_layout)) = self.paint_icon_with_label(
+                left,
+                size.height,
+                "git-icon.svg",
+                label,
+                ctx,
+                &data.config,
+            );
+            left = new_left;
+            if let Some((rect, svg)) = svg {
+                ctx.draw_svg(
+                    &svg,
+                    rect,
+                    Some(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                    ),
+                );
+            }
+            ctx.draw_text(&text_layout, point);
+        }
+
         for progress in data.progresses.iter() {
             let mut text = progress.title.clone();
             if let Some(message) = progress.message.as_ref() {
                 text += ": ";
                 text += message;
             }
+            if let Some(percentage) = progress.percentage {
+                text += &format!(" ({percentage}%)");
+            }
             let text_layout = ctx
                 .text()
                 .new_text_layout(text)
@@ -535,6 +592,20 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &druid::Env)
                     Target::Widget(data.id),
                 ),
             ));
+
+            let doc = data.main_split.content_doc(&editor.content);
+            if doc.content().is_file() {
+                let (_, _, (point, text_layout)) = self
+                    .paint_icon_with_label_from_right(
+                        right - 10.0,
+                        size.height,
+                        "",
+                        Self::document_settings_label(&doc, &data.config),
+                        ctx,
+                        &data.config,
+                    );
+                ctx.draw_text(&text_layout, point);
+            }
         }
     }
 }
@@ -18,8 +18,9 @@
 use lapce_data::{
     command::{
         CommandKind, LapceCommand, LapceUICommand, LapceWorkbenchCommand,
-        LAPCE_COMMAND, LAPCE_OPEN_FILE, LAPCE_OPEN_FOLDER, LAPCE_SAVE_FILE_AS,
-        LAPCE_UI_COMMAND,
+        LAPCE_COMMAND, LAPCE_EXPORT_SETTINGS_BUNDLE, LAPCE_IMPORT_KEYBINDINGS,
+        LAPCE_IMPORT_SETTINGS_BUNDLE, LAPCE_OPEN_FILE, LAPCE_OPEN_FOLDER,
+        LAPCE_SAVE_FILE_AS, LAPCE_UI_COMMAND,
     },
     completion::CompletionStatus,
     config::{LapceConfig, LapceTheme},
@@ -38,19 +39,22 @@
         PanelStyle,
     },
     plugin::plugin_install_status::{PluginInstallStatus, PluginInstallType},
-    proxy::path_from_url,
+    proxy::{path_from_url, ProxyStatus},
+    settings_bundle::SettingsBundle,
 };
 use lapce_rpc::proxy::ProxyResponse;
-use lsp_types::DiagnosticSeverity;
+use lsp_types::{DiagnosticSeverity, MessageType};
 use xi_rope::Rope;
 
 use crate::{
     about::AboutBox, alert::AlertBox, completion::CompletionContainer,
-    editor::view::LapceEditorView, explorer::FileExplorer, hover::HoverContainer,
-    panel::PanelContainer, picker::FilePicker, plugin::Plugin,
-    problem::new_problem_panel, search::new_search_panel,
-    source_control::new_source_control_panel, split::split_data_widget,
-    status::LapceStatus, svg::get_svg, terminal::TerminalPanel, title::Title,
+    editor::view::LapceEditorView, explorer::FileExplorer,
+    hierarchy::new_hierarchy_panel, hover::HoverContainer,
+    notification::NotificationList, panel::PanelContainer, picker::FilePicker,
+    plugin::Plugin, problem::new_problem_panel, search::new_search_panel,
+    signature::SignatureContainer, source_control::new_source_control_panel,
+    split::split_data_widget, status::LapceStatus, svg::get_svg,
+    terminal::TerminalPanel, title::Title,
 };
 
 pub const LAPCE_TAB_META: Selector<SingleUse<LapceTabMeta>> =
@@ -79,11 +83,13 @@ pub struct LapceTab {
     main_split: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     completion: WidgetPod<LapceTabData, CompletionContainer>,
     hover: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
+    signature: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     rename: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     status: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     picker: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     about: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     alert: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
+    notifications: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     panel_left: WidgetPod<LapceTabData, PanelContainer>,
     panel_bottom: WidgetPod<LapceTabData, PanelContainer>,
     panel_right: WidgetPod<LapceTabData, PanelContainer>,
@@ -107,6 +113,7 @@ pub fn new(data: &mut LapceTabData) -> Self {
 
         let completion = CompletionContainer::new(&data.completion);
         let hover = HoverContainer::new(&data.hover);
+        let signature = SignatureContainer::new(&data.signature);
         let rename =
             LapceEditorView::new(data.rename.view_id, data.rename.editor_id, None)
                 .hide_header()
@@ -117,6 +124,7 @@ pub fn new(data: &mut LapceTabData) -> Self {
 
         let about = AboutBox::new(data);
         let alert = AlertBox::new(data);
+        let notifications = NotificationList::new(data);
 
         let mut panel_left = PanelContainer::new(PanelContainerPosition::Left);
         let mut panel_bottom = PanelContainer::new(PanelContainerPosition::Bottom);
@@ -172,6 +180,22 @@ pub fn new(data: &mut LapceTabData) -> Self {
                             WidgetPod::new(new_problem_panel(&data.problem).boxed()),
                         );
                     }
+                    PanelKind::CallHierarchy => {
+                        panel.insert_panel(
+                            *kind,
+                            WidgetPod::new(
+                                new_hierarchy_panel(&data.call_hierarchy).boxed(),
+                            ),
+                        );
+                    }
+                    PanelKind::TypeHierarchy => {
+                        panel.insert_panel(
+                            *kind,
+                            WidgetPod::new(
+                                new_hierarchy_panel(&data.type_hierarchy).boxed(),
+                            ),
+                        );
+                    }
                 }
             }
         }
@@ -182,11 +206,13 @@ pub fn new(data: &mut LapceTabData) -> Self {
             main_split: WidgetPod::new(main_split.boxed()),
             completion: WidgetPod::new(completion),
             hover: WidgetPod::new(hover.boxed()),
+            signature: WidgetPod::new(signature.boxed()),
             rename: WidgetPod::new(rename.boxed()),
             picker: WidgetPod::new(picker.boxed()),
             status: WidgetPod::new(status.boxed()),
             about: WidgetPod::new(about.boxed()),
             alert: WidgetPod::new(alert.boxed()),
+            notifications: WidgetPod::new(notifications.boxed()),
             panel_left: WidgetPod::new(panel_left),
             panel_right: WidgetPod::new(panel_right),
             panel_bottom: WidgetPod::new(panel_bottom),
@@ -695,6 +721,88 @@ fn handle_command_event(
                     Target::Widget(data.id),
                 ));
             }
+            Event::Command(cmd) if cmd.is(LAPCE_IMPORT_KEYBINDINGS) => {
+                ctx.set_handled();
+                let file = cmd.get_unchecked(LAPCE_IMPORT_KEYBINDINGS);
+                let (message, typ) = match KeyPressData::import_file(&file.path) {
+                    Ok(import) => {
+                        let existing = if import.existing > 0 {
+                            format!(
+                                ", skipped {} already in the keymaps file",
+                                import.existing
+                            )
+                        } else {
+                            String::new()
+                        };
+                        if import.unmapped.is_empty() {
+                            (
+                                format!(
+                                    "Imported {} keybindings{existing}",
+                                    import.keymaps.len()
+                                ),
+                                MessageType::INFO,
+                            )
+                        } else {
+                            (
+                                format!(
+                                    "Imported {} keybindings{existing}, \
+                                     but not these:\n{}",
+                                    import.keymaps.len(),
+                                    import.unmapped.join("\n")
+                                ),
+                                MessageType::WARNING,
+                            )
+                        }
+                    }
+                    Err(err) => (
+                        format!("Couldn't import {:?}: {err}", file.path),
+                        MessageType::ERROR,
+                    ),
+                };
+                data.show_notification(
+                    "Import Keybindings".to_string(),
+                    message,
+                    typ,
+                    Vec::new(),
+                    None,
+                );
+            }
+            Event::Command(cmd) if cmd.is(LAPCE_EXPORT_SETTINGS_BUNDLE) => {
+                ctx.set_handled();
+                let file = cmd.get_unchecked(LAPCE_EXPORT_SETTINGS_BUNDLE);
+                let bundle = SettingsBundle::collect(&data.plugin, &data.db);
+                let (message, typ) = match bundle.save(&file.path) {
+                    Ok(()) => (
+                        format!("Exported the settings bundle to {:?}", file.path),
+                        MessageType::INFO,
+                    ),
+                    Err(err) => (
+                        format!("Couldn't export to {:?}: {err}", file.path),
+                        MessageType::ERROR,
+                    ),
+                };
+                data.show_notification(
+                    "Settings Bundle".to_string(),
+                    message,
+                    typ,
+                    Vec::new(),
+                    None,
+                );
+            }
+            Event::Command(cmd) if cmd.is(LAPCE_IMPORT_SETTINGS_BUNDLE) => {
+                ctx.set_handled();
+                let file = cmd.get_unchecked(LAPCE_IMPORT_SETTINGS_BUNDLE);
+                match SettingsBundle::load(&file.path) {
+                    Ok(bundle) => data.import_settings_bundle(ctx, bundle),
+                    Err(err) => data.show_notification(
+                        "Settings Bundle".to_string(),
+                        format!("Couldn't import {:?}: {err}", file.path),
+                        MessageType::ERROR,
+                        Vec::new(),
+                        None,
+                    ),
+                }
+            }
             Event::Command(cmd) if cmd.is(LAPCE_UI_COMMAND) => {
                 let command = cmd.get_unchecked(LAPCE_UI_COMMAND);
                 match command {
@@ -890,6 +998,18 @@ fn handle_command_event(
                     }
                     LapceUICommand::ProxyUpdateStatus(status) => {
                         data.proxy_status = Arc::new(*status);
+                        if let ProxyStatus::Connected = status {
+                            LapceTabData::load_commit_message(
+                                &data.proxy.proxy_rpc,
+                                false,
+                                data.id,
+                                ctx.get_external_handle(),
+                            );
+                        }
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::UpdateCommitMessage { message, amend } => {
+                        data.set_commit_message(message, *amend);
                         ctx.set_handled();
                     }
                     LapceUICommand::HomeDir(path) => {
@@ -1000,6 +1120,7 @@ fn handle_command_event(
 
                         if !(*only_installing) {
                             plugin.installed.remove(&id);
+                            Arc::make_mut(&mut data.palette).remove_providers(&id);
 
                             if plugin.disabled.remove(&id) {
                                 let _ = data.db.save_disabled_volts(
@@ -1017,6 +1138,8 @@ fn handle_command_event(
                         let plugin = Arc::make_mut(&mut data.plugin);
                         plugin.workspace_disabled.insert(volt.id());
                         data.proxy.proxy_rpc.disable_volt(volt.clone());
+                        Arc::make_mut(&mut data.palette)
+                            .remove_providers(&volt.id());
                         let _ = data.db.save_workspace_disabled_volts(
                             &data.workspace,
                             plugin.workspace_disabled.iter().collect(),
@@ -1038,6 +1161,8 @@ fn handle_command_event(
                         let plugin = Arc::make_mut(&mut data.plugin);
                         plugin.disabled.insert(volt.id());
                         data.proxy.proxy_rpc.disable_volt(volt.clone());
+                        Arc::make_mut(&mut data.palette)
+                            .remove_providers(&volt.id());
                         let _ = data
                             .db
                             .save_disabled_volts(plugin.disabled.iter().collect());
@@ -1058,6 +1183,13 @@ fn handle_command_event(
                         source_control.branch = diff.head.to_string();
                         source_control.branches =
                             diff.branches.iter().cloned().collect();
+                        source_control.remote_branches =
+                            diff.remote_branches.iter().cloned().collect();
+                        source_control.upstream = diff.upstream.clone();
+                        source_control.conflicts =
+                            diff.conflicts.iter().cloned().collect();
+                        source_control.stashes =
+                            diff.stashes.iter().cloned().collect();
                         source_control.file_diffs = diff
                             .diffs
                             .iter()
@@ -1123,6 +1255,17 @@ fn handle_command_event(
                             }
                         }
                     }
+                    LapceUICommand::ApplyWorkspaceEditRequest {
+                        edit,
+                        request_id,
+                    } => {
+                        data.main_split.apply_workspace_edit(
+                            ctx,
+                            edit,
+                            Some(*request_id),
+                            &data.config,
+                        );
+                    }
                     LapceUICommand::PublishDiagnostics(diagnostics) => {
                         let path = path_from_url(&diagnostics.uri);
                         let diagnostics = diagnostics
@@ -1145,7 +1288,8 @@ fn handle_command_event(
                                     })
                                     .unwrap_or(0)
                                     + d.message.matches('\n').count()
-                                    + 1,
+                                    + 1
+                                    + usize::from(d.code_description.is_some()),
                             })
                             .sorted_by_key(|d| d.diagnostic.range.start)
                             .collect();
@@ -1183,7 +1327,12 @@ fn handle_command_event(
                         ctx.set_handled();
                     }
                     LapceUICommand::DocumentSave(path, exit) => {
-                        data.main_split.document_save(ctx, path, *exit);
+                        data.main_split.document_save(
+                            ctx,
+                            path,
+                            *exit,
+                            &data.config,
+                        );
                         ctx.set_handled();
                     }
                     LapceUICommand::DocumentFormatAndSave(
@@ -1193,7 +1342,12 @@ fn handle_command_event(
                         exit,
                     ) => {
                         data.main_split.document_format_and_save(
-                            ctx, path, *rev, result, *exit,
+                            ctx,
+                            path,
+                            *rev,
+                            result,
+                            *exit,
+                            &data.config,
                         );
                         ctx.set_handled();
                     }
@@ -1222,10 +1376,37 @@ fn handle_command_event(
                         ));
                         ctx.set_handled();
                     }
+                    LapceUICommand::ShowNotification {
+                        title,
+                        message,
+                        typ,
+                        actions,
+                        request_id,
+                    } => {
+                        data.show_notification(
+                            title.to_owned(),
+                            message.to_owned(),
+                            *typ,
+                            actions.to_owned(),
+                            *request_id,
+                        );
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::CloseNotification { id, action } => {
+                        data.close_notification(*id, *action);
+                        ctx.set_handled();
+                    }
                     LapceUICommand::BufferSave(path, rev, exit_widget_id) => {
                         let doc = data.main_split.open_docs.get_mut(path).unwrap();
                         if doc.rev() == *rev {
                             Arc::make_mut(doc).buffer_mut().set_pristine();
+                            if doc.conflicts.is_empty()
+                                && data.source_control.conflicts.contains(path)
+                            {
+                                data.proxy
+                                    .proxy_rpc
+                                    .git_resolve_conflict(path.to_path_buf());
+                            }
                             if let Some(widget_id) = exit_widget_id {
                                 ctx.submit_command(Command::new(
                                     LAPCE_COMMAND,
@@ -1329,6 +1510,7 @@ fn handle_command_event(
                         }
                     }
                     LapceUICommand::OpenFile(path, same_tab) => {
+                        data.main_split.db.save_file_open(&data.workspace, path);
                         data.main_split.jump_to_location(
                             ctx,
                             None,
@@ -1425,6 +1607,30 @@ fn handle_command_event(
                             .or_insert(false);
                         *state = !*state;
                     }
+                    LapceUICommand::UpdateHierarchy {
+                        kind,
+                        plugin_id,
+                        items,
+                    } => {
+                        data.update_hierarchy(ctx, *kind, *plugin_id, items.clone());
+                    }
+                    LapceUICommand::UpdateHierarchyChildren {
+                        kind,
+                        rev,
+                        path,
+                        items,
+                    } => {
+                        let hierarchy = data.hierarchy_mut(*kind);
+                        if hierarchy.rev == *rev {
+                            hierarchy.set_children(path, items.clone());
+                        }
+                    }
+                    LapceUICommand::ToggleHierarchyItem { kind, path } => {
+                        data.toggle_hierarchy_item(ctx, *kind, path);
+                    }
+                    LapceUICommand::ToggleHierarchyDirection(kind) => {
+                        data.toggle_hierarchy_direction(ctx, *kind);
+                    }
                     LapceUICommand::JumpToLineLocation(editor_view_id, location) => {
                         data.main_split.jump_to_location(
                             ctx,
@@ -1458,6 +1664,15 @@ fn handle_command_event(
                         );
                         ctx.set_handled();
                     }
+                    LapceUICommand::JumpToLineCol(editor_view_id, position) => {
+                        data.main_split.jump_to_line_col(
+                            ctx,
+                            *editor_view_id,
+                            *position,
+                            &data.config,
+                        );
+                        ctx.set_handled();
+                    }
                     LapceUICommand::TerminalJumpToLine(line) => {
                         if let Some(terminal) = data
                             .terminal
@@ -1505,12 +1720,83 @@ fn handle_command_event(
                             }
                         }
                     }
-                    LapceUICommand::UpdateCodeActions(path, rev, offset, resp) => {
+                    LapceUICommand::UpdateFoldingRanges { path, rev, ranges } => {
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            if doc.rev() == *rev {
+                                Arc::make_mut(doc)
+                                    .set_folding_ranges(ranges.clone());
+                            }
+                        }
+                    }
+                    LapceUICommand::UpdateCodeLens {
+                        path,
+                        rev,
+                        plugin_id,
+                        lenses,
+                    } => {
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            if doc.rev() == *rev {
+                                Arc::make_mut(doc)
+                                    .set_code_lens(*plugin_id, lenses.clone());
+                            }
+                        }
+                    }
+                    LapceUICommand::UpdateDocumentHighlights {
+                        path,
+                        rev,
+                        offset,
+                        highlights,
+                    } => {
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            if doc.rev() == *rev {
+                                Arc::make_mut(doc).set_document_highlights(
+                                    *offset,
+                                    highlights.clone(),
+                                );
+                            }
+                        }
+                    }
+                    LapceUICommand::CodeLensResolved {
+                        path,
+                        rev,
+                        index,
+                        lens,
+                    } => {
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            if doc.rev() == *rev {
+                                Arc::make_mut(doc)
+                                    .update_code_lens(*index, lens.clone());
+                            }
+                        }
+                    }
+                    LapceUICommand::RegisterPaletteProvider(
+                        volt_id,
+                        plugin_id,
+                        provider,
+                    ) => {
+                        Arc::make_mut(&mut data.palette).register_provider(
+                            volt_id.clone(),
+                            *plugin_id,
+                            provider.clone(),
+                        );
+                    }
+                    LapceUICommand::RefreshCodeLens => {
+                        for doc in data.main_split.open_docs.values() {
+                            doc.get_code_lens();
+                        }
+                    }
+                    LapceUICommand::UpdateCodeActions(
+                        path,
+                        rev,
+                        offset,
+                        plugin_id,
+                        resp,
+                    ) => {
                         if let Some(doc) = data.main_split.open_docs.get_mut(path) {
                             if doc.rev() == *rev {
                                 Arc::make_mut(doc)
                                     .code_actions
-                                    .insert(*offset, resp.clone());
+                                    .insert(*offset, (*plugin_id, resp.clone()));
                             }
                         }
                     }
@@ -1551,6 +1837,12 @@ fn handle_command_event(
                         );
                         ctx.set_handled();
                     }
+                    LapceUICommand::UpdateEditorConfig(path, editorconfig) => {
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            Arc::make_mut(doc)
+                                .set_editorconfig(editorconfig.clone());
+                        }
+                    }
                     LapceUICommand::OpenFileChanged { path, content } => {
                         let doc = data.main_split.open_docs.get_mut(path).unwrap();
                         let doc = Arc::make_mut(doc);
@@ -1626,6 +1918,10 @@ fn handle_command_event(
                             })
                             .unwrap_or_else(|| "Lapce".to_string());
                         ctx.window().set_title(&dir);
+                        KeyPressData::update_commands(
+                            &mut data.keypress,
+                            &data.config,
+                        );
                         ctx.submit_command(Command::new(
                             LAPCE_UI_COMMAND,
                             LapceUICommand::Focus,
@@ -1904,9 +2200,16 @@ fn event(
         {
             self.hover.event(ctx, event, data, env);
         }
+        if data.signature.is_active() || event.should_propagate_to_hidden() {
+            self.signature.event(ctx, event, data, env);
+        }
         if data.rename.active || event.should_propagate_to_hidden() {
             self.rename.event(ctx, event, data, env);
         }
+        if !data.notifications.items.is_empty() || event.should_propagate_to_hidden()
+        {
+            self.notifications.event(ctx, event, data, env);
+        }
 
         self.handle_mouse_event(ctx, event, data, env);
 
@@ -2054,10 +2357,12 @@ fn lifecycle(
         self.status.lifecycle(ctx, event, data, env);
         self.completion.lifecycle(ctx, event, data, env);
         self.hover.lifecycle(ctx, event, data, env);
+        self.signature.lifecycle(ctx, event, data, env);
         self.rename.lifecycle(ctx, event, data, env);
         self.picker.lifecycle(ctx, event, data, env);
         self.about.lifecycle(ctx, event, data, env);
         self.alert.lifecycle(ctx, event, data, env);
+        self.notifications.lifecycle(ctx, event, data, env);
         self.panel_left.lifecycle(ctx, event, data, env);
         self.panel_right.lifecycle(ctx, event, data, env);
         self.panel_bottom.lifecycle(ctx, event, data, env);
@@ -2129,11 +2434,13 @@ fn update(
         self.main_split.update(ctx, data, env);
         self.completion.update(ctx, data, env);
         self.hover.update(ctx, data, env);
+        self.signature.update(ctx, data, env);
         self.rename.update(ctx, data, env);
         self.status.update(ctx, data, env);
         self.picker.update(ctx, data, env);
         self.about.update(ctx, data, env);
         self.alert.update(ctx, data, env);
+        self.notifications.update(ctx, data, env);
         self.panel_left.update(ctx, data, env);
         self.panel_right.update(ctx, data, env);
         self.panel_bottom.update(ctx, data, env);
@@ -2277,6 +2584,17 @@ fn layout(
             self.hover.set_origin(ctx, data, env, hover_origin);
         }
 
+        if data.signature.is_active() {
+            let signature_size = self.signature.layout(ctx, bc, data, env);
+            let signature_origin = data.signature_origin(
+                ctx.text(),
+                self_size,
+                signature_size,
+                &data.config,
+            );
+            self.signature.set_origin(ctx, data, env, signature_origin);
+        }
+
         if data.rename.active {
             let rename_size = self.rename.layout(
                 ctx,
@@ -2302,6 +2620,22 @@ fn layout(
             );
         }
 
+        if !data.notifications.items.is_empty() {
+            let notifications_size = self.notifications.layout(ctx, bc, data, env);
+            self.notifications.set_origin(
+                ctx,
+                data,
+                env,
+                Point::new(
+                    self_size.width - notifications_size.width - 10.0,
+                    self_size.height
+                        - status_size.height
+                        - notifications_size.height
+                        - 10.0,
+                ),
+            );
+        }
+
         if data.about.active {
             self.about.layout(ctx, bc, data, env);
             self.about.set_origin(ctx, data, env, Point::ZERO);
@@ -2423,7 +2757,11 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
         }
         self.completion.paint(ctx, data, env);
         self.hover.paint(ctx, data, env);
+        self.signature.paint(ctx, data, env);
         self.picker.paint(ctx, data, env);
+        if !data.notifications.items.is_empty() {
+            self.notifications.paint(ctx, data, env);
+        }
         ctx.incr_alpha_depth();
         self.paint_drag_on_panel(ctx, data);
         self.paint_drag(ctx, data);
@@ -296,7 +296,10 @@ fn update_source_control(
 
             let command_rect =
                 command_rect.with_size(Size::new(x - command_rect.x0, size.height));
-            let branches = data.source_control.branches.clone();
+            // Remote branches are listed after the local ones, checking one
+            // out creates a local branch tracking it
+            let mut branches = data.source_control.branches.clone();
+            branches.extend(data.source_control.remote_branches.iter().cloned());
             self.menus.push((
                 command_rect,
                 Command::new(
@@ -337,8 +337,14 @@ fn event(
                                 &disabled_volts,
                             ));
                         }
-                        Arc::make_mut(&mut data.keypress)
-                            .update_keymaps(&data.config);
+                        // The active tab's settings, so that the composite
+                        // commands of its workspace are there
+                        let config = data
+                            .tabs
+                            .get(&data.active_id)
+                            .map(|tab| tab.config.clone())
+                            .unwrap_or_else(|| data.config.clone());
+                        Arc::make_mut(&mut data.keypress).update_keymaps(&config);
                         ctx.set_handled();
                     }
                     LapceUICommand::ReloadWindow => {